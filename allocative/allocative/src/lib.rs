@@ -65,6 +65,7 @@ pub use crate::flamegraph::FlameGraph;
 pub use crate::flamegraph::FlameGraphBuilder;
 pub use crate::global_root::register_root;
 pub use crate::key::Key;
+pub use crate::size_of::size_of_allocated_data_per_root;
 pub use crate::size_of::size_of_unique;
 pub use crate::size_of::size_of_unique_allocated_data;
 pub use crate::visitor::Visitor;
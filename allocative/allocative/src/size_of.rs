@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+use std::collections::HashSet;
+
 use crate::visitor::NodeKind;
 use crate::visitor::VisitorImpl;
 use crate::Allocative;
@@ -82,6 +84,89 @@ pub fn size_of_unique_allocated_data(root: &dyn Allocative) -> usize {
     visitor_impl.size
 }
 
+/// Size of data allocated in unique and shared pointers by each of the roots.
+///
+/// * Exclude the roots themselves
+/// * Each shared pointee is counted once, for the first root it is reachable from, so the
+///   sizes add up to the memory used by all the roots together
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use allocative::Allocative;
+///
+/// #[derive(Allocative)]
+/// struct Foo {
+///     data: Arc<Vec<u8>>,
+/// }
+///
+/// let data = Arc::new(vec![10, 20, 30]);
+/// let first = Foo { data: data.clone() };
+/// let second = Foo { data };
+/// let sizes = allocative::size_of_allocated_data_per_root([
+///     &first as &dyn Allocative,
+///     &second as &dyn Allocative,
+/// ]);
+/// assert_eq!(0, sizes[1]);
+/// ```
+pub fn size_of_allocated_data_per_root<'a>(
+    roots: impl IntoIterator<Item = &'a dyn Allocative>,
+) -> Vec<usize> {
+    struct SizeOfAllocatedDataVisitor {
+        /// Size of the current root.
+        size: usize,
+        visited_shared: HashSet<*const ()>,
+    }
+
+    impl VisitorImpl for SizeOfAllocatedDataVisitor {
+        fn enter_inline_impl(&mut self, _name: Key, size: usize, parent: NodeKind) {
+            if let NodeKind::Unique | NodeKind::Shared = parent {
+                self.size += size;
+            }
+        }
+
+        fn enter_unique_impl(&mut self, _name: Key, _size: usize, _parent: NodeKind) {}
+
+        fn enter_shared_impl(
+            &mut self,
+            _name: Key,
+            _size: usize,
+            ptr: *const (),
+            _parent: NodeKind,
+        ) -> bool {
+            self.visited_shared.insert(ptr)
+        }
+
+        fn exit_inline_impl(&mut self) {}
+
+        fn exit_unique_impl(&mut self) {}
+
+        fn exit_shared_impl(&mut self) {}
+
+        fn exit_root_impl(&mut self) {}
+    }
+
+    let mut visitor_impl = SizeOfAllocatedDataVisitor {
+        size: 0,
+        visited_shared: HashSet::new(),
+    };
+    roots
+        .into_iter()
+        .map(|root| {
+            visitor_impl.size = 0;
+            let mut visitor = Visitor {
+                visitor: &mut visitor_impl,
+                node_kind: NodeKind::Root,
+            };
+            root.visit(&mut visitor);
+            visitor.exit();
+            visitor_impl.size
+        })
+        .collect()
+}
+
 /// Size of a piece of data and data allocated in unique pointers in the struct.
 ///
 /// * Excludes shared pointers
@@ -113,13 +198,40 @@ where
 #[cfg(test)]
 mod tests {
     use std::mem;
+    use std::sync::Arc;
 
     use allocative_derive::Allocative;
 
     use crate as allocative;
+    use crate::size_of_allocated_data_per_root;
     use crate::size_of_unique;
     use crate::size_of_unique_allocated_data;
 
+    #[test]
+    fn test_shared_counted_once() {
+        #[derive(Allocative)]
+        struct Shared {
+            data: Arc<u32>,
+            own: Box<u32>,
+        }
+
+        let data = Arc::new(17);
+        let first = Shared {
+            data: data.clone(),
+            own: Box::new(1),
+        };
+        let second = Shared {
+            data,
+            own: Box::new(2),
+        };
+
+        let arc_inner = 2 * mem::size_of::<usize>() + mem::size_of::<u32>();
+        assert_eq!(
+            vec![arc_inner + mem::size_of::<u32>(), mem::size_of::<u32>()],
+            size_of_allocated_data_per_root([&first as &dyn crate::Allocative, &second])
+        );
+    }
+
     #[test]
     fn test_box() {
         #[derive(Allocative)]
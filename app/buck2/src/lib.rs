@@ -31,6 +31,7 @@ use buck2_client::commands::lsp::LspCommand;
 use buck2_client::commands::profile::ProfileCommand;
 use buck2_client::commands::query::aquery::AqueryCommand;
 use buck2_client::commands::query::cquery::CqueryCommand;
+use buck2_client::commands::query::shell::QueryShellCommand;
 use buck2_client::commands::query::uquery::UqueryCommand;
 use buck2_client::commands::rage::RageCommand;
 use buck2_client::commands::root::RootCommand;
@@ -259,6 +260,7 @@ pub(crate) enum CommandKind {
     Root(RootCommand),
     /// Alias for `uquery`.
     Query(UqueryCommand),
+    QueryShell(QueryShellCommand),
     Run(RunCommand),
     Server(ServerCommand),
     Status(StatusCommand),
@@ -395,6 +397,7 @@ impl CommandKind {
             CommandKind::Starlark(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Run(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Uquery(cmd) => cmd.exec(matches, command_ctx),
+            CommandKind::QueryShell(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Debug(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Complete(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Completion(cmd) => cmd.exec(Opt::command(), matches, command_ctx),
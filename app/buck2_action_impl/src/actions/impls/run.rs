@@ -647,6 +647,12 @@ impl Action for RunAction {
         }
     }
 
+    fn aquery_command_line(&self, fs: &ExecutorFs) -> anyhow::Result<Option<ExpandedCommandLine>> {
+        let (expanded, _worker) =
+            self.expand_command_line_and_worker(fs, &mut SimpleCommandLineArtifactVisitor::new())?;
+        Ok(Some(expanded))
+    }
+
     fn error_handler(&self) -> Option<OwnedFrozenValue> {
         self.error_handler.clone()
     }
@@ -46,10 +46,14 @@ use buck2_query::query::syntax::simple::eval::label_indexed::LabelIndexedSet;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
 use dice::CancellationContext;
 use dice::DiceComputations;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
 use dupe::Dupe;
 use dupe::IterDupedExt;
 use futures::FutureExt;
+use serde::Deserialize;
+use serde::Serialize;
 use smallvec::SmallVec;
 
 use crate::analysis::env::get_user_defined_rule_spec;
@@ -69,8 +73,12 @@ struct RuleAnalysisCalculationInstance;
     PartialEq,
     Allocative
 )]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{}", "_0")]
-pub struct AnalysisKey(pub ConfiguredTargetLabel);
+pub struct AnalysisKey(
+    #[serde(with = "buck2_core::configuration::data::persisted_configured_target_label")]
+    pub ConfiguredTargetLabel,
+);
 
 pub(crate) fn init_rule_analysis_calculation() {
     RULE_ANALYSIS_CALCULATION.init(&RuleAnalysisCalculationInstance);
@@ -94,6 +102,22 @@ impl Key for AnalysisKey {
         // TODO consider if we want analysis result to be eq
         false
     }
+
+    fn evictable() -> bool {
+        // Analysis results are never equal anyway, so an evicted one is only recomputed, and
+        // they hold large frozen heaps.
+        true
+    }
+}
+
+impl PersistentKey for AnalysisKey {
+    const PERSISTENT_ID: &'static str = "AnalysisKey";
+}
+
+/// Registers the analysis key, so that analysis nodes are saved in DICE snapshots. Analysis
+/// results hold frozen starlark values, so they are saved without their values.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register_without_value::<AnalysisKey>()
 }
 
 #[async_trait]
@@ -18,3 +18,8 @@ pub fn init_late_bindings() {
     attrs::resolve::configured_attr::init_configured_attr_to_value();
     analysis::calculation::init_rule_analysis_calculation();
 }
+
+/// Registers the analysis keys, so that they are saved in DICE snapshots.
+pub fn register_persistent_keys(persistence: &mut dice::DicePersistence) -> anyhow::Result<()> {
+    analysis::calculation::register_persistent_keys(persistence)
+}
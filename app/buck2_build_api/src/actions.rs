@@ -68,6 +68,7 @@ use crate::actions::execute::action_execution_target::ActionExecutionTarget;
 use crate::actions::execute::action_executor::ActionExecutionMetadata;
 use crate::actions::execute::action_executor::ActionOutputs;
 use crate::actions::execute::error::ExecuteError;
+use crate::actions::impls::expanded_command_line::ExpandedCommandLine;
 use crate::actions::impls::run_action_knobs::RunActionKnobs;
 use crate::artifact_groups::ArtifactGroup;
 use crate::artifact_groups::ArtifactGroupValues;
@@ -155,6 +156,12 @@ pub trait Action: Allocative + Debug + Send + Sync + 'static {
         indexmap! {}
     }
 
+    /// The command line this action runs, for actions which run one. Used by aquery to print the
+    /// actions' details.
+    fn aquery_command_line(&self, _fs: &ExecutorFs) -> anyhow::Result<Option<ExpandedCommandLine>> {
+        Ok(None)
+    }
+
     /// error handler
     fn error_handler(&self) -> Option<OwnedFrozenValue> {
         None
@@ -18,6 +18,10 @@ use std::sync::Arc;
 
 use allocative::Allocative;
 use buck2_artifact::actions::key::ActionKey;
+use buck2_artifact::artifact::artifact_type::Artifact;
+use buck2_common::dice::file_ops::DiceFileComputations;
+use buck2_common::file_ops::FileDigestConfig;
+use buck2_common::file_ops::RawPathMetadata;
 use buck2_common::global_cfg_options::GlobalCfgOptions;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::cells::cell_path::CellPath;
@@ -25,9 +29,18 @@ use buck2_core::cells::CellResolver;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::package::PackageLabel;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_directory::directory::entry::DirectoryEntry;
 use buck2_execute::artifact::fs::ExecutorFs;
+use buck2_execute::digest_config::HasDigestConfig;
+use buck2_execute::directory::ActionDirectoryBuilder;
+use buck2_execute::directory::ActionDirectoryEntry;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::entry::build_entry_from_disk;
+use buck2_execute::execute::blocking::HasBlockingExecutor;
+use buck2_execute::materialize::materializer::HasMaterializer;
 use buck2_node::attrs::configured_attr::ConfiguredAttr;
 use buck2_query::query::environment::QueryTarget;
 use buck2_query::query::graph::node::LabeledNode;
@@ -37,6 +50,7 @@ use derivative::Derivative;
 use dice::DiceComputations;
 use dupe::Dupe;
 use either::Either;
+use futures::FutureExt;
 use gazebo::variants::VariantName;
 use indexmap::IndexMap;
 use internment::ArcIntern;
@@ -45,8 +59,10 @@ use serde::Serialize;
 use starlark::values::Heap;
 use starlark::values::Value;
 
+use crate::actions::impls::expanded_command_line::ExpandedCommandLine;
 use crate::actions::RegisteredAction;
 use crate::analysis::AnalysisResult;
+use crate::artifact_groups::ResolvedArtifactGroup;
 use crate::artifact_groups::TransitiveSetProjectionKey;
 use crate::interpreter::rule_defs::provider::collection::FrozenProviderCollectionValue;
 
@@ -223,17 +239,161 @@ pub struct ActionData {
 }
 
 impl ActionData {
-    fn attrs(&self) -> IndexMap<String, String> {
-        let mut attrs = self.action.action().aquery_attributes(&ExecutorFs::new(
+    fn executor_fs(&self) -> ExecutorFs {
+        ExecutorFs::new(
             &self.fs,
             self.action.execution_config().options.path_separator,
-        ));
+        )
+    }
+
+    fn attrs(&self) -> IndexMap<String, String> {
+        let mut attrs = self.action.action().aquery_attributes(&self.executor_fs());
         attrs.insert(
             "executor_configuration".to_owned(),
             self.action.execution_config().executor.to_string(),
         );
         attrs
     }
+
+    pub fn action(&self) -> &Arc<RegisteredAction> {
+        &self.action
+    }
+
+    /// The expanded command line of the action, if it runs one.
+    pub fn command_line(&self) -> anyhow::Result<Option<ExpandedCommandLine>> {
+        self.action
+            .action()
+            .aquery_command_line(&self.executor_fs())
+    }
+
+    /// The paths of the action's outputs.
+    pub fn output_paths(&self) -> Vec<ProjectRelativePathBuf> {
+        self.action
+            .outputs()
+            .iter()
+            .map(|output| self.fs.resolve_build(output.get_path()))
+            .collect()
+    }
+
+    /// Every artifact the action reads, including the contents of the transitive sets it
+    /// consumes, with their paths, sorted by path. Unlike `deps`, this includes source files.
+    async fn input_artifacts(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+    ) -> anyhow::Result<Vec<(ProjectRelativePathBuf, Artifact)>> {
+        let mut artifacts = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue = self.action.inputs()?.into_owned();
+        while let Some(input) = queue.pop() {
+            match input.resolved_artifact(ctx).await? {
+                ResolvedArtifactGroup::Artifact(artifact) => {
+                    artifacts.push((artifact.get_path().resolve(&self.fs)?, artifact));
+                }
+                ResolvedArtifactGroup::TransitiveSetProjection(key) => {
+                    if visited.insert(key.dupe()) {
+                        let set = key.key.lookup(ctx).await?;
+                        queue.extend(set.get_projection_sub_inputs(key.projection)?);
+                    }
+                }
+            }
+        }
+        artifacts.sort_by(|(a, _), (b, _)| a.cmp(b));
+        artifacts.dedup_by(|(a, _), (b, _)| a == b);
+        Ok(artifacts)
+    }
+
+    /// The paths of every artifact the action reads, sorted, including the contents of the
+    /// transitive sets it consumes. Unlike `deps`, this includes source files.
+    pub async fn input_paths(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+    ) -> anyhow::Result<Vec<ProjectRelativePathBuf>> {
+        Ok(self
+            .input_artifacts(ctx)
+            .await?
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect())
+    }
+
+    /// Like `input_paths`, with the contents of each input where they are known without building
+    /// anything: source files, and build outputs which are already materialized. The contents
+    /// of other inputs, including source directories and symlinks, are `None`.
+    pub async fn input_entries(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+    ) -> anyhow::Result<
+        Vec<(
+            ProjectRelativePathBuf,
+            Option<ActionDirectoryEntry<ActionDirectoryBuilder>>,
+        )>,
+    > {
+        let mut sources = Vec::new();
+        let mut builds = Vec::new();
+        for (path, artifact) in self.input_artifacts(ctx).await? {
+            match artifact.get_source() {
+                Some(source) => sources.push((path, source.get_path().to_cell_path())),
+                None => builds.push(path),
+            }
+        }
+
+        let mut entries = ctx
+            .try_compute_join(sources, |ctx, (path, cell_path)| {
+                async move {
+                    let entry = match DiceFileComputations::read_path_metadata_if_exists(
+                        ctx,
+                        cell_path.as_ref(),
+                    )
+                    .await?
+                    {
+                        Some(RawPathMetadata::File(meta)) => {
+                            Some(DirectoryEntry::Leaf(ActionDirectoryMember::File(meta)))
+                        }
+                        _ => None,
+                    };
+                    anyhow::Ok((path, entry))
+                }
+                .boxed()
+            })
+            .await?;
+
+        // Build outputs are only hashed if they are on disk already: they are not built here.
+        let materialized = ctx
+            .per_transaction_data()
+            .get_materializer()
+            .get_materialized_file_paths(builds.clone())
+            .await?;
+        let digest_config =
+            FileDigestConfig::build(ctx.global_data().get_digest_config().cas_digest_config());
+        let blocking_executor = ctx.get_blocking_executor();
+        let project_root = self.fs.fs();
+        entries.extend(
+            buck2_util::future::try_join_all(builds.into_iter().zip(materialized).map(
+                |(path, materialized)| {
+                    let blocking_executor = blocking_executor.dupe();
+                    async move {
+                        let entry = match materialized {
+                            Ok(_) => {
+                                build_entry_from_disk(
+                                    project_root.resolve(&path),
+                                    digest_config,
+                                    &*blocking_executor,
+                                    project_root.root(),
+                                )
+                                .await?
+                                .0
+                            }
+                            Err(_) => None,
+                        };
+                        anyhow::Ok((path, entry))
+                    }
+                },
+            ))
+            .await?,
+        );
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(entries)
+    }
 }
 
 #[derive(
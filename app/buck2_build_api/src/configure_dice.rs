@@ -55,6 +55,18 @@ pub async fn configure_dice_for_buck(
     };
     dice.set_io_provider(io);
     dice.set_digest_config(digest_config);
+    if let Some(limit) = root_config
+        .map(|c| {
+            c.parse::<usize>(BuckconfigKeyRef {
+                section: "buck2",
+                property: "dice_concurrency_limit",
+            })
+        })
+        .transpose()?
+        .flatten()
+    {
+        dice.set_concurrency_limit(limit);
+    }
 
     let dice = dice.build(detect_cycles);
     let mut dice_ctx = dice.updater();
@@ -17,9 +17,15 @@ use buck2_core::fs::buck_out_path::BuckOutPathResolver;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::DiceTransactionUpdater;
 use dice::InjectedKey;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 #[async_trait]
 pub trait HasBuildContextData {
@@ -31,11 +37,13 @@ pub trait SetBuildContextData {
 }
 
 #[derive(PartialEq, Eq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub struct BuildData {
     buck_out_path: ProjectRelativePathBuf,
 }
 
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{:?}", self)]
 struct BuildDataKey;
 
@@ -47,6 +55,27 @@ impl InjectedKey for BuildDataKey {
     }
 }
 
+impl PersistentKey for BuildDataKey {
+    const PERSISTENT_ID: &'static str = "BuildDataKey";
+}
+
+impl PersistentValue for BuildDataKey {
+    type Persisted = Arc<BuildData>;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        Some(value.dupe())
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(value)
+    }
+}
+
+/// Registers the build context data, so that it is saved in DICE snapshots.
+pub fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<BuildDataKey>()
+}
+
 #[async_trait]
 impl HasBuildContextData for DiceComputations<'_> {
     async fn get_buck_out_path(&mut self) -> anyhow::Result<BuckOutPathResolver> {
@@ -15,7 +15,9 @@ use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_node::configured_universe::CqueryUniverse;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
 use buck2_node::nodes::unconfigured::TargetNode;
+use buck2_query::query::syntax::simple::eval::explain::QueryExplain;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use buck2_util::late_binding::LateBinding;
 use dice::DiceComputations;
 
@@ -29,6 +31,8 @@ pub trait QueryFrontend: Send + Sync + 'static {
         working_dir: &ProjectRelativePath,
         query: &str,
         query_args: &[String],
+        variables: &QueryVariables<TargetNode>,
+        explain: Option<&QueryExplain>,
     ) -> anyhow::Result<QueryEvaluationResult<TargetNode>>;
 
     async fn eval_cquery(
@@ -37,9 +41,11 @@ pub trait QueryFrontend: Send + Sync + 'static {
         working_dir: &ProjectRelativePath,
         query: &str,
         query_args: &[String],
+        variables: &QueryVariables<ConfiguredTargetNode>,
         global_cfg_options: GlobalCfgOptions,
         target_universe: Option<&[String]>,
         collect_universes: bool,
+        explain: Option<&QueryExplain>,
     ) -> anyhow::Result<(
         QueryEvaluationResult<ConfiguredTargetNode>,
         Option<Vec<Arc<CqueryUniverse>>>,
@@ -51,7 +57,9 @@ pub trait QueryFrontend: Send + Sync + 'static {
         working_dir: &ProjectRelativePath,
         query: &str,
         query_args: &[String],
+        variables: &QueryVariables<ActionQueryNode>,
         global_cfg_options: GlobalCfgOptions,
+        explain: Option<&QueryExplain>,
     ) -> anyhow::Result<QueryEvaluationResult<ActionQueryNode>>;
 }
 
@@ -18,6 +18,7 @@ use buck2_core::provider::label::ConfiguredProvidersLabel;
 use buck2_core::target::label::label::TargetLabel;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use buck2_query::query::syntax::simple::functions::helpers::CapturedExpr;
 use derivative::Derivative;
 use derive_more::Display;
@@ -339,7 +340,9 @@ fn aquery_methods(builder: &mut MethodsBuilder) {
                                 &ctx.working_dir()?,
                                 query,
                                 &query_args,
+                                &QueryVariables::default(),
                                 this.global_cfg_options_override.clone(),
+                                None,
                             )
                             .await?,
                         eval,
@@ -14,6 +14,7 @@ use buck2_build_api::query::oneshot::QUERY_FRONTEND;
 use buck2_common::global_cfg_options::GlobalCfgOptions;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use buck2_query::query::syntax::simple::functions::helpers::CapturedExpr;
 use derivative::Derivative;
 use derive_more::Display;
@@ -527,9 +528,11 @@ fn cquery_methods(builder: &mut MethodsBuilder) {
                                 &ctx.working_dir()?,
                                 query,
                                 &query_args,
+                                &QueryVariables::default(),
                                 this.global_cfg_options_override.clone(),
                                 target_universe.into_option().as_ref().map(|v| &v.items[..]),
                                 false,
+                                None,
                             )
                             .await?
                             .0,
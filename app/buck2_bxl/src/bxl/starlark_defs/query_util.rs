@@ -28,6 +28,9 @@ pub(crate) fn parse_query_evaluation_result<'v, T: NodeLike>(
                 eval.heap().alloc(StarlarkTargetSet::from(targets))
             }
             QueryEvaluationValue::FileSet(files) => eval.heap().alloc(StarlarkFileSet::from(files)),
+            QueryEvaluationValue::TargetPaths(paths) => eval
+                .heap()
+                .alloc(StarlarkTargetSet::from(paths.into_targets())),
         },
         QueryEvaluationResult::Multiple(multi) => eval.heap().alloc(Dict::new(
             multi
@@ -46,6 +49,9 @@ pub(crate) fn parse_query_evaluation_result<'v, T: NodeLike>(
                             QueryEvaluationValue::FileSet(files) => {
                                 eval.heap().alloc(StarlarkFileSet::from(files))
                             }
+                            QueryEvaluationValue::TargetPaths(paths) => eval
+                                .heap()
+                                .alloc(StarlarkTargetSet::from(paths.into_targets())),
                         },
                     ))
                 })
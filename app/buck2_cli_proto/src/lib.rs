@@ -145,6 +145,25 @@ impl From<DapRequest> for StreamingRequest {
     }
 }
 
+impl TryFrom<StreamingRequest> for QueryShellRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(value: StreamingRequest) -> Result<Self, Self::Error> {
+        match value.request {
+            Some(streaming_request::Request::QueryShell(req)) => Ok(req),
+            _ => Err(wrong_request_type("QueryShellRequest")),
+        }
+    }
+}
+
+impl From<QueryShellRequest> for StreamingRequest {
+    fn from(request: QueryShellRequest) -> Self {
+        Self {
+            request: Some(streaming_request::Request::QueryShell(request)),
+        }
+    }
+}
+
 /// Trait for requests that have CommonBuildOptions.
 pub trait HasBuildOptions {
     fn build_options(&self) -> Option<&CommonBuildOptions>;
@@ -303,6 +322,7 @@ result_convert!(InstallResponse);
 result_convert!(CleanStaleResponse);
 result_convert!(LspResponse);
 result_convert!(DapResponse);
+result_convert!(QueryShellResponse);
 result_convert!(AllocativeResponse);
 result_convert!(SubscriptionCommandResponse);
 result_convert!(TraceIoResponse);
@@ -312,6 +332,7 @@ partial_result_convert!(StdoutBytes);
 partial_result_convert!(LspMessage);
 partial_result_convert!(SubscriptionResponseWrapper);
 partial_result_convert!(DapMessage);
+partial_result_convert!(QueryShellMessage);
 
 define_request!(KillRequest);
 define_request!(StatusRequest);
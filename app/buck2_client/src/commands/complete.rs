@@ -7,11 +7,11 @@
  * of this source tree.
  */
 
-mod package;
+pub(crate) mod package;
 mod path_completer;
 mod path_sanitizer;
 mod results;
-mod target;
+pub(crate) mod target;
 
 use std::time::Duration;
 use std::time::Instant;
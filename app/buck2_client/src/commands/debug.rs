@@ -16,6 +16,8 @@ use buck2_common::argv::SanitizedArgv;
 use chrome_trace::ChromeTraceCommand;
 use crash::CrashCommand;
 use dice_dump::DiceDumpCommand;
+use dice_record::DiceRecordCommand;
+use dice_why::DiceWhyCommand;
 use file_status::FileStatusCommand;
 use flush_dep_files::FlushDepFilesCommand;
 use heap_dump::HeapDumpCommand;
@@ -42,6 +44,8 @@ mod chrome_trace;
 mod crash;
 mod daemon_dir;
 mod dice_dump;
+mod dice_record;
+mod dice_why;
 mod eval;
 mod exe;
 mod file_status;
@@ -71,6 +75,13 @@ pub enum DebugCommand {
     AllocatorStats(AllocatorStatsCommand),
     /// Dump the DICE graph to a file and saves it to disk.
     DiceDump(DiceDumpCommand),
+    /// Shows the dependency paths through which recent changes invalidated DICE keys.
+    ///
+    /// Requires `buck2.dice_invalidation_tracing = true` in the buckconfig the daemon started with.
+    DiceWhy(DiceWhyCommand),
+    /// Records the DICE transactions of the daemon, to replay them with
+    /// `fuzzy_dice replay-recording`.
+    DiceRecord(DiceRecordCommand),
     #[clap(hide = true)]
     Replay(DebugReplayCommand),
     /// Prints the hash of the buck2 binary
@@ -110,6 +121,8 @@ impl DebugCommand {
         let matches = matches.subcommand().expect("subcommand not found").1;
         match self {
             DebugCommand::DiceDump(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::DiceWhy(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::DiceRecord(cmd) => cmd.exec(matches, ctx),
             DebugCommand::Crash(cmd) => cmd.exec(matches, ctx),
             DebugCommand::HeapDump(cmd) => cmd.exec(matches, ctx),
             DebugCommand::AllocatorStats(cmd) => cmd.exec(matches, ctx),
@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_cli_proto::unstable_dice_record_request;
+use buck2_cli_proto::UnstableDiceRecordRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+#[derive(Debug, clap::Parser)]
+pub struct DiceRecordCommand {
+    #[clap(subcommand)]
+    action: Action,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Action {
+    /// Starts recording, dropping anything recorded before.
+    Start,
+    /// Stops recording and writes the recording.
+    Stop {
+        /// The path to write the recording to, as JSON.
+        #[clap(short, long, value_name = "PATH")]
+        output: PathArg,
+    },
+}
+
+#[async_trait]
+impl StreamingCommand for DiceRecordCommand {
+    const COMMAND_NAME: &'static str = "dice_record";
+
+    fn existing_only() -> bool {
+        true
+    }
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        _matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let action = match &self.action {
+            Action::Start => {
+                unstable_dice_record_request::Action::Start(unstable_dice_record_request::Start {})
+            }
+            Action::Stop { output } => {
+                unstable_dice_record_request::Action::Stop(unstable_dice_record_request::Stop {
+                    output_path: output.resolve(&ctx.working_dir).into_string()?,
+                })
+            }
+        };
+        let res = buckd
+            .with_flushing()
+            .unstable_dice_record(UnstableDiceRecordRequest {
+                action: Some(action),
+            })
+            .await?;
+
+        if let Action::Stop { .. } = self.action {
+            buck2_client_ctx::eprintln!("Recorded {} transactions.", res.transactions)?;
+        }
+
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        CommonConsoleOptions::none_ref()
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        CommonEventLogOptions::default_ref()
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        CommonBuildConfigurationOptions::default_ref()
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        CommonStarlarkOptions::default_ref()
+    }
+}
@@ -0,0 +1,95 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_cli_proto::UnstableDiceWhyRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+#[derive(Debug, clap::Parser)]
+pub struct DiceWhyCommand {
+    /// The DICE key to explain, exactly as it is displayed in the printed paths.
+    #[clap(value_name = "KEY")]
+    key: String,
+
+    /// Explain every dirtied key whose description contains `KEY`, e.g. a target.
+    #[clap(long)]
+    contains: bool,
+
+    /// Print at most this many paths.
+    #[clap(long, default_value = "10")]
+    limit: u64,
+}
+
+#[async_trait]
+impl StreamingCommand for DiceWhyCommand {
+    const COMMAND_NAME: &'static str = "dice_why";
+
+    fn existing_only() -> bool {
+        true
+    }
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        _matches: &clap::ArgMatches,
+        _ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let res = buckd
+            .with_flushing()
+            .unstable_dice_why(UnstableDiceWhyRequest {
+                key: self.key,
+                contains: self.contains,
+                limit: self.limit,
+            })
+            .await?;
+
+        if res.paths.is_empty() {
+            buck2_client_ctx::eprintln!(
+                "No recorded invalidations match. Is `buck2.dice_invalidation_tracing` enabled?"
+            )?;
+            if !self.contains {
+                buck2_client_ctx::eprintln!("Use `--contains` to match part of a key.")?;
+            }
+        }
+        for path in res.paths {
+            buck2_client_ctx::println!("{}: {}", path.version, path.keys.join(" -> "))?;
+        }
+        if res.omitted > 0 {
+            buck2_client_ctx::eprintln!(
+                "...and {} more paths, use `--limit` to show them",
+                res.omitted
+            )?;
+        }
+
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        CommonConsoleOptions::none_ref()
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        CommonEventLogOptions::default_ref()
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        CommonBuildConfigurationOptions::default_ref()
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        CommonStarlarkOptions::default_ref()
+    }
+}
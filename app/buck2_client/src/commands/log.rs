@@ -10,6 +10,8 @@
 mod critical_path;
 pub(crate) mod debug_replay;
 pub(crate) mod debug_what_ran;
+mod diff;
+mod export_otlp;
 pub(crate) mod options;
 pub(crate) mod path_log;
 mod replay;
@@ -91,6 +93,8 @@ pub enum LogCommand {
     Replay(replay::ReplayCommand),
     ShowUser(show_user_log::ShowUserLogCommand),
     Summary(summary::SummaryCommand),
+    Diff(diff::DiffCommand),
+    ExportOtlp(export_otlp::ExportOtlpCommand),
 }
 
 impl LogCommand {
@@ -108,6 +112,8 @@ impl LogCommand {
             Self::Replay(cmd) => cmd.exec(matches, ctx),
             Self::ShowUser(cmd) => cmd.exec(matches, ctx),
             Self::Summary(cmd) => cmd.exec(matches, ctx),
+            Self::Diff(cmd) => cmd.exec(matches, ctx),
+            Self::ExportOtlp(cmd) => cmd.exec(matches, ctx),
         }
     }
 
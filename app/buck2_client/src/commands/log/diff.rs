@@ -0,0 +1,628 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_data::command_execution_kind::Command;
+use buck2_event_log::read::EventLogPathBuf;
+use buck2_event_log::stream_value::StreamValue;
+use buck2_event_observer::display;
+use buck2_event_observer::display::TargetDisplayOptions;
+use tokio_stream::StreamExt;
+
+use crate::commands::log::transform_format;
+use crate::commands::log::LogCommandOutputFormat;
+use crate::commands::log::LogCommandOutputFormatWithWriter;
+
+/// Compares the actions executed by two invocations, to explain why one of them missed the cache.
+///
+/// Actions are matched by their owner, category and identifier. The output is a series of
+/// tab-delimited records, one per difference, with the following structure:
+///
+/// The identity of the action.
+///
+/// The kind of difference: `only_in_a` or `only_in_b` for actions executed by only one of the
+/// invocations, `execution_kind` for actions executed differently (e.g. locally in one and from
+/// the action cache in the other, even with the same digest), `cache_hit` for remote commands
+/// hitting the cache in only one, `digest` for actions whose action digest differs, and `arg`,
+/// `env` or `input` for the parts of a differing action that changed, where the logs record them.
+///
+/// What differs (the environment variable, or the argument or input present in only one).
+///
+/// The value in the first log, or `-`.
+///
+/// The value in the second log, or `-`.
+///
+/// Local commands record their arguments and environment, and remote commands which failed may
+/// record their inputs, if `--materialize-failed-inputs` was passed.
+#[derive(Debug, clap::Parser)]
+pub struct DiffCommand {
+    /// A path to the event-log file of the first invocation.
+    #[clap(value_name = "PATH")]
+    log_a: PathArg,
+
+    /// A path to the event-log file of the second invocation.
+    #[clap(value_name = "PATH")]
+    log_b: PathArg,
+
+    #[clap(
+        long = "format",
+        help = "Which output format to use for this command",
+        default_value = "tabulated",
+        ignore_case = true,
+        value_enum
+    )]
+    pub output: LogCommandOutputFormat,
+}
+
+/// Identifies an action across invocations.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct ActionId {
+    owner: String,
+    category: String,
+    identifier: String,
+}
+
+impl Display for ActionId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.identifier.is_empty() {
+            write!(f, "{} ({})", self.owner, self.category)
+        } else {
+            write!(f, "{} ({} {})", self.owner, self.category, self.identifier)
+        }
+    }
+}
+
+/// What the log records about the command an action ran, if it ran one.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct ActionCommand {
+    /// How the action was executed, e.g. `ACTION_EXECUTION_KIND_LOCAL`.
+    execution_kind: Option<String>,
+    /// Only recorded for remote commands.
+    cache_hit: Option<bool>,
+    digest: Option<String>,
+    argv: Option<Vec<String>>,
+    env: Option<BTreeMap<String, String>>,
+    inputs: Option<BTreeSet<String>>,
+}
+
+impl ActionCommand {
+    fn from_action(action: &buck2_data::ActionExecutionEnd) -> Self {
+        // The last command is the one that produced the action's result.
+        let command = action
+            .commands
+            .last()
+            .and_then(|c| c.details.as_ref())
+            .and_then(|d| d.command_kind.as_ref())
+            .and_then(|k| k.command.as_ref());
+
+        fn env(env: &[buck2_data::EnvironmentEntry]) -> BTreeMap<String, String> {
+            env.iter()
+                .map(|e| (e.key.clone(), e.value.clone()))
+                .collect()
+        }
+
+        let command = match command {
+            Some(Command::LocalCommand(c)) => Self {
+                digest: Some(c.action_digest.clone()),
+                argv: Some(c.argv.clone()),
+                env: Some(env(&c.env)),
+                ..Self::default()
+            },
+            Some(Command::WorkerCommand(c)) => Self {
+                digest: Some(c.action_digest.clone()),
+                argv: Some(c.argv.clone()),
+                env: Some(env(&c.env)),
+                ..Self::default()
+            },
+            Some(Command::WorkerInitCommand(c)) => Self {
+                argv: Some(c.argv.clone()),
+                env: Some(env(&c.env)),
+                ..Self::default()
+            },
+            Some(Command::RemoteCommand(c)) => Self {
+                cache_hit: Some(c.cache_hit),
+                digest: Some(c.action_digest.clone()),
+                inputs: if c.materialized_inputs_for_failed.is_empty() {
+                    None
+                } else {
+                    Some(c.materialized_inputs_for_failed.iter().cloned().collect())
+                },
+                ..Self::default()
+            },
+            Some(Command::OmittedLocalCommand(c)) => Self {
+                digest: Some(c.action_digest.clone()),
+                ..Self::default()
+            },
+            None => Self::default(),
+        };
+        Self {
+            execution_kind: buck2_data::ActionExecutionKind::from_i32(action.execution_kind)
+                .map(|kind| kind.as_str_name().to_owned()),
+            ..command
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DiffKind {
+    OnlyInA,
+    OnlyInB,
+    ExecutionKind,
+    CacheHit,
+    Digest,
+    Arg,
+    Env,
+    Input,
+}
+
+impl Display for DiffKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::OnlyInA => "only_in_a",
+            Self::OnlyInB => "only_in_b",
+            Self::ExecutionKind => "execution_kind",
+            Self::CacheHit => "cache_hit",
+            Self::Digest => "digest",
+            Self::Arg => "arg",
+            Self::Env => "env",
+            Self::Input => "input",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+struct DiffRecord {
+    action: String,
+    kind: DiffKind,
+    detail: Option<String>,
+    a: Option<String>,
+    b: Option<String>,
+}
+
+impl DiffRecord {
+    fn new(
+        action: &ActionId,
+        kind: DiffKind,
+        detail: Option<String>,
+        a: Option<String>,
+        b: Option<String>,
+    ) -> Self {
+        Self {
+            action: action.to_string(),
+            kind,
+            detail,
+            a,
+            b,
+        }
+    }
+}
+
+impl Display for DiffRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let or_dash = |s: &Option<String>| s.as_deref().unwrap_or("-").to_owned();
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}",
+            self.action,
+            self.kind,
+            or_dash(&self.detail),
+            or_dash(&self.a),
+            or_dash(&self.b)
+        )
+    }
+}
+
+fn diff_commands(action: &ActionId, a: &ActionCommand, b: &ActionCommand) -> Vec<DiffRecord> {
+    let mut records = Vec::new();
+    // Actions with the same digest can still be executed differently, which is what explains a
+    // cache miss when nothing else changed.
+    if a.execution_kind != b.execution_kind {
+        records.push(DiffRecord::new(
+            action,
+            DiffKind::ExecutionKind,
+            None,
+            a.execution_kind.clone(),
+            b.execution_kind.clone(),
+        ));
+    }
+    if let (Some(cache_hit_a), Some(cache_hit_b)) = (a.cache_hit, b.cache_hit) {
+        if cache_hit_a != cache_hit_b {
+            records.push(DiffRecord::new(
+                action,
+                DiffKind::CacheHit,
+                None,
+                Some(cache_hit_a.to_string()),
+                Some(cache_hit_b.to_string()),
+            ));
+        }
+    }
+    match (&a.digest, &b.digest) {
+        // The action digest covers the command, its environment and its inputs.
+        (Some(digest_a), Some(digest_b)) if digest_a == digest_b => return records,
+        (Some(digest_a), Some(digest_b)) => {
+            records.push(DiffRecord::new(
+                action,
+                DiffKind::Digest,
+                None,
+                Some(digest_a.clone()),
+                Some(digest_b.clone()),
+            ));
+        }
+        // Without digests on both sides, only the recorded details can tell the actions apart.
+        _ => {}
+    }
+
+    if let (Some(argv_a), Some(argv_b)) = (&a.argv, &b.argv) {
+        if argv_a != argv_b {
+            let set_a: BTreeSet<&String> = argv_a.iter().collect();
+            let set_b: BTreeSet<&String> = argv_b.iter().collect();
+            if set_a == set_b {
+                // Same arguments in a different order (or repeated differently).
+                records.push(DiffRecord::new(
+                    action,
+                    DiffKind::Arg,
+                    None,
+                    Some(argv_a.join(" ")),
+                    Some(argv_b.join(" ")),
+                ));
+            } else {
+                for arg in set_a.difference(&set_b) {
+                    records.push(DiffRecord::new(
+                        action,
+                        DiffKind::Arg,
+                        Some((*arg).clone()),
+                        Some((*arg).clone()),
+                        None,
+                    ));
+                }
+                for arg in set_b.difference(&set_a) {
+                    records.push(DiffRecord::new(
+                        action,
+                        DiffKind::Arg,
+                        Some((*arg).clone()),
+                        None,
+                        Some((*arg).clone()),
+                    ));
+                }
+            }
+        }
+    }
+
+    if let (Some(env_a), Some(env_b)) = (&a.env, &b.env) {
+        let keys: BTreeSet<&String> = env_a.keys().chain(env_b.keys()).collect();
+        for key in keys {
+            let value_a = env_a.get(key);
+            let value_b = env_b.get(key);
+            if value_a != value_b {
+                records.push(DiffRecord::new(
+                    action,
+                    DiffKind::Env,
+                    Some(key.clone()),
+                    value_a.cloned(),
+                    value_b.cloned(),
+                ));
+            }
+        }
+    }
+
+    if let (Some(inputs_a), Some(inputs_b)) = (&a.inputs, &b.inputs) {
+        for input in inputs_a.difference(inputs_b) {
+            records.push(DiffRecord::new(
+                action,
+                DiffKind::Input,
+                Some(input.clone()),
+                Some(input.clone()),
+                None,
+            ));
+        }
+        for input in inputs_b.difference(inputs_a) {
+            records.push(DiffRecord::new(
+                action,
+                DiffKind::Input,
+                Some(input.clone()),
+                None,
+                Some(input.clone()),
+            ));
+        }
+    }
+
+    records
+}
+
+fn diff_actions(
+    a: &BTreeMap<ActionId, ActionCommand>,
+    b: &BTreeMap<ActionId, ActionCommand>,
+) -> Vec<DiffRecord> {
+    let mut records = Vec::new();
+    for (action, command_a) in a {
+        match b.get(action) {
+            Some(command_b) => records.extend(diff_commands(action, command_a, command_b)),
+            None => records.push(DiffRecord::new(
+                action,
+                DiffKind::OnlyInA,
+                None,
+                command_a.digest.clone(),
+                None,
+            )),
+        }
+    }
+    for (action, command_b) in b {
+        if !a.contains_key(action) {
+            records.push(DiffRecord::new(
+                action,
+                DiffKind::OnlyInB,
+                None,
+                None,
+                command_b.digest.clone(),
+            ));
+        }
+    }
+    records
+}
+
+async fn read_actions(
+    log_path: &EventLogPathBuf,
+) -> anyhow::Result<BTreeMap<ActionId, ActionCommand>> {
+    let (invocation, mut events) = log_path.unpack_stream().await?;
+    buck2_client_ctx::eprintln!(
+        "Reading actions from: {}",
+        invocation.display_command_line()
+    )?;
+
+    let mut actions = BTreeMap::new();
+    while let Some(event) = events.try_next().await? {
+        match event {
+            StreamValue::Event(event) => match event.data {
+                Some(buck2_data::buck_event::Data::SpanEnd(end)) => match end.data {
+                    Some(buck2_data::span_end_event::Data::ActionExecution(action)) => {
+                        let (Some(key), Some(name)) = (&action.key, &action.name) else {
+                            continue;
+                        };
+                        let owner =
+                            display::display_action_key(key, TargetDisplayOptions::for_log())?;
+                        actions.insert(
+                            ActionId {
+                                owner,
+                                category: name.category.clone(),
+                                identifier: name.identifier.clone(),
+                            },
+                            ActionCommand::from_action(&action),
+                        );
+                    }
+                    _ => {}
+                },
+                _ => {}
+            },
+            StreamValue::Result(..) | StreamValue::PartialResult(..) => {}
+        }
+    }
+    Ok(actions)
+}
+
+fn print_record(
+    output: &mut LogCommandOutputFormatWithWriter,
+    record: &DiffRecord,
+) -> anyhow::Result<()> {
+    match output {
+        LogCommandOutputFormatWithWriter::Tabulated(w) => {
+            Ok(w.write_all(format!("{}\n", record).as_bytes())?)
+        }
+        LogCommandOutputFormatWithWriter::Csv(writer) => Ok(writer.serialize(record)?),
+        LogCommandOutputFormatWithWriter::Json(w) => {
+            serde_json::to_writer(w, &record)?;
+            buck2_client_ctx::println!("")
+        }
+    }
+}
+
+impl DiffCommand {
+    pub fn exec(self, _matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let Self {
+            log_a,
+            log_b,
+            output,
+        } = self;
+
+        buck2_client_ctx::stdio::print_with_writer::<anyhow::Error, _>(|w| {
+            let mut output = transform_format(output, w);
+            ctx.with_runtime(|ctx| async move {
+                let log_a = EventLogPathBuf::infer(log_a.resolve(&ctx.working_dir))?;
+                let log_b = EventLogPathBuf::infer(log_b.resolve(&ctx.working_dir))?;
+
+                let actions_a = read_actions(&log_a).await?;
+                let actions_b = read_actions(&log_b).await?;
+
+                let records = diff_actions(&actions_a, &actions_b);
+                for record in &records {
+                    print_record(&mut output, record)?;
+                }
+                buck2_client_ctx::eprintln!(
+                    "actions: {} in a, {} in b, differences: {}",
+                    actions_a.len(),
+                    actions_b.len(),
+                    records.len()
+                )?;
+
+                anyhow::Ok(())
+            })?;
+            anyhow::Ok(())
+        })?;
+        ExitResult::success()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(owner: &str) -> ActionId {
+        ActionId {
+            owner: owner.to_owned(),
+            category: "cxx_compile".to_owned(),
+            identifier: "main.cpp".to_owned(),
+        }
+    }
+
+    fn local(digest: &str, argv: &[&str], env: &[(&str, &str)]) -> ActionCommand {
+        ActionCommand {
+            execution_kind: Some("ACTION_EXECUTION_KIND_LOCAL".to_owned()),
+            cache_hit: None,
+            digest: Some(digest.to_owned()),
+            argv: Some(argv.iter().map(|s| (*s).to_owned()).collect()),
+            env: Some(
+                env.iter()
+                    .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                    .collect(),
+            ),
+            inputs: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_actions_only_in_one() {
+        let a = BTreeMap::from([(id("root//:a"), local("d1", &[], &[]))]);
+        let b = BTreeMap::from([(id("root//:b"), local("d2", &[], &[]))]);
+
+        let records = diff_actions(&a, &b);
+        assert_eq!(
+            records,
+            vec![
+                DiffRecord::new(
+                    &id("root//:a"),
+                    DiffKind::OnlyInA,
+                    None,
+                    Some("d1".to_owned()),
+                    None
+                ),
+                DiffRecord::new(
+                    &id("root//:b"),
+                    DiffKind::OnlyInB,
+                    None,
+                    None,
+                    Some("d2".to_owned())
+                ),
+            ]
+        );
+        assert_eq!(
+            records[0].to_string(),
+            "root//:a (cxx_compile main.cpp)\tonly_in_a\t-\td1\t-"
+        );
+    }
+
+    #[test]
+    fn test_diff_actions_same_digest_is_not_reported() {
+        let a = BTreeMap::from([(id("root//:a"), local("d", &["cc", "-O1"], &[]))]);
+        let b = BTreeMap::from([(id("root//:a"), local("d", &["cc", "-O1"], &[]))]);
+
+        assert_eq!(diff_actions(&a, &b), vec![]);
+    }
+
+    #[test]
+    fn test_diff_actions_reports_what_changed() {
+        let a = BTreeMap::from([(
+            id("root//:a"),
+            local(
+                "d1",
+                &["cc", "-O1", "main.cpp"],
+                &[("TMP", "/tmp/a"), ("LANG", "C")],
+            ),
+        )]);
+        let b = BTreeMap::from([(
+            id("root//:a"),
+            local(
+                "d2",
+                &["cc", "-O2", "main.cpp"],
+                &[("TMP", "/tmp/b"), ("LANG", "C")],
+            ),
+        )]);
+
+        let some = |s: &str| Some(s.to_owned());
+        assert_eq!(
+            diff_actions(&a, &b),
+            vec![
+                DiffRecord::new(
+                    &id("root//:a"),
+                    DiffKind::Digest,
+                    None,
+                    some("d1"),
+                    some("d2")
+                ),
+                DiffRecord::new(
+                    &id("root//:a"),
+                    DiffKind::Arg,
+                    some("-O1"),
+                    some("-O1"),
+                    None
+                ),
+                DiffRecord::new(
+                    &id("root//:a"),
+                    DiffKind::Arg,
+                    some("-O2"),
+                    None,
+                    some("-O2")
+                ),
+                DiffRecord::new(
+                    &id("root//:a"),
+                    DiffKind::Env,
+                    some("TMP"),
+                    some("/tmp/a"),
+                    some("/tmp/b")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_actions_reports_execution_kind_with_same_digest() {
+        let remote = |execution_kind: &str, cache_hit| ActionCommand {
+            execution_kind: Some(execution_kind.to_owned()),
+            cache_hit: Some(cache_hit),
+            digest: Some("d".to_owned()),
+            ..ActionCommand::default()
+        };
+        let a = BTreeMap::from([(
+            id("root//:a"),
+            remote("ACTION_EXECUTION_KIND_ACTION_CACHE", true),
+        )]);
+        let b = BTreeMap::from([(
+            id("root//:a"),
+            remote("ACTION_EXECUTION_KIND_REMOTE", false),
+        )]);
+
+        let some = |s: &str| Some(s.to_owned());
+        assert_eq!(
+            diff_actions(&a, &b),
+            vec![
+                DiffRecord::new(
+                    &id("root//:a"),
+                    DiffKind::ExecutionKind,
+                    None,
+                    some("ACTION_EXECUTION_KIND_ACTION_CACHE"),
+                    some("ACTION_EXECUTION_KIND_REMOTE")
+                ),
+                DiffRecord::new(
+                    &id("root//:a"),
+                    DiffKind::CacheHit,
+                    None,
+                    some("true"),
+                    some("false")
+                ),
+            ]
+        );
+    }
+}
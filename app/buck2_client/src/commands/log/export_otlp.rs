@@ -0,0 +1,87 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::otlp::convert::OtlpSpanConverter;
+use buck2_client_ctx::otlp::export::OtlpExporter;
+use buck2_client_ctx::otlp::export::OtlpProtocol;
+use buck2_event_log::stream_value::StreamValue;
+use buck2_events::BuckEvent;
+use tokio_stream::StreamExt;
+
+use crate::commands::log::options::EventLogOptions;
+
+/// Spans are pushed in batches of this size.
+const BATCH_SIZE: usize = 512;
+
+/// Converts the command, analysis, action and RE spans of the selected invocation to OpenTelemetry
+/// traces, and pushes them to an OTLP collector.
+///
+/// This is what buck2 does while running a command when `BUCK2_OTLP_ENDPOINT` is set.
+#[derive(Debug, clap::Parser)]
+pub struct ExportOtlpCommand {
+    #[clap(flatten)]
+    event_log: EventLogOptions,
+
+    /// The base URL of the collector, e.g. `http://localhost:4318`.
+    #[clap(long, value_name = "URL")]
+    endpoint: String,
+
+    /// The protocol the collector accepts.
+    #[clap(long, default_value = "http", ignore_case = true, value_enum)]
+    protocol: OtlpProtocol,
+}
+
+impl ExportOtlpCommand {
+    pub fn exec(self, _matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let Self {
+            event_log,
+            endpoint,
+            protocol,
+        } = self;
+
+        ctx.with_runtime(|ctx| async move {
+            let log_path = event_log.get(&ctx).await?;
+            // Logs written to a path of the user's choosing don't name their command.
+            let mut converter =
+                OtlpSpanConverter::new(log_path.command_from_filename().unwrap_or("command"));
+            let exporter = OtlpExporter::new(&endpoint, protocol).await?;
+
+            let (invocation, mut events) = log_path.unpack_stream().await?;
+            buck2_client_ctx::eprintln!(
+                "Exporting spans from: {}",
+                invocation.display_command_line()
+            )?;
+
+            let mut spans = Vec::new();
+            let mut exported = 0;
+            while let Some(event) = events.try_next().await? {
+                match event {
+                    StreamValue::Event(event) => {
+                        if let Some(span) = converter.handle_event(&BuckEvent::try_from(event)?)? {
+                            spans.push(span);
+                        }
+                        if spans.len() >= BATCH_SIZE {
+                            exported += spans.len();
+                            exporter.export(std::mem::take(&mut spans)).await?;
+                        }
+                    }
+                    StreamValue::Result(..) | StreamValue::PartialResult(..) => {}
+                }
+            }
+            exported += spans.len();
+            exporter.export(spans).await?;
+
+            buck2_client_ctx::eprintln!("Exported {} spans to `{}`", exported, endpoint)?;
+            anyhow::Ok(())
+        })?;
+        ExitResult::success()
+    }
+}
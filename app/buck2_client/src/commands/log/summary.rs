@@ -17,6 +17,7 @@ use buck2_client_ctx::exit_result::ExitResult;
 use buck2_client_ctx::subscribers::recorder::process_memory;
 use buck2_data::ActionExecutionKind;
 use buck2_event_log::stream_value::StreamValue;
+use buck2_event_observer::dice_state::DiceState;
 use buck2_event_observer::fmt_duration;
 use buck2_event_observer::humanized::HumanizedBytes;
 use buck2_event_observer::humanized::HumanizedBytesPerSecond;
@@ -43,6 +44,8 @@ struct Stats {
     peak_used_disk_space_bytes: Option<u64>,
     total_disk_space_bytes: Option<u64>,
     system_total_memory_bytes: Option<u64>,
+    test_scheduling: Option<buck2_data::TestScheduling>,
+    dice_state: DiceState,
 }
 
 impl Stats {
@@ -93,6 +96,12 @@ impl Stats {
                         self.total_disk_space_bytes = system_info.total_disk_space_bytes;
                         self.system_total_memory_bytes = system_info.system_total_memory_bytes;
                     }
+                    Some(buck2_data::instant_event::Data::TestScheduling(test_scheduling)) => {
+                        self.test_scheduling = Some(test_scheduling.clone());
+                    }
+                    Some(buck2_data::instant_event::Data::DiceStateSnapshot(dice_state)) => {
+                        self.dice_state.update(dice_state);
+                    }
                     _ => {}
                 }
             }
@@ -106,6 +115,10 @@ fn get_event_timestamp(event: &buck2_data::BuckEvent) -> Option<SystemTime> {
     SystemTime::try_from(event.timestamp.clone()?).ok()
 }
 
+fn to_std_duration(duration: &prost_types::Duration) -> std::time::Duration {
+    std::time::Duration::new(duration.seconds as u64, duration.nanos as u32)
+}
+
 impl Display for Stats {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(
@@ -158,8 +171,45 @@ impl Display for Stats {
                 HumanizedBytesPerSecond::fixed_width(re_avg_upload_speed)
             )?;
         }
+        if let Some(test_scheduling) = &self.test_scheduling {
+            match (
+                &test_scheduling.actual_wall_time,
+                &test_scheduling.estimated_wall_time,
+            ) {
+                (Some(actual), Some(estimated)) => writeln!(
+                    f,
+                    "test wall time: {} (estimated {} from {} of {} tests with history)",
+                    fmt_duration::fmt_duration(to_std_duration(actual), 1.0),
+                    fmt_duration::fmt_duration(to_std_duration(estimated), 1.0),
+                    test_scheduling.tests_with_history,
+                    test_scheduling.tests,
+                )?,
+                (Some(actual), None) => writeln!(
+                    f,
+                    "test wall time: {} ({} tests)",
+                    fmt_duration::fmt_duration(to_std_duration(actual), 1.0),
+                    test_scheduling.tests,
+                )?,
+                _ => {}
+            }
+        }
+        // The dice tracker is created for each command, so its counts are for this command only.
+        let mut early_cutoffs = self.dice_state.early_cutoffs().peekable();
+        if early_cutoffs.peek().is_some() {
+            writeln!(f, "dice early cutoffs in this command:")?;
+            for (key_type, cutoffs, recomputations) in early_cutoffs {
+                writeln!(
+                    f,
+                    "  {}: {} of {} recomputations ({:.1}%)",
+                    key_type,
+                    cutoffs,
+                    recomputations,
+                    100.0 * cutoffs as f64 / recomputations as f64
+                )?;
+            }
+        }
         if let Some(duration) = &self.duration {
-            let duration = std::time::Duration::new(duration.seconds as u64, duration.nanos as u32);
+            let duration = to_std_duration(duration);
             writeln!(f, "duration: {}", fmt_duration::fmt_duration(duration, 1.0))
         } else {
             // TODO(ezgi): when there is no CommandEnd, take the timestamp from the last event and calculate the duration
@@ -11,4 +11,5 @@ pub mod aquery;
 pub(crate) mod common;
 pub mod cquery;
 pub(crate) mod profile;
+pub mod shell;
 pub mod uquery;
@@ -53,6 +53,10 @@ List all the commands for run actions for building a target
 
 `buck2 aquery 'kind(run, deps("//java/com/example/app:amazing+more"))' --output-attribute=cmd`
 
+Print the full command lines and inputs of the actions which read a file
+
+`buck2 aquery 'actions_consuming(java/com/example/app/Main.java, all_actions(//java/com/example/app:amazing))' --output-actions`
+
 Dynamic outputs (`ctx.actions.dynamic_output`):
 
 Currently, aquery interacts poorly with dynamic outputs. It may
@@ -72,6 +76,13 @@ pub struct AqueryCommand {
     #[clap(flatten)]
     query_common: CommonQueryOptions,
 
+    /// Print the actions in the result as JSON, with their inputs, outputs and, for `run`
+    /// actions, the expanded command line and environment. Since aquery does not build anything,
+    /// input digests are only known for source files and outputs which are already materialized;
+    /// the rest are `unknown`.
+    #[clap(long, conflicts_with = "output_attribute_flags")]
+    output_actions: bool,
+
     #[clap(flatten)]
     target_cfg: TargetCfgOptions,
 
@@ -89,7 +100,7 @@ impl StreamingCommand for AqueryCommand {
         matches: &clap::ArgMatches,
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
-        let (query, query_args) = self.query_common.get_query();
+        let (query, query_args) = self.query_common.get_query(ctx)?;
         let unstable_output_format = self.query_common.output_format() as i32;
         let output_attributes = self.query_common.attributes.get()?;
         let context = ctx.client_context(matches, &self)?;
@@ -104,6 +115,8 @@ impl StreamingCommand for AqueryCommand {
                     context: Some(context),
                     output_attributes,
                     unstable_output_format,
+                    explain: self.query_common.explain,
+                    output_actions: self.output_actions,
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
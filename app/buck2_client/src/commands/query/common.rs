@@ -7,8 +7,13 @@
  * of this source tree.
  */
 
+use anyhow::Context;
 use buck2_cli_proto::QueryOutputFormat;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::path_arg::PathArg;
 use buck2_client_ctx::query_args::CommonAttributeArgs;
+use buck2_core::fs::fs_util;
+use buck2_query_parser::macros::QueryMacros;
 use buck2_query_parser::placeholder::QUERY_PERCENT_SS_PLACEHOLDER;
 use dupe::Dupe;
 
@@ -21,11 +26,28 @@ use dupe::Dupe;
     serde::Deserialize
 )]
 #[clap(rename_all = "snake_case")]
-enum QueryOutputFormatArg {
+pub(crate) enum QueryOutputFormatArg {
     Dot,
     Json,
     DotCompact,
     Starlark,
+    Ndjson,
+    Graphml,
+    Mermaid,
+}
+
+impl From<QueryOutputFormatArg> for QueryOutputFormat {
+    fn from(output_format: QueryOutputFormatArg) -> Self {
+        match output_format {
+            QueryOutputFormatArg::Json => QueryOutputFormat::Json,
+            QueryOutputFormatArg::Dot => QueryOutputFormat::Dot,
+            QueryOutputFormatArg::DotCompact => QueryOutputFormat::DotCompact,
+            QueryOutputFormatArg::Starlark => QueryOutputFormat::Starlark,
+            QueryOutputFormatArg::Ndjson => QueryOutputFormat::Ndjson,
+            QueryOutputFormatArg::Graphml => QueryOutputFormat::Graphml,
+            QueryOutputFormatArg::Mermaid => QueryOutputFormat::Mermaid,
+        }
+    }
 }
 
 /// Args common to all the query commands
@@ -55,9 +77,12 @@ pub(crate) struct CommonQueryOptions {
            dot -  dot graph format. \n
            dot_compact - compact alternative to dot format. \n
            json - JSON format. \n
-           starlark - targets are printed like starlark code that would produce them.
+           starlark - targets are printed like starlark code that would produce them. \n
+           ndjson - one JSON object per target per line. The query is evaluated first, then each target is printed as soon as its attributes (and providers) are ready. \n
+           graphml - GraphML graph format. \n
+           mermaid - Mermaid flowchart format.
          ",
-        value_name = "dot|dot_compact|json|starlark",
+        value_name = "dot|dot_compact|json|starlark|ndjson|graphml|mermaid",
         value_enum
     )]
     output_format: Option<QueryOutputFormatArg>,
@@ -67,6 +92,25 @@ pub(crate) struct CommonQueryOptions {
         help = "list of literals for a multi-query (one containing `%s` or `%Ss`)"
     )]
     query_args: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "PATH",
+        help = "File of named query macros, which the query can refer to as `$NAME`",
+        long_help = "File of named query macros, which the query can refer to as `$NAME`. \n
+           Each macro is defined as `NAME = EXPR`, and may refer to the macros before it. \n
+           Lines starting with `#` are comments."
+    )]
+    query_macros: Option<PathArg>,
+
+    #[clap(
+        long,
+        help = "Print to stderr how long each part of the query took to evaluate",
+        long_help = "Print to stderr how long each part of the query took to evaluate. \n
+           For every expression this shows the inclusive wall time, the number of targets produced, \n
+           and how many packages and configured nodes had to be computed while it was evaluated."
+    )]
+    pub explain: bool,
 }
 
 impl CommonQueryOptions {
@@ -86,10 +130,7 @@ impl CommonQueryOptions {
 
     pub fn output_format(&self) -> QueryOutputFormat {
         match self.output_format {
-            Some(QueryOutputFormatArg::Json) => QueryOutputFormat::Json,
-            Some(QueryOutputFormatArg::Dot) => QueryOutputFormat::Dot,
-            Some(QueryOutputFormatArg::DotCompact) => QueryOutputFormat::DotCompact,
-            Some(QueryOutputFormatArg::Starlark) => QueryOutputFormat::Starlark,
+            Some(output_format) => output_format.into(),
             None => {
                 if self.json {
                     QueryOutputFormat::Json
@@ -104,8 +145,11 @@ impl CommonQueryOptions {
         }
     }
 
-    pub fn get_query(&self) -> (String, Vec<String>) {
-        if self.query.contains(QUERY_PERCENT_SS_PLACEHOLDER) {
+    pub fn get_query(
+        &self,
+        ctx: &ClientCommandContext<'_>,
+    ) -> anyhow::Result<(String, Vec<String>)> {
+        let (query, query_args) = if self.query.contains(QUERY_PERCENT_SS_PLACEHOLDER) {
             let replacement = Self::args_as_set(&self.query_args);
             (
                 self.query
@@ -114,6 +158,16 @@ impl CommonQueryOptions {
             )
         } else {
             (self.query.clone(), self.query_args.clone())
+        };
+
+        match &self.query_macros {
+            Some(path) => {
+                let macros = fs_util::read_to_string(path.resolve(&ctx.working_dir))?;
+                let macros = QueryMacros::parse(&macros)
+                    .with_context(|| format!("Error parsing query macros `{}`", path.display()))?;
+                Ok((macros.bind(&query)?, query_args))
+            }
+            None => Ok((query, query_args)),
         }
     }
 }
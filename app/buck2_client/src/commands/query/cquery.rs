@@ -63,6 +63,10 @@ List the deps of a target (special characters in a target will
 require quotes):
 
 `buck2 cquery 'deps("//java/com/example/app:amazing+more")'`
+
+Show the dependencies which differ between two platforms:
+
+`buck2 cquery --diff-target-platforms //platforms:linux,//platforms:macos 'deps(//java/com/example/app:amazing)'`
 "#
     )
 }
@@ -84,6 +88,17 @@ pub struct CqueryCommand {
     )]
     show_providers: bool,
 
+    /// Evaluate the query under each of the two given target platforms and print the targets
+    /// and dependency edges found under only one of them, matching targets by unconfigured label.
+    /// Cannot be combined with `--target-platforms`.
+    #[clap(
+        long,
+        value_delimiter = ',',
+        num_args = 1,
+        value_name = "PLATFORM,PLATFORM"
+    )]
+    diff_target_platforms: Vec<String>,
+
     #[clap(flatten)]
     target_cfg: TargetCfgWithUniverseOptions,
 
@@ -104,7 +119,7 @@ impl StreamingCommand for CqueryCommand {
         matches: &clap::ArgMatches,
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
-        let (query, query_args) = self.query_common.get_query();
+        let (query, query_args) = self.query_common.get_query(ctx)?;
         let unstable_output_format = self.query_common.output_format() as i32;
         let output_attributes = self.query_common.attributes.get()?;
         let context = ctx.client_context(matches, &self)?;
@@ -121,6 +136,8 @@ impl StreamingCommand for CqueryCommand {
                     target_cfg: Some(self.target_cfg.target_cfg.target_cfg()),
                     show_providers: self.show_providers,
                     unstable_output_format,
+                    explain: self.query_common.explain,
+                    diff_target_platforms: self.diff_target_platforms,
                     profile_mode: self.profile_options.profile_mode_proto().map(|m| m as i32),
                     profile_output: self
                         .profile_options
@@ -0,0 +1,669 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use buck2_cli_proto::query_shell_let;
+use buck2_cli_proto::query_shell_message;
+use buck2_cli_proto::query_shell_request;
+use buck2_cli_proto::AqueryRequest;
+use buck2_cli_proto::ClientContext;
+use buck2_cli_proto::CqueryRequest;
+use buck2_cli_proto::QueryOutputFormat;
+use buck2_cli_proto::QueryShellBound;
+use buck2_cli_proto::QueryShellComplete;
+use buck2_cli_proto::QueryShellLet;
+use buck2_cli_proto::QueryShellMessage;
+use buck2_cli_proto::QueryShellRequest;
+use buck2_cli_proto::QueryShellResponse;
+use buck2_cli_proto::TargetCfg;
+use buck2_cli_proto::UqueryRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::command_outcome::CommandOutcome;
+use buck2_client_ctx::common::target_cfg::TargetCfgWithUniverseOptions;
+use buck2_client_ctx::common::ui::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonEventLogOptions;
+use buck2_client_ctx::common::CommonStarlarkOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::events_ctx::PartialResultCtx;
+use buck2_client_ctx::events_ctx::PartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_common::invocation_roots::InvocationRoots;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_query_parser::macros::QueryMacros;
+use clap::ValueEnum;
+use dupe::Dupe;
+use futures::channel::mpsc::UnboundedSender;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::Editor;
+use rustyline::Helper;
+
+use crate::commands::complete::package::PackageCompleter;
+use crate::commands::complete::target::TargetCompleter;
+use crate::commands::complete::target::TargetResolver;
+use crate::commands::query::common::QueryOutputFormatArg;
+
+const HELP: &str = "\
+Enter a query to evaluate it, or one of:
+
+  let NAME = EXPR        evaluate EXPR and bind its result to `$NAME` in the
+                         following queries of the current mode
+  :mode [MODE]           show or switch the query mode (uquery, cquery or aquery)
+  :output [FORMAT]       show or switch the output format (default, json, dot, ...)
+  :help                  show this help
+  :quit                  exit the shell (or press Ctrl-D)
+
+Press Tab to complete target patterns.";
+
+#[derive(Debug, buck2_error::Error)]
+enum QueryShellError {
+    #[error("The query shell was disconnected from the daemon")]
+    Disconnected,
+    #[error("Unexpected reply from the daemon")]
+    UnexpectedReply,
+    #[error("Unknown shell command `{0}`, see `:help`")]
+    UnknownCommand(String),
+    #[error("Unknown query mode `{0}`, expected `uquery`, `cquery` or `aquery`")]
+    UnknownMode(String),
+    #[error("Unknown output format `{0}`")]
+    UnknownOutputFormat(String),
+}
+
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq, clap::ValueEnum)]
+#[clap(rename_all = "snake_case")]
+enum QueryShellMode {
+    Uquery,
+    Cquery,
+    Aquery,
+}
+
+impl QueryShellMode {
+    fn name(self) -> &'static str {
+        match self {
+            QueryShellMode::Uquery => "uquery",
+            QueryShellMode::Cquery => "cquery",
+            QueryShellMode::Aquery => "aquery",
+        }
+    }
+}
+
+/// Start an interactive query shell.
+///
+/// Each query sees the files as they are when it is entered, and only the build files
+/// changed since the previous query are loaded again. The results bound by `let` are kept
+/// as they were evaluated, and are separate for each query mode.
+#[derive(Debug, clap::Parser)]
+#[clap(name = "query-shell", verbatim_doc_comment)]
+pub struct QueryShellCommand {
+    /// The query mode the shell starts in. It can be switched with `:mode`.
+    #[clap(long, value_enum, default_value = "cquery")]
+    mode: QueryShellMode,
+
+    /// Used for the `cquery` and `aquery` modes.
+    #[clap(flatten)]
+    target_cfg: TargetCfgWithUniverseOptions,
+
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+}
+
+#[async_trait]
+impl StreamingCommand for QueryShellCommand {
+    const COMMAND_NAME: &'static str = "query-shell";
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(matches, &self)?;
+        let (requests, request_stream) = futures::channel::mpsc::unbounded();
+        let (replies_sender, replies) = mpsc::channel();
+
+        let session = Arc::new(QueryShellSession {
+            requests,
+            replies: Mutex::new(replies),
+        });
+        let shell = QueryShell {
+            session: session.dupe(),
+            context: context.clone(),
+            target_cfg: self.target_cfg.target_cfg.target_cfg(),
+            target_universe: self.target_cfg.target_universe,
+            mode: self.mode,
+            output_format: QueryOutputFormat::Default,
+        };
+        let helper = QueryShellHelper {
+            session,
+            context: context.clone(),
+            cwd: ctx.working_dir.path().to_owned(),
+            roots: ctx.paths()?.roots.clone(),
+        };
+        // Line editing blocks, so the shell runs on its own thread while this one streams the
+        // requests to the daemon.
+        let shell_thread = std::thread::Builder::new()
+            .name("query-shell".to_owned())
+            .spawn(move || shell.run(helper))?;
+
+        let mut partial_result_handler = QueryShellPartialResultHandler {
+            replies: replies_sender,
+        };
+        let QueryShellResponse {} = buckd
+            .with_flushing()
+            .query_shell(context, request_stream, &mut partial_result_handler)
+            .await??;
+
+        // The daemon ends the stream once the shell has dropped its side of it.
+        shell_thread
+            .join()
+            .map_err(|_| anyhow::anyhow!("Query shell thread panicked"))??;
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        // The shell owns the terminal.
+        CommonConsoleOptions::none_ref()
+    }
+
+    fn event_log_opts(&self) -> &CommonEventLogOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn build_config_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+
+    fn starlark_opts(&self) -> &CommonStarlarkOptions {
+        &self.common_opts.starlark_opts
+    }
+}
+
+/// The connection of the shell thread to the daemon.
+struct QueryShellSession {
+    requests: UnboundedSender<QueryShellRequest>,
+    replies: Mutex<mpsc::Receiver<QueryShellMessage>>,
+}
+
+impl QueryShellSession {
+    /// Send a request and wait for its reply. The daemon replies to each request with exactly one
+    /// message, in order.
+    fn send(
+        &self,
+        request: query_shell_request::Request,
+    ) -> anyhow::Result<query_shell_message::Message> {
+        let replies = self.replies.lock().unwrap();
+        self.requests
+            .unbounded_send(QueryShellRequest {
+                request: Some(request),
+            })
+            .map_err(|_| QueryShellError::Disconnected)?;
+        match replies.recv() {
+            Ok(QueryShellMessage {
+                message: Some(message),
+            }) => Ok(message),
+            _ => Err(QueryShellError::Disconnected.into()),
+        }
+    }
+}
+
+struct QueryShell {
+    session: Arc<QueryShellSession>,
+    context: ClientContext,
+    target_cfg: TargetCfg,
+    target_universe: Vec<String>,
+    mode: QueryShellMode,
+    output_format: QueryOutputFormat,
+}
+
+enum ShellAction {
+    Continue,
+    Quit,
+}
+
+/// A line entered in the shell.
+#[derive(Debug, PartialEq, Eq)]
+enum ShellLine {
+    Quit,
+    Help,
+    ShowMode,
+    SetMode(QueryShellMode),
+    ShowOutput,
+    SetOutput(QueryOutputFormat),
+    /// `let NAME = EXPR`, which the daemon evaluates in the current mode and keeps.
+    Let {
+        name: String,
+        query: String,
+    },
+    Query(String),
+}
+
+impl ShellLine {
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        if let Some(command) = line.strip_prefix(':') {
+            let (command, arg) = match command.split_once(char::is_whitespace) {
+                Some((command, arg)) => (command, arg.trim()),
+                None => (command, ""),
+            };
+            return Ok(match command {
+                "q" | "quit" | "exit" => ShellLine::Quit,
+                "h" | "help" => ShellLine::Help,
+                "mode" if arg.is_empty() => ShellLine::ShowMode,
+                "mode" => ShellLine::SetMode(
+                    QueryShellMode::from_str(arg, true)
+                        .map_err(|_| QueryShellError::UnknownMode(arg.to_owned()))?,
+                ),
+                "output" if arg.is_empty() => ShellLine::ShowOutput,
+                "output" if arg.eq_ignore_ascii_case("default") => {
+                    ShellLine::SetOutput(QueryOutputFormat::Default)
+                }
+                "output" => ShellLine::SetOutput(
+                    QueryOutputFormatArg::from_str(arg, true)
+                        .map_err(|_| QueryShellError::UnknownOutputFormat(arg.to_owned()))?
+                        .into(),
+                ),
+                _ => return Err(QueryShellError::UnknownCommand(command.to_owned()).into()),
+            });
+        }
+
+        // `let NAME = EXPR` without a body defines a binding, `let NAME = EXPR in BODY` is a
+        // regular query.
+        if let Some(definition) = line.strip_prefix("let ") {
+            if let Ok(definitions) = QueryMacros::parse(definition) {
+                let mut definitions = definitions.iter();
+                if let (Some((name, query)), None) = (definitions.next(), definitions.next()) {
+                    return Ok(ShellLine::Let {
+                        name: name.to_owned(),
+                        query: query.to_owned(),
+                    });
+                }
+            }
+        }
+
+        Ok(ShellLine::Query(line.to_owned()))
+    }
+}
+
+impl QueryShell {
+    fn run(mut self, helper: QueryShellHelper) -> anyhow::Result<()> {
+        let mut editor = Editor::<QueryShellHelper, DefaultHistory>::new()?;
+        editor.set_helper(Some(helper));
+        buck2_client_ctx::eprintln!("Type `:help` for help.")?;
+        loop {
+            let line = match editor.readline(&format!("{}> ", self.mode.name())) {
+                Ok(line) => line,
+                // Ctrl-C discards the current line, like in a shell.
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            editor.add_history_entry(line)?;
+            match self.eval(line) {
+                Ok(ShellAction::Continue) => {}
+                Ok(ShellAction::Quit) => break,
+                Err(e) => buck2_client_ctx::eprintln!("{:#}", e)?,
+            }
+        }
+        // Dropping the session closes the request stream, which ends the command.
+        Ok(())
+    }
+
+    fn eval(&mut self, line: &str) -> anyhow::Result<ShellAction> {
+        let request = match ShellLine::parse(line)? {
+            ShellLine::Quit => return Ok(ShellAction::Quit),
+            ShellLine::Help => {
+                buck2_client_ctx::eprintln!("{}", HELP)?;
+                return Ok(ShellAction::Continue);
+            }
+            ShellLine::ShowMode => {
+                buck2_client_ctx::eprintln!("{}", self.mode.name())?;
+                return Ok(ShellAction::Continue);
+            }
+            ShellLine::SetMode(mode) => {
+                self.mode = mode;
+                return Ok(ShellAction::Continue);
+            }
+            ShellLine::ShowOutput => {
+                buck2_client_ctx::eprintln!("{}", self.output_format.as_str_name().to_lowercase())?;
+                return Ok(ShellAction::Continue);
+            }
+            ShellLine::SetOutput(output_format) => {
+                self.output_format = output_format;
+                return Ok(ShellAction::Continue);
+            }
+            ShellLine::Let { name, query } => query_shell_request::Request::Let(QueryShellLet {
+                name,
+                query: Some(self.query(query)),
+            }),
+            ShellLine::Query(query) => match self.query(query) {
+                query_shell_let::Query::Uquery(req) => query_shell_request::Request::Uquery(req),
+                query_shell_let::Query::Cquery(req) => query_shell_request::Request::Cquery(req),
+                query_shell_let::Query::Aquery(req) => query_shell_request::Request::Aquery(req),
+            },
+        };
+
+        match self.session.send(request)? {
+            query_shell_message::Message::Stdout(stdout) => {
+                buck2_client_ctx::stdio::print_bytes(&stdout)?;
+                buck2_client_ctx::stdio::flush()?;
+            }
+            query_shell_message::Message::Error(error) => {
+                buck2_client_ctx::eprintln!("{}", error)?;
+            }
+            query_shell_message::Message::Bound(QueryShellBound { name, size }) => {
+                buck2_client_ctx::eprintln!(
+                    "Bound `${}` to {} results in {} mode",
+                    name,
+                    size,
+                    self.mode.name()
+                )?;
+            }
+            query_shell_message::Message::Completions(_) => {
+                return Err(QueryShellError::UnexpectedReply.into());
+            }
+        }
+        Ok(ShellAction::Continue)
+    }
+
+    /// The request to evaluate `query` in the current mode.
+    fn query(&self, query: String) -> query_shell_let::Query {
+        let context = Some(self.context.clone());
+        let unstable_output_format = self.output_format as i32;
+        match self.mode {
+            QueryShellMode::Uquery => query_shell_let::Query::Uquery(UqueryRequest {
+                context,
+                query,
+                unstable_output_format,
+                ..Default::default()
+            }),
+            QueryShellMode::Cquery => query_shell_let::Query::Cquery(CqueryRequest {
+                context,
+                query,
+                target_universe: self.target_universe.clone(),
+                target_cfg: Some(self.target_cfg.clone()),
+                unstable_output_format,
+                ..Default::default()
+            }),
+            QueryShellMode::Aquery => query_shell_let::Query::Aquery(AqueryRequest {
+                context,
+                query,
+                target_cfg: Some(self.target_cfg.clone()),
+                unstable_output_format,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// Completes target patterns with the same logic as `buck2 complete`, asking the daemon for the
+/// targets of a package through the shell session.
+struct QueryShellHelper {
+    session: Arc<QueryShellSession>,
+    context: ClientContext,
+    cwd: AbsNormPathBuf,
+    roots: InvocationRoots,
+}
+
+impl QueryShellHelper {
+    fn complete_pattern(&self, partial: &str) -> Vec<String> {
+        let outcome = futures::executor::block_on(async {
+            match partial.split_once(':') {
+                None => {
+                    PackageCompleter::new(&self.cwd, &self.roots)
+                        .await?
+                        .complete(partial)
+                        .await
+                }
+                Some((package, partial_target)) => {
+                    let mut resolver = SessionTargetResolver {
+                        session: &self.session,
+                        context: &self.context,
+                    };
+                    TargetCompleter::new(&self.cwd, &self.roots, &mut resolver)
+                        .await?
+                        .complete(package, partial_target)
+                        .await
+                }
+            }
+        });
+        match outcome {
+            CommandOutcome::Success(completions) => completions,
+            CommandOutcome::Failure(_) => Vec::new(),
+        }
+    }
+}
+
+impl Completer for QueryShellHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = word_start(line, pos);
+        let partial = &line[start..pos];
+        if partial.is_empty() || (start == 0 && partial.starts_with(':')) {
+            return Ok((pos, Vec::new()));
+        }
+        Ok((start, self.complete_pattern(partial)))
+    }
+}
+
+impl Hinter for QueryShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for QueryShellHelper {}
+
+impl Validator for QueryShellHelper {}
+
+impl Helper for QueryShellHelper {}
+
+/// Start of the word under the cursor, which is after the last separator of the query language.
+fn word_start(line: &str, pos: usize) -> usize {
+    line[..pos]
+        .rfind(|c: char| c.is_whitespace() || "(),'\"".contains(c))
+        .map_or(0, |i| i + 1)
+}
+
+struct SessionTargetResolver<'a> {
+    session: &'a QueryShellSession,
+    context: &'a ClientContext,
+}
+
+impl<'a> TargetResolver for SessionTargetResolver<'a> {
+    fn resolve(&mut self, partial_target: String) -> BoxFuture<CommandOutcome<Vec<String>>> {
+        let request = query_shell_request::Request::Complete(QueryShellComplete {
+            partial_target,
+            context: Some(self.context.clone()),
+        });
+        let outcome = match self.session.send(request) {
+            Ok(query_shell_message::Message::Completions(completions)) => {
+                CommandOutcome::Success(completions.completions)
+            }
+            Ok(_) => CommandOutcome::Success(Vec::new()),
+            Err(e) => CommandOutcome::Failure(ExitResult::err(e)),
+        };
+        futures::future::ready(outcome).boxed()
+    }
+}
+
+/// Forwards the replies of the daemon to the shell thread.
+struct QueryShellPartialResultHandler {
+    replies: mpsc::Sender<QueryShellMessage>,
+}
+
+#[async_trait]
+impl PartialResultHandler for QueryShellPartialResultHandler {
+    type PartialResult = QueryShellMessage;
+
+    async fn handle_partial_result(
+        &mut self,
+        _ctx: PartialResultCtx<'_, '_>,
+        partial_res: Self::PartialResult,
+    ) -> anyhow::Result<()> {
+        // The shell has exited if the receiver is gone, in which case the reply is not needed.
+        let _ignored = self.replies.send(partial_res);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::mpsc::UnboundedReceiver;
+
+    use super::*;
+
+    /// A shell whose session is not connected to a daemon: the test sends the replies up front
+    /// and checks the requests afterwards.
+    fn test_shell(
+        mode: QueryShellMode,
+    ) -> (
+        QueryShell,
+        UnboundedReceiver<QueryShellRequest>,
+        mpsc::Sender<QueryShellMessage>,
+    ) {
+        let (requests, request_stream) = futures::channel::mpsc::unbounded();
+        let (replies_sender, replies) = mpsc::channel();
+        let shell = QueryShell {
+            session: Arc::new(QueryShellSession {
+                requests,
+                replies: Mutex::new(replies),
+            }),
+            context: ClientContext::default(),
+            target_cfg: TargetCfg::default(),
+            target_universe: Vec::new(),
+            mode,
+            output_format: QueryOutputFormat::Default,
+        };
+        (shell, request_stream, replies_sender)
+    }
+
+    fn reply(replies: &mpsc::Sender<QueryShellMessage>, message: query_shell_message::Message) {
+        replies
+            .send(QueryShellMessage {
+                message: Some(message),
+            })
+            .unwrap();
+    }
+
+    fn next_request(
+        requests: &mut UnboundedReceiver<QueryShellRequest>,
+    ) -> query_shell_request::Request {
+        requests.try_next().unwrap().unwrap().request.unwrap()
+    }
+
+    #[test]
+    fn test_parse_line() -> anyhow::Result<()> {
+        assert_eq!(ShellLine::Quit, ShellLine::parse(":q")?);
+        assert_eq!(ShellLine::ShowMode, ShellLine::parse(":mode")?);
+        assert_eq!(
+            ShellLine::SetMode(QueryShellMode::Aquery),
+            ShellLine::parse(":mode  aquery")?
+        );
+        assert!(ShellLine::parse(":mode bquery").is_err());
+        assert_eq!(
+            ShellLine::SetOutput(QueryOutputFormat::Default),
+            ShellLine::parse(":output default")?
+        );
+        assert!(ShellLine::parse(":frobnicate").is_err());
+        assert_eq!(
+            ShellLine::Let {
+                name: "libs".to_owned(),
+                query: "deps(//lib:a)".to_owned()
+            },
+            ShellLine::parse("let libs = deps(//lib:a)")?
+        );
+        // A `let` with a body is a regular query.
+        assert_eq!(
+            ShellLine::Query("let x = //a:b in deps($x)".to_owned()),
+            ShellLine::parse("let x = //a:b in deps($x)")?
+        );
+        assert_eq!(
+            ShellLine::Query("deps($libs)".to_owned()),
+            ShellLine::parse("deps($libs)")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_let_and_mode() -> anyhow::Result<()> {
+        let (mut shell, mut requests, replies) = test_shell(QueryShellMode::Cquery);
+
+        // `let` is evaluated by the daemon, in the current mode, rather than kept as text.
+        reply(
+            &replies,
+            query_shell_message::Message::Bound(QueryShellBound {
+                name: "x".to_owned(),
+                size: 1,
+            }),
+        );
+        shell.eval("let x = deps(//a:b)")?;
+        match next_request(&mut requests) {
+            query_shell_request::Request::Let(QueryShellLet {
+                name,
+                query: Some(query_shell_let::Query::Cquery(req)),
+            }) => {
+                assert_eq!("x", name);
+                assert_eq!("deps(//a:b)", req.query);
+                assert!(req.target_cfg.is_some());
+            }
+            request => panic!("unexpected request: {:?}", request),
+        }
+
+        // Switching modes only changes the kind of the following requests.
+        shell.eval(":mode uquery")?;
+        assert_eq!(QueryShellMode::Uquery, shell.mode);
+        assert!(requests.try_next().is_err());
+
+        // Queries are sent as entered: the daemon resolves `$x`.
+        reply(&replies, query_shell_message::Message::Stdout(Vec::new()));
+        shell.eval("$x + //c:d")?;
+        match next_request(&mut requests) {
+            query_shell_request::Request::Uquery(req) => assert_eq!("$x + //c:d", req.query),
+            request => panic!("unexpected request: {:?}", request),
+        }
+
+        reply(&replies, query_shell_message::Message::Stdout(Vec::new()));
+        shell.eval(":mode aquery")?;
+        shell.eval("//c:d")?;
+        assert!(matches!(
+            next_request(&mut requests),
+            query_shell_request::Request::Aquery(_)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_word_start() {
+        assert_eq!(0, word_start("//foo:b", 7));
+        assert_eq!(5, word_start("deps(//foo:b", 12));
+        assert_eq!(10, word_start("deps(//a, //foo", 15));
+        assert_eq!(6, word_start("deps(\"//foo", 11));
+        assert_eq!(5, word_start("deps(", 5));
+    }
+}
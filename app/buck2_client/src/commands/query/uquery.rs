@@ -98,7 +98,7 @@ impl StreamingCommand for UqueryCommand {
         matches: &clap::ArgMatches,
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
-        let (query, query_args) = self.query_common.get_query();
+        let (query, query_args) = self.query_common.get_query(ctx)?;
         let unstable_output_format = self.query_common.output_format() as i32;
         let output_attributes = self.query_common.attributes.get()?;
         let context = ctx.client_context(matches, &self)?;
@@ -112,6 +112,7 @@ impl StreamingCommand for UqueryCommand {
                     context: Some(context),
                     output_attributes,
                     unstable_output_format,
+                    explain: self.query_common.explain,
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
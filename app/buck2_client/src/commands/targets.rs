@@ -119,6 +119,14 @@ pub struct TargetsCommand {
     #[clap(long)]
     stats: bool,
 
+    /// Print targets and the dependencies between them as a GraphML graph
+    #[clap(long, conflicts_with_all = ["json", "json_lines", "stats", "mermaid"])]
+    graphml: bool,
+
+    /// Print targets and the dependencies between them as a Mermaid flowchart
+    #[clap(long, conflicts_with_all = ["json", "json_lines", "stats"])]
+    mermaid: bool,
+
     /// Print the fully-qualified build target for the specified aliases
     #[clap(long, alias = "resolvealias")]
     resolve_alias: bool,
@@ -248,6 +256,15 @@ impl TargetsCommand {
                 return Err(TargetsError::IncompatibleArguments.into());
             }
             Ok(OutputFormat::JsonLines)
+        } else if self.graphml || self.mermaid {
+            if self.stats || (self.graphml && self.mermaid) || !self.attributes.get()?.is_empty() {
+                return Err(TargetsError::IncompatibleArguments.into());
+            }
+            if self.graphml {
+                Ok(OutputFormat::Graphml)
+            } else {
+                Ok(OutputFormat::Mermaid)
+            }
         } else if !self.attributes.get()?.is_empty() {
             Ok(OutputFormat::Json)
         } else if self.package_values || !self.package_values_regex.is_empty() {
@@ -7,8 +7,12 @@
  * of this source tree.
  */
 
+use std::io::Write;
+use std::path::Path;
+
 use anyhow::Context;
 use async_trait::async_trait;
+use buck2_cli_proto::test_request::ChangedFiles;
 use buck2_cli_proto::CounterWithExamples;
 use buck2_cli_proto::TestRequest;
 use buck2_cli_proto::TestSessionOptions;
@@ -31,9 +35,15 @@ use buck2_client_ctx::streaming::StreamingCommand;
 use buck2_client_ctx::subscribers::superconsole::test::span_from_build_failure_count;
 use buck2_client_ctx::subscribers::superconsole::test::TestCounterColumn;
 use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_path::AbsPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::working_dir::WorkingDir;
+use buck2_util::process::async_background_command;
+use dupe::Dupe;
 use superconsole::Line;
 use superconsole::Span;
+use tokio::io::AsyncReadExt;
 
 use crate::commands::build::print_build_result;
 
@@ -46,6 +56,104 @@ fn forward_output_to_path(
         .context("Failed to write test executor output to path")
 }
 
+/// Resolve `--changed-since` into a list of project-relative paths.
+async fn read_changed_files(
+    changed_since: &str,
+    ctx: &mut ClientCommandContext<'_>,
+) -> anyhow::Result<Vec<String>> {
+    let project_root = ctx.paths()?.project_root().dupe();
+
+    let paths = if changed_since == "-" {
+        let mut listing = String::new();
+        ctx.stdin()
+            .read_to_string(&mut listing)
+            .await
+            .context("Error reading changed files from stdin")?;
+        resolve_listing(&listing, |p| ctx.working_dir.resolve(p))
+    } else if let Some(path) = changed_since.strip_prefix('@') {
+        let listing = fs_util::read_to_string(ctx.working_dir.resolve(Path::new(path)))
+            .context("Error reading changed files")?;
+        resolve_listing(&listing, |p| ctx.working_dir.resolve(p))
+    } else {
+        let listing = changed_files_from_source_control(&project_root, changed_since).await?;
+        resolve_listing(&listing, |p| project_root.root().as_abs_path().join(p))
+    };
+
+    paths
+        .into_iter()
+        .map(|p| Ok(project_root.relativize_any(&p)?.to_string()))
+        .collect()
+}
+
+fn resolve_listing(listing: &str, resolve: impl Fn(&Path) -> AbsPathBuf) -> Vec<AbsPathBuf> {
+    listing
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| resolve(Path::new(l)))
+        .collect()
+}
+
+/// List files changed since `rev`, relative to the project root. This includes files that are not
+/// tracked by source control yet (but not ignored ones).
+async fn changed_files_from_source_control(
+    project_root: &ProjectRoot,
+    rev: &str,
+) -> anyhow::Result<String> {
+    let root = project_root.root();
+    if fs_util::try_exists(root.join(ForwardRelativePath::new(".hg")?))? {
+        // `hg status` lists unknown files alongside the changed ones.
+        run_source_control(
+            project_root,
+            rev,
+            "hg",
+            &["status", "--no-status", "--rev", rev, "."],
+        )
+        .await
+    } else {
+        let mut listing = run_source_control(
+            project_root,
+            rev,
+            "git",
+            &["diff", "--name-only", "--relative", rev],
+        )
+        .await?;
+        listing.push('\n');
+        listing.push_str(
+            &run_source_control(
+                project_root,
+                rev,
+                "git",
+                &["ls-files", "--others", "--exclude-standard"],
+            )
+            .await?,
+        );
+        Ok(listing)
+    }
+}
+
+async fn run_source_control(
+    project_root: &ProjectRoot,
+    rev: &str,
+    program: &str,
+    args: &[&str],
+) -> anyhow::Result<String> {
+    let output = async_background_command(program)
+        .args(args)
+        .current_dir(project_root.root().as_path())
+        .output()
+        .await
+        .context("Error running source control to compute changed files")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to compute files changed since `{}`: {}",
+            rev,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    String::from_utf8(output.stdout).context("Source control output is not UTF-8")
+}
+
 fn print_error_counter(
     console: &FinalConsole,
     counter: &CounterWithExamples,
@@ -108,6 +216,15 @@ If include patterns are present, regardless of whether exclude patterns are pres
     #[clap(long, group = "re_options", alias = "unstable-force-tests-on-re")]
     unstable_allow_all_tests_on_re: bool,
 
+    /// Collect native coverage from tests.
+    ///
+    /// Tests get `LLVM_PROFILE_FILE` pointing into their undeclared outputs directory. Once all
+    /// tests have finished, raw profiles are merged into a single LCOV report by the tool set in
+    /// `test.coverage_merger`. The report is written next to the build report if one was
+    /// requested, and otherwise in the test output directory.
+    #[clap(long)]
+    coverage: bool,
+
     // NOTE: the field below is given a different name from the test runner's `timeout` to avoid
     // confusion between the two parameters.
     /// How long to execute tests for. If the timeout is exceeded, Buck2 will exit
@@ -146,6 +263,23 @@ If include patterns are present, regardless of whether exclude patterns are pres
     #[clap(long)]
     ignore_tests_attribute: bool,
 
+    /// Only run tests affected by a set of changed files.
+    ///
+    /// The target patterns are used as the universe: tests are selected if they (transitively)
+    /// depend on a target owning one of the changed files, or are listed in the `tests` attribute
+    /// of such a target.
+    ///
+    /// --changed-since=- reads a newline-separated list of files from stdin
+    ///
+    /// --changed-since=@FILEPATH reads a newline-separated list of files from the provided path
+    ///
+    /// --changed-since=REV asks source control for files changed since the provided revision,
+    /// including untracked files that are not ignored
+    ///
+    /// Paths read from stdin or a file are relative to the current directory.
+    #[clap(long, value_name = "REV_OR_FILE_LIST")]
+    changed_since: Option<String>,
+
     /// Writes the test executor stderr to the provided path
     ///
     /// --test-executor-stderr=- will write to stderr
@@ -195,6 +329,12 @@ impl StreamingCommand for TestCommand {
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
         let context = ctx.client_context(matches, &self)?;
+        let changed_files = match &self.changed_since {
+            Some(changed_since) => Some(ChangedFiles {
+                paths: read_changed_files(changed_since, ctx).await?,
+            }),
+            None => None,
+        };
         let response = buckd
             .with_flushing()
             .test(
@@ -215,6 +355,7 @@ impl StreamingCommand for TestCommand {
                             || self.unstable_allow_all_tests_on_re,
                         force_use_project_relative_paths: self.unstable_allow_all_tests_on_re,
                         force_run_from_project_root: self.unstable_allow_all_tests_on_re,
+                        coverage: self.coverage,
                     }),
                     timeout: self
                         .timeout
@@ -225,6 +366,7 @@ impl StreamingCommand for TestCommand {
                         .transpose()
                         .context("Invalid `timeout`")?,
                     ignore_tests_attribute: self.ignore_tests_attribute,
+                    changed_files,
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
@@ -294,6 +436,16 @@ impl StreamingCommand for TestCommand {
             console.print_stderr(message.as_str())?;
         }
 
+        for outputs in &response.undeclared_outputs {
+            for path in &outputs.paths {
+                console.print_stderr(&format!("Test outputs for {}: {}", outputs.target, path))?;
+            }
+        }
+
+        if let Some(coverage_report) = &response.coverage_report {
+            console.print_stderr(&format!("Coverage report: {}", coverage_report))?;
+        }
+
         match self.test_executor_stderr {
             Some(OutputDestinationArg::Path(path)) => {
                 forward_output_to_path(&response.executor_stderr, &path, &ctx.working_dir)?;
@@ -316,16 +468,24 @@ impl StreamingCommand for TestCommand {
             ExitResult::bail("Test executor did not provide an exit code")
         };
 
+        let mut stdout = Vec::new();
+
+        if let Some(build_report) = response.serialized_build_report {
+            stdout.extend(build_report.as_bytes());
+            writeln!(&mut stdout)?;
+        }
+
         match self.test_executor_stdout {
             Some(OutputDestinationArg::Path(path)) => {
                 forward_output_to_path(&response.executor_stdout, &path, &ctx.working_dir)?;
-                exit_result
             }
             Some(OutputDestinationArg::Stream) => {
-                exit_result.with_stdout(response.executor_stdout.into_bytes())
+                stdout.extend(response.executor_stdout.into_bytes());
             }
-            _ => exit_result,
+            _ => {}
         }
+
+        exit_result.with_stdout(stdout)
     }
 
     fn console_opts(&self) -> &CommonConsoleOptions {
@@ -558,6 +558,12 @@ impl<'a, 'b> FlushingBuckdClient<'a, 'b> {
 
     bidirectional_stream_method!(lsp, LspRequest, LspResponse, LspMessage);
     bidirectional_stream_method!(dap, DapRequest, DapResponse, DapMessage);
+    bidirectional_stream_method!(
+        query_shell,
+        QueryShellRequest,
+        QueryShellResponse,
+        QueryShellMessage
+    );
     bidirectional_stream_method!(
         subscription,
         SubscriptionRequestWrapper,
@@ -584,6 +590,16 @@ impl<'a, 'b> FlushingBuckdClient<'a, 'b> {
         UnstableDiceDumpRequest,
         UnstableDiceDumpResponse
     );
+    debug_method!(
+        unstable_dice_why,
+        UnstableDiceWhyRequest,
+        UnstableDiceWhyResponse
+    );
+    debug_method!(
+        unstable_dice_record,
+        UnstableDiceRecordRequest,
+        UnstableDiceRecordResponse
+    );
 
     wrap_method!(status(snapshot: bool), StatusResponse);
     wrap_method!(set_log_filter(log_filter: SetLogFilterRequest), ());
@@ -33,6 +33,7 @@ pub mod file_tailer;
 pub mod final_console;
 pub mod ide_support;
 pub mod immediate_config;
+pub mod otlp;
 pub mod output_destination_arg;
 pub mod path_arg;
 pub mod query_args;
@@ -0,0 +1,353 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Converts buck2 spans into OTLP spans.
+//!
+//! Only command, analysis, action and RE spans are exported. Exported spans are parented to their
+//! nearest exported ancestor, so e.g. an RE execution is a child of the action it ran for.
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context;
+use buck2_data::buck_event::Data;
+use buck2_data::executor_stage_start::Stage;
+use buck2_data::span_end_event;
+use buck2_data::span_start_event;
+use buck2_event_observer::display;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_events::span::SpanId;
+use buck2_events::BuckEvent;
+use buck2_otlp_proto::opentelemetry::proto::common::v1::any_value;
+use buck2_otlp_proto::opentelemetry::proto::common::v1::AnyValue;
+use buck2_otlp_proto::opentelemetry::proto::common::v1::KeyValue;
+use buck2_otlp_proto::opentelemetry::proto::trace::v1::span::SpanKind;
+use buck2_otlp_proto::opentelemetry::proto::trace::v1::status::StatusCode;
+use buck2_otlp_proto::opentelemetry::proto::trace::v1::Span;
+use buck2_otlp_proto::opentelemetry::proto::trace::v1::Status;
+
+pub struct OtlpSpanConverter {
+    command_name: String,
+    /// Exported spans which have started but not yet ended.
+    open: HashMap<SpanId, OpenSpan>,
+    /// Spans which aren't exported, mapped to their nearest exported ancestor.
+    hidden: HashMap<SpanId, Option<SpanId>>,
+}
+
+struct OpenSpan {
+    trace_id: Vec<u8>,
+    parent: Option<SpanId>,
+    name: String,
+    start: SystemTime,
+    attributes: Vec<KeyValue>,
+}
+
+impl OtlpSpanConverter {
+    /// `command_name` names the span of the command, e.g. `build`.
+    pub fn new(command_name: &str) -> Self {
+        Self {
+            command_name: command_name.to_owned(),
+            open: HashMap::new(),
+            hidden: HashMap::new(),
+        }
+    }
+
+    /// Returns the OTLP span when `event` ends an exported span.
+    pub fn handle_event(&mut self, event: &BuckEvent) -> anyhow::Result<Option<Span>> {
+        let Some(span_id) = event.span_id() else {
+            return Ok(None);
+        };
+
+        match event.data() {
+            Data::SpanStart(start) => {
+                let parent = self.exported_ancestor(event.parent_id());
+                match self.describe_start(start.data.as_ref())? {
+                    Some((name, attributes)) => {
+                        self.open.insert(
+                            span_id,
+                            OpenSpan {
+                                trace_id: trace_id(event)?,
+                                parent,
+                                name,
+                                start: event.timestamp(),
+                                attributes,
+                            },
+                        );
+                    }
+                    None => {
+                        self.hidden.insert(span_id, parent);
+                    }
+                }
+                Ok(None)
+            }
+            Data::SpanEnd(end) => {
+                if self.hidden.remove(&span_id).is_some() {
+                    return Ok(None);
+                }
+                let Some(open) = self.open.remove(&span_id) else {
+                    // Started before we were listening.
+                    return Ok(None);
+                };
+
+                let mut attributes = open.attributes;
+                let failed = describe_end(end.data.as_ref(), &mut attributes);
+                Ok(Some(Span {
+                    trace_id: open.trace_id,
+                    span_id: span_id_bytes(span_id),
+                    parent_span_id: open.parent.map(span_id_bytes).unwrap_or_default(),
+                    name: open.name,
+                    kind: SpanKind::Internal as i32,
+                    start_time_unix_nano: unix_nanos(open.start),
+                    end_time_unix_nano: unix_nanos(event.timestamp()),
+                    attributes,
+                    status: failed.then(|| Status {
+                        message: String::new(),
+                        code: StatusCode::Error as i32,
+                    }),
+                    ..Default::default()
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn exported_ancestor(&self, parent: Option<SpanId>) -> Option<SpanId> {
+        let parent = parent?;
+        if self.open.contains_key(&parent) {
+            Some(parent)
+        } else {
+            self.hidden.get(&parent).copied().flatten()
+        }
+    }
+
+    /// The name and attributes of the span, if it is exported.
+    fn describe_start(
+        &self,
+        start: Option<&span_start_event::Data>,
+    ) -> anyhow::Result<Option<(String, Vec<KeyValue>)>> {
+        let opts = TargetDisplayOptions::for_log();
+        Ok(match start {
+            Some(span_start_event::Data::Command(_)) => Some((
+                format!("buck2 {}", self.command_name),
+                vec![string_attribute("buck2.command", &self.command_name)],
+            )),
+            Some(span_start_event::Data::Analysis(analysis)) => {
+                let target = display::display_analysis_target(
+                    analysis.target.as_ref().context("Missing `target`")?,
+                    opts,
+                )?;
+                Some((
+                    format!("analysis {}", target),
+                    vec![
+                        string_attribute("buck2.target", target),
+                        string_attribute("buck2.rule", &analysis.rule),
+                    ],
+                ))
+            }
+            Some(span_start_event::Data::ActionExecution(action)) => {
+                let identity = display::display_action_identity(
+                    action.key.as_ref(),
+                    action.name.as_ref(),
+                    opts,
+                )?;
+                let mut attributes = Vec::new();
+                if let Some(name) = &action.name {
+                    attributes.push(string_attribute("buck2.category", &name.category));
+                    attributes.push(string_attribute("buck2.identifier", &name.identifier));
+                }
+                Some((identity, attributes))
+            }
+            Some(span_start_event::Data::ExecutorStage(stage)) => match &stage.stage {
+                Some(stage @ (Stage::Re(..) | Stage::CacheQuery(..) | Stage::CacheHit(..))) => {
+                    display::display_executor_stage(stage).map(|name| (name.to_owned(), vec![]))
+                }
+                _ => None,
+            },
+            Some(span_start_event::Data::ReUpload(..)) => Some(("re_upload".to_owned(), vec![])),
+            _ => None,
+        })
+    }
+}
+
+/// Adds the attributes known at the end of the span, and returns whether it failed.
+fn describe_end(end: Option<&span_end_event::Data>, attributes: &mut Vec<KeyValue>) -> bool {
+    match end {
+        Some(span_end_event::Data::Command(command)) => {
+            attributes.push(bool_attribute("buck2.success", command.is_success));
+            !command.is_success
+        }
+        Some(span_end_event::Data::ActionExecution(action)) => {
+            if let Some(kind) = buck2_data::ActionExecutionKind::from_i32(action.execution_kind) {
+                attributes.push(string_attribute("buck2.execution_kind", kind.as_str_name()));
+            }
+            action.failed
+        }
+        Some(span_end_event::Data::ReUpload(upload)) => {
+            if let Some(digests) = upload.digests_uploaded {
+                attributes.push(int_attribute("buck2.digests_uploaded", digests as i64));
+            }
+            if let Some(bytes) = upload.bytes_uploaded {
+                attributes.push(int_attribute("buck2.bytes_uploaded", bytes as i64));
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn string_attribute(key: &str, value: impl Into<String>) -> KeyValue {
+    attribute(key, any_value::Value::StringValue(value.into()))
+}
+
+fn bool_attribute(key: &str, value: bool) -> KeyValue {
+    attribute(key, any_value::Value::BoolValue(value))
+}
+
+fn int_attribute(key: &str, value: i64) -> KeyValue {
+    attribute(key, any_value::Value::IntValue(value))
+}
+
+fn attribute(key: &str, value: any_value::Value) -> KeyValue {
+    KeyValue {
+        key: key.to_owned(),
+        value: Some(AnyValue { value: Some(value) }),
+    }
+}
+
+/// OTLP trace ids are the 16 bytes of our trace id, which is a UUID.
+fn trace_id(event: &BuckEvent) -> anyhow::Result<Vec<u8>> {
+    let trace_id = event.trace_id()?.to_string().replace('-', "");
+    hex::decode(&trace_id).with_context(|| format!("Invalid trace id `{}`", trace_id))
+}
+
+fn span_id_bytes(span_id: SpanId) -> Vec<u8> {
+    span_id.0.get().to_be_bytes().to_vec()
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use buck2_wrapper_common::invocation_id::TraceId;
+    use dupe::Dupe;
+
+    use super::*;
+
+    fn event(
+        trace_id: &TraceId,
+        secs: u64,
+        span_id: u64,
+        parent_id: u64,
+        data: buck2_data::buck_event::Data,
+    ) -> BuckEvent {
+        BuckEvent::new(
+            UNIX_EPOCH + Duration::from_secs(secs),
+            trace_id.dupe(),
+            SpanId::from_u64_opt(span_id),
+            SpanId::from_u64_opt(parent_id),
+            data,
+        )
+    }
+
+    fn start(data: span_start_event::Data) -> buck2_data::buck_event::Data {
+        Data::SpanStart(buck2_data::SpanStartEvent { data: Some(data) })
+    }
+
+    fn end(data: span_end_event::Data) -> buck2_data::buck_event::Data {
+        Data::SpanEnd(buck2_data::SpanEndEvent {
+            data: Some(data),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_spans_are_parented_to_exported_ancestors() -> anyhow::Result<()> {
+        let trace_id = TraceId::new();
+        let mut converter = OtlpSpanConverter::new("build");
+
+        let command = start(span_start_event::Data::Command(Default::default()));
+        // Not exported, so the upload below is parented to the command.
+        let load = start(span_start_event::Data::Load(Default::default()));
+        let upload = start(span_start_event::Data::ReUpload(Default::default()));
+
+        assert_eq!(
+            None,
+            converter.handle_event(&event(&trace_id, 1, 1, 0, command))?
+        );
+        assert_eq!(
+            None,
+            converter.handle_event(&event(&trace_id, 2, 2, 1, load))?
+        );
+        assert_eq!(
+            None,
+            converter.handle_event(&event(&trace_id, 3, 3, 2, upload))?
+        );
+
+        let upload = converter
+            .handle_event(&event(
+                &trace_id,
+                4,
+                3,
+                2,
+                end(span_end_event::Data::ReUpload(buck2_data::ReUploadEnd {
+                    digests_uploaded: Some(2),
+                    ..Default::default()
+                })),
+            ))?
+            .unwrap();
+        assert_eq!("re_upload", upload.name);
+        assert_eq!(span_id_bytes(SpanId::from_u64(3)?), upload.span_id);
+        assert_eq!(span_id_bytes(SpanId::from_u64(1)?), upload.parent_span_id);
+        assert_eq!(3_000_000_000, upload.start_time_unix_nano);
+        assert_eq!(4_000_000_000, upload.end_time_unix_nano);
+        assert_eq!(
+            vec![int_attribute("buck2.digests_uploaded", 2)],
+            upload.attributes
+        );
+
+        assert_eq!(
+            None,
+            converter.handle_event(&event(
+                &trace_id,
+                5,
+                2,
+                1,
+                end(span_end_event::Data::Load(Default::default()))
+            ))?
+        );
+
+        let command = converter
+            .handle_event(&event(
+                &trace_id,
+                6,
+                1,
+                0,
+                end(span_end_event::Data::Command(buck2_data::CommandEnd {
+                    is_success: false,
+                    ..Default::default()
+                })),
+            ))?
+            .unwrap();
+        assert_eq!("buck2 build", command.name);
+        assert!(command.parent_span_id.is_empty());
+        assert_eq!(16, command.trace_id.len());
+        assert_eq!(
+            Some(StatusCode::Error as i32),
+            command.status.map(|s| s.code)
+        );
+
+        Ok(())
+    }
+}
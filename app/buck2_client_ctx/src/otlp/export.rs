@@ -0,0 +1,244 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Pushes OTLP spans to a collector, over gRPC or HTTP (with protobuf payloads).
+
+use std::str::FromStr;
+
+use buck2_http::HttpClient;
+use buck2_http::HttpClientBuilder;
+use buck2_otlp_proto::opentelemetry::proto::collector::trace::v1::trace_service_client::TraceServiceClient;
+use buck2_otlp_proto::opentelemetry::proto::collector::trace::v1::ExportTraceServiceRequest;
+use buck2_otlp_proto::opentelemetry::proto::collector::trace::v1::ExportTraceServiceResponse;
+use buck2_otlp_proto::opentelemetry::proto::common::v1::InstrumentationScope;
+use buck2_otlp_proto::opentelemetry::proto::resource::v1::Resource;
+use buck2_otlp_proto::opentelemetry::proto::trace::v1::ResourceSpans;
+use buck2_otlp_proto::opentelemetry::proto::trace::v1::ScopeSpans;
+use buck2_otlp_proto::opentelemetry::proto::trace::v1::Span;
+use bytes::Bytes;
+use dupe::Dupe;
+use prost::Message;
+use tonic::transport::Channel;
+use tonic::transport::ClientTlsConfig;
+use tonic::transport::Endpoint;
+
+use crate::otlp::convert::string_attribute;
+use crate::version::BuckVersion;
+
+const HTTP_EXPORT_PATH: &str = "/v1/traces";
+
+#[derive(Debug, buck2_error::Error)]
+enum OtlpExportError {
+    #[error("Unknown OTLP protocol `{0}`, expected `grpc` or `http`")]
+    UnknownProtocol(String),
+    #[error("OTLP collector rejected {0} spans: {1}")]
+    Rejected(i64, String),
+}
+
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq, clap::ValueEnum)]
+#[clap(rename_all = "snake_case")]
+pub enum OtlpProtocol {
+    Grpc,
+    Http,
+}
+
+impl FromStr for OtlpProtocol {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "grpc" => Ok(Self::Grpc),
+            "http" => Ok(Self::Http),
+            _ => Err(OtlpExportError::UnknownProtocol(s.to_owned()).into()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum OtlpExporter {
+    Grpc(Channel),
+    Http { client: HttpClient, url: String },
+}
+
+impl OtlpExporter {
+    /// `endpoint` is the base URL of the collector, e.g. `http://localhost:4317` for gRPC or
+    /// `http://localhost:4318` for HTTP.
+    pub async fn new(endpoint: &str, protocol: OtlpProtocol) -> anyhow::Result<Self> {
+        match protocol {
+            OtlpProtocol::Grpc => {
+                let mut endpoint = Endpoint::from_shared(endpoint.to_owned())?;
+                if endpoint.uri().scheme_str() == Some("https") {
+                    endpoint = endpoint.tls_config(ClientTlsConfig::new())?;
+                }
+                Ok(Self::Grpc(endpoint.connect_lazy()))
+            }
+            OtlpProtocol::Http => Ok(Self::Http {
+                client: HttpClientBuilder::https_with_system_roots().await?.build(),
+                url: format!("{}{}", endpoint.trim_end_matches('/'), HTTP_EXPORT_PATH),
+            }),
+        }
+    }
+
+    pub async fn export(&self, spans: Vec<Span>) -> anyhow::Result<()> {
+        if spans.is_empty() {
+            return Ok(());
+        }
+        let request = export_request(spans);
+
+        let response = match self {
+            Self::Grpc(channel) => TraceServiceClient::new(channel.clone())
+                .export(request)
+                .await?
+                .into_inner(),
+            Self::Http { client, url } => {
+                let response = client
+                    .post(
+                        url,
+                        Bytes::from(request.encode_to_vec()),
+                        vec![(
+                            "Content-Type".to_owned(),
+                            "application/x-protobuf".to_owned(),
+                        )],
+                    )
+                    .await?;
+                let body = buck2_http::to_bytes(response.into_body()).await?;
+                ExportTraceServiceResponse::decode(body)?
+            }
+        };
+
+        match response.partial_success {
+            Some(partial) if partial.rejected_spans > 0 => {
+                Err(OtlpExportError::Rejected(partial.rejected_spans, partial.error_message).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn export_request(spans: Vec<Span>) -> ExportTraceServiceRequest {
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![string_attribute("service.name", "buck2")],
+                ..Default::default()
+            }),
+            scope_spans: vec![ScopeSpans {
+                scope: Some(InstrumentationScope {
+                    name: "buck2".to_owned(),
+                    version: BuckVersion::get_version().to_owned(),
+                    ..Default::default()
+                }),
+                spans,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_otlp_proto::opentelemetry::proto::collector::trace::v1::trace_service_server::TraceService;
+    use buck2_otlp_proto::opentelemetry::proto::collector::trace::v1::trace_service_server::TraceServiceServer;
+    use buck2_otlp_proto::opentelemetry::proto::collector::trace::v1::ExportTracePartialSuccess;
+    use httptest::matchers::*;
+    use httptest::responders;
+    use httptest::Expectation;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_http_export() -> anyhow::Result<()> {
+        let span = Span {
+            name: "re_upload".to_owned(),
+            ..Default::default()
+        };
+        let expected_body = export_request(vec![span.clone()]).encode_to_vec();
+
+        // Stands in for a collector.
+        let collector = httptest::Server::run();
+        collector.expect(
+            Expectation::matching(all_of![
+                request::method_path("POST", HTTP_EXPORT_PATH),
+                request::headers(contains(("content-type", "application/x-protobuf"))),
+                request::body(eq(expected_body)),
+            ])
+            .respond_with(responders::status_code(200)),
+        );
+
+        let exporter = OtlpExporter::new(&collector.url_str("/"), OtlpProtocol::Http).await?;
+        exporter.export(vec![span]).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_export_rejected() -> anyhow::Result<()> {
+        let response = ExportTraceServiceResponse {
+            partial_success: Some(ExportTracePartialSuccess {
+                rejected_spans: 1,
+                error_message: "no".to_owned(),
+            }),
+        };
+
+        let collector = httptest::Server::run();
+        collector.expect(
+            Expectation::matching(request::method_path("POST", HTTP_EXPORT_PATH))
+                .respond_with(responders::status_code(200).body(response.encode_to_vec())),
+        );
+
+        let exporter = OtlpExporter::new(&collector.url_str(""), OtlpProtocol::Http).await?;
+        assert!(exporter.export(vec![Span::default()]).await.is_err());
+
+        Ok(())
+    }
+
+    /// Stands in for a gRPC collector, passing on the requests it receives.
+    struct Collector {
+        requests: mpsc::UnboundedSender<ExportTraceServiceRequest>,
+    }
+
+    #[async_trait::async_trait]
+    impl TraceService for Collector {
+        async fn export(
+            &self,
+            request: tonic::Request<ExportTraceServiceRequest>,
+        ) -> Result<tonic::Response<ExportTraceServiceResponse>, tonic::Status> {
+            let _ignored = self.requests.send(request.into_inner());
+            Ok(tonic::Response::new(ExportTraceServiceResponse::default()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grpc_export() -> anyhow::Result<()> {
+        let span = Span {
+            name: "re_upload".to_owned(),
+            ..Default::default()
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let (requests, mut received) = mpsc::unbounded_channel();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(TraceServiceServer::new(Collector { requests }))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let exporter = OtlpExporter::new(&format!("http://{}", addr), OtlpProtocol::Grpc).await?;
+        exporter.export(vec![span.clone()]).await?;
+
+        assert_eq!(Some(export_request(vec![span])), received.recv().await);
+
+        Ok(())
+    }
+}
@@ -33,6 +33,7 @@ use crate::subscribers::get::get_console_with_root;
 use crate::subscribers::get::try_get_build_graph_stats;
 use crate::subscribers::get::try_get_build_id_writer;
 use crate::subscribers::get::try_get_event_log_subscriber;
+use crate::subscribers::get::try_get_otlp_subscriber;
 use crate::subscribers::get::try_get_re_log_subscriber;
 use crate::subscribers::recorder::try_get_invocation_recorder;
 use crate::subscribers::subscriber::EventSubscriber;
@@ -73,6 +74,9 @@ fn default_subscribers<'a, T: StreamingCommand>(
     if let Some(build_graph_stats) = try_get_build_graph_stats(cmd, ctx)? {
         subscribers.push(build_graph_stats)
     }
+    if let Some(otlp) = try_get_otlp_subscriber(cmd.logging_name())? {
+        subscribers.push(otlp)
+    }
     let recorder = try_get_invocation_recorder(
         ctx,
         cmd.event_log_opts(),
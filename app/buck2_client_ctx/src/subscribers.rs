@@ -14,6 +14,7 @@ pub(crate) mod errorconsole;
 pub mod event_log;
 pub mod get;
 pub(crate) mod observer;
+pub(crate) mod otlp;
 pub mod re_log;
 pub mod recorder;
 pub(crate) mod simpleconsole;
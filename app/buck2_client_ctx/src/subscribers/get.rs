@@ -10,6 +10,7 @@
 use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 
+use buck2_core::buck2_env;
 use buck2_event_observer::event_observer::NoopEventObserverExtra;
 use buck2_event_observer::verbosity::Verbosity;
 use buck2_wrapper_common::invocation_id::TraceId;
@@ -18,11 +19,13 @@ use dupe::Dupe;
 use crate::client_ctx::ClientCommandContext;
 use crate::common::ui::ConsoleType;
 use crate::common::CommonEventLogOptions;
+use crate::otlp::export::OtlpProtocol;
 use crate::streaming::StreamingCommand;
 use crate::subscribers::build_graph_stats::BuildGraphStats;
 use crate::subscribers::build_id_writer::BuildIdWriter;
 use crate::subscribers::errorconsole::ErrorConsole;
 use crate::subscribers::event_log::EventLog;
+use crate::subscribers::otlp::OtlpSubscriber;
 use crate::subscribers::re_log::ReLog;
 use crate::subscribers::simpleconsole::SimpleConsole;
 use crate::subscribers::subscriber::EventSubscriber;
@@ -138,6 +141,35 @@ pub(crate) fn try_get_build_id_writer<'a>(
     }
 }
 
+/// Push spans to an OTLP collector, if one is configured. A malformed configuration is reported
+/// but doesn't fail the command.
+pub(crate) fn try_get_otlp_subscriber<'a>(
+    command_name: &str,
+) -> anyhow::Result<Option<Box<dyn EventSubscriber + 'a>>> {
+    let config: anyhow::Result<_> = try {
+        let Some(endpoint) = buck2_env!("BUCK2_OTLP_ENDPOINT")? else {
+            return Ok(None);
+        };
+        let protocol = buck2_env!(
+            "BUCK2_OTLP_PROTOCOL",
+            type=OtlpProtocol,
+            default=OtlpProtocol::Http
+        )?;
+        (endpoint, protocol)
+    };
+    match config {
+        Ok((endpoint, protocol)) => Ok(Some(Box::new(OtlpSubscriber::new(
+            endpoint,
+            protocol,
+            command_name,
+        )))),
+        Err(e) => {
+            tracing::warn!("Not pushing spans to an OTLP collector: {:#}", e);
+            Ok(None)
+        }
+    }
+}
+
 pub(crate) fn try_get_build_graph_stats<'a, T: StreamingCommand>(
     cmd: &T,
     ctx: &ClientCommandContext<'a>,
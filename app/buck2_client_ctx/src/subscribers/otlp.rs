@@ -0,0 +1,148 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use buck2_events::BuckEvent;
+use buck2_otlp_proto::opentelemetry::proto::trace::v1::Span;
+use tokio::task::JoinHandle;
+
+use crate::otlp::convert::OtlpSpanConverter;
+use crate::otlp::export::OtlpExporter;
+use crate::otlp::export::OtlpProtocol;
+use crate::subscribers::subscriber::EventSubscriber;
+
+/// Spans are pushed in batches of this size while the command runs.
+const BATCH_SIZE: usize = 512;
+
+/// How long to wait on exit for the spans to be pushed.
+const EXIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pushes the spans of the command to an OTLP collector, as configured by `BUCK2_OTLP_ENDPOINT`
+/// and `BUCK2_OTLP_PROTOCOL`. Failing to push spans does not fail the command.
+pub(crate) struct OtlpSubscriber {
+    endpoint: String,
+    protocol: OtlpProtocol,
+    /// Created on the first push.
+    exporter: Option<OtlpExporter>,
+    /// Set when the exporter could not be created, after which spans are dropped.
+    disabled: bool,
+    converter: OtlpSpanConverter,
+    /// Events which could not be converted to spans, and were dropped.
+    unconverted: usize,
+    pending: Vec<Span>,
+    pushes: Vec<JoinHandle<anyhow::Result<()>>>,
+}
+
+impl OtlpSubscriber {
+    pub(crate) fn new(endpoint: &str, protocol: OtlpProtocol, command_name: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_owned(),
+            protocol,
+            exporter: None,
+            disabled: false,
+            converter: OtlpSpanConverter::new(command_name),
+            unconverted: 0,
+            pending: Vec::new(),
+            pushes: Vec::new(),
+        }
+    }
+
+    /// Starts pushing the pending spans. Spans that can't be pushed are dropped.
+    async fn push_pending(&mut self) {
+        if self.pending.is_empty() || self.disabled {
+            self.pending.clear();
+            return;
+        }
+        let exporter = match self.exporter.clone() {
+            Some(exporter) => exporter,
+            None => match OtlpExporter::new(&self.endpoint, self.protocol).await {
+                Ok(exporter) => {
+                    self.exporter = Some(exporter.clone());
+                    exporter
+                }
+                Err(e) => {
+                    tracing::warn!("Not pushing spans to `{}`: {:#}", self.endpoint, e);
+                    self.disabled = true;
+                    self.pending.clear();
+                    return;
+                }
+            },
+        };
+        let spans = std::mem::take(&mut self.pending);
+        // Don't hold up the console while pushing.
+        self.pushes
+            .push(tokio::spawn(async move { exporter.export(spans).await }));
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for OtlpSubscriber {
+    async fn handle_events(&mut self, events: &[Arc<BuckEvent>]) -> anyhow::Result<()> {
+        for event in events {
+            match self.converter.handle_event(event) {
+                Ok(Some(span)) => self.pending.push(span),
+                Ok(None) => {}
+                Err(e) => {
+                    if self.unconverted == 0 {
+                        tracing::warn!("Failed to convert event to an OTLP span: {:#}", e);
+                    }
+                    self.unconverted += 1;
+                }
+            }
+        }
+        if self.pending.len() >= BATCH_SIZE {
+            self.push_pending().await;
+        }
+        Ok(())
+    }
+
+    async fn exit(&mut self) -> anyhow::Result<()> {
+        if self.unconverted > 0 {
+            tracing::warn!(
+                "Dropped {} events which could not be converted to OTLP spans",
+                self.unconverted
+            );
+        }
+        self.push_pending().await;
+        let pushes = futures::future::join_all(std::mem::take(&mut self.pushes));
+        match tokio::time::timeout(EXIT_TIMEOUT, pushes).await {
+            Ok(results) => {
+                for result in results {
+                    if let Err(e) = result.map_err(anyhow::Error::from).and_then(|r| r) {
+                        tracing::warn!("Failed to push spans to `{}`: {:#}", self.endpoint, e);
+                    }
+                }
+            }
+            Err(_) => tracing::warn!(
+                "Timed out pushing spans to `{}` after {}s",
+                self.endpoint,
+                EXIT_TIMEOUT.as_secs()
+            ),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_malformed_endpoint_drops_spans() -> anyhow::Result<()> {
+        let mut subscriber = OtlpSubscriber::new("not a url", OtlpProtocol::Grpc, "build");
+        subscriber.pending.push(Span::default());
+        subscriber.exit().await?;
+        assert!(subscriber.disabled);
+        assert!(subscriber.pending.is_empty());
+        Ok(())
+    }
+}
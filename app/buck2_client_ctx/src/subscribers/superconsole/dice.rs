@@ -59,6 +59,36 @@ impl<'s> Component for DiceComponent<'s> {
             ));
         }
         lines.push("-".repeat(header_len));
+
+        let class_states = self.dice_state.concurrency_class_states();
+        if !class_states.is_empty() {
+            lines.push("Dice Concurrency Classes".to_owned());
+            let header = format!(
+                "  {:<42}  {:>6}  {:>6}  {:>6}  {:>6}",
+                "  Class", "Running", "Queued", "Limit", "Priority"
+            );
+            let header_len = header.len();
+            lines.push(header);
+            lines.push("-".repeat(header_len));
+            for (class, v) in class_states {
+                let running = v.started.saturating_sub(v.finished);
+                let queued = v.queued.saturating_sub(v.dequeued);
+                lines.push(format!(
+                    "    {:<40} |  {}  |  {}  |  {}  |  {:>6}",
+                    if class.len() > 40 {
+                        &class[..40]
+                    } else {
+                        class
+                    },
+                    HumanizedCount::fixed_width(running.into()),
+                    HumanizedCount::fixed_width(queued.into()),
+                    HumanizedCount::fixed_width(v.limit),
+                    v.priority,
+                ));
+            }
+            lines.push("-".repeat(header_len));
+        }
+
         Ok(Lines(lines.into_try_map(|v| vec![v].try_into())?))
     }
 }
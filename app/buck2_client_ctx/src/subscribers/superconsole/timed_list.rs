@@ -545,10 +545,13 @@ mod tests {
                                         check_deps_finished: 1,
                                         compute_started: 4,
                                         compute_finished: 2,
+                                        early_cutoffs: 0,
+                                        recompute_changes: 0,
                                     },
                                 );
                                 map
                             },
+                            concurrency_class_states: HashMap::new(),
                         }
                         .into(),
                     ),
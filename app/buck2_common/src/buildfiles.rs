@@ -14,7 +14,11 @@ use buck2_core::cells::name::CellName;
 use buck2_core::fs::paths::file_name::FileNameBuf;
 use dice::CancellationContext;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use gazebo::prelude::SliceExt as _;
 use gazebo::prelude::VecExt as _;
 
@@ -82,6 +86,7 @@ pub trait HasBuildfiles {
     PartialEq,
     allocative::Allocative
 )]
+#[derive(serde::Serialize, serde::Deserialize)]
 #[display(fmt = "BuildfilesKey({})", "self.0")]
 struct BuildfilesKey(CellName);
 
@@ -106,6 +111,34 @@ impl Key for BuildfilesKey {
     }
 }
 
+impl PersistentKey for BuildfilesKey {
+    const PERSISTENT_ID: &'static str = "BuildfilesKey";
+}
+
+impl PersistentValue for BuildfilesKey {
+    type Persisted = Vec<String>;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        value
+            .as_ref()
+            .ok()
+            .map(|names| names.iter().map(|name| name.as_str().to_owned()).collect())
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(Ok(value
+            .into_iter()
+            .map(FileNameBuf::try_from)
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into()))
+    }
+}
+
+/// Registers the buildfile names, so that they are saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<BuildfilesKey>()
+}
+
 impl HasBuildfiles for DiceComputations<'_> {
     async fn get_buildfiles(&mut self, cell: CellName) -> anyhow::Result<Arc<[FileNameBuf]>> {
         Ok(self.compute(&BuildfilesKey(cell)).await??)
@@ -9,7 +9,20 @@
 
 //! Common dice operations
 
+use dice::DicePersistence;
+
 pub mod cells;
 pub mod cycles;
 pub mod data;
 pub mod file_ops;
+
+/// Registers the keys of this crate which are saved in DICE snapshots.
+pub fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    cells::register_persistent_keys(persistence)?;
+    file_ops::register_persistent_keys(persistence)?;
+    crate::legacy_configs::dice::register_persistent_keys(persistence)?;
+    crate::buildfiles::register_persistent_keys(persistence)?;
+    crate::package_boundary::register_persistent_keys(persistence)?;
+    crate::package_listing::dice::register_persistent_keys(persistence)?;
+    crate::target_aliases::register_persistent_keys(persistence)
+}
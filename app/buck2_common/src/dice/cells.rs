@@ -9,19 +9,36 @@
 
 //! Core dice computations relating to cells
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use allocative::Allocative;
 use async_trait::async_trait;
+use buck2_core::cells::alias::NonEmptyCellAlias;
+use buck2_core::cells::cell_root_path::CellRootPath;
+use buck2_core::cells::cell_root_path::CellRootPathBuf;
+use buck2_core::cells::external::ExternalCellOrigin;
+use buck2_core::cells::external::GitCellSetup;
+use buck2_core::cells::instance::CellInstance;
 use buck2_core::cells::name::CellName;
+use buck2_core::cells::nested::NestedCells;
 use buck2_core::cells::CellAliasResolver;
 use buck2_core::cells::CellResolver;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use derive_more::Display;
 use dice::CancellationContext;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::DiceTransactionUpdater;
 use dice::InjectedKey;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::legacy_configs::cells::BuckConfigBasedCells;
 use crate::legacy_configs::dice::HasLegacyConfigs;
@@ -50,6 +67,7 @@ pub trait SetCellResolver {
 }
 
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{:?}", self)]
 struct CellResolverKey;
 
@@ -65,6 +83,131 @@ impl InjectedKey for CellResolverKey {
     }
 }
 
+impl PersistentKey for CellResolverKey {
+    const PERSISTENT_ID: &'static str = "CellResolverKey";
+}
+
+impl PersistentValue for CellResolverKey {
+    type Persisted = Option<PersistedCellResolver>;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        Some(value.as_ref().map(PersistedCellResolver::new))
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        value
+            .map(PersistedCellResolver::into_cell_resolver)
+            .transpose()
+    }
+}
+
+/// A `CellResolver` as saved in DICE snapshots. The nested cells aren't saved, they are derived
+/// from the cell roots again when it is restored, like `CellsAggregator` does.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct PersistedCellResolver {
+    /// Sorted by name.
+    cells: Vec<PersistedCell>,
+    root_cell: CellName,
+    /// Sorted by alias.
+    root_aliases: Vec<(String, CellName)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct PersistedCell {
+    name: CellName,
+    path: ProjectRelativePathBuf,
+    external: Option<PersistedExternalCellOrigin>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum PersistedExternalCellOrigin {
+    Bundled(CellName),
+    Git { git_origin: String, commit: String },
+}
+
+impl PersistedCellResolver {
+    fn new(resolver: &CellResolver) -> Self {
+        let mut cells: Vec<_> = resolver
+            .cells()
+            .map(|(name, instance)| PersistedCell {
+                name,
+                path: instance.path().as_project_relative_path().to_buf(),
+                external: instance.external().map(|origin| match origin {
+                    ExternalCellOrigin::Bundled(cell) => {
+                        PersistedExternalCellOrigin::Bundled(*cell)
+                    }
+                    ExternalCellOrigin::Git(setup) => PersistedExternalCellOrigin::Git {
+                        git_origin: setup.git_origin.to_string(),
+                        commit: setup.commit.to_string(),
+                    },
+                }),
+            })
+            .collect();
+        cells.sort_by_key(|cell| cell.name);
+        let root_cell_alias_resolver = resolver.root_cell_cell_alias_resolver();
+        let mut root_aliases: Vec<_> = root_cell_alias_resolver
+            .mappings()
+            .map(|(alias, name)| (alias.as_str().to_owned(), name))
+            .collect();
+        root_aliases.sort();
+        PersistedCellResolver {
+            cells,
+            root_cell: root_cell_alias_resolver.resolve_self(),
+            root_aliases,
+        }
+    }
+
+    fn into_cell_resolver(self) -> anyhow::Result<CellResolver> {
+        let roots: Vec<(CellName, CellRootPathBuf)> = self
+            .cells
+            .iter()
+            .map(|cell| (cell.name, CellRootPathBuf::new(cell.path.clone())))
+            .collect();
+        let all_roots: Vec<(CellName, &CellRootPath)> = roots
+            .iter()
+            .map(|(name, path)| (*name, path.as_path()))
+            .collect();
+        let instances = self
+            .cells
+            .into_iter()
+            .zip(roots.iter())
+            .map(|(cell, (_, path))| {
+                let external = cell.external.map(|origin| match origin {
+                    PersistedExternalCellOrigin::Bundled(cell) => ExternalCellOrigin::Bundled(cell),
+                    PersistedExternalCellOrigin::Git { git_origin, commit } => {
+                        ExternalCellOrigin::Git(GitCellSetup {
+                            git_origin: Arc::from(git_origin),
+                            commit: Arc::from(commit),
+                        })
+                    }
+                });
+                let nested_cells = NestedCells::from_cell_roots(&all_roots, path);
+                CellInstance::new(cell.name, path.clone(), external, nested_cells)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let root_aliases = self
+            .root_aliases
+            .into_iter()
+            .map(|(alias, name)| Ok((NonEmptyCellAlias::new(alias)?, name)))
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+        CellResolver::new(
+            instances,
+            CellAliasResolver::new(self.root_cell, root_aliases)?,
+        )
+    }
+}
+
+impl PersistentKey for CellAliasResolverKey {
+    const PERSISTENT_ID: &'static str = "CellAliasResolverKey";
+}
+
+/// Registers the cell resolver and the cell alias resolvers, so that they are saved in DICE
+/// snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<CellResolverKey>()?;
+    persistence.register_without_value::<CellAliasResolverKey>()
+}
+
 #[async_trait]
 impl HasCellResolver for DiceComputations<'_> {
     async fn get_cell_resolver(&mut self) -> anyhow::Result<CellResolver> {
@@ -95,6 +238,7 @@ impl HasCellResolver for DiceComputations<'_> {
 
 /// Only used for cell alias resolvers parsed within dice, currently those for external cells
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 struct CellAliasResolverKey(CellName);
 
 #[async_trait]
@@ -138,3 +282,35 @@ impl SetCellResolver for DiceTransactionUpdater {
         Ok(self.changed_to(vec![(CellResolverKey, None)])?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use buck2_core::cells::alias::NonEmptyCellAlias;
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
+    use buck2_core::cells::name::CellName;
+    use buck2_core::cells::CellResolver;
+
+    use crate::dice::cells::PersistedCellResolver;
+
+    #[test]
+    fn test_persisted_cell_resolver() -> anyhow::Result<()> {
+        let root = CellName::testing_new("root");
+        let other = CellName::testing_new("other");
+        let nested = CellName::testing_new("nested");
+        let resolver = CellResolver::testing_with_names_and_paths_with_alias(
+            &[
+                (root, CellRootPathBuf::testing_new("")),
+                (other, CellRootPathBuf::testing_new("other")),
+                (nested, CellRootPathBuf::testing_new("other/nested")),
+            ],
+            HashMap::from([(NonEmptyCellAlias::testing_new("alias"), other)]),
+        );
+
+        let persisted = serde_json::to_string(&PersistedCellResolver::new(&resolver))?;
+        let persisted: PersistedCellResolver = serde_json::from_str(&persisted)?;
+        assert_eq!(resolver, persisted.into_cell_resolver()?);
+        Ok(())
+    }
+}
@@ -41,6 +41,27 @@ impl SetIoProvider for DiceDataBuilder {
     }
 }
 
+/// The digest config of the daemon, which restoring file digests from DICE snapshots needs.
+pub trait HasCasDigestConfig {
+    fn get_cas_digest_config(&self) -> anyhow::Result<CasDigestConfig>;
+}
+
+pub trait SetCasDigestConfig {
+    fn set_cas_digest_config(&mut self, config: CasDigestConfig);
+}
+
+impl HasCasDigestConfig for DiceData {
+    fn get_cas_digest_config(&self) -> anyhow::Result<CasDigestConfig> {
+        Ok(*self.get::<CasDigestConfig>()?)
+    }
+}
+
+impl SetCasDigestConfig for DiceDataBuilder {
+    fn set_cas_digest_config(&mut self, config: CasDigestConfig) {
+        self.set(config)
+    }
+}
+
 pub mod testing {
     use buck2_core::fs::project::ProjectRootTemp;
 
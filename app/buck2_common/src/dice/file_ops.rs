@@ -10,6 +10,7 @@
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use allocative::Allocative;
@@ -18,23 +19,38 @@ use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::cell_path::CellPathRef;
 use buck2_core::cells::name::CellName;
 use buck2_core::fs::paths::file_name::FileNameBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
 use buck2_futures::cancellation::CancellationContext;
 use cmp_any::PartialEqAny;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::DiceTransactionUpdater;
 use dice::Key;
 use dice::LinearRecomputeDiceComputations;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use dupe::Dupe;
 use futures::future::BoxFuture;
 use futures::FutureExt;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::buildfiles::HasBuildfiles;
+use crate::dice::data::HasCasDigestConfig;
 use crate::dice::file_ops::delegate::get_delegated_file_ops;
+use crate::external_symlink::ExternalSymlink;
+use crate::file_ops::FileDigest;
+use crate::file_ops::FileMetadata;
 use crate::file_ops::FileOps;
 use crate::file_ops::FileOpsError;
+use crate::file_ops::FileType;
 use crate::file_ops::RawPathMetadata;
+use crate::file_ops::RawSymlink;
 use crate::file_ops::ReadDirOutput;
+use crate::file_ops::SimpleDirEntry;
+use crate::file_ops::TrackedFileDigest;
 use crate::ignores::file_ignores::FileIgnoreResult;
 use crate::io::ReadDirError;
 
@@ -146,6 +162,7 @@ impl DiceFileComputations {
 }
 
 #[derive(Debug, Display, Clone, Dupe, Copy, PartialEq, Eq, Hash, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub(crate) enum CheckIgnores {
     Yes,
     No,
@@ -244,6 +261,7 @@ impl FileChangeTracker {
 }
 
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 struct ReadFileKey(Arc<CellPath>);
 
 #[async_trait]
@@ -262,6 +280,7 @@ impl Key for ReadFileKey {
 }
 
 #[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{}", path)]
 struct ReadDirKey {
     path: CellPath,
@@ -296,6 +315,7 @@ impl Key for ReadDirKey {
 }
 
 #[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 struct PathMetadataKey(CellPath);
 
 #[async_trait]
@@ -336,6 +356,156 @@ impl Key for PathMetadataKey {
     }
 }
 
+impl PersistentKey for ReadFileKey {
+    const PERSISTENT_ID: &'static str = "ReadFileKey";
+}
+
+impl PersistentValue for ReadFileKey {
+    type Persisted = ();
+
+    fn to_persisted(_value: &()) -> Option<()> {
+        Some(())
+    }
+
+    fn from_persisted(_value: (), _data: &DiceData) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+impl PersistentKey for ReadDirKey {
+    const PERSISTENT_ID: &'static str = "ReadDirKey";
+}
+
+impl PersistentValue for ReadDirKey {
+    type Persisted = Vec<(String, FileType)>;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        let output = value.as_ref().ok()?;
+        Some(
+            output
+                .included
+                .iter()
+                .map(|entry| (entry.file_name.as_str().to_owned(), entry.file_type.dupe()))
+                .collect(),
+        )
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        let included = value
+            .into_iter()
+            .map(|(file_name, file_type)| {
+                Ok(SimpleDirEntry {
+                    file_name: FileNameBuf::try_from(file_name)?,
+                    file_type,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Ok(ReadDirOutput { included }))
+    }
+}
+
+impl PersistentKey for PathMetadataKey {
+    const PERSISTENT_ID: &'static str = "PathMetadataKey";
+}
+
+impl PersistentValue for PathMetadataKey {
+    type Persisted = Option<PersistedPathMetadata>;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        Some(match value.as_ref().ok()? {
+            None => None,
+            Some(RawPathMetadata::File(meta)) => Some(PersistedPathMetadata::File {
+                algorithm: meta.digest.raw_digest().algorithm().to_string(),
+                digest: meta.digest.raw_digest().as_bytes().to_vec(),
+                size: meta.digest.size(),
+                is_executable: meta.is_executable,
+            }),
+            Some(RawPathMetadata::Symlink { at, to }) => Some(PersistedPathMetadata::Symlink {
+                at: (**at).clone(),
+                to: match to {
+                    RawSymlink::Relative(to) => PersistedSymlink::Relative((**to).clone()),
+                    RawSymlink::External(to) => PersistedSymlink::External {
+                        target: to.target_str().to_owned(),
+                        remaining_path: to.remaining_path().as_str().to_owned(),
+                    },
+                },
+            }),
+            Some(RawPathMetadata::Directory) => Some(PersistedPathMetadata::Directory),
+        })
+    }
+
+    fn from_persisted(value: Self::Persisted, data: &DiceData) -> anyhow::Result<Self::Value> {
+        let Some(value) = value else {
+            return Ok(Ok(None));
+        };
+        Ok(Ok(Some(match value {
+            PersistedPathMetadata::File {
+                algorithm,
+                digest,
+                size,
+                is_executable,
+            } => {
+                let digest = FileDigest::from_digest_bytes(algorithm.parse()?, &digest, size)?;
+                RawPathMetadata::File(FileMetadata {
+                    digest: TrackedFileDigest::new(
+                        digest,
+                        data.get_cas_digest_config()?.source_files_config(),
+                    ),
+                    is_executable,
+                })
+            }
+            PersistedPathMetadata::Symlink { at, to } => RawPathMetadata::Symlink {
+                at: Arc::new(at),
+                to: match to {
+                    PersistedSymlink::Relative(to) => RawSymlink::Relative(Arc::new(to)),
+                    PersistedSymlink::External {
+                        target,
+                        remaining_path,
+                    } => RawSymlink::External(Arc::new(ExternalSymlink::new(
+                        PathBuf::from(target),
+                        ForwardRelativePathBuf::new(remaining_path)?,
+                    )?)),
+                },
+            },
+            PersistedPathMetadata::Directory => RawPathMetadata::Directory,
+        })))
+    }
+}
+
+/// A `RawPathMetadata` as saved in DICE snapshots.
+#[derive(Serialize, Deserialize)]
+enum PersistedPathMetadata {
+    File {
+        /// A `DigestAlgorithmKind`.
+        algorithm: String,
+        digest: Vec<u8>,
+        size: u64,
+        is_executable: bool,
+    },
+    Symlink {
+        at: CellPath,
+        to: PersistedSymlink,
+    },
+    Directory,
+}
+
+#[derive(Serialize, Deserialize)]
+enum PersistedSymlink {
+    Relative(CellPath),
+    External {
+        target: String,
+        remaining_path: String,
+    },
+}
+
+/// Registers the file operation keys, so that they are saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    delegate::register_persistent_keys(persistence)?;
+    persistence.register::<ReadFileKey>()?;
+    persistence.register::<ReadDirKey>()?;
+    persistence.register::<PathMetadataKey>()
+}
+
 #[async_trait]
 impl FileOps for DiceFileOps<'_, '_> {
     async fn read_file_if_exists(
@@ -25,7 +25,9 @@ use buck2_futures::cancellation::CancellationContext;
 use cmp_any::PartialEqAny;
 use derivative::Derivative;
 use dice::DiceComputations;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
 use dupe::Dupe;
 
 use crate::dice::cells::HasCellResolver;
@@ -50,11 +52,14 @@ mod keys {
     use buck2_core::cells::name::CellName;
     use derive_more::Display;
     use dupe::Dupe;
+    use serde::Deserialize;
+    use serde::Serialize;
 
     use crate::dice::file_ops::delegate::FileOpsDelegateWithIgnores;
     use crate::dice::file_ops::CheckIgnores;
 
     #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+    #[derive(Serialize, Deserialize)]
     #[display(fmt = "{:?}", self)]
     pub(crate) struct FileOpsKey {
         pub cell: CellName,
@@ -207,6 +212,15 @@ impl Key for FileOpsKey {
     }
 }
 
+/// The delegates are rebuilt when first needed, only the keys are saved.
+impl PersistentKey for FileOpsKey {
+    const PERSISTENT_ID: &'static str = "FileOpsKey";
+}
+
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register_without_value::<FileOpsKey>()
+}
+
 pub(crate) async fn get_delegated_file_ops(
     dice: &mut DiceComputations<'_>,
     cell: CellName,
@@ -25,6 +25,8 @@ use compact_str::CompactString;
 use derive_more::Display;
 use dupe::Dupe;
 use gazebo::variants::VariantName;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::cas_digest::CasDigest;
 use crate::cas_digest::CasDigestConfig;
@@ -46,6 +48,7 @@ pub(crate) enum FileOpsError {
 /// std::fs::FileType is an opaque type that isn't constructible. This is
 /// basically the equivalent.
 #[derive(Clone, Dupe, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub enum FileType {
     Directory,
     File,
@@ -288,6 +288,8 @@ pub struct DaemonStartupConfig {
     pub materializations: Option<String>,
     pub http: HttpConfig,
     pub resource_control: ResourceControlConfig,
+    /// Address to serve Prometheus metrics from, e.g. `127.0.0.1:9464`.
+    pub metrics_address: Option<String>,
 }
 
 impl DaemonStartupConfig {
@@ -329,6 +331,12 @@ impl DaemonStartupConfig {
                 .map(ToOwned::to_owned),
             http: HttpConfig::from_config(config)?,
             resource_control: ResourceControlConfig::from_config(config)?,
+            metrics_address: config
+                .get(BuckconfigKeyRef {
+                    section: "buck2",
+                    property: "metrics_address",
+                })
+                .map(ToOwned::to_owned),
         })
     }
 
@@ -350,6 +358,7 @@ impl DaemonStartupConfig {
             materializations: None,
             http: HttpConfig::default(),
             resource_control: ResourceControlConfig::default(),
+            metrics_address: None,
         }
     }
 }
@@ -160,8 +160,21 @@ impl InvocationPaths {
         FileName::unchecked_new("materializer_state")
     }
 
+    /// Subdirectory of `cache_dir` storing the snapshot of the DICE graph written on shutdown,
+    /// see `buck2.dice_snapshot`
+    pub fn dice_snapshot_path(&self) -> AbsNormPathBuf {
+        self.cache_dir_path().join(self.dice_snapshot_dir_name())
+    }
+
+    pub fn dice_snapshot_dir_name(&self) -> &FileName {
+        FileName::unchecked_new("dice_snapshot")
+    }
+
     pub fn valid_cache_dirs(&self) -> Vec<&FileName> {
-        vec![self.materializer_state_dir_name()]
+        vec![
+            self.materializer_state_dir_name(),
+            self.dice_snapshot_dir_name(),
+        ]
     }
 }
 
@@ -28,6 +28,7 @@ use crate::legacy_configs::parser::LegacyConfigParser;
 
 /// Representation of a processed config arg, namely after file path resolution has been performed.
 #[derive(Debug, Clone, PartialEq, Eq, allocative::Allocative)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub(crate) enum ResolvedLegacyConfigArg {
     /// A single config key-value pair (in `a.b=c` format).
     Flag(ResolvedConfigFlag),
@@ -36,6 +37,7 @@ pub(crate) enum ResolvedLegacyConfigArg {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, allocative::Allocative)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub(crate) enum ResolvedConfigFile {
     /// If the config file is project relative, the path of the file
     Project(ProjectRelativePathBuf),
@@ -44,6 +46,7 @@ pub(crate) enum ResolvedConfigFile {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, allocative::Allocative)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub(crate) struct ResolvedConfigFlag {
     pub(crate) section: String,
     pub(crate) key: String,
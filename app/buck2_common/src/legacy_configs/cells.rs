@@ -29,6 +29,8 @@ use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use dice::DiceComputations;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::cas_digest::RawDigest;
 use crate::dice::cells::HasCellResolver;
@@ -54,6 +56,7 @@ use crate::legacy_configs::path::DEFAULT_PROJECT_CONFIG_SOURCES;
 /// Buckconfigs can partially be loaded from within dice. However, some parts of what makes up the
 /// buckconfig comes from outside the buildgraph, and this type represents those parts.
 #[derive(PartialEq, Eq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub struct ExternalBuckconfigData {
     parse_state: LegacyConfigParser,
     args: Vec<ResolvedLegacyConfigArg>,
@@ -18,6 +18,8 @@ use buck2_cli_proto::ConfigOverride;
 use buck2_core::cells::cell_root_path::CellRootPath;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark_map::sorted_map::SortedMap;
 
 use crate::legacy_configs::args::ResolvedConfigFile;
@@ -35,6 +37,7 @@ pub(crate) struct ConfigData {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub(crate) enum ResolvedValue {
     // A placeholder used before we do resolution.
     Unknown,
@@ -45,18 +48,21 @@ pub(crate) enum ResolvedValue {
 }
 
 #[derive(Debug, PartialEq, Eq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub(crate) struct ConfigFileLocation {
     pub(crate) path: String,
     pub(crate) include_source: Option<Location>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub(crate) struct ConfigFileLocationWithLine {
     pub(crate) source_file: Arc<ConfigFileLocation>,
     pub(crate) line: usize,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub(crate) enum Location {
     File(ConfigFileLocationWithLine),
     CommandLineArgument,
@@ -130,6 +136,7 @@ pub fn parse_config_section_and_key(
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub(crate) struct ConfigValue {
     raw_value: String,
     pub(crate) resolved_value: ResolvedValue,
@@ -19,13 +19,20 @@ use buck2_core::cells::name::CellName;
 use buck2_futures::cancellation::CancellationContext;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::DiceProjectionComputations;
 use dice::DiceTransactionUpdater;
 use dice::InjectedKey;
 use dice::Key;
 use dice::OpaqueValue;
+use dice::PersistentKey;
+use dice::PersistentProjection;
+use dice::PersistentValue;
 use dice::ProjectionKey;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::dice::cells::HasCellResolver;
 use crate::legacy_configs::cells::BuckConfigBasedCells;
@@ -161,6 +168,7 @@ pub trait SetLegacyConfigs {
 }
 
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{:?}", self)]
 struct LegacyExternalBuckConfigDataKey;
 
@@ -173,6 +181,7 @@ impl InjectedKey for LegacyExternalBuckConfigDataKey {
 }
 
 #[derive(Clone, Display, Debug, Hash, Eq, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "LegacyBuckConfigForCellKey({})", "self.cell_name")]
 struct LegacyBuckConfigForCellKey {
     cell_name: CellName,
@@ -213,6 +222,7 @@ impl Key for LegacyBuckConfigForCellKey {
 /// projection key to extract just the error from the cell computation, and compute that when
 /// constructing the `OpaqueLegacyBuckConfigOnDice`.
 #[derive(Debug, Display, Hash, Eq, PartialEq, Clone, Allocative)]
+#[derive(Serialize, Deserialize)]
 struct LegacyBuckConfigErrorKey();
 
 impl ProjectionKey for LegacyBuckConfigErrorKey {
@@ -233,6 +243,7 @@ impl ProjectionKey for LegacyBuckConfigErrorKey {
 }
 
 #[derive(Debug, Display, Hash, Eq, PartialEq, Clone, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{}.{}", section, property)]
 struct LegacyBuckConfigPropertyProjectionKey {
     section: String,
@@ -263,6 +274,66 @@ impl ProjectionKey for LegacyBuckConfigPropertyProjectionKey {
     }
 }
 
+impl PersistentKey for LegacyExternalBuckConfigDataKey {
+    const PERSISTENT_ID: &'static str = "LegacyExternalBuckConfigDataKey";
+}
+
+impl PersistentValue for LegacyExternalBuckConfigDataKey {
+    type Persisted = Option<Arc<ExternalBuckconfigData>>;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        Some(value.clone())
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(value)
+    }
+}
+
+/// The parsed buckconfigs are only saved through their projections, and are parsed again from
+/// the saved files when they are first needed.
+impl PersistentKey for LegacyBuckConfigForCellKey {
+    const PERSISTENT_ID: &'static str = "LegacyBuckConfigForCellKey";
+}
+
+/// Only the absence of errors is saved, so that cells whose buckconfigs failed to parse are
+/// parsed again.
+impl PersistentProjection for LegacyBuckConfigErrorKey {
+    const PERSISTENT_ID: &'static str = "LegacyBuckConfigErrorKey";
+
+    type Persisted = ();
+
+    fn to_persisted(value: &Self::Value) -> Option<()> {
+        value.is_none().then_some(())
+    }
+
+    fn from_persisted(_value: (), _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(None)
+    }
+}
+
+impl PersistentProjection for LegacyBuckConfigPropertyProjectionKey {
+    const PERSISTENT_ID: &'static str = "LegacyBuckConfigPropertyProjectionKey";
+
+    type Persisted = Option<Arc<str>>;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        Some(value.clone())
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(value)
+    }
+}
+
+/// Registers the buckconfig keys, so that they are saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<LegacyExternalBuckConfigDataKey>()?;
+    persistence.register_without_value::<LegacyBuckConfigForCellKey>()?;
+    persistence.register_projection::<LegacyBuckConfigErrorKey>()?;
+    persistence.register_projection::<LegacyBuckConfigPropertyProjectionKey>()
+}
+
 impl HasInjectedLegacyConfigs for DiceComputations<'_> {
     async fn get_injected_external_buckconfig_data(
         &mut self,
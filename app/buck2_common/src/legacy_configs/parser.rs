@@ -21,6 +21,8 @@ use futures::FutureExt;
 use itertools::Itertools;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark_map::sorted_map::SortedMap;
 
 use crate::legacy_configs::args::ResolvedConfigFlag;
@@ -64,6 +66,7 @@ fn format_cycle(cycle: &[(String, String)]) -> String {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Allocative)]
+#[derive(Serialize, Deserialize)]
 struct SectionBuilder {
     values: BTreeMap<String, ConfigValue>,
 }
@@ -80,6 +83,7 @@ impl SectionBuilder {
 ///
 /// A buckconfig will generally be parsed by combining multiple command args and files
 #[derive(Debug, Clone, PartialEq, Eq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub(crate) struct LegacyConfigParser {
     values: BTreeMap<String, SectionBuilder>,
 }
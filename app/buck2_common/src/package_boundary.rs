@@ -23,9 +23,15 @@ use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
 use buck2_futures::cancellation::CancellationContext;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use dupe::Dupe;
 use ref_cast::RefCast;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::legacy_configs::dice::HasLegacyConfigs;
 use crate::legacy_configs::key::BuckconfigKeyRef;
@@ -95,6 +101,7 @@ impl CellPackageBoundaryExceptions {
 }
 
 #[derive(Hash, Eq, PartialEq, Clone, Dupe, Display, Debug, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{:?}", self)]
 struct CellPackageBoundaryExceptionsKey(CellName);
 
@@ -135,6 +142,69 @@ impl Key for CellPackageBoundaryExceptionsKey {
     }
 }
 
+#[derive(Hash, Eq, PartialEq, Clone, Display, Debug, RefCast, Allocative)]
+#[derive(Serialize, Deserialize)]
+#[repr(transparent)]
+struct PackageBoundaryExceptionKey(CellPath);
+
+#[async_trait]
+impl Key for PackageBoundaryExceptionKey {
+    type Value = buck2_error::Result<Option<Arc<CellPath>>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let Some(exceptions) = ctx
+            .compute(&CellPackageBoundaryExceptionsKey(self.0.cell()))
+            .await??
+        else {
+            return Ok(None);
+        };
+        Ok(exceptions
+            .get_package_boundary_exception_path(self.0.path())
+            .map(|p| Arc::new(CellPath::new(self.0.cell(), p))))
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+impl PersistentKey for CellPackageBoundaryExceptionsKey {
+    const PERSISTENT_ID: &'static str = "CellPackageBoundaryExceptionsKey";
+}
+
+impl PersistentKey for PackageBoundaryExceptionKey {
+    const PERSISTENT_ID: &'static str = "PackageBoundaryExceptionKey";
+}
+
+impl PersistentValue for PackageBoundaryExceptionKey {
+    type Persisted = Option<CellPath>;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        value.as_ref().ok().map(|p| p.as_deref().cloned())
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(Ok(value.map(Arc::new)))
+    }
+}
+
+/// Registers the package boundary exceptions, so that they are saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register_without_value::<CellPackageBoundaryExceptionsKey>()?;
+    persistence.register::<PackageBoundaryExceptionKey>()
+}
+
 #[async_trait]
 pub trait HasPackageBoundaryExceptions {
     async fn get_package_boundary_exception(
@@ -149,42 +219,6 @@ impl HasPackageBoundaryExceptions for DiceComputations<'_> {
         &mut self,
         path: CellPathRef<'async_trait>,
     ) -> buck2_error::Result<Option<Arc<CellPath>>> {
-        #[derive(Hash, Eq, PartialEq, Clone, Display, Debug, RefCast, Allocative)]
-        #[repr(transparent)]
-        struct PackageBoundaryExceptionKey(CellPath);
-
-        #[async_trait]
-        impl Key for PackageBoundaryExceptionKey {
-            type Value = buck2_error::Result<Option<Arc<CellPath>>>;
-
-            async fn compute(
-                &self,
-                ctx: &mut DiceComputations,
-                _cancellations: &CancellationContext,
-            ) -> Self::Value {
-                let Some(exceptions) = ctx
-                    .compute(&CellPackageBoundaryExceptionsKey(self.0.cell()))
-                    .await??
-                else {
-                    return Ok(None);
-                };
-                Ok(exceptions
-                    .get_package_boundary_exception_path(self.0.path())
-                    .map(|p| Arc::new(CellPath::new(self.0.cell(), p))))
-            }
-
-            fn validity(x: &Self::Value) -> bool {
-                x.is_ok()
-            }
-
-            fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-                match (x, y) {
-                    (Ok(x), Ok(y)) => x == y,
-                    _ => false,
-                }
-            }
-        }
-
         self.compute(&PackageBoundaryExceptionKey(path.to_owned()))
             .await?
     }
@@ -17,13 +17,21 @@ use buck2_core::package::PackageLabel;
 use buck2_events::dispatch::async_record_root_spans;
 use buck2_events::span::SpanId;
 use buck2_futures::cancellation::CancellationContext;
+use dice::ConcurrencyClass;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 use smallvec::SmallVec;
 
 use crate::package_listing::interpreter::InterpreterPackageListingResolver;
 use crate::package_listing::listing::PackageListing;
+use crate::package_listing::listing::PersistedPackageListing;
 use crate::package_listing::resolver::PackageListingResolver;
 
 #[derive(
@@ -36,8 +44,14 @@ use crate::package_listing::resolver::PackageListingResolver;
     PartialEq,
     Allocative
 )]
+#[derive(Serialize, Deserialize)]
 pub struct PackageListingKey(pub PackageLabel);
 
+/// Listing a package walks its directories, so wide fan-outs of listings are limited to leave
+/// room for the interpreter, which starts first.
+const PACKAGE_LISTING_CONCURRENCY: ConcurrencyClass =
+    ConcurrencyClass::new("package_listing", 64, 0);
+
 pub struct PackageListingKeyActivationData {
     pub duration: Duration,
     pub spans: SmallVec<[SpanId; 1]>,
@@ -72,6 +86,31 @@ impl Key for PackageListingKey {
             _ => false,
         }
     }
+
+    fn concurrency_class() -> Option<ConcurrencyClass> {
+        Some(PACKAGE_LISTING_CONCURRENCY)
+    }
+}
+
+impl PersistentKey for PackageListingKey {
+    const PERSISTENT_ID: &'static str = "PackageListingKey";
+}
+
+impl PersistentValue for PackageListingKey {
+    type Persisted = PersistedPackageListing;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        value.as_ref().ok().map(PackageListing::to_persisted)
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(Ok(value.into_package_listing()?))
+    }
+}
+
+/// Registers the package listings, so that they are saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<PackageListingKey>()
 }
 
 pub struct DicePackageListingResolver<'compute, 'dice>(pub &'compute mut DiceComputations<'dice>);
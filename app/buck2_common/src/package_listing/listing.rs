@@ -15,6 +15,8 @@ use buck2_core::fs::paths::file_name::FileNameBuf;
 use buck2_core::package::package_relative_path::PackageRelativePath;
 use buck2_util::arc_str::ArcS;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark_map::sorted_set::SortedSet;
 use starlark_map::sorted_vec::SortedVec;
 
@@ -99,6 +101,46 @@ impl PackageListing {
     pub fn buildfile(&self) -> &FileName {
         &self.listing.buildfile
     }
+
+    pub(crate) fn to_persisted(&self) -> PersistedPackageListing {
+        fn paths<'a>(paths: impl Iterator<Item = &'a ArcS<PackageRelativePath>>) -> Vec<String> {
+            paths.map(|p| p.as_str().to_owned()).collect()
+        }
+
+        PersistedPackageListing {
+            files: paths(self.listing.files.files.iter()),
+            directories: paths(self.listing.directories.iter()),
+            subpackages: paths(self.listing.subpackages.iter()),
+            buildfile: self.listing.buildfile.as_str().to_owned(),
+        }
+    }
+}
+
+/// A `PackageListing` as saved in DICE snapshots.
+#[derive(Serialize, Deserialize)]
+pub struct PersistedPackageListing {
+    files: Vec<String>,
+    directories: Vec<String>,
+    subpackages: Vec<String>,
+    buildfile: String,
+}
+
+impl PersistedPackageListing {
+    pub(crate) fn into_package_listing(self) -> anyhow::Result<PackageListing> {
+        fn paths(paths: Vec<String>) -> anyhow::Result<Vec<ArcS<PackageRelativePath>>> {
+            paths
+                .iter()
+                .map(|p| Ok(PackageRelativePath::new(p)?.to_arc()))
+                .collect()
+        }
+
+        Ok(PackageListing::new(
+            SortedSet::from_iter(paths(self.files)?),
+            SortedSet::from_iter(paths(self.directories)?),
+            SortedVec::from_iter(paths(self.subpackages)?),
+            FileNameBuf::try_from(self.buildfile)?,
+        ))
+    }
 }
 
 pub mod testing {
@@ -13,10 +13,14 @@ use buck2_core::target_aliases::TargetAliasResolver;
 use buck2_futures::cancellation::CancellationContext;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
 use dupe::Dupe;
 use indexmap::IndexSet;
 use itertools::Itertools;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::dice::cells::HasCellResolver;
 use crate::legacy_configs::configs::LegacyBuckConfig;
@@ -129,6 +133,7 @@ pub trait HasTargetAliasResolver {
 }
 
 #[derive(Debug, Display, Hash, PartialEq, Eq, Clone, Allocative)]
+#[derive(Serialize, Deserialize)]
 struct TargetAliasResolverKey();
 
 #[async_trait]
@@ -153,6 +158,15 @@ impl Key for TargetAliasResolverKey {
     }
 }
 
+impl PersistentKey for TargetAliasResolverKey {
+    const PERSISTENT_ID: &'static str = "TargetAliasResolverKey";
+}
+
+/// Registers the target alias resolver, so that it is saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register_without_value::<TargetAliasResolverKey>()
+}
+
 #[async_trait]
 impl HasTargetAliasResolver for DiceComputations<'_> {
     async fn target_alias_resolver(&mut self) -> anyhow::Result<BuckConfigTargetAliasResolver> {
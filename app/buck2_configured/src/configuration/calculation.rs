@@ -22,6 +22,7 @@ use starlark_map::unordered_map::UnorderedMap;
 use buck2_core::configuration::compatibility::MaybeCompatible;
 use buck2_core::configuration::config_setting::ConfigSettingData;
 use buck2_core::configuration::data::ConfigurationData;
+use buck2_core::configuration::data::PersistedConfigurationData;
 use buck2_core::configuration::pair::ConfigurationNoExec;
 use buck2_core::target::label::label::TargetLabel;
 use buck2_core::execution_types::execution::ExecutionPlatform;
@@ -40,8 +41,14 @@ use buck2_node::configuration::toolchain_constraints::ToolchainConstraints;
 use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 use buck2_futures::cancellation::CancellationContext;
 use buck2_build_api::analysis::calculation::RuleAnalysisCalculation;
 use buck2_build_api::interpreter::rule_defs::provider::builtin::configuration_info::FrozenConfigurationInfo;
@@ -65,59 +72,60 @@ pub enum ConfigurationError {
     PlatformEvalUnequalConfiguration(TargetLabel, TargetLabel),
 }
 
+#[derive(Clone, Display, Debug, Dupe, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "TargetPlatformDetectorKey")]
+struct TargetPlatformDetectorKey;
+
+#[async_trait]
+impl Key for TargetPlatformDetectorKey {
+    type Value = buck2_error::Result<Arc<TargetPlatformDetector>>;
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        // We get this off the root cell's config. It's not clear that that's the appropriate way to do it, but its the easiest to get working at FB.
+        // TODO(cjhopman): Consider revisiting that approach.
+        let resolver = ctx.get_cell_resolver().await?;
+        let root_cell = resolver.root_cell();
+        let cell_alias_resolver = ctx.get_cell_alias_resolver(root_cell).await?;
+
+        Ok(Arc::new(
+            match ctx
+                .get_legacy_config_property(
+                    root_cell,
+                    BuckconfigKeyRef {
+                        section: "parser",
+                        property: "target_platform_detector_spec",
+                    },
+                )
+                .await?
+            {
+                None => TargetPlatformDetector::empty(),
+                Some(spec) => TargetPlatformDetector::parse_spec(
+                    &spec,
+                    root_cell,
+                    &resolver,
+                    &cell_alias_resolver,
+                )?,
+            },
+        ))
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
 async fn get_target_platform_detector(
     ctx: &mut DiceComputations<'_>,
 ) -> buck2_error::Result<Arc<TargetPlatformDetector>> {
     // This requires a bit of computation so cache it on the graph.
     // TODO(cjhopman): Should we construct this (and similar buckconfig-derived objects) as part of the buck config itself?
-    #[derive(Clone, Display, Debug, Dupe, Eq, Hash, PartialEq, Allocative)]
-    #[display(fmt = "TargetPlatformDetectorKey")]
-    struct TargetPlatformDetectorKey;
-
-    #[async_trait]
-    impl Key for TargetPlatformDetectorKey {
-        type Value = buck2_error::Result<Arc<TargetPlatformDetector>>;
-        async fn compute(
-            &self,
-            ctx: &mut DiceComputations,
-            _cancellation: &CancellationContext,
-        ) -> Self::Value {
-            // We get this off the root cell's config. It's not clear that that's the appropriate way to do it, but its the easiest to get working at FB.
-            // TODO(cjhopman): Consider revisiting that approach.
-            let resolver = ctx.get_cell_resolver().await?;
-            let root_cell = resolver.root_cell();
-            let cell_alias_resolver = ctx.get_cell_alias_resolver(root_cell).await?;
-
-            Ok(Arc::new(
-                match ctx
-                    .get_legacy_config_property(
-                        root_cell,
-                        BuckconfigKeyRef {
-                            section: "parser",
-                            property: "target_platform_detector_spec",
-                        },
-                    )
-                    .await?
-                {
-                    None => TargetPlatformDetector::empty(),
-                    Some(spec) => TargetPlatformDetector::parse_spec(
-                        &spec,
-                        root_cell,
-                        &resolver,
-                        &cell_alias_resolver,
-                    )?,
-                },
-            ))
-        }
-
-        fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-            match (x, y) {
-                (Ok(x), Ok(y)) => x == y,
-                _ => false,
-            }
-        }
-    }
-
     ctx.compute(&TargetPlatformDetectorKey).await?
 }
 
@@ -331,18 +339,22 @@ async fn configuration_matches(
 }
 
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "ExecutionPlatforms")]
 pub struct ExecutionPlatformsKey;
 
 #[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "ConfigurationNode({}, {})", cfg_target, target_cfg)]
 struct ConfigurationNodeKey {
+    #[serde(with = "buck2_core::configuration::data::persisted")]
     target_cfg: ConfigurationData,
     target_cell: CellName,
     cfg_target: ConfigurationSettingKey,
 }
 
 #[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(
     fmt = "ResolvedConfigurationKey(target_cfg: {}, cell: {}, configuration_deps size {})",
     target_cfg,
@@ -350,6 +362,7 @@ struct ConfigurationNodeKey {
     "configuration_deps.len()"
 )]
 struct ResolvedConfigurationKey {
+    #[serde(with = "buck2_core::configuration::data::persisted")]
     target_cfg: ConfigurationData,
     target_cell: CellName,
     configuration_deps: Vec<ConfigurationSettingKey>,
@@ -533,37 +546,75 @@ impl Key for ConfigurationNodeKey {
     }
 }
 
+#[derive(derive_more::Display, Debug, Eq, Hash, PartialEq, Clone, Allocative)]
+#[derive(Serialize, Deserialize)]
+struct PlatformConfigurationKey(TargetLabel);
+
+#[async_trait]
+impl Key for PlatformConfigurationKey {
+    type Value = buck2_error::Result<ConfigurationData>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        compute_platform_configuration(ctx, &self.0)
+            .await
+            .map_err(buck2_error::Error::from)
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "{:?}", self)]
+struct ExecutionPlatformResolutionKey {
+    target_node_cell: CellName,
+    exec_compatible_with: Arc<[ConfigurationSettingKey]>,
+    exec_deps: Arc<[TargetLabel]>,
+    toolchain_allows: Arc<[ToolchainConstraints]>,
+}
+
+#[async_trait]
+impl Key for ExecutionPlatformResolutionKey {
+    type Value = buck2_error::Result<ExecutionPlatformResolution>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        resolve_execution_platform_from_constraints(
+            ctx,
+            self.target_node_cell,
+            &self.exec_compatible_with,
+            &self.exec_deps,
+            &self.toolchain_allows,
+        )
+        .await
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
 #[async_trait]
 impl ConfigurationCalculation for DiceComputations<'_> {
     async fn get_platform_configuration(
         &mut self,
         target: &TargetLabel,
     ) -> anyhow::Result<ConfigurationData> {
-        #[derive(derive_more::Display, Debug, Eq, Hash, PartialEq, Clone, Allocative)]
-        struct PlatformConfigurationKey(TargetLabel);
-
-        #[async_trait]
-        impl Key for PlatformConfigurationKey {
-            type Value = buck2_error::Result<ConfigurationData>;
-
-            async fn compute(
-                &self,
-                ctx: &mut DiceComputations,
-                _cancellation: &CancellationContext,
-            ) -> Self::Value {
-                compute_platform_configuration(ctx, &self.0)
-                    .await
-                    .map_err(buck2_error::Error::from)
-            }
-
-            fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-                match (x, y) {
-                    (Ok(x), Ok(y)) => x == y,
-                    _ => false,
-                }
-            }
-        }
-
         self.compute(&PlatformConfigurationKey(target.dupe()))
             .await?
             .map_err(anyhow::Error::from)
@@ -631,41 +682,6 @@ impl ConfigurationCalculation for DiceComputations<'_> {
         exec_deps: Arc<[TargetLabel]>,
         toolchain_allows: Arc<[ToolchainConstraints]>,
     ) -> buck2_error::Result<ExecutionPlatformResolution> {
-        #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
-        #[display(fmt = "{:?}", self)]
-        struct ExecutionPlatformResolutionKey {
-            target_node_cell: CellName,
-            exec_compatible_with: Arc<[ConfigurationSettingKey]>,
-            exec_deps: Arc<[TargetLabel]>,
-            toolchain_allows: Arc<[ToolchainConstraints]>,
-        }
-
-        #[async_trait]
-        impl Key for ExecutionPlatformResolutionKey {
-            type Value = buck2_error::Result<ExecutionPlatformResolution>;
-
-            async fn compute(
-                &self,
-                ctx: &mut DiceComputations,
-                _cancellation: &CancellationContext,
-            ) -> Self::Value {
-                resolve_execution_platform_from_constraints(
-                    ctx,
-                    self.target_node_cell,
-                    &self.exec_compatible_with,
-                    &self.exec_deps,
-                    &self.toolchain_allows,
-                )
-                .await
-            }
-
-            fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-                match (x, y) {
-                    (Ok(x), Ok(y)) => x == y,
-                    _ => false,
-                }
-            }
-        }
         self.compute(&ExecutionPlatformResolutionKey {
             target_node_cell,
             exec_compatible_with,
@@ -705,6 +721,52 @@ impl GetExecutionPlatformsImpl for GetExecutionPlatformsInstance {
     }
 }
 
+impl PersistentKey for TargetPlatformDetectorKey {
+    const PERSISTENT_ID: &'static str = "TargetPlatformDetectorKey";
+}
+
+impl PersistentKey for ExecutionPlatformsKey {
+    const PERSISTENT_ID: &'static str = "ExecutionPlatformsKey";
+}
+
+impl PersistentKey for ConfigurationNodeKey {
+    const PERSISTENT_ID: &'static str = "ConfigurationNodeKey";
+}
+
+impl PersistentKey for ResolvedConfigurationKey {
+    const PERSISTENT_ID: &'static str = "ResolvedConfigurationKey";
+}
+
+impl PersistentKey for PlatformConfigurationKey {
+    const PERSISTENT_ID: &'static str = "PlatformConfigurationKey";
+}
+
+impl PersistentValue for PlatformConfigurationKey {
+    type Persisted = PersistedConfigurationData;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        value.as_ref().ok().map(ConfigurationData::to_persisted)
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(Ok(value.into_configuration_data()?))
+    }
+}
+
+impl PersistentKey for ExecutionPlatformResolutionKey {
+    const PERSISTENT_ID: &'static str = "ExecutionPlatformResolutionKey";
+}
+
+/// Registers the configuration keys, so that they are saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register_without_value::<TargetPlatformDetectorKey>()?;
+    persistence.register_without_value::<ExecutionPlatformsKey>()?;
+    persistence.register_without_value::<ConfigurationNodeKey>()?;
+    persistence.register_without_value::<ResolvedConfigurationKey>()?;
+    persistence.register::<PlatformConfigurationKey>()?;
+    persistence.register_without_value::<ExecutionPlatformResolutionKey>()
+}
+
 pub(crate) fn init_get_execution_platforms() {
     GET_EXECUTION_PLATFORMS.init(&GetExecutionPlatformsInstance);
 }
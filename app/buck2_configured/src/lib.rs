@@ -20,3 +20,10 @@ pub fn init_late_bindings() {
     configuration::calculation::init_get_execution_platforms();
     nodes::calculation::init_configured_target_node_calculation();
 }
+
+/// Registers the configuration and configured target node keys, so that they are saved in DICE
+/// snapshots.
+pub fn register_persistent_keys(persistence: &mut dice::DicePersistence) -> anyhow::Result<()> {
+    configuration::calculation::register_persistent_keys(persistence)?;
+    nodes::calculation::register_persistent_keys(persistence)
+}
@@ -52,6 +52,7 @@ use buck2_node::attrs::inspect_options::AttrInspectOptions;
 use buck2_node::attrs::internal::EXEC_COMPATIBLE_WITH_ATTRIBUTE_FIELD;
 use buck2_node::attrs::internal::LEGACY_TARGET_COMPATIBLE_WITH_ATTRIBUTE_FIELD;
 use buck2_node::attrs::internal::TARGET_COMPATIBLE_WITH_ATTRIBUTE_FIELD;
+use buck2_node::compute_counters::HasComputeCounters;
 use buck2_node::configuration::resolved::ConfigurationSettingKey;
 use buck2_node::configuration::resolved::ResolvedConfiguration;
 use buck2_node::configuration::resolved::ResolvedConfigurationSettings;
@@ -67,9 +68,15 @@ use buck2_node::nodes::unconfigured::TargetNodeRef;
 use buck2_node::visibility::VisibilityError;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use dupe::Dupe;
 use futures::FutureExt;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark_map::ordered_map::OrderedMap;
 use starlark_map::small_map::SmallMap;
 use starlark_map::small_set::SmallSet;
@@ -277,73 +284,74 @@ impl ExecutionPlatformConstraints {
     }
 }
 
-async fn execution_platforms_for_toolchain(
-    ctx: &mut DiceComputations<'_>,
-    target: TargetConfiguredTargetLabel,
-) -> buck2_error::Result<ToolchainConstraints> {
-    #[derive(Clone, Display, Debug, Dupe, Eq, Hash, PartialEq, Allocative)]
-    struct ExecutionPlatformsForToolchainKey(TargetConfiguredTargetLabel);
+#[derive(Clone, Display, Debug, Dupe, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
+struct ExecutionPlatformsForToolchainKey(TargetConfiguredTargetLabel);
 
-    #[async_trait]
-    impl Key for ExecutionPlatformsForToolchainKey {
-        type Value = buck2_error::Result<ToolchainConstraints>;
-        async fn compute(
-            &self,
-            ctx: &mut DiceComputations,
-            _cancellation: &CancellationContext,
-        ) -> Self::Value {
-            let node = ctx.get_target_node(self.0.unconfigured()).await?;
-            if node.transition_deps().next().is_some() {
-                // We could actually check this when defining the rule, but a bit of a corner
-                // case, and much simpler to do so here.
-                return Err(buck2_error::Error::new(
-                    ToolchainDepError::ToolchainTransitionDep(self.0.unconfigured().dupe()),
-                ));
-            }
-            let resolved_configuration = &ctx
-                .get_resolved_configuration(
-                    self.0.cfg(),
-                    self.0.pkg().cell_name(),
-                    node.get_configuration_deps(),
-                )
-                .await?;
-            let platform_cfgs = compute_platform_cfgs(ctx, node.as_ref()).await?;
-            // We don't really need `resolved_transitions` here:
-            // `Traversal` declared above ignores transitioned dependencies.
-            // But we pass `resolved_transitions` here to prevent breakages in the future
-            // if something here changes.
-            let resolved_transitions = OrderedMap::new();
-            let cfg_ctx = AttrConfigurationContextImpl::new(
-                resolved_configuration,
-                ConfigurationNoExec::unbound_exec(),
-                &resolved_transitions,
-                &platform_cfgs,
-            );
-            let (gathered_deps, errors_and_incompats) =
-                gather_deps(&self.0, node.as_ref(), &cfg_ctx, ctx).await?;
-            if let Some(ret) = errors_and_incompats.finalize() {
-                // Statically assert that we hit one of the `?`s
-                enum Void {}
-                let _: Void = ret?.require_compatible()?;
-            }
-            let constraints =
-                ExecutionPlatformConstraints::new(node.as_ref(), &gathered_deps, &cfg_ctx)?;
-            let toolchain_allows = constraints.toolchain_allows(ctx).await?;
-            Ok(ToolchainConstraints::new(
-                &constraints.exec_deps,
-                &constraints.exec_compatible_with,
-                &toolchain_allows,
-            ))
+#[async_trait]
+impl Key for ExecutionPlatformsForToolchainKey {
+    type Value = buck2_error::Result<ToolchainConstraints>;
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let node = ctx.get_target_node(self.0.unconfigured()).await?;
+        if node.transition_deps().next().is_some() {
+            // We could actually check this when defining the rule, but a bit of a corner
+            // case, and much simpler to do so here.
+            return Err(buck2_error::Error::new(
+                ToolchainDepError::ToolchainTransitionDep(self.0.unconfigured().dupe()),
+            ));
+        }
+        let resolved_configuration = &ctx
+            .get_resolved_configuration(
+                self.0.cfg(),
+                self.0.pkg().cell_name(),
+                node.get_configuration_deps(),
+            )
+            .await?;
+        let platform_cfgs = compute_platform_cfgs(ctx, node.as_ref()).await?;
+        // We don't really need `resolved_transitions` here:
+        // `Traversal` declared above ignores transitioned dependencies.
+        // But we pass `resolved_transitions` here to prevent breakages in the future
+        // if something here changes.
+        let resolved_transitions = OrderedMap::new();
+        let cfg_ctx = AttrConfigurationContextImpl::new(
+            resolved_configuration,
+            ConfigurationNoExec::unbound_exec(),
+            &resolved_transitions,
+            &platform_cfgs,
+        );
+        let (gathered_deps, errors_and_incompats) =
+            gather_deps(&self.0, node.as_ref(), &cfg_ctx, ctx).await?;
+        if let Some(ret) = errors_and_incompats.finalize() {
+            // Statically assert that we hit one of the `?`s
+            enum Void {}
+            let _: Void = ret?.require_compatible()?;
         }
+        let constraints =
+            ExecutionPlatformConstraints::new(node.as_ref(), &gathered_deps, &cfg_ctx)?;
+        let toolchain_allows = constraints.toolchain_allows(ctx).await?;
+        Ok(ToolchainConstraints::new(
+            &constraints.exec_deps,
+            &constraints.exec_compatible_with,
+            &toolchain_allows,
+        ))
+    }
 
-        fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-            match (x, y) {
-                (Ok(x), Ok(y)) => x == y,
-                _ => false,
-            }
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
         }
     }
+}
 
+async fn execution_platforms_for_toolchain(
+    ctx: &mut DiceComputations<'_>,
+    target: TargetConfiguredTargetLabel,
+) -> buck2_error::Result<ToolchainConstraints> {
     ctx.compute(&ExecutionPlatformsForToolchainKey(target))
         .await?
 }
@@ -1308,16 +1316,23 @@ async fn compute_configured_forward_target_node(
 }
 
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
-pub struct ConfiguredTargetNodeKey(pub ConfiguredTargetLabel);
+#[derive(Serialize, Deserialize)]
+pub struct ConfiguredTargetNodeKey(
+    #[serde(with = "buck2_core::configuration::data::persisted_configured_target_label")]
+    pub  ConfiguredTargetLabel,
+);
 
 /// Similar to [`ConfiguredTargetNodeKey`], but used when the target
 /// is transitioned to different configuration because rule definition requires it.
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "ConfiguredTransitionedNodeKey({}, {})", forward, transitioned)]
 pub struct ConfiguredTransitionedNodeKey {
     /// Forward node label.
+    #[serde(with = "buck2_core::configuration::data::persisted_configured_target_label")]
     forward: ConfiguredTargetLabel,
     /// Transitional node label.
+    #[serde(with = "buck2_core::configuration::data::persisted_configured_target_label")]
     transitioned: ConfiguredTargetLabel,
 }
 
@@ -1411,6 +1426,7 @@ impl Key for ConfiguredTargetNodeKey {
         ctx: &mut DiceComputations,
         _cancellation: &CancellationContext,
     ) -> Self::Value {
+        ctx.record_configured_node_computed();
         let res = compute_configured_target_node(self, ctx).await;
         Ok(LookingUpConfiguredNodeContext::add_context(
             res,
@@ -1426,6 +1442,39 @@ impl Key for ConfiguredTargetNodeKey {
     }
 }
 
+impl PersistentKey for ExecutionPlatformsForToolchainKey {
+    const PERSISTENT_ID: &'static str = "ExecutionPlatformsForToolchainKey";
+}
+
+impl PersistentValue for ExecutionPlatformsForToolchainKey {
+    type Persisted = ToolchainConstraints;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        value.as_ref().ok().map(Dupe::dupe)
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(Ok(value))
+    }
+}
+
+impl PersistentKey for ConfiguredTargetNodeKey {
+    const PERSISTENT_ID: &'static str = "ConfiguredTargetNodeKey";
+}
+
+impl PersistentKey for ConfiguredTransitionedNodeKey {
+    const PERSISTENT_ID: &'static str = "ConfiguredTransitionedNodeKey";
+}
+
+/// Registers the configured target node keys, so that they are saved in DICE snapshots. Nodes
+/// which depend on transitions or on the configuration constructor aren't saved, as their keys
+/// aren't registered.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<ExecutionPlatformsForToolchainKey>()?;
+    persistence.register_without_value::<ConfiguredTargetNodeKey>()?;
+    persistence.register_without_value::<ConfiguredTransitionedNodeKey>()
+}
+
 #[async_trait]
 impl ConfiguredTargetNodeCalculationImpl for ConfiguredTargetNodeCalculationInstance {
     async fn get_configured_target_node(
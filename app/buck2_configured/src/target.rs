@@ -11,6 +11,8 @@ use buck2_core::configuration::data::ConfigurationData;
 use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
 use buck2_core::target::label::label::TargetLabel;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 /// A wrapper around a configured target label.
 ///
@@ -32,7 +34,11 @@ use dupe::Dupe;
     PartialOrd,
     allocative::Allocative
 )]
-pub struct TargetConfiguredTargetLabel(ConfiguredTargetLabel);
+#[derive(Serialize, Deserialize)]
+pub struct TargetConfiguredTargetLabel(
+    #[serde(with = "buck2_core::configuration::data::persisted_configured_target_label")]
+    ConfiguredTargetLabel,
+);
 
 impl TargetConfiguredTargetLabel {
     pub fn new_without_exec_cfg(label: ConfiguredTargetLabel) -> Self {
@@ -11,6 +11,8 @@ use std::fmt::Display;
 use std::fmt::Formatter;
 
 use allocative::Allocative;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::cells::build_file_cell::BuildFileCell;
 use crate::cells::cell_path::CellPath;
@@ -29,6 +31,7 @@ enum ImportPathError {
 
 /// Path of a `.bzl` file.
 #[derive(Clone, Hash, Eq, PartialEq, Debug, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub struct ImportPath {
     /// The path to the import as a 'CellPath', which contains the cell
     /// information and the cell relative path to the bzl file itself, including the bzl suffix
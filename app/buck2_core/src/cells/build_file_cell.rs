@@ -9,6 +9,8 @@
 
 use allocative::Allocative;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::cells::name::CellName;
 
@@ -26,6 +28,7 @@ use crate::cells::name::CellName;
     derive_more::Display,
     Allocative
 )]
+#[derive(Serialize, Deserialize)]
 #[repr(C)]
 pub struct BuildFileCell(CellName);
 
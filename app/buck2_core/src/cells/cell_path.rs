@@ -11,6 +11,11 @@ use allocative::Allocative;
 use anyhow::Context;
 use dupe::Dupe;
 use relative_path::RelativePath;
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
 
 use crate::cells::name::CellName;
 use crate::cells::paths::CellRelativePath;
@@ -303,6 +308,27 @@ impl CellPath {
     }
 }
 
+/// Serialized as a `(cell, path)` pair.
+impl Serialize for CellPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (self.cell, self.path.as_str()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CellPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (cell, path) = <(CellName, String)>::deserialize(deserializer)?;
+        let path = CellRelativePathBuf::try_from(path).map_err(D::Error::custom)?;
+        Ok(CellPath::new(cell, path))
+    }
+}
+
 #[derive(Debug, Clone, Dupe, Copy, Eq, PartialEq, Hash, derive_more::Display)]
 #[display(fmt = "{}//{}", cell, path)]
 pub struct CellPathRef<'a> {
@@ -12,6 +12,8 @@ use std::ops::Deref;
 
 use allocative::Allocative;
 use ref_cast::RefCast;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::cells::paths::CellRelativePath;
 use crate::fs::project_rel_path::ProjectRelativePath;
@@ -69,6 +71,7 @@ impl AsRef<ProjectRelativePath> for CellRootPath {
 
 /// Path to the cell root.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, derive_more::Display, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub struct CellRootPathBuf(ProjectRelativePathBuf);
 
 impl CellRootPathBuf {
@@ -15,6 +15,11 @@ use buck2_util::hash::BuckHasher;
 use derive_more::Display;
 use dupe::Dupe;
 use equivalent::Equivalent;
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
 use static_interner::Intern;
 use static_interner::Interner;
 
@@ -85,3 +90,22 @@ impl CellName {
         &self.0.deref_static().0
     }
 }
+
+impl Serialize for CellName {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.as_str().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CellName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        CellName::unchecked_new(&name).map_err(D::Error::custom)
+    }
+}
@@ -13,10 +13,13 @@ use std::fmt::Formatter;
 
 use allocative::Allocative;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 #[derive(
     Debug, Copy, Clone, Dupe, Eq, PartialEq, Hash, Ord, PartialOrd, Allocative
 )]
+#[derive(Serialize, Deserialize)]
 pub enum BuiltinPlatform {
     /// The unbound platform is used when we don't yet have a platform bound. This is to support initialization
     /// and is used when analyzing a platform target itself (since we clearly can't have a platform yet bound
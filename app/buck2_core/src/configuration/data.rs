@@ -18,6 +18,7 @@ use buck2_util::hash::BuckHasher;
 use dupe::Dupe;
 use equivalent::Equivalent;
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 use serde::Serialize;
 use serde::Serializer;
 use static_interner::Intern;
@@ -29,6 +30,9 @@ use crate::configuration::builtin::BuiltinPlatform;
 use crate::configuration::constraints::ConstraintKey;
 use crate::configuration::constraints::ConstraintValue;
 use crate::configuration::hash::ConfigurationHash;
+use crate::configuration::pair::Configuration;
+use crate::target::configured_target_label::ConfiguredTargetLabel;
+use crate::target::label::label::TargetLabel;
 
 #[derive(Debug, buck2_error::Error)]
 #[buck2(input)]
@@ -270,6 +274,135 @@ impl Serialize for ConfigurationData {
     }
 }
 
+/// A `ConfigurationData` serialized with its label and constraints rather than its name, so that
+/// another process can restore it (e.g. from DICE snapshots).
+#[derive(Serialize, Deserialize, Debug)]
+pub enum PersistedConfigurationData {
+    Builtin(BuiltinPlatform),
+    Bound {
+        label: String,
+        constraints: Vec<(TargetLabel, TargetLabel)>,
+    },
+}
+
+impl ConfigurationData {
+    pub fn to_persisted(&self) -> PersistedConfigurationData {
+        match &self.0.configuration_platform {
+            ConfigurationPlatform::Builtin(builtin) => {
+                PersistedConfigurationData::Builtin(*builtin)
+            }
+            ConfigurationPlatform::Bound(label, data) => PersistedConfigurationData::Bound {
+                label: label.as_str().to_owned(),
+                constraints: data
+                    .constraints
+                    .iter()
+                    .map(|(k, v)| (k.0.dupe(), v.0.dupe()))
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl PersistedConfigurationData {
+    pub fn into_configuration_data(self) -> anyhow::Result<ConfigurationData> {
+        match self {
+            PersistedConfigurationData::Builtin(builtin) => Ok(ConfigurationData::builtin(builtin)),
+            PersistedConfigurationData::Bound { label, constraints } => {
+                ConfigurationData::from_platform(
+                    label,
+                    ConfigurationDataData::new(
+                        constraints
+                            .into_iter()
+                            .map(|(k, v)| (ConstraintKey(k), ConstraintValue(v)))
+                            .collect(),
+                    ),
+                )
+            }
+        }
+    }
+}
+
+/// For `#[serde(with = "...")]`, to (de)serialize a `ConfigurationData` as a
+/// [`PersistedConfigurationData`].
+pub mod persisted {
+    use serde::de::Error as _;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    use crate::configuration::data::ConfigurationData;
+    use crate::configuration::data::PersistedConfigurationData;
+    use crate::configuration::data::PersistedConfiguredTargetLabel;
+    use crate::configuration::pair::Configuration;
+
+    pub fn serialize<S: Serializer>(cfg: &ConfigurationData, s: S) -> Result<S::Ok, S::Error> {
+        cfg.to_persisted().serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<ConfigurationData, D::Error> {
+        PersistedConfigurationData::deserialize(d)?
+            .into_configuration_data()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// A `ConfiguredTargetLabel` serialized with its configurations as
+/// [`PersistedConfigurationData`], so that another process can restore it.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PersistedConfiguredTargetLabel {
+    target: TargetLabel,
+    cfg: PersistedConfigurationData,
+    exec_cfg: Option<PersistedConfigurationData>,
+}
+
+impl PersistedConfiguredTargetLabel {
+    pub fn new(label: &ConfiguredTargetLabel) -> Self {
+        PersistedConfiguredTargetLabel {
+            target: label.unconfigured().dupe(),
+            cfg: label.cfg().to_persisted(),
+            exec_cfg: label.exec_cfg().map(ConfigurationData::to_persisted),
+        }
+    }
+
+    pub fn into_label(self) -> anyhow::Result<ConfiguredTargetLabel> {
+        let cfg = self.cfg.into_configuration_data()?;
+        let exec_cfg = self
+            .exec_cfg
+            .map(PersistedConfigurationData::into_configuration_data)
+            .transpose()?;
+        Ok(self
+            .target
+            .configure_pair(Configuration::new(cfg, exec_cfg)))
+    }
+}
+
+/// For `#[serde(with = "...")]`, to (de)serialize a `ConfiguredTargetLabel` as a
+/// [`PersistedConfiguredTargetLabel`].
+pub mod persisted_configured_target_label {
+    use serde::de::Error as _;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    use crate::configuration::data::PersistedConfiguredTargetLabel;
+    use crate::target::configured_target_label::ConfiguredTargetLabel;
+
+    pub fn serialize<S: Serializer>(
+        label: &ConfiguredTargetLabel,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        PersistedConfiguredTargetLabel::new(label).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<ConfiguredTargetLabel, D::Error> {
+        PersistedConfiguredTargetLabel::deserialize(d)?
+            .into_label()
+            .map_err(D::Error::custom)
+    }
+}
+
 impl ToProtoMessage for ConfigurationData {
     type Message = buck2_data::Configuration;
 
@@ -400,6 +533,7 @@ mod tests {
     use crate::configuration::constraints::ConstraintValue;
     use crate::configuration::data::ConfigurationData;
     use crate::configuration::data::ConfigurationDataData;
+    use crate::configuration::data::PersistedConfigurationData;
     use crate::target::label::label::TargetLabel;
 
     /// We don't want the output hash to change by accident. This test is here to assert that it
@@ -433,6 +567,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_persisted_round_trip() -> anyhow::Result<()> {
+        let bound = ConfigurationData::from_platform(
+            "cfg_for//:testing_persisted".to_owned(),
+            ConfigurationDataData {
+                constraints: BTreeMap::from_iter([(
+                    ConstraintKey(TargetLabel::testing_parse("foo//bar:c")),
+                    ConstraintValue(TargetLabel::testing_parse("foo//bar:v")),
+                )]),
+            },
+        )?;
+        for configuration in [bound, ConfigurationData::unbound_exec()] {
+            let json = serde_json::to_string(&configuration.to_persisted())?;
+            let restored = serde_json::from_str::<PersistedConfigurationData>(&json)?
+                .into_configuration_data()?;
+            assert_eq!(configuration, restored);
+            assert_eq!(configuration.output_hash(), restored.output_hash());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_configured_target_label_round_trip() -> anyhow::Result<()> {
+        let label = TargetLabel::testing_parse("foo//bar:baz").configure_pair(Configuration::new(
+            ConfigurationData::testing_new(),
+            Some(ConfigurationData::unbound_exec()),
+        ));
+        let json = serde_json::to_string(&PersistedConfiguredTargetLabel::new(&label))?;
+        let restored =
+            serde_json::from_str::<PersistedConfiguredTargetLabel>(&json)?.into_label()?;
+        assert_eq!(label, restored);
+        Ok(())
+    }
+
     #[test]
     fn test_lookup_from_string() {
         let configuration = ConfigurationData::from_platform(
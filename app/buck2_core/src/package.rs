@@ -49,6 +49,10 @@ use buck2_util::hash::BuckHasher;
 use derive_more::Display;
 use dupe::Dupe;
 use equivalent::Equivalent;
+use serde::de::value::StrDeserializer;
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Deserializer;
 use serde::Serialize;
 use serde::Serializer;
 use static_interner::Intern;
@@ -60,6 +64,8 @@ use crate::cells::name::CellName;
 use crate::cells::paths::CellRelativePath;
 use crate::fs::paths::fmt::quoted_display;
 use crate::fs::paths::forward_rel_path::ForwardRelativePath;
+use crate::target::label::label::TargetLabel;
+use crate::target::name::TargetNameRef;
 
 /// A 'Package' as defined above.
 ///
@@ -78,6 +84,32 @@ impl Serialize for PackageLabel {
     }
 }
 
+/// Parses the fully qualified form written by `Serialize`, e.g. `cell//path/to/package`.
+impl<'de> Deserialize<'de> for PackageLabel {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let label = String::deserialize(d)?;
+        let (cell, path) = label
+            .split_once("//")
+            .ok_or_else(|| D::Error::custom(format!("Invalid package label `{label}`")))?;
+        let cell = CellName::unchecked_new(cell).map_err(D::Error::custom)?;
+        let path = ForwardRelativePath::new(path).map_err(D::Error::custom)?;
+        Ok(PackageLabel::new(cell, CellRelativePath::new(path)))
+    }
+}
+
+/// Parses the fully qualified form written by `Serialize`, e.g. `cell//path/to/package:name`.
+impl<'de> Deserialize<'de> for TargetLabel {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let label = String::deserialize(d)?;
+        let (package, name) = label
+            .rsplit_once(':')
+            .ok_or_else(|| D::Error::custom(format!("Invalid target label `{label}`")))?;
+        let package = PackageLabel::deserialize(StrDeserializer::<D::Error>::new(package))?;
+        let name = TargetNameRef::new(name).map_err(D::Error::custom)?;
+        Ok(TargetLabel::new(package, name))
+    }
+}
+
 #[derive(Debug, Display, Eq, PartialEq, Ord, PartialOrd, Allocative)]
 struct PackageLabelData(CellPath);
 
@@ -184,6 +216,7 @@ impl PackageLabel {
 #[cfg(test)]
 mod tests {
     use crate::package::PackageLabel;
+    use crate::target::label::label::TargetLabel;
 
     #[test]
     fn test_serialize() {
@@ -192,4 +225,26 @@ mod tests {
             serde_json::to_string(&PackageLabel::testing_parse("foo//bar/baz")).unwrap()
         );
     }
+
+    #[test]
+    fn test_deserialize() {
+        for label in ["foo//bar/baz", "foo//bar"] {
+            let package = PackageLabel::testing_parse(label);
+            let json = serde_json::to_string(&package).unwrap();
+            assert_eq!(
+                package,
+                serde_json::from_str::<PackageLabel>(&json).unwrap()
+            );
+        }
+        assert!(serde_json::from_str::<PackageLabel>(r#""foo/bar""#).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_target_label() {
+        let label = TargetLabel::testing_parse("foo//bar/baz:qux");
+        let json = serde_json::to_string(&label).unwrap();
+        assert_eq!(r#""foo//bar/baz:qux""#, json);
+        assert_eq!(label, serde_json::from_str::<TargetLabel>(&json).unwrap());
+        assert!(serde_json::from_str::<TargetLabel>(r#""foo//bar/baz""#).is_err());
+    }
 }
@@ -230,6 +230,14 @@ fn main() -> io::Result<()> {
             "buck.data.CommandExecutionKind.command",
             "#[derive(::derive_more::From, ::gazebo::variants::VariantName)]",
         )
+        .field_attribute(
+            "buck.data.TestScheduling.estimated_wall_time",
+            "#[serde(rename = \"estimated_wall_time_us\", with = \"crate::serialize_duration_as_micros\")]",
+        )
+        .field_attribute(
+            "buck.data.TestScheduling.actual_wall_time",
+            "#[serde(rename = \"actual_wall_time_us\", with = \"crate::serialize_duration_as_micros\")]",
+        )
         .field_attribute(
             "buck.data.CommandExecutionMetadata.wall_time",
             "#[serde(rename = \"wall_time_us\", with = \"crate::serialize_duration_as_micros\")]",
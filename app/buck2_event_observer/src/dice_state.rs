@@ -9,17 +9,20 @@
 
 use std::collections::BTreeMap;
 
+use buck2_data::DiceConcurrencyClassState;
 use buck2_data::DiceKeyState;
 use buck2_data::DiceStateSnapshot;
 
 pub struct DiceState {
     key_states: BTreeMap<String, DiceKeyState>,
+    concurrency_class_states: BTreeMap<String, DiceConcurrencyClassState>,
 }
 
 impl DiceState {
     pub fn new() -> Self {
         Self {
             key_states: BTreeMap::new(),
+            concurrency_class_states: BTreeMap::new(),
         }
     }
 
@@ -27,9 +30,32 @@ impl DiceState {
         for (k, v) in &update.key_states {
             self.key_states.insert(k.clone(), v.clone());
         }
+        for (k, v) in &update.concurrency_class_states {
+            self.concurrency_class_states.insert(k.clone(), v.clone());
+        }
     }
 
     pub fn key_states(&self) -> &BTreeMap<String, DiceKeyState> {
         &self.key_states
     }
+
+    pub fn concurrency_class_states(&self) -> &BTreeMap<String, DiceConcurrencyClassState> {
+        &self.concurrency_class_states
+    }
+
+    /// For each key type that was recomputed, the number of recomputations that were cut off as
+    /// their value didn't change, and the total number of recomputations. Snapshots count from the
+    /// start of the command that emitted them, not of the daemon.
+    pub fn early_cutoffs(&self) -> impl Iterator<Item = (&str, u32, u32)> {
+        self.key_states.iter().filter_map(|(k, v)| {
+            let recomputations = v.early_cutoffs + v.recompute_changes;
+            (recomputations > 0).then_some((k.as_str(), v.early_cutoffs, recomputations))
+        })
+    }
+}
+
+impl Default for DiceState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
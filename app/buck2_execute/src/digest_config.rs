@@ -13,6 +13,7 @@ use allocative::Allocative;
 use buck2_common::cas_digest::CasDigestConfig;
 use buck2_common::cas_digest::CasDigestConfigError;
 use buck2_common::cas_digest::DigestAlgorithm;
+use buck2_common::dice::data::SetCasDigestConfig;
 use buck2_common::file_ops::FileMetadata;
 use derivative::Derivative;
 use dice::DiceData;
@@ -116,6 +117,7 @@ impl HasDigestConfig for DiceData {
 
 impl SetDigestConfig for DiceDataBuilder {
     fn set_digest_config(&mut self, digest_config: DigestConfig) {
+        self.set_cas_digest_config(digest_config.cas_digest_config());
         self.set(digest_config)
     }
 }
@@ -291,6 +291,9 @@ pub struct CommandExecutionRequest {
     paths: CommandExecutionPaths,
     env: SortedVectorMap<String, String>,
     timeout: Option<Duration>,
+    /// Command to run against the process when it times out, before it gets killed. Only
+    /// supported for local execution via the forkserver.
+    timeout_hook: Option<Vec<String>>,
     executor_preference: ExecutorPreference,
     host_sharing_requirements: HostSharingRequirements,
     // Used to disable the low pass filter for concurrent local actions. Enabled by default
@@ -336,6 +339,7 @@ impl CommandExecutionRequest {
             paths,
             env,
             timeout: None,
+            timeout_hook: None,
             executor_preference: ExecutorPreference::Default,
             host_sharing_requirements: HostSharingRequirements::default(),
             low_pass_filter: true,
@@ -362,6 +366,11 @@ impl CommandExecutionRequest {
         self
     }
 
+    pub fn with_timeout_hook(mut self, timeout_hook: Vec<String>) -> Self {
+        self.timeout_hook = Some(timeout_hook);
+        self
+    }
+
     pub fn with_executor_preference(mut self, executor_preference: ExecutorPreference) -> Self {
         self.executor_preference = executor_preference;
         self
@@ -457,6 +466,10 @@ impl CommandExecutionRequest {
         self.timeout
     }
 
+    pub fn timeout_hook(&self) -> Option<&[String]> {
+        self.timeout_hook.as_deref()
+    }
+
     pub fn executor_preference(&self) -> ExecutorPreference {
         self.executor_preference
     }
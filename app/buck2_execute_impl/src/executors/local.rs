@@ -63,10 +63,11 @@ use buck2_execute::knobs::ExecutorGlobalKnobs;
 use buck2_execute::materialize::materializer::MaterializationError;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_forkserver::client::ForkserverClient;
-use buck2_forkserver::run::gather_output;
+use buck2_forkserver::run::gather_output_with_timeout_hook;
 use buck2_forkserver::run::maybe_absolutize_exe;
 use buck2_forkserver::run::timeout_into_cancellation;
 use buck2_forkserver::run::GatherOutputStatus;
+use buck2_forkserver::run::TimeoutHook;
 use buck2_futures::cancellable_future::CancellationObserver;
 use buck2_futures::cancellation::CancellationContext;
 use buck2_util::process::background_command;
@@ -142,6 +143,7 @@ impl LocalExecutor {
         env: impl IntoIterator<Item = (impl AsRef<OsStr> + Send, impl AsRef<OsStr> + Send)> + Send + 'a,
         working_directory: Option<&'a ProjectRelativePath>,
         timeout: Option<Duration>,
+        timeout_hook: Option<&'a [String]>,
         env_inheritance: Option<&'a EnvironmentInheritance>,
         liveliness_observer: impl LivelinessObserver + 'static,
         disable_miniperf: bool,
@@ -166,6 +168,7 @@ impl LocalExecutor {
                             env,
                             &working_directory,
                             timeout,
+                            timeout_hook,
                             env_inheritance,
                             liveliness_observer,
                             self.knobs.enable_miniperf && !disable_miniperf,
@@ -181,6 +184,10 @@ impl LocalExecutor {
                 }
 
                 None => {
+                    let timeout_hook = timeout_hook
+                        .filter(|hook| !hook.is_empty())
+                        .map(|hook| TimeoutHook::new(hook.iter().map(OsString::from)))
+                        .transpose()?;
                     let exe = maybe_absolutize_exe(exe, &working_directory)?;
                     let mut cmd = background_command(exe.as_ref());
                     cmd.current_dir(working_directory.as_path());
@@ -200,7 +207,7 @@ impl LocalExecutor {
                     let cancellation =
                         select(timeout.boxed(), alive.boxed()).map(|r| r.factor_first().0);
 
-                    gather_output(cmd, cancellation).await
+                    gather_output_with_timeout_hook(cmd, cancellation, timeout_hook).await
                 }
                 .with_context(|| format!("Failed to gather output from command: {}", exe)),
             }
@@ -441,6 +448,7 @@ impl LocalExecutor {
                         env,
                         request.working_directory(),
                         request.timeout(),
+                        request.timeout_hook(),
                         request.local_environment_inheritance(),
                         liveliness_observer,
                         request.disable_miniperf(),
@@ -1085,6 +1093,7 @@ mod unix {
         env: impl IntoIterator<Item = (impl AsRef<OsStr>, impl AsRef<OsStr>)>,
         working_directory: &AbsPath,
         command_timeout: Option<Duration>,
+        timeout_hook: Option<&[String]>,
         env_inheritance: Option<&EnvironmentInheritance>,
         liveliness_observer: impl LivelinessObserver + 'static,
         enable_miniperf: bool,
@@ -1105,6 +1114,11 @@ mod unix {
             enable_miniperf,
             std_redirects: None,
             graceful_shutdown_timeout_s: None,
+            timeout_hook: timeout_hook
+                .unwrap_or_default()
+                .iter()
+                .map(|s| s.as_bytes().to_vec())
+                .collect(),
         };
         apply_local_execution_environment(&mut req, working_directory, env, env_inheritance);
         forkserver
@@ -1170,6 +1184,7 @@ mod tests {
     use buck2_core::fs::project::ProjectRootTemp;
     use buck2_execute::execute::blocking::testing::DummyBlockingExecutor;
     use buck2_execute::materialize::nodisk::NoDiskMaterializer;
+    use buck2_forkserver::run::gather_output;
     use host_sharing::HostSharingStrategy;
 
     use super::*;
@@ -1310,6 +1325,7 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
                 NoopLivelinessObserver::create(),
                 false,
             )
@@ -1345,6 +1361,7 @@ mod tests {
                 &HashMap::<String, String>::default(),
                 None,
                 None,
+                None,
                 Some(&EnvironmentInheritance::empty()),
                 NoopLivelinessObserver::create(),
                 false,
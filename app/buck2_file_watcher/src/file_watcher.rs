@@ -33,6 +33,19 @@ pub trait FileWatcher: Allocative + Send + Sync + 'static {
         &self,
         dice: DiceTransactionUpdater,
     ) -> anyhow::Result<(DiceTransactionUpdater, Mergebase)>;
+
+    /// An opaque position in the stream of file changes, which a file watcher in a later daemon
+    /// can `resume` from. `None` if this file watcher can't resume, or hasn't synced yet.
+    async fn cursor(&self) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Makes the first sync invalidate everything changed since `cursor` was taken, rather than
+    /// everything. Must be called before the first sync. Returns `false` if this file watcher
+    /// can't resume from `cursor`, in which case nothing changes.
+    async fn resume(&self, _cursor: &str) -> anyhow::Result<bool> {
+        Ok(false)
+    }
 }
 
 impl dyn FileWatcher {
@@ -21,6 +21,7 @@ use buck2_core::buck2_env;
 use dupe::Dupe;
 use futures::future::Future;
 use serde::Deserialize;
+use serde::Serialize;
 use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::oneshot;
@@ -222,6 +223,17 @@ pub trait SyncableQueryProcessor: Send + Sync {
 /// commands to be sent to the SyncableQueryHandler.
 enum SyncableQueryCommand<T, P> {
     Sync(P, oneshot::Sender<anyhow::Result<(T, P)>>),
+    Cursor(oneshot::Sender<Option<WatchmanCursor>>),
+    Resume(WatchmanCursor),
+}
+
+/// Where the last sync of a `SyncableQuery` left off. A query resumed from it (see
+/// `SyncableQuery::resume`) reports the changes made since, instead of starting with a fresh
+/// instance.
+#[derive(Serialize, Deserialize)]
+pub struct WatchmanCursor {
+    clock: String,
+    mergebase: Option<String>,
 }
 
 /// A SyncableQuery is similar to a subscription. When created, it accepts a query expression
@@ -264,6 +276,8 @@ struct SyncableQueryHandler<T, P> {
     query: QueryRequestCommon,
     last_clock: ClockSpec,
     last_mergebase: Option<String>,
+    /// Whether `last_clock` comes from a sync on the current connection.
+    synced: bool,
     mergebase_with: Option<String>,
     control_rx: UnboundedReceiver<SyncableQueryCommand<T, P>>,
 }
@@ -291,6 +305,13 @@ where
                     // job. That's fine.
                     let _ignore = sync_tx.send(res);
                 }
+                Some(SyncableQueryCommand::Cursor(cursor_tx)) => {
+                    let _ignore = cursor_tx.send(self.cursor());
+                }
+                Some(SyncableQueryCommand::Resume(cursor)) => {
+                    self.last_clock = ClockSpec::StringClock(cursor.clock);
+                    self.last_mergebase = cursor.mergebase;
+                }
                 None => {
                     // This indicates the controlling SyncableQuery has been dropped.
                     return;
@@ -353,13 +374,25 @@ where
 
         self.last_mergebase = new_mergebase;
         self.last_clock = clock;
+        self.synced = true;
 
         Ok(res)
     }
 
+    fn cursor(&self) -> Option<WatchmanCursor> {
+        match &self.last_clock {
+            ClockSpec::StringClock(clock) if self.synced => Some(WatchmanCursor {
+                clock: clock.clone(),
+                mergebase: self.last_mergebase.clone(),
+            }),
+            _ => None,
+        }
+    }
+
     async fn reconnect(&mut self, client: &mut Option<WatchmanClient>) -> anyhow::Result<()> {
         self.last_clock = Default::default();
         self.last_mergebase = None;
+        self.synced = false;
         *client = Some(
             WatchmanClient::connect(&self.connector, self.path.clone())
                 .await
@@ -481,6 +514,31 @@ where
         }
     }
 
+    /// Where the last sync left off, if there was one since watchman was last (re)connected.
+    pub fn cursor(&self) -> impl Future<Output = anyhow::Result<Option<WatchmanCursor>>> {
+        let (cursor_tx, cursor_rx) = tokio::sync::oneshot::channel();
+        let tx_res = self
+            .control_tx
+            .send(SyncableQueryCommand::Cursor(cursor_tx));
+
+        async move {
+            tx_res.ok().context("SyncableQueryHandler has exited")?;
+            cursor_rx
+                .await
+                .context("SyncableQueryHandler did not return a response for cursor request")
+        }
+    }
+
+    /// Makes the next sync report the changes made since `cursor`. Must be called before the
+    /// first sync. If watchman restarted or had to be reconnected to since, the next sync is a
+    /// fresh instance regardless.
+    pub fn resume(&self, cursor: WatchmanCursor) -> anyhow::Result<()> {
+        self.control_tx
+            .send(SyncableQueryCommand::Resume(cursor))
+            .ok()
+            .context("SyncableQueryHandler has exited")
+    }
+
     pub fn new(
         connector: Connector,
         path: impl AsRef<Path>,
@@ -517,6 +575,7 @@ where
                 query,
                 last_clock: ClockSpec::default(),
                 last_mergebase: None,
+                synced: false,
                 mergebase_with,
                 processor,
                 control_rx,
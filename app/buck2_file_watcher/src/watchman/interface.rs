@@ -41,6 +41,9 @@ use crate::watchman::core::WatchmanEvent;
 use crate::watchman::core::WatchmanEventType;
 use crate::watchman::core::WatchmanKind;
 
+/// Distinguishes the cursors of this file watcher from the ones of other file watchers.
+const CURSOR_PREFIX: &str = "watchman:";
+
 struct WatchmanQueryProcessor {
     // FIXME(JakobDegen): Storing these values statically is completely broken. See
     // `tests/e2e/cells/test_file_watcher_resolution:test_changing_cell_location_bug` for a repro of
@@ -396,4 +399,25 @@ impl FileWatcher for WatchmanFileWatcher {
         )
         .await
     }
+
+    async fn cursor(&self) -> anyhow::Result<Option<String>> {
+        let Some(cursor) = self.query.cursor().await? else {
+            return Ok(None);
+        };
+        Ok(Some(format!(
+            "{}{}",
+            CURSOR_PREFIX,
+            serde_json::to_string(&cursor)?
+        )))
+    }
+
+    async fn resume(&self, cursor: &str) -> anyhow::Result<bool> {
+        let Some(cursor) = cursor.strip_prefix(CURSOR_PREFIX) else {
+            return Ok(false);
+        };
+        self.query.resume(
+            serde_json::from_str(cursor).context("Invalid watchman file watcher cursor")?,
+        )?;
+        Ok(true)
+    }
 }
@@ -9,6 +9,8 @@
 
 use std::io;
 use std::process::ExitStatus;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use allocative::Allocative;
@@ -50,6 +52,26 @@ struct ForkserverClientInner {
     pid: u32,
     #[allocative(skip)]
     rpc: buck2_forkserver_proto::forkserver_client::ForkserverClient<Channel>,
+    /// Commands dispatched to the forkserver since it started.
+    started: AtomicU64,
+    /// Commands dispatched to the forkserver which have not finished yet.
+    running: AtomicU64,
+}
+
+/// Counts of the commands run through the forkserver.
+#[derive(Debug, Default, Clone, Copy, Dupe)]
+pub struct ForkserverStats {
+    pub started: u64,
+    pub running: u64,
+}
+
+/// Decrements the running count when the command finishes or is dropped.
+struct RunningCommand<'a>(&'a AtomicU64);
+
+impl Drop for RunningCommand<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 impl ForkserverClient {
@@ -79,7 +101,13 @@ impl ForkserverClient {
         });
 
         Self {
-            inner: Arc::new(ForkserverClientInner { error, pid, rpc }),
+            inner: Arc::new(ForkserverClientInner {
+                error,
+                pid,
+                rpc,
+                started: AtomicU64::new(0),
+                running: AtomicU64::new(0),
+            }),
         }
     }
 
@@ -87,6 +115,13 @@ impl ForkserverClient {
         self.inner.pid
     }
 
+    pub fn stats(&self) -> ForkserverStats {
+        ForkserverStats {
+            started: self.inner.started.load(Ordering::Relaxed),
+            running: self.inner.running.load(Ordering::Relaxed),
+        }
+    }
+
     pub async fn execute<C>(
         &self,
         req: buck2_forkserver_proto::CommandRequest,
@@ -105,6 +140,10 @@ impl ForkserverClient {
             ));
         }
 
+        self.inner.started.fetch_add(1, Ordering::Relaxed);
+        self.inner.running.fetch_add(1, Ordering::Relaxed);
+        let _running = RunningCommand(&self.inner.running);
+
         let stream = stream::once(future::ready(buck2_forkserver_proto::RequestEvent {
             data: Some(req.into()),
         }))
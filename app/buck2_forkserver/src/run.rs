@@ -12,10 +12,12 @@ pub mod process_group;
 pub mod status_decoder;
 
 use std::borrow::Cow;
+use std::ffi::OsString;
 use std::path::Path;
 use std::pin::Pin;
 use std::process::Command;
 use std::process::ExitStatus;
+use std::process::Stdio;
 use std::task::Context;
 use std::task::Poll;
 use std::time::Duration;
@@ -98,6 +100,9 @@ impl From<StdioEvent> for CommandEvent {
 struct CommandEventStream<Status, Stdio> {
     exit: Option<anyhow::Result<GatherOutputStatus>>,
 
+    /// Output of the timeout hook, if one ran. Yielded as stderr right before the exit status.
+    timeout_hook_output: Option<Bytes>,
+
     done: bool,
 
     #[pin]
@@ -115,6 +120,7 @@ where
     fn new(status: Status, stdio: Stdio) -> Self {
         Self {
             exit: None,
+            timeout_hook_output: None,
             done: false,
             status: status.fuse(),
             stdio: stdio.fuse(),
@@ -124,7 +130,7 @@ where
 
 impl<Status, Stdio> Stream for CommandEventStream<Status, Stdio>
 where
-    Status: Future<Output = anyhow::Result<GatherOutputStatus>>,
+    Status: Future<Output = anyhow::Result<(GatherOutputStatus, Option<Bytes>)>>,
     Stdio: Stream<Item = anyhow::Result<StdioEvent>> + InterruptNotifiable,
 {
     type Item = anyhow::Result<CommandEvent>;
@@ -139,7 +145,10 @@ where
         // This future is fused so it's guaranteed to be ready once. If it does, capture the exit
         // status, we'll return it later.
         if let Poll::Ready(status) = this.status.poll(cx) {
-            *this.exit = Some(status);
+            *this.exit = Some(status.map(|(status, timeout_hook_output)| {
+                *this.timeout_hook_output = timeout_hook_output;
+                status
+            }));
             this.stdio.as_mut().get_pin_mut().notify_interrupt();
         }
 
@@ -151,6 +160,10 @@ where
 
         // If we got here that means the stream is done. If we have it we return, and if we don't
         // we report we're pending, because we'll have polled it already earlier.
+        if let Some(output) = this.timeout_hook_output.take() {
+            return Poll::Ready(Some(Ok(CommandEvent::Stderr(output))));
+        }
+
         if let Some(exit) = this.exit.take() {
             *this.done = true;
             return Poll::Ready(Some(exit.map(CommandEvent::Exit)));
@@ -172,12 +185,84 @@ pub async fn timeout_into_cancellation(
     }
 }
 
+/// A command to run against a process that timed out, before it gets killed (e.g. to dump its
+/// stacks). Occurrences of `{pid}` in its arguments are replaced with the pid of the process.
+#[derive(Debug, Clone)]
+pub struct TimeoutHook {
+    exe: OsString,
+    args: Vec<OsString>,
+}
+
+impl TimeoutHook {
+    pub const PID_PLACEHOLDER: &'static str = "{pid}";
+
+    /// How long the hook itself is given to run.
+    const TIMEOUT: Duration = Duration::from_secs(60);
+
+    pub fn new(argv: impl IntoIterator<Item = OsString>) -> anyhow::Result<Self> {
+        let mut argv = argv.into_iter();
+        let exe = argv.next().context("Timeout hook is empty")?;
+        Ok(Self {
+            exe,
+            args: argv.collect(),
+        })
+    }
+
+    /// Run the hook against `pid`. Failures are reported in the output rather than as errors,
+    /// since the output ends up next to the output of the process that timed out.
+    async fn run(&self, pid: u32) -> Bytes {
+        let pid = pid.to_string();
+        let args = self.args.iter().map(|arg| match arg.to_str() {
+            Some(arg) => OsString::from(arg.replace(Self::PID_PLACEHOLDER, &pid)),
+            None => arg.clone(),
+        });
+
+        let mut output = format!(
+            "\n---- Timeout hook `{}` ----\n",
+            Path::new(&self.exe).display()
+        )
+        .into_bytes();
+
+        let result = tokio::time::timeout(
+            Self::TIMEOUT,
+            tokio::process::Command::new(&self.exe)
+                .args(args)
+                .stdin(Stdio::null())
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(result)) => {
+                output.extend(result.stdout);
+                output.extend(result.stderr);
+                if !result.status.success() {
+                    output.extend(format!("Timeout hook failed: {}\n", result.status).into_bytes());
+                }
+            }
+            Ok(Err(e)) => {
+                output.extend(format!("Error running timeout hook: {}\n", e).into_bytes());
+            }
+            Err(_) => {
+                output.extend(
+                    format!("Timeout hook did not finish within {:?}\n", Self::TIMEOUT)
+                        .into_bytes(),
+                );
+            }
+        }
+
+        Bytes::from(output)
+    }
+}
+
 pub(crate) fn stream_command_events<T>(
     process_group: anyhow::Result<ProcessGroup>,
     cancellation: T,
     decoder: impl StatusDecoder,
     kill_process: impl KillProcess,
     stream_stdio: bool,
+    timeout_hook: Option<TimeoutHook>,
 ) -> anyhow::Result<impl Stream<Item = anyhow::Result<CommandEvent>>>
 where
     T: Future<Output = anyhow::Result<GatherOutputStatus>> + Send,
@@ -240,8 +325,16 @@ where
         };
 
         anyhow::Ok(match execute.await? {
-            Outcome::Finished(status) => decoder.decode_status(status).await?.into(),
+            Outcome::Finished(status) => (decoder.decode_status(status).await?.into(), None),
             Outcome::Cancelled(res) => {
+                // Give the hook a chance to inspect the process while it's still there.
+                let timeout_hook_output = match (&res, &timeout_hook, process_group.id()) {
+                    (GatherOutputStatus::TimedOut(..), Some(hook), Some(pid)) => {
+                        Some(hook.run(pid).await)
+                    }
+                    _ => None,
+                };
+
                 kill_process
                     .kill(&mut process_group)
                     .await
@@ -259,7 +352,7 @@ where
                     .await
                     .context("Failed to await child after kill")?;
 
-                res
+                (res, timeout_hook_output)
             }
         })
     };
@@ -295,6 +388,18 @@ pub async fn gather_output<T>(
     cmd: Command,
     cancellation: T,
 ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
+where
+    T: Future<Output = anyhow::Result<GatherOutputStatus>> + Send,
+{
+    gather_output_with_timeout_hook(cmd, cancellation, None).await
+}
+
+/// Like `gather_output`, running `timeout_hook` against the process if it times out.
+pub async fn gather_output_with_timeout_hook<T>(
+    cmd: Command,
+    cancellation: T,
+    timeout_hook: Option<TimeoutHook>,
+) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
 where
     T: Future<Output = anyhow::Result<GatherOutputStatus>> + Send,
 {
@@ -309,6 +414,7 @@ where
         DefaultStatusDecoder,
         DefaultKillProcess::default(),
         true,
+        timeout_hook,
     )?;
     decode_command_event_stream(stream).await
 }
@@ -606,6 +712,7 @@ mod tests {
             DefaultStatusDecoder,
             DefaultKillProcess::default(),
             true,
+            None,
         )?
         .boxed();
         assert_matches!(events.next().await, Some(Ok(CommandEvent::Exit(..))));
@@ -689,6 +796,7 @@ mod tests {
                 killed: killed.dupe(),
             },
             true,
+            None,
         )?;
 
         let (status, _stdout, _stderr) = decode_command_event_stream(stream).await?;
@@ -700,6 +808,37 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timeout_hook() -> anyhow::Result<()> {
+        let mut cmd = background_command("sh");
+        cmd.args(["-c", "echo started; sleep 10000"]);
+
+        let mut cmd = ProcessCommand::new(cmd);
+        let process = cmd.spawn().map_err(anyhow::Error::from);
+        let pid = process.as_ref().ok().and_then(|p| p.id()).unwrap();
+
+        let hook =
+            TimeoutHook::new(["sh", "-c", "echo \"hook for $0\"", "{pid}"].map(OsString::from))?;
+        let stream = stream_command_events(
+            process,
+            timeout_into_cancellation(Some(Duration::from_secs(1))),
+            DefaultStatusDecoder,
+            DefaultKillProcess::default(),
+            true,
+            Some(hook),
+        )?;
+
+        let (status, stdout, stderr) = decode_command_event_stream(stream).await?;
+        assert_matches!(status, GatherOutputStatus::TimedOut(..));
+        assert_eq!(str::from_utf8(&stdout)?, "started\n");
+        let stderr = str::from_utf8(&stderr)?;
+        assert!(stderr.contains("---- Timeout hook `sh` ----"), "{}", stderr);
+        assert!(stderr.contains(&format!("hook for {}", pid)), "{}", stderr);
+
+        Ok(())
+    }
+
     #[cfg(unix)]
     #[tokio::test]
     async fn test_no_stdio_stream_command_events() -> anyhow::Result<()> {
@@ -718,6 +857,7 @@ mod tests {
             DefaultStatusDecoder,
             DefaultKillProcess::default(),
             false,
+            None,
         )?
         .boxed();
         assert_matches!(events.next().await, Some(Ok(CommandEvent::Exit(..))));
@@ -51,6 +51,7 @@ use crate::run::stream_command_events;
 use crate::run::timeout_into_cancellation;
 use crate::run::DefaultKillProcess;
 use crate::run::GatherOutputStatus;
+use crate::run::TimeoutHook;
 
 // Not quite BoxStream: it has to be Sync (...)
 type RunStream =
@@ -115,6 +116,7 @@ impl Forkserver for UnixForkserverService {
                 enable_miniperf,
                 std_redirects,
                 graceful_shutdown_timeout_s,
+                timeout_hook,
             } = msg;
 
             let exe = OsStr::from_bytes(&exe);
@@ -126,6 +128,14 @@ impl Forkserver for UnixForkserverService {
                 .transpose()
                 .context("Invalid timeout")?;
 
+            let timeout_hook = if timeout_hook.is_empty() {
+                None
+            } else {
+                Some(TimeoutHook::new(
+                    timeout_hook.iter().map(|a| OsStr::from_bytes(a).to_owned()),
+                )?)
+            };
+
             let exe = maybe_absolutize_exe(exe, cwd)?;
 
             let (mut cmd, miniperf_output) = match (enable_miniperf, &self.miniperf) {
@@ -182,6 +192,7 @@ impl Forkserver for UnixForkserverService {
                         graceful_shutdown_timeout_s,
                     },
                     stream_stdio,
+                    timeout_hook,
                 )?
                 .left_stream(),
                 None => stream_command_events(
@@ -192,6 +203,7 @@ impl Forkserver for UnixForkserverService {
                         graceful_shutdown_timeout_s,
                     },
                     stream_stdio,
+                    timeout_hook,
                 )?
                 .right_stream(),
             };
@@ -16,6 +16,15 @@
 //! implements InterpreterFileOps by basically putting DefaultInterpreterFileOps
 //! onto the dice graph).
 
+use dice::DicePersistence;
+
 pub mod starlark_debug;
 pub mod starlark_provider;
 pub mod starlark_types;
+
+/// Registers the keys of this crate which are saved in DICE snapshots.
+pub fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    starlark_types::register_persistent_keys(persistence)?;
+    crate::import_paths::register_persistent_keys(persistence)?;
+    crate::starlark_profiler::config::register_persistent_keys(persistence)
+}
@@ -10,11 +10,18 @@
 use allocative::Allocative;
 use async_trait::async_trait;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::DiceTransactionUpdater;
 use dice::InjectedKey;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 #[derive(Debug, Clone, Dupe, Eq, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 struct StarlarkTypesValue {
     disable_starlark_types: bool,
     unstable_typecheck: bool,
@@ -31,6 +38,7 @@ struct StarlarkTypesValue {
     Hash,
     Allocative
 )]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{:?}", self)]
 struct StarlarkTypesKey;
 
@@ -42,6 +50,27 @@ impl InjectedKey for StarlarkTypesKey {
     }
 }
 
+impl PersistentKey for StarlarkTypesKey {
+    const PERSISTENT_ID: &'static str = "StarlarkTypesKey";
+}
+
+impl PersistentValue for StarlarkTypesKey {
+    type Persisted = StarlarkTypesValue;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        Some(value.dupe())
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(value)
+    }
+}
+
+/// Registers the Starlark types flags, so that they are saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<StarlarkTypesKey>()
+}
+
 pub trait SetStarlarkTypes {
     fn set_starlark_types(
         &mut self,
@@ -11,8 +11,11 @@ pub mod xcode;
 
 use allocative::Allocative;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 #[derive(Copy, Clone, Dupe, Debug, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub enum InterpreterHostPlatform {
     Linux,
     MacOS,
@@ -22,6 +25,7 @@ pub enum InterpreterHostPlatform {
 }
 
 #[derive(Copy, Clone, Dupe, Debug, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub enum InterpreterHostArchitecture {
     AArch64,
     X86_64,
@@ -17,6 +17,7 @@ use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::paths::abs_path::AbsPath;
 use regex::Regex;
 use serde::Deserialize;
+use serde::Serialize;
 
 #[derive(buck2_error::Error, Debug)]
 enum XcodeVersionError {
@@ -40,6 +41,7 @@ struct XcodeVersionPlistSchema {
 
 /// Versioning information for the currently selected Xcode on the host machine.
 #[derive(Debug, Default, PartialEq, Clone, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub struct XcodeVersionInfo {
     /// e.g. "14.0.1"
     pub version_string: String,
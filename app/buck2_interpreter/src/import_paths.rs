@@ -22,8 +22,12 @@ use buck2_core::cells::paths::CellRelativePathBuf;
 use buck2_core::cells::CellAliasResolver;
 use buck2_futures::cancellation::CancellationContext;
 use dice::DiceComputations;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::package_imports::PackageImplicitImports;
 
@@ -78,6 +82,49 @@ impl ImplicitImportPaths {
     }
 }
 
+#[derive(Debug, Eq, PartialEq, Hash, Clone, derive_more::Display, Allocative)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "{}", cell_name)]
+struct ImportPathsKey {
+    cell_name: BuildFileCell,
+}
+
+#[async_trait]
+impl Key for ImportPathsKey {
+    type Value = buck2_error::Result<Arc<ImplicitImportPaths>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let config = ctx.get_legacy_config_on_dice(self.cell_name.name()).await?;
+        let cell_alias_resolver = ctx.get_cell_alias_resolver(self.cell_name.name()).await?;
+
+        Ok(Arc::new(ImplicitImportPaths::parse(
+            config.view(ctx),
+            self.cell_name,
+            &cell_alias_resolver,
+        )?))
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+impl PersistentKey for ImportPathsKey {
+    const PERSISTENT_ID: &'static str = "ImportPathsKey";
+}
+
+/// Registers the implicit import paths, so that they are saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register_without_value::<ImportPathsKey>()
+}
+
 #[async_trait]
 pub trait HasImportPaths {
     async fn import_paths_for_cell(
@@ -92,40 +139,6 @@ impl HasImportPaths for DiceComputations<'_> {
         &mut self,
         cell_name: BuildFileCell,
     ) -> anyhow::Result<Arc<ImplicitImportPaths>> {
-        #[derive(Debug, Eq, PartialEq, Hash, Clone, derive_more::Display, Allocative)]
-        #[display(fmt = "{}", cell_name)]
-        struct ImportPathsKey {
-            cell_name: BuildFileCell,
-        }
-
-        #[async_trait]
-        impl Key for ImportPathsKey {
-            type Value = buck2_error::Result<Arc<ImplicitImportPaths>>;
-
-            async fn compute(
-                &self,
-                ctx: &mut DiceComputations,
-                _cancellation: &CancellationContext,
-            ) -> Self::Value {
-                let config = ctx.get_legacy_config_on_dice(self.cell_name.name()).await?;
-                let cell_alias_resolver =
-                    ctx.get_cell_alias_resolver(self.cell_name.name()).await?;
-
-                Ok(Arc::new(ImplicitImportPaths::parse(
-                    config.view(ctx),
-                    self.cell_name,
-                    &cell_alias_resolver,
-                )?))
-            }
-
-            fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-                match (x, y) {
-                    (Ok(x), Ok(y)) => x == y,
-                    _ => false,
-                }
-            }
-        }
-
         self.compute(&ImportPathsKey { cell_name })
             .await?
             .map_err(anyhow::Error::from)
@@ -12,6 +12,8 @@ use buck2_core::cells::build_file_cell::BuildFileCell;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::paths::CellRelativePathBuf;
+use serde::Deserialize;
+use serde::Serialize;
 
 /// Path of a `bxl` file for `bxl` commands
 #[derive(
@@ -25,6 +27,7 @@ use buck2_core::cells::paths::CellRelativePathBuf;
     PartialOrd,
     Allocative
 )]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{}", path)]
 pub struct BxlFilePath {
     /// The path of this bxl file, including the `bxl` extension
@@ -18,6 +18,8 @@ use buck2_core::cells::cell_path::CellPathRef;
 use buck2_core::cells::name::CellName;
 use dupe::Dupe;
 use gazebo::variants::UnpackVariants;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark::collections::Equivalent;
 
 use crate::paths::bxl::BxlFilePath;
@@ -85,6 +87,7 @@ impl<'a> StarlarkModulePath<'a> {
 }
 
 #[derive(Clone, derive_more::Display, Debug, Eq, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{}", self.borrow())]
 pub enum OwnedStarlarkModulePath {
     LoadFile(ImportPath),
@@ -13,8 +13,11 @@ use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::paths::CellRelativePathBuf;
 use buck2_core::cells::CellResolver;
+use serde::Deserialize;
+use serde::Serialize;
 
 #[derive(Debug, derive_more::Display, Clone, Eq, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub struct PreludePath(ImportPath);
 
 impl PreludePath {
@@ -21,12 +21,19 @@ use buck2_core::pattern::unparsed::UnparsedPatternPredicate;
 use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
 use buck2_futures::cancellation::CancellationContext;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::DiceProjectionComputations;
 use dice::DiceTransactionUpdater;
 use dice::InjectedKey;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentProjection;
+use dice::PersistentValue;
 use dice::ProjectionKey;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark::eval::ProfileMode;
 
 use crate::starlark_profiler::mode::StarlarkProfileMode;
@@ -71,6 +78,7 @@ enum StarlarkProfilerConfigurationResolved {
     Hash,
     Allocative
 )]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{:?}", self)]
 struct StarlarkProfilerConfigurationResolvedKey;
 
@@ -166,7 +174,11 @@ impl Key for StarlarkProfilerConfigurationResolvedKey {
     Hash,
     Allocative
 )]
-struct StarlarkProfileModeForAnalysisKey(ConfiguredTargetLabel);
+#[derive(Serialize, Deserialize)]
+struct StarlarkProfileModeForAnalysisKey(
+    #[serde(with = "buck2_core::configuration::data::persisted_configured_target_label")]
+    ConfiguredTargetLabel,
+);
 
 impl ProjectionKey for StarlarkProfileModeForAnalysisKey {
     type DeriveFromKey = StarlarkProfilerConfigurationResolvedKey;
@@ -212,6 +224,7 @@ impl ProjectionKey for StarlarkProfileModeForAnalysisKey {
     Hash,
     Allocative
 )]
+#[derive(Serialize, Deserialize)]
 struct StarlarkProfileModeForLoadingKey(PackageLabel);
 
 impl ProjectionKey for StarlarkProfileModeForLoadingKey {
@@ -266,6 +279,7 @@ impl ProjectionKey for StarlarkProfileModeForLoadingKey {
     Hash,
     Allocative
 )]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{:?}", self)]
 pub struct StarlarkProfilerConfigurationKey;
 
@@ -277,6 +291,65 @@ impl InjectedKey for StarlarkProfilerConfigurationKey {
     }
 }
 
+impl PersistentKey for StarlarkProfilerConfigurationKey {
+    const PERSISTENT_ID: &'static str = "StarlarkProfilerConfigurationKey";
+}
+
+/// Only the default configuration is saved: a profiling command is usually followed by commands
+/// that don't profile, which would recompute everything that depends on it anyway.
+impl PersistentValue for StarlarkProfilerConfigurationKey {
+    type Persisted = ();
+
+    fn to_persisted(value: &Self::Value) -> Option<()> {
+        (**value == StarlarkProfilerConfiguration::None).then_some(())
+    }
+
+    fn from_persisted(_value: (), _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(Arc::new(StarlarkProfilerConfiguration::None))
+    }
+}
+
+impl PersistentKey for StarlarkProfilerConfigurationResolvedKey {
+    const PERSISTENT_ID: &'static str = "StarlarkProfilerConfigurationResolvedKey";
+}
+
+/// Only saved when not profiling, which is the only case where the configuration is saved.
+impl PersistentProjection for StarlarkProfileModeForAnalysisKey {
+    const PERSISTENT_ID: &'static str = "StarlarkProfileModeForAnalysisKey";
+
+    type Persisted = ();
+
+    fn to_persisted(value: &Self::Value) -> Option<()> {
+        matches!(value, Ok(StarlarkProfileMode::None)).then_some(())
+    }
+
+    fn from_persisted(_value: (), _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(StarlarkProfileMode::None)
+    }
+}
+
+impl PersistentProjection for StarlarkProfileModeForLoadingKey {
+    const PERSISTENT_ID: &'static str = "StarlarkProfileModeForLoadingKey";
+
+    type Persisted = ();
+
+    fn to_persisted(value: &Self::Value) -> Option<()> {
+        matches!(value, Ok(StarlarkProfileMode::None)).then_some(())
+    }
+
+    fn from_persisted(_value: (), _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(StarlarkProfileMode::None)
+    }
+}
+
+/// Registers the profiler configuration keys, so that they are saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<StarlarkProfilerConfigurationKey>()?;
+    persistence.register_without_value::<StarlarkProfilerConfigurationResolvedKey>()?;
+    persistence.register_projection::<StarlarkProfileModeForAnalysisKey>()?;
+    persistence.register_projection::<StarlarkProfileModeForLoadingKey>()
+}
+
 #[async_trait]
 pub trait SetStarlarkProfilerInstrumentation {
     fn set_starlark_profiler_configuration(
@@ -30,3 +30,14 @@ pub mod package_file_calculation;
 pub mod package_file_extra;
 pub mod selector;
 pub mod testing;
+
+use dice::DicePersistence;
+
+/// Registers the keys of this crate which are saved in DICE snapshots.
+pub fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    context::register_persistent_keys(persistence)?;
+    global_interpreter_state::register_persistent_keys(persistence)?;
+    check_starlark_stack_size::register_persistent_keys(persistence)?;
+    dice_calculation_delegate::register_persistent_keys(persistence)?;
+    calculation::register_persistent_keys(persistence)
+}
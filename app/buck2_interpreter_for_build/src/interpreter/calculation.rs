@@ -32,6 +32,7 @@ use buck2_interpreter::paths::module::StarlarkModulePath;
 use buck2_interpreter::paths::package::PackageFilePath;
 use buck2_interpreter::paths::path::StarlarkPath;
 use buck2_interpreter::prelude_path::PreludePath;
+use buck2_node::compute_counters::HasComputeCounters;
 use buck2_node::metadata::key::MetadataKey;
 use buck2_node::nodes::eval_result::EvaluationResult;
 use buck2_node::nodes::frontend::TargetGraphCalculation;
@@ -40,11 +41,15 @@ use buck2_node::nodes::frontend::TARGET_GRAPH_CALCULATION_IMPL;
 use buck2_node::package_values_calculation::PackageValuesCalculation;
 use buck2_node::package_values_calculation::PACKAGE_VALUES_CALCULATION;
 use derive_more::Display;
+use dice::ConcurrencyClass;
 use dice::DiceComputations;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
 use dupe::Dupe;
 use futures::future::BoxFuture;
 use futures::FutureExt;
+use once_cell::sync::Lazy;
 use smallvec::SmallVec;
 use starlark::environment::Globals;
 use starlark_map::small_map::SmallMap;
@@ -55,8 +60,19 @@ use crate::interpreter::global_interpreter_state::HasGlobalInterpreterState;
 
 // Key for 'InterpreterCalculation::get_interpreter_results'
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct InterpreterResultsKey(pub PackageLabel);
 
+/// Evaluating build files and their imports is CPU bound, so at most one evaluation runs per core
+/// and the others wait, ahead of package listings.
+static INTERPRETER_CONCURRENCY: Lazy<ConcurrencyClass> = Lazy::new(|| {
+    ConcurrencyClass::new(
+        "interpreter",
+        std::thread::available_parallelism().map_or(1, |n| n.get()),
+        1,
+    )
+});
+
 struct TargetGraphCalculationInstance;
 
 pub(crate) fn init_target_graph_calculation_impl() {
@@ -72,6 +88,7 @@ impl Key for InterpreterResultsKey {
         _cancellation: &CancellationContext,
     ) -> Self::Value {
         let now = Instant::now();
+        ctx.record_package_computed();
 
         let (result, spans) =
             async_record_root_spans(ctx.get_interpreter_results_uncached(self.0.dupe())).await;
@@ -90,9 +107,23 @@ impl Key for InterpreterResultsKey {
         false
     }
 
+    fn evictable() -> bool {
+        // Results are never equal anyway, so recomputing an evicted one costs nothing beyond the
+        // evaluation, and they hold the evaluated targets of a whole package.
+        true
+    }
+
     fn validity(x: &Self::Value) -> bool {
         x.is_ok()
     }
+
+    fn concurrency_class() -> Option<ConcurrencyClass> {
+        Some(*INTERPRETER_CONCURRENCY)
+    }
+}
+
+impl PersistentKey for InterpreterResultsKey {
+    const PERSISTENT_ID: &'static str = "InterpreterResultsKey";
 }
 
 #[async_trait]
@@ -158,6 +189,21 @@ impl Key for EvalImportKey {
     fn validity(x: &Self::Value) -> bool {
         x.is_ok()
     }
+
+    fn concurrency_class() -> Option<ConcurrencyClass> {
+        Some(*INTERPRETER_CONCURRENCY)
+    }
+}
+
+impl PersistentKey for EvalImportKey {
+    const PERSISTENT_ID: &'static str = "EvalImportKey";
+}
+
+/// Registers the evaluated build files and imports, so that they are saved in DICE snapshots. Their
+/// values can't be saved: restored keys evaluate their file again when they are first needed.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register_without_value::<InterpreterResultsKey>()?;
+    persistence.register_without_value::<EvalImportKey>()
 }
 
 #[async_trait]
@@ -14,8 +14,14 @@ use buck2_interpreter::dice::starlark_provider::with_starlark_eval_provider;
 use buck2_interpreter::error::BuckStarlarkError;
 use buck2_interpreter::starlark_profiler::profiler::StarlarkProfilerOpt;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use indoc::indoc;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark::environment::Globals;
 use starlark::environment::Module;
 use starlark::syntax::AstModule;
@@ -27,66 +33,86 @@ enum CheckStarlarkStackSizeError {
     CheckStarlarkStackSizeError,
 }
 
-// In order to prevent non deterministic crashes
-// we intentionally set off a starlark stack overflow, to make
-// sure that starlark catches the overflow and reports an error
-// before the native stack overflows
-pub(crate) async fn check_starlark_stack_size(
-    ctx: &mut DiceComputations<'_>,
-) -> anyhow::Result<()> {
-    #[derive(Debug, derive_more::Display, Clone, Allocative, Eq, PartialEq, Hash)]
-    struct StarlarkStackSizeChecker;
+#[derive(Debug, derive_more::Display, Clone, Allocative, Eq, PartialEq, Hash)]
+#[derive(Serialize, Deserialize)]
+struct StarlarkStackSizeChecker;
 
-    #[async_trait]
-    impl Key for StarlarkStackSizeChecker {
-        type Value = buck2_error::Result<()>;
+#[async_trait]
+impl Key for StarlarkStackSizeChecker {
+    type Value = buck2_error::Result<()>;
 
-        async fn compute(
-            &self,
-            ctx: &mut DiceComputations,
-            _cancellation: &CancellationContext,
-        ) -> Self::Value {
-            with_starlark_eval_provider(
-                ctx,
-                &mut StarlarkProfilerOpt::disabled(),
-                "Check starlark stack size".to_owned(),
-                move |provider, _| {
-                    let env = Module::new();
-                    let (mut eval, _) = provider.make(&env)?;
-                    let content = indoc!(
-                        r#"
-                                def f():
-                                    f()
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        with_starlark_eval_provider(
+            ctx,
+            &mut StarlarkProfilerOpt::disabled(),
+            "Check starlark stack size".to_owned(),
+            move |provider, _| {
+                let env = Module::new();
+                let (mut eval, _) = provider.make(&env)?;
+                let content = indoc!(
+                    r#"
+                            def f():
                                 f()
-                        "#
-                    );
-                    let ast = AstModule::parse("x.star", content.to_owned(), &Dialect::Extended)
-                        .map_err(BuckStarlarkError::new)?;
-                    match eval.eval_module(ast, &Globals::standard()) {
-                        Err(e) if e.to_string().contains("Starlark call stack overflow") => Ok(()),
-                        Err(p) => Err(BuckStarlarkError::new(p).into()),
-                        Ok(_) => {
-                            Err(CheckStarlarkStackSizeError::CheckStarlarkStackSizeError.into())
-                        }
-                    }
-                },
-            )
-            .await?;
-            Ok(())
-        }
+                            f()
+                    "#
+                );
+                let ast = AstModule::parse("x.star", content.to_owned(), &Dialect::Extended)
+                    .map_err(BuckStarlarkError::new)?;
+                match eval.eval_module(ast, &Globals::standard()) {
+                    Err(e) if e.to_string().contains("Starlark call stack overflow") => Ok(()),
+                    Err(p) => Err(BuckStarlarkError::new(p).into()),
+                    Ok(_) => Err(CheckStarlarkStackSizeError::CheckStarlarkStackSizeError.into()),
+                }
+            },
+        )
+        .await?;
+        Ok(())
+    }
 
-        fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-            match (x, y) {
-                (Ok(x), Ok(y)) => x == y,
-                _ => false,
-            }
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
         }
+    }
 
-        fn validity(x: &Self::Value) -> bool {
-            x.is_ok()
-        }
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
     }
+}
+
+impl PersistentKey for StarlarkStackSizeChecker {
+    const PERSISTENT_ID: &'static str = "StarlarkStackSizeChecker";
+}
+
+impl PersistentValue for StarlarkStackSizeChecker {
+    type Persisted = ();
 
+    fn to_persisted(value: &Self::Value) -> Option<()> {
+        value.as_ref().ok().copied()
+    }
+
+    fn from_persisted(_value: (), _data: &DiceData) -> anyhow::Result<Self::Value> {
+        Ok(Ok(()))
+    }
+}
+
+/// Registers the stack size check, so that it is saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<StarlarkStackSizeChecker>()
+}
+
+// In order to prevent non deterministic crashes
+// we intentionally set off a starlark stack overflow, to make
+// sure that starlark catches the overflow and reports an error
+// before the native stack overflows
+pub(crate) async fn check_starlark_stack_size(
+    ctx: &mut DiceComputations<'_>,
+) -> anyhow::Result<()> {
     ctx.compute(&StarlarkStackSizeChecker)
         .await?
         .map_err(anyhow::Error::from)
@@ -23,6 +23,8 @@ use buck2_interpreter::paths::module::StarlarkModulePath;
 use buck2_interpreter::prelude_path::PreludePath;
 use buck2_node::super_package::SuperPackage;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark::environment::Globals;
 use starlark::environment::GlobalsBuilder;
 
@@ -95,6 +97,21 @@ impl BuildInterpreterConfiguror {
         }))
     }
 
+    /// Returns `None` when there are additional globals, which can't be saved.
+    pub(crate) fn to_persisted(&self) -> Option<PersistedBuildInterpreterConfiguror> {
+        if self.additional_globals.is_some() {
+            return None;
+        }
+        Some(PersistedBuildInterpreterConfiguror {
+            prelude_import: self.prelude_import.clone(),
+            host_platform: self.host_info.platform(),
+            host_architecture: self.host_info.arch(),
+            host_xcode_version: self.host_info.xcode().cloned(),
+            record_target_call_stack: self.record_target_call_stack,
+            skip_targets_with_duplicate_names: self.skip_targets_with_duplicate_names,
+        })
+    }
+
     pub(crate) fn globals(&self) -> Globals {
         base_globals()
             .with(|g| {
@@ -163,3 +180,31 @@ impl BuildInterpreterConfiguror {
         self.prelude_import.as_ref()
     }
 }
+
+/// A `BuildInterpreterConfiguror` as saved in DICE snapshots.
+#[derive(Serialize, Deserialize)]
+pub struct PersistedBuildInterpreterConfiguror {
+    prelude_import: Option<PreludePath>,
+    host_platform: InterpreterHostPlatform,
+    host_architecture: InterpreterHostArchitecture,
+    host_xcode_version: Option<XcodeVersionInfo>,
+    record_target_call_stack: bool,
+    skip_targets_with_duplicate_names: bool,
+}
+
+impl PersistedBuildInterpreterConfiguror {
+    /// The target label interner isn't saved: it is only there to share labels, and is compared
+    /// equal to the one the new daemon sets, so the restored one is kept.
+    pub(crate) fn into_configuror(self) -> anyhow::Result<Arc<BuildInterpreterConfiguror>> {
+        BuildInterpreterConfiguror::new(
+            self.prelude_import,
+            self.host_platform,
+            self.host_architecture,
+            self.host_xcode_version,
+            self.record_target_call_stack,
+            self.skip_targets_with_duplicate_names,
+            None,
+            Arc::new(ConcurrentTargetLabelInterner::default()),
+        )
+    }
+}
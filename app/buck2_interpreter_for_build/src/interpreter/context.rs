@@ -13,13 +13,21 @@ use allocative::Allocative;
 use async_trait::async_trait;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DicePersistence;
 use dice::DiceTransactionUpdater;
 use dice::InjectedKey;
+use dice::PersistentKey;
+use dice::PersistentValue;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::interpreter::configuror::BuildInterpreterConfiguror;
+use crate::interpreter::configuror::PersistedBuildInterpreterConfiguror;
 
 #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
 #[display(fmt = "{:?}", self)]
 struct BuildContextKey();
 
@@ -31,6 +39,27 @@ impl InjectedKey for BuildContextKey {
     }
 }
 
+impl PersistentKey for BuildContextKey {
+    const PERSISTENT_ID: &'static str = "BuildContextKey";
+}
+
+impl PersistentValue for BuildContextKey {
+    type Persisted = PersistedBuildInterpreterConfiguror;
+
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted> {
+        value.to_persisted()
+    }
+
+    fn from_persisted(value: Self::Persisted, _data: &DiceData) -> anyhow::Result<Self::Value> {
+        value.into_configuror()
+    }
+}
+
+/// Registers the interpreter configuration, so that it is saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register::<BuildContextKey>()
+}
+
 #[async_trait]
 pub trait HasInterpreterContext {
     async fn get_interpreter_configuror(
@@ -47,9 +47,13 @@ use buck2_node::nodes::eval_result::EvaluationResult;
 use buck2_node::super_package::SuperPackage;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
 use dupe::Dupe;
 use futures::FutureExt;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark::codemap::FileSpan;
 use starlark::syntax::AstModule;
 
@@ -71,6 +75,90 @@ enum DiceCalculationDelegateError {
     EvalModuleError(String),
 }
 
+#[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "{}@{}", _0, _1)]
+struct InterpreterConfigForCellKey(CellName, BuildFileCell);
+
+#[async_trait]
+impl Key for InterpreterConfigForCellKey {
+    type Value = buck2_error::Result<Arc<InterpreterForCell>>;
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let global_state = ctx.get_global_interpreter_state().await?;
+
+        let cell_alias_resolver = ctx.get_cell_alias_resolver(self.0).await?;
+
+        let implicit_import_paths = ctx.import_paths_for_cell(self.1).await?;
+
+        let cell_info =
+            InterpreterCellInfo::new(self.1, ctx.get_cell_resolver().await?, cell_alias_resolver)?;
+
+        Ok(Arc::new(InterpreterForCell::new(
+            cell_info,
+            global_state.dupe(),
+            implicit_import_paths,
+        )?))
+    }
+
+    fn equality(_: &Self::Value, _: &Self::Value) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Display, Clone, Allocative, Eq, PartialEq, Hash)]
+#[derive(Serialize, Deserialize)]
+struct PackageFileKey(PackageLabel);
+
+#[async_trait]
+impl Key for PackageFileKey {
+    type Value = buck2_error::Result<SuperPackage>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let cell_name = self.0.as_cell_path().cell();
+        let mut interpreter = ctx
+            .get_interpreter_calculator(cell_name, BuildFileCell::new(cell_name))
+            .await?;
+        interpreter
+            .eval_package_file_uncached(self.0.dupe())
+            .await
+            .map_err(buck2_error::Error::from)
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+}
+
+impl PersistentKey for InterpreterConfigForCellKey {
+    const PERSISTENT_ID: &'static str = "InterpreterConfigForCellKey";
+}
+
+impl PersistentKey for PackageFileKey {
+    const PERSISTENT_ID: &'static str = "PackageFileKey";
+}
+
+/// Registers the interpreters for cells and the evaluated `PACKAGE` files, so that they are saved
+/// in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register_without_value::<InterpreterConfigForCellKey>()?;
+    persistence.register_without_value::<PackageFileKey>()
+}
+
 #[async_trait]
 pub trait HasCalculationDelegate<'c, 'd> {
     /// Get calculator for a file evaluation.
@@ -91,42 +179,6 @@ impl<'c, 'd> HasCalculationDelegate<'c, 'd> for DiceComputations<'d> {
         cell: CellName,
         build_file_cell: BuildFileCell,
     ) -> anyhow::Result<DiceCalculationDelegate<'c, 'd>> {
-        #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
-        #[display(fmt = "{}@{}", _0, _1)]
-        struct InterpreterConfigForCellKey(CellName, BuildFileCell);
-
-        #[async_trait]
-        impl Key for InterpreterConfigForCellKey {
-            type Value = buck2_error::Result<Arc<InterpreterForCell>>;
-            async fn compute(
-                &self,
-                ctx: &mut DiceComputations,
-                _cancellation: &CancellationContext,
-            ) -> Self::Value {
-                let global_state = ctx.get_global_interpreter_state().await?;
-
-                let cell_alias_resolver = ctx.get_cell_alias_resolver(self.0).await?;
-
-                let implicit_import_paths = ctx.import_paths_for_cell(self.1).await?;
-
-                let cell_info = InterpreterCellInfo::new(
-                    self.1,
-                    ctx.get_cell_resolver().await?,
-                    cell_alias_resolver,
-                )?;
-
-                Ok(Arc::new(InterpreterForCell::new(
-                    cell_info,
-                    global_state.dupe(),
-                    implicit_import_paths,
-                )?))
-            }
-
-            fn equality(_: &Self::Value, _: &Self::Value) -> bool {
-                false
-            }
-        }
-
         let configs = self
             .compute(&InterpreterConfigForCellKey(cell, build_file_cell))
             .await??;
@@ -357,40 +409,6 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
         &mut self,
         path: PackageLabel,
     ) -> anyhow::Result<SuperPackage> {
-        #[derive(Debug, Display, Clone, Allocative, Eq, PartialEq, Hash)]
-        struct PackageFileKey(PackageLabel);
-
-        #[async_trait]
-        impl Key for PackageFileKey {
-            type Value = buck2_error::Result<SuperPackage>;
-
-            async fn compute(
-                &self,
-                ctx: &mut DiceComputations,
-                _cancellation: &CancellationContext,
-            ) -> Self::Value {
-                let cell_name = self.0.as_cell_path().cell();
-                let mut interpreter = ctx
-                    .get_interpreter_calculator(cell_name, BuildFileCell::new(cell_name))
-                    .await?;
-                interpreter
-                    .eval_package_file_uncached(self.0.dupe())
-                    .await
-                    .map_err(buck2_error::Error::from)
-            }
-
-            fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-                match (x, y) {
-                    (Ok(x), Ok(y)) => x == y,
-                    _ => false,
-                }
-            }
-
-            fn validity(x: &Self::Value) -> bool {
-                x.is_ok()
-            }
-        }
-
         self.ctx
             .compute(&PackageFileKey(path))
             .await?
@@ -552,8 +570,11 @@ mod keys {
     use allocative::Allocative;
     use buck2_interpreter::paths::module::OwnedStarlarkModulePath;
     use derive_more::Display;
+    use serde::Deserialize;
+    use serde::Serialize;
 
     #[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+    #[derive(Serialize, Deserialize)]
     pub struct EvalImportKey(pub OwnedStarlarkModulePath);
 }
 
@@ -174,4 +174,16 @@ impl HostInfo {
             value,
         }
     }
+
+    pub(crate) fn platform(&self) -> InterpreterHostPlatform {
+        self.platform
+    }
+
+    pub(crate) fn arch(&self) -> InterpreterHostArchitecture {
+        self.arch
+    }
+
+    pub(crate) fn xcode(&self) -> Option<&XcodeVersionInfo> {
+        self.xcode.as_ref()
+    }
 }
@@ -16,8 +16,12 @@ use buck2_core::cells::CellResolver;
 use buck2_futures::cancellation::CancellationContext;
 use buck2_interpreter::dice::starlark_types::GetStarlarkTypes;
 use dice::DiceComputations;
+use dice::DicePersistence;
 use dice::Key;
+use dice::PersistentKey;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark::environment::Globals;
 
 use crate::interpreter::configuror::BuildInterpreterConfiguror;
@@ -70,6 +74,58 @@ impl GlobalInterpreterState {
     }
 }
 
+#[derive(Clone, Dupe, Allocative)]
+struct GisValue(Arc<GlobalInterpreterState>);
+
+#[derive(
+    Clone,
+    derive_more::Display,
+    Dupe,
+    Debug,
+    Eq,
+    Hash,
+    PartialEq,
+    Allocative
+)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "{:?}", self)]
+struct GisKey();
+
+#[async_trait]
+impl Key for GisKey {
+    type Value = buck2_error::Result<GisValue>;
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let interpreter_configuror = ctx.get_interpreter_configuror().await?;
+        let cell_resolver = ctx.get_cell_resolver().await?;
+        let disable_starlark_types = ctx.get_disable_starlark_types().await?;
+        let unstable_typecheck = ctx.get_unstable_typecheck().await?;
+
+        Ok(GisValue(Arc::new(GlobalInterpreterState::new(
+            cell_resolver,
+            interpreter_configuror,
+            disable_starlark_types,
+            unstable_typecheck,
+        )?)))
+    }
+
+    fn equality(_: &Self::Value, _: &Self::Value) -> bool {
+        false
+    }
+}
+
+impl PersistentKey for GisKey {
+    const PERSISTENT_ID: &'static str = "GisKey";
+}
+
+/// Registers the global interpreter state, so that it is saved in DICE snapshots.
+pub(crate) fn register_persistent_keys(persistence: &mut DicePersistence) -> anyhow::Result<()> {
+    persistence.register_without_value::<GisKey>()
+}
+
 #[async_trait]
 pub trait HasGlobalInterpreterState {
     async fn get_global_interpreter_state(&mut self)
@@ -81,48 +137,6 @@ impl HasGlobalInterpreterState for DiceComputations<'_> {
     async fn get_global_interpreter_state(
         &mut self,
     ) -> anyhow::Result<Arc<GlobalInterpreterState>> {
-        #[derive(Clone, Dupe, Allocative)]
-        struct GisValue(Arc<GlobalInterpreterState>);
-
-        #[derive(
-            Clone,
-            derive_more::Display,
-            Dupe,
-            Debug,
-            Eq,
-            Hash,
-            PartialEq,
-            Allocative
-        )]
-        #[display(fmt = "{:?}", self)]
-        struct GisKey();
-
-        #[async_trait]
-        impl Key for GisKey {
-            type Value = buck2_error::Result<GisValue>;
-            async fn compute(
-                &self,
-                ctx: &mut DiceComputations,
-                _cancellation: &CancellationContext,
-            ) -> Self::Value {
-                let interpreter_configuror = ctx.get_interpreter_configuror().await?;
-                let cell_resolver = ctx.get_cell_resolver().await?;
-                let disable_starlark_types = ctx.get_disable_starlark_types().await?;
-                let unstable_typecheck = ctx.get_unstable_typecheck().await?;
-
-                Ok(GisValue(Arc::new(GlobalInterpreterState::new(
-                    cell_resolver,
-                    interpreter_configuror,
-                    disable_starlark_types,
-                    unstable_typecheck,
-                )?)))
-            }
-
-            fn equality(_: &Self::Value, _: &Self::Value) -> bool {
-                false
-            }
-        }
-
         Ok(self.compute(&GisKey()).await??.0)
     }
 }
@@ -0,0 +1,243 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lookup of values nested inside attributes, used by the structured query attribute filters.
+
+use crate::attrs::attr_type::any_matches::AnyMatches;
+use crate::attrs::coerced_attr::CoercedAttr;
+use crate::attrs::configured_attr::ConfiguredAttr;
+
+/// Find the values nested inside an attribute along a path of dict keys and list indices.
+pub trait AttrPathLookup: AnyMatches + Sized {
+    /// Calls `func` on every value at `path` within `self`, stopping at the first for which it
+    /// returns `true`. Dict segments match keys by their string form, list and tuple segments
+    /// are indices. All branches of a `select()` and all parts of a concatenation are searched.
+    fn any_at_path(
+        &self,
+        path: &[String],
+        func: &mut dyn FnMut(&Self) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<bool>;
+
+    /// Whether any value at `path` matches `filter`, with the same matching as `any_matches`.
+    fn any_matches_at_path(
+        &self,
+        path: &[String],
+        filter: &dyn Fn(&str) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<bool> {
+        self.any_at_path(path, &mut |value| value.any_matches(filter))
+    }
+}
+
+fn key_matches(key: &impl AnyMatches, segment: &str) -> anyhow::Result<bool> {
+    key.any_matches(&|key| Ok(key == segment))
+}
+
+fn any_at_index<A: AttrPathLookup>(
+    items: &[A],
+    segment: &str,
+    rest: &[String],
+    func: &mut dyn FnMut(&A) -> anyhow::Result<bool>,
+) -> anyhow::Result<bool> {
+    match segment.parse::<usize>().ok().and_then(|i| items.get(i)) {
+        Some(item) => item.any_at_path(rest, func),
+        None => Ok(false),
+    }
+}
+
+fn any_at_key<A: AttrPathLookup>(
+    entries: &[(A, A)],
+    segment: &str,
+    rest: &[String],
+    func: &mut dyn FnMut(&A) -> anyhow::Result<bool>,
+) -> anyhow::Result<bool> {
+    for (key, value) in entries {
+        if key_matches(key, segment)? && value.any_at_path(rest, func)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+impl AttrPathLookup for CoercedAttr {
+    fn any_at_path(
+        &self,
+        path: &[String],
+        func: &mut dyn FnMut(&Self) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<bool> {
+        match self {
+            CoercedAttr::Selector(selector) => {
+                for value in selector.all_values() {
+                    if value.any_at_path(path, func)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            CoercedAttr::Concat(items) => {
+                for item in &**items {
+                    if item.any_at_path(path, func)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            CoercedAttr::OneOf(value, _) => value.any_at_path(path, func),
+            _ => match path.split_first() {
+                None => func(self),
+                Some((segment, rest)) => match self {
+                    CoercedAttr::List(items) => any_at_index(items, segment, rest, func),
+                    CoercedAttr::Tuple(items) => any_at_index(items, segment, rest, func),
+                    CoercedAttr::Dict(entries) => any_at_key(entries, segment, rest, func),
+                    _ => Ok(false),
+                },
+            },
+        }
+    }
+}
+
+impl AttrPathLookup for ConfiguredAttr {
+    fn any_at_path(
+        &self,
+        path: &[String],
+        func: &mut dyn FnMut(&Self) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<bool> {
+        match self {
+            ConfiguredAttr::OneOf(value, _) => value.any_at_path(path, func),
+            _ => match path.split_first() {
+                None => func(self),
+                Some((segment, rest)) => match self {
+                    ConfiguredAttr::List(items) => any_at_index(items, segment, rest, func),
+                    ConfiguredAttr::Tuple(items) => any_at_index(items, segment, rest, func),
+                    ConfiguredAttr::Dict(entries) => any_at_key(entries, segment, rest, func),
+                    _ => Ok(false),
+                },
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_util::arc_str::ArcStr;
+    use dupe::Dupe;
+
+    use crate::attrs::attr_type::dict::DictLiteral;
+    use crate::attrs::attr_type::list::ListLiteral;
+    use crate::attrs::attr_type::string::StringLiteral;
+    use crate::attrs::coerced_attr::CoercedAttr;
+    use crate::attrs::coerced_attr::CoercedSelector;
+    use crate::attrs::configured_attr::ConfiguredAttr;
+    use crate::attrs::path_lookup::AttrPathLookup;
+    use crate::configuration::resolved::ConfigurationSettingKey;
+
+    fn string(s: &str) -> CoercedAttr {
+        CoercedAttr::String(StringLiteral(ArcStr::from(s)))
+    }
+
+    fn path(segments: &[&str]) -> Vec<String> {
+        segments.iter().map(|s| (*s).to_owned()).collect()
+    }
+
+    fn matches(attr: &CoercedAttr, segments: &[&str], value: &str) -> bool {
+        attr.any_matches_at_path(&path(segments), &|s| Ok(s == value))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_any_matches_at_path() {
+        let env = CoercedAttr::Dict(DictLiteral(
+            vec![
+                (string("CC"), string("clang")),
+                (
+                    string("FLAGS"),
+                    CoercedAttr::List(ListLiteral(vec![string("-O2"), string("-g")].into())),
+                ),
+            ]
+            .into(),
+        ));
+
+        assert!(matches(&env, &[], "clang"));
+        assert!(matches(&env, &["CC"], "clang"));
+        assert!(!matches(&env, &["FLAGS"], "clang"));
+        assert!(matches(&env, &["FLAGS"], "-g"));
+        assert!(matches(&env, &["FLAGS", "1"], "-g"));
+        assert!(!matches(&env, &["FLAGS", "0"], "-g"));
+        assert!(!matches(&env, &["FLAGS", "2"], "-g"));
+        assert!(!matches(&env, &["CC", "0"], "clang"));
+    }
+
+    #[test]
+    fn test_any_matches_at_path_in_select() {
+        let key = ConfigurationSettingKey::testing_parse("foo//:linux");
+        let selector = CoercedAttr::Selector(Box::new(
+            CoercedSelector::new(
+                vec![(
+                    key.dupe(),
+                    CoercedAttr::Dict(DictLiteral(vec![(string("CC"), string("gcc"))].into())),
+                )]
+                .into(),
+                Some(CoercedAttr::Dict(DictLiteral(
+                    vec![(string("CC"), string("clang"))].into(),
+                ))),
+            )
+            .unwrap(),
+        ));
+
+        assert!(matches(&selector, &["CC"], "gcc"));
+        assert!(matches(&selector, &["CC"], "clang"));
+        assert!(!matches(&selector, &["CXX"], "clang"));
+    }
+
+    fn configured_string(s: &str) -> ConfiguredAttr {
+        ConfiguredAttr::String(StringLiteral(ArcStr::from(s)))
+    }
+
+    #[test]
+    fn test_configured_any_at_path() {
+        let env = ConfiguredAttr::OneOf(
+            Box::new(ConfiguredAttr::Dict(DictLiteral(
+                vec![
+                    (configured_string("CC"), configured_string("clang")),
+                    (configured_string("LD"), ConfiguredAttr::None),
+                    (
+                        configured_string("FLAGS"),
+                        ConfiguredAttr::List(ListLiteral(
+                            vec![configured_string("-O2"), configured_string("-g")].into(),
+                        )),
+                    ),
+                ]
+                .into(),
+            ))),
+            0,
+        );
+        let matches = |segments: &[&str], value: &str| {
+            env.any_matches_at_path(&path(segments), &|s| Ok(s == value))
+                .unwrap()
+        };
+        let exists = |segments: &[&str]| {
+            env.any_at_path(&path(segments), &mut |v| {
+                Ok(!matches!(v, ConfiguredAttr::None))
+            })
+            .unwrap()
+        };
+
+        assert!(matches(&[], "clang"));
+        assert!(matches(&["CC"], "clang"));
+        assert!(!matches(&["CXX"], "clang"));
+        assert!(matches(&["FLAGS", "1"], "-g"));
+        assert!(!matches(&["FLAGS", "0"], "-g"));
+        assert!(!matches(&["CC", "0"], "clang"));
+
+        assert!(exists(&["CC"]));
+        assert!(!exists(&["LD"]));
+        assert!(!exists(&["CXX"]));
+        assert!(exists(&["FLAGS", "0"]));
+        assert!(!exists(&["FLAGS", "2"]));
+    }
+}
@@ -0,0 +1,86 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Counts of the package and configured node computations of a DICE transaction, used by
+//! `query --explain` to attribute work to query expressions. They are kept per transaction so
+//! that the computations of concurrent commands are not attributed to the query.
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use buck2_query::query::syntax::simple::eval::explain::ComputeCounters;
+use dice::DiceComputations;
+use dice::UserComputationData;
+use dupe::Dupe;
+
+#[derive(Default)]
+pub struct TransactionComputeCounters {
+    packages: AtomicU64,
+    configured_nodes: AtomicU64,
+}
+
+impl TransactionComputeCounters {
+    pub fn get(&self) -> ComputeCounters {
+        ComputeCounters {
+            packages: self.packages.load(Ordering::Relaxed),
+            configured_nodes: self.configured_nodes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub trait SetComputeCounters {
+    fn set_compute_counters(&mut self);
+}
+
+impl SetComputeCounters for UserComputationData {
+    fn set_compute_counters(&mut self) {
+        self.data
+            .set(Arc::new(TransactionComputeCounters::default()));
+    }
+}
+
+pub trait HasComputeCounters {
+    /// The counters of this transaction, if they were set up for it.
+    fn compute_counters(&self) -> Option<Arc<TransactionComputeCounters>>;
+
+    fn record_package_computed(&self);
+
+    fn record_configured_node_computed(&self);
+}
+
+impl HasComputeCounters for DiceComputations<'_> {
+    fn compute_counters(&self) -> Option<Arc<TransactionComputeCounters>> {
+        self.per_transaction_data()
+            .data
+            .get::<Arc<TransactionComputeCounters>>()
+            .ok()
+            .map(|counters| counters.dupe())
+    }
+
+    fn record_package_computed(&self) {
+        if let Ok(counters) = self
+            .per_transaction_data()
+            .data
+            .get::<Arc<TransactionComputeCounters>>()
+        {
+            counters.packages.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn record_configured_node_computed(&self) {
+        if let Ok(counters) = self
+            .per_transaction_data()
+            .data
+            .get::<Arc<TransactionComputeCounters>>()
+        {
+            counters.configured_nodes.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
@@ -15,6 +15,8 @@ use buck2_core::configuration::config_setting::ConfigSettingData;
 use buck2_core::configuration::pair::ConfigurationNoExec;
 use buck2_core::target::label::label::TargetLabel;
 use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
 use starlark_map::unordered_map::UnorderedMap;
 
 /// Key in `select` or an item in `target_compatible_with`.
@@ -31,6 +33,7 @@ use starlark_map::unordered_map::UnorderedMap;
     Ord,
     PartialOrd
 )]
+#[derive(Serialize, Deserialize)]
 pub struct ConfigurationSettingKey(pub TargetLabel);
 
 impl ConfigurationSettingKey {
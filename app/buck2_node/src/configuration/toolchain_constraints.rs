@@ -13,15 +13,19 @@ use allocative::Allocative;
 use buck2_core::target::label::label::TargetLabel;
 use dupe::Dupe;
 use dupe::IterDupedExt;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::configuration::resolved::ConfigurationSettingKey;
 
 /// The constraint introduced on execution platform resolution by
 /// a toolchain rule (reached via a toolchain_dep).
 #[derive(Debug, Dupe, Clone, PartialEq, Eq, Hash, Allocative)]
+#[derive(Serialize, Deserialize)]
 pub struct ToolchainConstraints(Arc<ToolchainConstraintsImpl>);
 
 #[derive(Debug, PartialEq, Eq, Hash, Allocative)]
+#[derive(Serialize, Deserialize)]
 struct ToolchainConstraintsImpl {
     exec_deps: Vec<TargetLabel>,
     exec_compatible_with: Vec<ConfigurationSettingKey>,
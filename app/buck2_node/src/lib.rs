@@ -14,6 +14,7 @@
 pub mod attrs;
 pub mod call_stack;
 pub mod cfg_constructor;
+pub mod compute_counters;
 pub mod configuration;
 pub mod configured_universe;
 pub mod execution;
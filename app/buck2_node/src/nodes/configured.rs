@@ -203,6 +203,25 @@ impl ConfiguredTargetNode {
         execution_platform_resolution: ExecutionPlatformResolution,
         attrs: Vec<(&str, Attribute, CoercedAttr)>,
         internal_attrs: Vec<(&str, Attribute, CoercedAttr)>,
+    ) -> Self {
+        Self::testing_new_with_deps(
+            name,
+            rule_type,
+            execution_platform_resolution,
+            attrs,
+            internal_attrs,
+            Vec::new(),
+        )
+    }
+
+    /// Like `testing_new`, with the given (non-exec) dependencies.
+    pub fn testing_new_with_deps(
+        name: ConfiguredTargetLabel,
+        rule_type: &str,
+        execution_platform_resolution: ExecutionPlatformResolution,
+        attrs: Vec<(&str, Attribute, CoercedAttr)>,
+        internal_attrs: Vec<(&str, Attribute, CoercedAttr)>,
+        deps: Vec<ConfiguredTargetNode>,
     ) -> Self {
         use crate::nodes::unconfigured::testing::TargetNodeExt;
 
@@ -220,7 +239,7 @@ impl ConfiguredTargetNode {
             ),
             OrderedMap::new(),
             execution_platform_resolution,
-            Vec::new(),
+            deps,
             Vec::new(),
             OrderedMap::new(),
             PluginLists::new(),
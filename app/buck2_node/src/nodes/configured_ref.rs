@@ -23,6 +23,7 @@ use ref_cast::RefCast;
 use crate::attrs::attr_type::any_matches::AnyMatches;
 use crate::attrs::configured_attr::ConfiguredAttr;
 use crate::attrs::inspect_options::AttrInspectOptions;
+use crate::attrs::path_lookup::AttrPathLookup;
 use crate::nodes::configured::ConfiguredTargetNode;
 
 /// `ConfiguredTargetNode` as both `LabeledNode` and `NodeLabel` and also `QueryTarget`.
@@ -147,6 +148,20 @@ impl QueryTarget for ConfiguredGraphNodeRef {
         attr.any_matches(filter)
     }
 
+    fn attr_path_any_matches(
+        attr: &Self::Attr<'_>,
+        path: &[String],
+        filter: &dyn Fn(&str) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<bool> {
+        attr.any_matches_at_path(path, filter)
+    }
+
+    fn attr_path_exists(attr: &Self::Attr<'_>, path: &[String]) -> anyhow::Result<bool> {
+        attr.any_at_path(path, &mut |value| {
+            Ok(!matches!(value, ConfiguredAttr::None))
+        })
+    }
+
     fn special_attrs_for_each<E, F: FnMut(&str, &Self::Attr<'_>) -> Result<(), E>>(
         &self,
         mut func: F,
@@ -20,6 +20,7 @@ use starlark_map::Hashed;
 use crate::attrs::attr_type::any_matches::AnyMatches;
 use crate::attrs::configured_attr::ConfiguredAttr;
 use crate::attrs::inspect_options::AttrInspectOptions;
+use crate::attrs::path_lookup::AttrPathLookup;
 use crate::nodes::configured::ConfiguredTargetNode;
 use crate::nodes::configured::ConfiguredTargetNodeRef;
 
@@ -94,6 +95,25 @@ impl QueryTarget for ConfiguredTargetNode {
         attr.any_matches(filter)
     }
 
+    fn attr_path_any_matches(
+        attr: &Self::Attr<'_>,
+        path: &[String],
+        filter: &dyn Fn(&str) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<bool> {
+        attr.any_matches_at_path(path, filter)
+    }
+
+    fn attr_path_exists(attr: &Self::Attr<'_>, path: &[String]) -> anyhow::Result<bool> {
+        attr.any_at_path(path, &mut |value| {
+            Ok(!matches!(value, ConfiguredAttr::None))
+        })
+    }
+
+    /// Visibility is declared on unconfigured targets, so it does not depend on configurations.
+    fn is_visible_to_target(&self, target: &Self) -> anyhow::Result<bool> {
+        ConfiguredTargetNode::is_visible_to(self, target.label().unconfigured())
+    }
+
     fn attrs_for_each<E, F: FnMut(&str, &Self::Attr<'_>) -> Result<(), E>>(
         &self,
         mut func: F,
@@ -144,3 +164,86 @@ impl<'a> LabeledNode for ConfiguredTargetNodeRef<'a> {
         ConfiguredTargetNodeRef::hashed_label(*self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::execution_types::execution::ExecutionPlatformResolution;
+    use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+    use buck2_query::query::syntax::simple::eval::set::TargetSet;
+    use buck2_util::arc_str::ArcStr;
+
+    use crate::attrs::attr::Attribute;
+    use crate::attrs::attr_type::dict::DictLiteral;
+    use crate::attrs::attr_type::string::StringLiteral;
+    use crate::attrs::attr_type::AttrType;
+    use crate::attrs::coerced_attr::CoercedAttr;
+    use crate::nodes::configured::ConfiguredTargetNode;
+
+    fn string(s: &str) -> CoercedAttr {
+        CoercedAttr::String(StringLiteral(ArcStr::from(s)))
+    }
+
+    fn node(label: &str, env: Vec<(CoercedAttr, CoercedAttr)>) -> ConfiguredTargetNode {
+        ConfiguredTargetNode::testing_new(
+            ConfiguredTargetLabel::testing_parse(label, ConfigurationData::testing_new()),
+            "some_rule",
+            ExecutionPlatformResolution::new(None, Vec::new()),
+            vec![(
+                "env",
+                Attribute::new(
+                    None,
+                    "",
+                    AttrType::dict(
+                        AttrType::string(),
+                        AttrType::option(AttrType::string()),
+                        false,
+                    ),
+                ),
+                CoercedAttr::Dict(DictLiteral(env.into())),
+            )],
+            vec![],
+        )
+    }
+
+    fn names(targets: TargetSet<ConfiguredTargetNode>) -> Vec<String> {
+        targets
+            .iter()
+            .map(|t| t.label().name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_attr_paths() -> anyhow::Result<()> {
+        let targets = TargetSet::from_iter([
+            node("root//:clang", vec![(string("CC"), string("clang"))]),
+            node("root//:gcc", vec![(string("CC"), string("gcc"))]),
+            node("root//:unset", vec![(string("CC"), CoercedAttr::None)]),
+            node("root//:empty", vec![]),
+        ]);
+
+        assert_eq!(
+            vec!["clang", "gcc", "unset", "empty"],
+            names(targets.attrexists("env")?)
+        );
+        assert_eq!(vec!["clang", "gcc"], names(targets.attrexists("env[CC]")?));
+        assert_eq!(Vec::<String>::new(), names(targets.attrexists("env[CXX]")?));
+
+        assert_eq!(
+            vec!["clang"],
+            names(targets.attrfilter("env[CC]", &|v| Ok(v == "clang"))?)
+        );
+        assert_eq!(
+            vec!["gcc", "unset", "empty"],
+            names(targets.nattrfilter("env[CC]", &|v| Ok(v == "clang"))?)
+        );
+
+        // An empty attribute matches nothing, while a malformed path is an error.
+        assert_eq!(
+            Vec::<String>::new(),
+            names(targets.attrfilter("", &|_| Ok(true))?)
+        );
+        assert!(targets.attrexists("env[CC").is_err());
+        Ok(())
+    }
+}
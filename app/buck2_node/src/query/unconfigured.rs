@@ -18,6 +18,7 @@ use dupe::Dupe;
 
 use crate::attrs::coerced_attr::CoercedAttr;
 use crate::attrs::inspect_options::AttrInspectOptions;
+use crate::attrs::path_lookup::AttrPathLookup;
 use crate::nodes::unconfigured::TargetNode;
 use crate::nodes::unconfigured::TargetNodeData;
 
@@ -74,6 +75,22 @@ impl QueryTarget for TargetNode {
         attr.any_matches(filter)
     }
 
+    fn attr_path_any_matches(
+        attr: &Self::Attr<'_>,
+        path: &[String],
+        filter: &dyn Fn(&str) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<bool> {
+        attr.any_matches_at_path(path, filter)
+    }
+
+    fn attr_path_exists(attr: &Self::Attr<'_>, path: &[String]) -> anyhow::Result<bool> {
+        attr.any_at_path(path, &mut |value| Ok(!matches!(value, CoercedAttr::None)))
+    }
+
+    fn is_visible_to_target(&self, target: &Self) -> anyhow::Result<bool> {
+        TargetNode::is_visible_to(self, target.label())
+    }
+
     fn special_attrs_for_each<E, F: FnMut(&str, &Self::Attr<'_>) -> Result<(), E>>(
         &self,
         mut func: F,
@@ -114,6 +114,32 @@ pub trait QueryTarget: LabeledNode + Dupe + Send + Sync + 'static {
         filter: &dyn Fn(&str) -> anyhow::Result<bool>,
     ) -> anyhow::Result<bool>;
 
+    /// Like `attr_any_matches`, but only considers the values nested inside `attr` at `path`
+    /// (see `AttrPath`). Targets which don't support nested lookups only match the empty path.
+    fn attr_path_any_matches(
+        attr: &Self::Attr<'_>,
+        path: &[String],
+        filter: &dyn Fn(&str) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<bool> {
+        if path.is_empty() {
+            Self::attr_any_matches(attr, filter)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Whether `attr` has a value other than `None` at `path`.
+    fn attr_path_exists(_attr: &Self::Attr<'_>, path: &[String]) -> anyhow::Result<bool> {
+        Ok(path.is_empty())
+    }
+
+    /// Whether `target` is allowed to depend on this target, according to its visibility.
+    fn is_visible_to_target(&self, _target: &Self) -> anyhow::Result<bool> {
+        Err(anyhow::anyhow!(QueryError::FunctionUnimplemented(
+            "visible_to() is implemented only for uquery and cquery."
+        )))
+    }
+
     fn special_attrs_for_each<E, F: FnMut(&str, &Self::Attr<'_>) -> Result<(), E>>(
         &self,
         func: F,
@@ -266,6 +292,39 @@ pub trait QueryEnvironment: Send + Sync {
         Ok(rdeps)
     }
 
+    /// Reverse dependencies of `targets` among all the targets in the repository, without an
+    /// explicit universe.
+    async fn allrdeps(
+        &self,
+        _targets: &TargetSet<Self::Target>,
+        _depth: Option<i32>,
+    ) -> anyhow::Result<TargetSet<Self::Target>> {
+        Err(anyhow::anyhow!(QueryError::FunctionUnimplemented(
+            "allrdeps() is implemented only for uquery and cquery."
+        )))
+    }
+
+    /// The targets of `targets` visible to every target of `to`.
+    async fn visible_to(
+        &self,
+        _to: &TargetSet<Self::Target>,
+        _targets: &TargetSet<Self::Target>,
+    ) -> anyhow::Result<TargetSet<Self::Target>> {
+        Err(anyhow::anyhow!(QueryError::FunctionUnimplemented(
+            "visible_to() is implemented only for uquery and cquery."
+        )))
+    }
+
+    /// All the targets declared in the packages of `targets`.
+    async fn siblings(
+        &self,
+        _targets: &TargetSet<Self::Target>,
+    ) -> anyhow::Result<TargetSet<Self::Target>> {
+        Err(anyhow::anyhow!(QueryError::FunctionUnimplemented(
+            "siblings() is implemented only for uquery and cquery."
+        )))
+    }
+
     async fn testsof(
         &self,
         targets: &TargetSet<Self::Target>,
@@ -21,6 +21,7 @@ use derive_more::From;
 use indexmap::IndexSet;
 
 use super::*;
+use crate::query::syntax::simple::eval::paths::TargetPaths;
 
 #[derive(Debug, Copy, Clone, Dupe, Eq, PartialEq, Hash, Display, From)]
 struct TestTargetId(u64);
@@ -265,6 +266,49 @@ async fn test_one_path() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_path_edges() -> anyhow::Result<()> {
+    let mut env = TestEnvBuilder::default();
+    env.edge(1, 2);
+    env.edge(2, 3);
+    env.edge(1, 10);
+    env.edge(10, 3);
+    env.edge(1, 3);
+    // Not on a path
+    env.edge(3, 4);
+    env.edge(10, 20);
+    let env = env.build();
+
+    let paths = TargetPaths::subgraph(env.allpaths(&env.set("1")?, &env.set("3")?).await?);
+    assert!(!paths.is_single_path());
+    let deps = |id| {
+        paths
+            .deps(&TestTargetId(id))
+            .iter()
+            .map(|t| t.0)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec![2, 10, 3], deps(1));
+    assert_eq!(vec![3], deps(2));
+    assert_eq!(vec![3], deps(10));
+    assert_eq!(Vec::<u64>::new(), deps(3));
+
+    let paths = TargetPaths::single_path(env.set("1,2,3")?.into_iter());
+    assert!(paths.is_single_path());
+    let deps = |id| {
+        paths
+            .deps(&TestTargetId(id))
+            .iter()
+            .map(|t| t.0)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec![2], deps(1));
+    assert_eq!(vec![3], deps(2));
+    assert_eq!(Vec::<u64>::new(), deps(3));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_many_paths() -> anyhow::Result<()> {
     let mut env = TestEnvBuilder::default();
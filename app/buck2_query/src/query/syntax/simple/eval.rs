@@ -7,12 +7,15 @@
  * of this source tree.
  */
 
+pub mod attr_path;
 pub mod error;
 pub mod evaluator;
+pub mod explain;
 pub mod file_set;
 pub mod label_indexed;
 pub mod literals;
 pub mod multi_query;
+pub mod paths;
 pub mod set;
 pub mod tests;
 pub mod values;
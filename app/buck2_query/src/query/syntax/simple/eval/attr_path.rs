@@ -0,0 +1,82 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+#[derive(Debug, buck2_error::Error)]
+enum AttrPathError {
+    #[error(
+        "Invalid attribute path `{0}`, expected an attribute name followed by any number of `[key]` or `[index]`"
+    )]
+    Invalid(String),
+}
+
+/// The attribute argument of the attribute filters: an attribute name, optionally followed by
+/// `[segment]`s which select the values nested inside it, e.g. `env[CC]` or `srcs[0]`. Segments
+/// may be quoted, so that they can contain `]`. An empty path names no attribute, so it matches
+/// no target, like any other attribute which targets don't have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttrPath {
+    pub attr: String,
+    pub segments: Vec<String>,
+}
+
+impl AttrPath {
+    pub fn parse(path: &str) -> anyhow::Result<AttrPath> {
+        let invalid = || AttrPathError::Invalid(path.to_owned());
+
+        let (attr, mut rest) = path.split_at(path.find('[').unwrap_or(path.len()));
+        if attr.is_empty() && !rest.is_empty() {
+            return Err(invalid().into());
+        }
+
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            let inner = rest.strip_prefix('[').ok_or_else(invalid)?;
+            let (segment, after) = match inner.chars().next() {
+                Some(quote @ ('\'' | '"')) => {
+                    let end = inner[1..].find(quote).ok_or_else(invalid)? + 1;
+                    let after = inner[end + 1..].strip_prefix(']').ok_or_else(invalid)?;
+                    (&inner[1..end], after)
+                }
+                _ => inner.split_once(']').ok_or_else(invalid)?,
+            };
+            segments.push(segment.to_owned());
+            rest = after;
+        }
+
+        Ok(AttrPath {
+            attr: attr.to_owned(),
+            segments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::syntax::simple::eval::attr_path::AttrPath;
+
+    fn parse(path: &str) -> (String, Vec<String>) {
+        let path = AttrPath::parse(path).unwrap();
+        (path.attr, path.segments)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(("deps".to_owned(), vec![]), parse("deps"));
+        assert_eq!(("env".to_owned(), vec!["CC".to_owned()]), parse("env[CC]"));
+        assert_eq!(
+            ("env".to_owned(), vec!["a]b".to_owned(), "0".to_owned()]),
+            parse("env['a]b'][0]")
+        );
+        assert_eq!(("".to_owned(), vec![]), parse(""));
+        assert!(AttrPath::parse("[CC]").is_err());
+        assert!(AttrPath::parse("env[CC").is_err());
+        assert!(AttrPath::parse("env[CC]x").is_err());
+        assert!(AttrPath::parse("env['CC]").is_err());
+    }
+}
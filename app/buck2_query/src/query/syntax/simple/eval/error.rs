@@ -39,6 +39,8 @@ pub enum QueryError {
         min: usize,
         actual: usize,
     },
+    #[error("unbound variable `${0}`")]
+    UnboundVariable(String),
     #[error("function `{0}` is not implemented yet")]
     FunctionUnimplemented(&'static str),
     #[error("Argument `{1}` to `{0}` is not yet supported in buck2")]
@@ -9,31 +9,80 @@
 
 //! Implementation of the cli and query_* attr query language.
 
-use buck2_query_parser::parse_expr;
+use buck2_query_parser::parse_expr_with_variables;
 use buck2_query_parser::spanned::Spanned;
 use buck2_query_parser::Expr;
 use futures::FutureExt;
 use gazebo::prelude::*;
 use gazebo::variants::VariantName;
+use tokio::sync::OnceCell;
 
 use crate::__derive_refs::indexmap::IndexSet;
 use crate::query::environment::QueryEnvironment;
+use crate::query::environment::QueryTarget;
 use crate::query::syntax::simple::eval::error::QueryError;
+use crate::query::syntax::simple::eval::explain::QueryExplain;
 use crate::query::syntax::simple::eval::file_set::FileNode;
 use crate::query::syntax::simple::eval::file_set::FileSet;
 use crate::query::syntax::simple::eval::set::TargetSet;
 use crate::query::syntax::simple::eval::values::QueryEvaluationValue;
 use crate::query::syntax::simple::eval::values::QueryResult;
 use crate::query::syntax::simple::eval::values::QueryValue;
+use crate::query::syntax::simple::eval::values::QueryVariables;
 use crate::query::syntax::simple::functions::QueryFunctions;
+
+/// A value bound by `let`. It's evaluated the first time it's referenced, and then reused.
+struct Binding<'e, T: QueryTarget> {
+    name: &'e str,
+    expr: &'e Spanned<Expr<'e>>,
+    value: OnceCell<QueryValue<T>>,
+    /// The enclosing bindings, which are the ones visible to `expr`.
+    parent: Option<&'e Binding<'e, T>>,
+}
+
+impl<'e, T: QueryTarget> Binding<'e, T> {
+    fn lookup(&'e self, name: &str) -> Option<&'e Binding<'e, T>> {
+        let mut binding = Some(self);
+        while let Some(b) = binding {
+            if b.name == name {
+                return Some(b);
+            }
+            binding = b.parent;
+        }
+        None
+    }
+}
+
 pub struct QueryEvaluator<'e, Env: QueryEnvironment> {
     env: &'e Env,
     functions: &'e dyn QueryFunctions<Env = Env>,
+    bindings: Option<&'e Binding<'e, Env::Target>>,
+    variables: Option<&'e QueryVariables<Env::Target>>,
+    explain: Option<&'e QueryExplain>,
 }
 
 impl<'e, Env: QueryEnvironment> QueryEvaluator<'e, Env> {
     pub fn new(env: &'e Env, functions: &'e dyn QueryFunctions<Env = Env>) -> Self {
-        Self { env, functions }
+        Self {
+            env,
+            functions,
+            bindings: None,
+            variables: None,
+            explain: None,
+        }
+    }
+
+    /// Make `variables` available to the evaluated queries as `$NAME`.
+    pub fn with_variables(self, variables: &'e QueryVariables<Env::Target>) -> Self {
+        Self {
+            variables: Some(variables),
+            ..self
+        }
+    }
+
+    /// Record the cost of every evaluated expression in `explain`.
+    pub fn with_explain(self, explain: Option<&'e QueryExplain>) -> Self {
+        Self { explain, ..self }
     }
 
     pub fn env(&self) -> &Env {
@@ -105,6 +154,48 @@ impl<'e, Env: QueryEnvironment> QueryEvaluator<'e, Env> {
 
                 Ok(files.into())
             }
+            Expr::Let { name, value, body } => {
+                let binding = Binding {
+                    name: *name.fragment(),
+                    expr: value,
+                    value: OnceCell::new(),
+                    parent: self.bindings,
+                };
+                let evaluator = QueryEvaluator {
+                    env: self.env,
+                    functions: self.functions,
+                    bindings: Some(&binding),
+                    variables: self.variables,
+                    explain: self.explain,
+                };
+                Ok(evaluator.eval(body).await?.value)
+            }
+            Expr::Variable(name) => {
+                let Some(binding) = self
+                    .bindings
+                    .and_then(|bindings| bindings.lookup(name.fragment()))
+                else {
+                    return self
+                        .variables
+                        .and_then(|variables| variables.get(name.fragment()))
+                        .cloned()
+                        .ok_or_else(|| QueryError::UnboundVariable((*name.fragment()).to_owned()));
+                };
+                let value = binding
+                    .value
+                    .get_or_try_init(|| async {
+                        let evaluator = QueryEvaluator {
+                            env: self.env,
+                            functions: self.functions,
+                            bindings: binding.parent,
+                            variables: self.variables,
+                            explain: self.explain,
+                        };
+                        Ok::<_, QueryError>(evaluator.eval(binding.expr).await?.value)
+                    })
+                    .await?;
+                Ok(value.clone())
+            }
         }
     }
 
@@ -114,14 +205,24 @@ impl<'e, Env: QueryEnvironment> QueryEvaluator<'e, Env> {
     ) -> std::pin::Pin<
         Box<dyn std::future::Future<Output = QueryResult<QueryValue<Env::Target>>> + Send + 'a>,
     > {
-        async move { expr.span(self.eval_internal(&expr.value).await) }.boxed()
+        async move {
+            let Some(explain) = self.explain else {
+                return expr.span(self.eval_internal(&expr.value).await);
+            };
+            let timer = explain.start();
+            let result = self.eval_internal(&expr.value).await;
+            explain.record(&expr.position, timer, result.as_ref().ok());
+            expr.span(result)
+        }
+        .boxed()
     }
 
     pub async fn eval_query<'a>(
         &self,
         query: &str,
     ) -> anyhow::Result<QueryEvaluationValue<Env::Target>> {
-        let parsed_query = parse_expr(query)?;
+        let variables = self.variables.map(|v| v.names()).unwrap_or_default();
+        let parsed_query = parse_expr_with_variables(query, &variables)?;
         match self.eval_parsed_query(&parsed_query).await {
             Ok(v) => Ok(v.value),
             Err(e) => Err(QueryError::convert_error(e, query)),
@@ -143,6 +244,7 @@ impl<'e, Env: QueryEnvironment> QueryEvaluator<'e, Env> {
                     )),
                     QueryValue::TargetSet(targets) => Ok(QueryEvaluationValue::TargetSet(targets)),
                     QueryValue::FileSet(files) => Ok(QueryEvaluationValue::FileSet(files)),
+                    QueryValue::TargetPaths(paths) => Ok(QueryEvaluationValue::TargetPaths(paths)),
                     _ => Err(QueryError::InvalidType {
                         expected: "targets",
                         actual: value.variant_name(),
@@ -0,0 +1,220 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Per-expression cost breakdown of a query evaluation, reported by `--explain`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use buck2_query_parser::parse_expr;
+use buck2_query_parser::spanned::Spanned;
+use buck2_query_parser::Expr;
+use buck2_util::truncate::truncate;
+
+use crate::query::environment::QueryTarget;
+use crate::query::syntax::simple::eval::values::QueryValue;
+
+/// Counts of computations which are expensive enough to be worth attributing to query
+/// expressions. The counters are cumulative, so they are only meaningful as a difference
+/// between two snapshots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComputeCounters {
+    /// Packages loaded by the interpreter.
+    pub packages: u64,
+    /// Configured target nodes computed.
+    pub configured_nodes: u64,
+}
+
+impl ComputeCounters {
+    fn since(self, earlier: ComputeCounters) -> ComputeCounters {
+        ComputeCounters {
+            packages: self.packages.saturating_sub(earlier.packages),
+            configured_nodes: self
+                .configured_nodes
+                .saturating_sub(earlier.configured_nodes),
+        }
+    }
+
+    fn add(&mut self, other: ComputeCounters) {
+        self.packages += other.packages;
+        self.configured_nodes += other.configured_nodes;
+    }
+}
+
+/// Started when an expression begins evaluating, consumed when it is recorded.
+pub struct ExplainTimer {
+    start: Instant,
+    counters: ComputeCounters,
+}
+
+#[derive(Default)]
+struct ExprStats {
+    evaluations: u32,
+    time: Duration,
+    /// Size of the last value produced, if it was a set.
+    produced: Option<(usize, &'static str)>,
+    computed: ComputeCounters,
+}
+
+/// Collects wall time, result size and computations for every expression evaluated by a
+/// `QueryEvaluator`. Expressions are identified by their span in the query.
+pub struct QueryExplain {
+    counters: Box<dyn Fn() -> ComputeCounters + Send + Sync>,
+    setup: Mutex<Option<ExprStats>>,
+    exprs: Mutex<HashMap<Range<usize>, ExprStats>>,
+}
+
+impl QueryExplain {
+    /// `counters` should only count the work done for the query being explained, so that
+    /// concurrent commands don't show up in its explanation.
+    pub fn new(counters: impl Fn() -> ComputeCounters + Send + Sync + 'static) -> Self {
+        Self {
+            counters: Box::new(counters),
+            setup: Mutex::new(None),
+            exprs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn start(&self) -> ExplainTimer {
+        ExplainTimer {
+            start: Instant::now(),
+            counters: (self.counters)(),
+        }
+    }
+
+    fn finish(&self, timer: ExplainTimer, stats: &mut ExprStats) {
+        stats.evaluations += 1;
+        stats.time += timer.start.elapsed();
+        stats.computed.add((self.counters)().since(timer.counters));
+    }
+
+    /// Record the work done before evaluation started, e.g. resolving literals and building
+    /// the target universe.
+    pub fn record_setup(&self, timer: ExplainTimer) {
+        let mut setup = self.setup.lock().unwrap();
+        self.finish(timer, setup.get_or_insert_with(ExprStats::default));
+    }
+
+    pub(crate) fn record<T: QueryTarget>(
+        &self,
+        position: &Range<usize>,
+        timer: ExplainTimer,
+        value: Option<&QueryValue<T>>,
+    ) {
+        let mut exprs = self.exprs.lock().unwrap();
+        let stats = exprs.entry(position.clone()).or_default();
+        self.finish(timer, stats);
+        if let Some(value) = value {
+            stats.produced = match value {
+                QueryValue::TargetSet(targets) => Some((targets.len(), "targets")),
+                QueryValue::TargetPaths(paths) => Some((paths.targets().len(), "targets")),
+                QueryValue::FileSet(files) => Some((files.len(), "files")),
+                QueryValue::String(_) | QueryValue::Integer(_) => None,
+            };
+        }
+    }
+
+    /// Render the recorded stats as the AST of `query`, one line per expression.
+    pub fn render(&self, query: &str) -> anyhow::Result<String> {
+        let parsed = parse_expr(query)?;
+        let exprs = self.exprs.lock().unwrap();
+        let mut out = String::new();
+        writeln!(
+            out,
+            "Query explain (times are inclusive; concurrently evaluated expressions may share computations)"
+        )?;
+        if let Some(setup) = &*self.setup.lock().unwrap() {
+            writeln!(out, "<setup>  {}", format_stats(setup))?;
+        }
+        render_expr(&mut out, query, &parsed, None, &exprs, "", "")?;
+        Ok(out)
+    }
+}
+
+fn format_stats(stats: &ExprStats) -> String {
+    let mut parts = vec![format!("{:.1?}", stats.time)];
+    if stats.evaluations > 1 {
+        parts.push(format!("evaluated {} times", stats.evaluations));
+    }
+    if let Some((len, what)) = stats.produced {
+        parts.push(format!("{} {}", len, what));
+    }
+    if stats.computed.packages != 0 {
+        parts.push(format!("{} packages loaded", stats.computed.packages));
+    }
+    if stats.computed.configured_nodes != 0 {
+        parts.push(format!(
+            "{} configured nodes computed",
+            stats.computed.configured_nodes
+        ));
+    }
+    parts.join(", ")
+}
+
+fn label(query: &str, expr: &Spanned<Expr>) -> String {
+    match &expr.value {
+        Expr::Function { function_name, .. } => format!("{}()", function_name.fragment()),
+        Expr::Let { name, .. } => format!("let {}", name.fragment()),
+        _ => truncate(&query[expr.position.clone()], 60),
+    }
+}
+
+fn render_expr(
+    out: &mut String,
+    query: &str,
+    expr: &Spanned<Expr>,
+    prefix_label: Option<&str>,
+    exprs: &HashMap<Range<usize>, ExprStats>,
+    indent: &str,
+    child_indent: &str,
+) -> anyhow::Result<()> {
+    let stats = match exprs.get(&expr.position) {
+        Some(stats) => format_stats(stats),
+        None => "not evaluated".to_owned(),
+    };
+    writeln!(
+        out,
+        "{}{}{}  {}",
+        indent,
+        prefix_label.unwrap_or_default(),
+        label(query, expr),
+        stats
+    )?;
+
+    let children: Vec<(Option<String>, &Spanned<Expr>)> = match &expr.value {
+        Expr::String(..) | Expr::Integer(..) | Expr::Set(..) | Expr::FileSet(..) => Vec::new(),
+        Expr::Variable(..) => Vec::new(),
+        Expr::Function { args, .. } => args.iter().map(|arg| (None, arg)).collect(),
+        Expr::BinaryOpSequence(left, rights) => std::iter::once((None, &**left))
+            .chain(
+                rights
+                    .iter()
+                    .map(|(op, right)| (Some(format!("{} ", op)), right)),
+            )
+            .collect(),
+        Expr::Let { value, body, .. } => vec![(Some("= ".to_owned()), &**value), (None, &**body)],
+    };
+    for (i, (child_label, child)) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        render_expr(
+            out,
+            query,
+            child,
+            child_label.as_deref(),
+            exprs,
+            &format!("{}{}", child_indent, if last { "└─ " } else { "├─ " }),
+            &format!("{}{}", child_indent, if last { "   " } else { "│  " }),
+        )?;
+    }
+    Ok(())
+}
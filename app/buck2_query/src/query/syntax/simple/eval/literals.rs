@@ -9,7 +9,7 @@
 
 //! Implementation of the cli and query_* attr query language.
 
-use buck2_query_parser::parse_expr;
+use buck2_query_parser::parse_expr_with_variables;
 use buck2_query_parser::placeholder::QUERY_PERCENT_S_PLACEHOLDER;
 use starlark_map::small_set::SmallSet;
 
@@ -19,12 +19,13 @@ use crate::query::syntax::simple::functions::QueryFunctionsVisitLiterals;
 use crate::query::syntax::simple::functions::QueryLiteralVisitor;
 
 /// Look through the expression to find all the target literals.
-/// Adds those that are found to `result` set.
+/// Adds those that are found to `result` set. `variables` are the names bound outside of the query.
 pub fn extract_target_literals<F: QueryFunctions>(
     functions: &F,
     query: &str,
+    variables: &[&str],
 ) -> anyhow::Result<Vec<String>> {
-    let parsed = parse_expr(query)?;
+    let parsed = parse_expr_with_variables(query, variables)?;
     struct LiteralExtractor {
         literals: SmallSet<String>,
     }
@@ -19,6 +19,16 @@ use crate::query::environment::QueryTarget;
 use crate::query::syntax::simple::eval::set::TargetSet;
 use crate::query::syntax::simple::eval::values::QueryEvaluationValue;
 
+/// Paths can't be merged, so are merged as their targets.
+fn mergeable<T: QueryTarget>(value: QueryEvaluationValue<T>) -> QueryEvaluationValue<T> {
+    match value {
+        QueryEvaluationValue::TargetPaths(paths) => {
+            QueryEvaluationValue::TargetSet(paths.into_targets())
+        }
+        value => value,
+    }
+}
+
 /// Used to represent the results for a "multi-query" (one that contains a "%s" and potentially is applied against multiple literals).
 pub struct MultiQueryResult<T: QueryTarget>(
     pub IndexMap<String, buck2_error::Result<QueryEvaluationValue<T>>>,
@@ -28,13 +38,13 @@ impl<T: QueryTarget> MultiQueryResult<T> {
     pub fn merged(self) -> anyhow::Result<QueryEvaluationValue<T>> {
         let mut iter = self.0.into_iter();
         let (first_literal, mut results) = match iter.next() {
-            Some((literal, value)) => (literal, value?),
+            Some((literal, value)) => (literal, mergeable(value?)),
             None => {
                 return Ok(QueryEvaluationValue::TargetSet(TargetSet::new()));
             }
         };
         for (name, value) in iter {
-            let value = value?;
+            let value = mergeable(value?);
             match (value, &mut results) {
                 (
                     QueryEvaluationValue::TargetSet(value),
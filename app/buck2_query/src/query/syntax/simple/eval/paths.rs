@@ -0,0 +1,80 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::HashMap;
+
+use crate::query::environment::QueryTarget;
+use crate::query::graph::node::LabeledNode;
+use crate::query::syntax::simple::eval::set::TargetSet;
+
+/// The result of `somepath()` or `allpaths()`: the targets on the dependency paths, along with
+/// the dependencies between them that lie on those paths. Anywhere a target set is expected,
+/// this is used as just its targets.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TargetPaths<T: QueryTarget> {
+    targets: TargetSet<T>,
+    deps: HashMap<T::Key, Vec<T::Key>>,
+    single_path: bool,
+}
+
+impl<T: QueryTarget> TargetPaths<T> {
+    /// A single path, ordered from the dependent to the dependency.
+    pub fn single_path(path: impl IntoIterator<Item = T>) -> Self {
+        let targets: TargetSet<T> = path.into_iter().collect();
+        let deps = targets
+            .iter()
+            .zip(targets.iter().skip(1))
+            .map(|(from, to)| (from.node_key().clone(), vec![to.node_key().clone()]))
+            .collect();
+        Self {
+            targets,
+            deps,
+            single_path: true,
+        }
+    }
+
+    /// All the paths through `targets`. Every dependency between two targets on a path lies on a
+    /// path itself, so these are all the dependencies between `targets`.
+    pub fn subgraph(targets: TargetSet<T>) -> Self {
+        let deps = targets
+            .iter()
+            .map(|target| {
+                let deps = target
+                    .deps()
+                    .filter(|dep| targets.contains(dep))
+                    .cloned()
+                    .collect();
+                (target.node_key().clone(), deps)
+            })
+            .collect();
+        Self {
+            targets,
+            deps,
+            single_path: false,
+        }
+    }
+
+    /// Whether this is a single path, in which case `targets` are in path order.
+    pub fn is_single_path(&self) -> bool {
+        self.single_path
+    }
+
+    pub fn targets(&self) -> &TargetSet<T> {
+        &self.targets
+    }
+
+    pub fn into_targets(self) -> TargetSet<T> {
+        self.targets
+    }
+
+    /// The dependencies of `target` that lie on the paths.
+    pub fn deps(&self, target: &T::Key) -> &[T::Key] {
+        self.deps.get(target).map_or(&[], |deps| deps.as_slice())
+    }
+}
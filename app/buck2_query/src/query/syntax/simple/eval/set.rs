@@ -18,6 +18,7 @@ use fancy_regex::RegexBuilder;
 use indexmap::IndexSet;
 
 use crate::query::environment::QueryTarget;
+use crate::query::syntax::simple::eval::attr_path::AttrPath;
 use crate::query::syntax::simple::eval::file_set::FileNode;
 use crate::query::syntax::simple::eval::file_set::FileSet;
 use crate::query::syntax::simple::eval::label_indexed;
@@ -169,15 +170,17 @@ impl<T: QueryTarget> FromIterator<T> for TargetSet<T> {
 /// This contains additional TargetSet functions implemented via the core
 /// functions on TargetSet itself.
 impl<T: QueryTarget> TargetSet<T> {
+    /// `attribute` is parsed as an `AttrPath`, so it can select values nested inside the attribute.
     pub fn attrfilter(
         &self,
         attribute: &str,
         filter: &dyn Fn(&str) -> anyhow::Result<bool>,
     ) -> anyhow::Result<TargetSet<T>> {
+        let path = AttrPath::parse(attribute)?;
         self.filter(move |node| {
-            node.map_attr(attribute, |val| match val {
+            node.map_attr(&path.attr, |val| match val {
                 None => Ok(false),
-                Some(v) => T::attr_any_matches(v, &filter),
+                Some(v) => T::attr_path_any_matches(v, &path.segments, &filter),
             })
         })
     }
@@ -187,10 +190,23 @@ impl<T: QueryTarget> TargetSet<T> {
         attribute: &str,
         filter: &dyn Fn(&str) -> anyhow::Result<bool>,
     ) -> anyhow::Result<TargetSet<T>> {
+        let path = AttrPath::parse(attribute)?;
         self.filter(move |node| {
-            node.map_attr(attribute, |val| match val {
+            node.map_attr(&path.attr, |val| match val {
                 None => Ok(false),
-                Some(v) => Ok(!T::attr_any_matches(v, &filter)?),
+                Some(v) => Ok(!T::attr_path_any_matches(v, &path.segments, &filter)?),
+            })
+        })
+    }
+
+    /// Targets which have a value other than `None` for `attribute`, which is parsed as an
+    /// `AttrPath`.
+    pub fn attrexists(&self, attribute: &str) -> anyhow::Result<TargetSet<T>> {
+        let path = AttrPath::parse(attribute)?;
+        self.filter(move |node| {
+            node.map_attr(&path.attr, |val| match val {
+                None => Ok(false),
+                Some(v) => T::attr_path_exists(v, &path.segments),
             })
         })
     }
@@ -18,6 +18,7 @@ use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::configuration::compatibility::MaybeCompatible;
 use buck2_query_parser::parse_expr;
+use buck2_query_parser::parse_expr_with_variables;
 use derive_more::Display;
 use dupe::Dupe;
 
@@ -28,8 +29,12 @@ use crate::query::graph::node::NodeKey;
 use crate::query::graph::successors::AsyncChildVisitor;
 use crate::query::syntax::simple::eval::error::QueryError;
 use crate::query::syntax::simple::eval::evaluator::QueryEvaluator;
+use crate::query::syntax::simple::eval::explain::ComputeCounters;
+use crate::query::syntax::simple::eval::explain::QueryExplain;
 use crate::query::syntax::simple::eval::file_set::FileSet;
 use crate::query::syntax::simple::eval::set::TargetSet;
+use crate::query::syntax::simple::eval::values::QueryValue;
+use crate::query::syntax::simple::eval::values::QueryVariables;
 use crate::query::syntax::simple::functions::DefaultQueryFunctionsModule;
 
 #[derive(Clone, Hash, PartialEq, Eq, Debug, Display)]
@@ -213,3 +218,89 @@ pub async fn test_missing_arg() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[tokio::test]
+pub async fn test_let() -> anyhow::Result<()> {
+    let functions = DefaultQueryFunctionsModule::new();
+    let evaluator = &QueryEvaluator::new(&Env, &functions);
+
+    let eval = |input: &'static str| async move {
+        let parsed = parse_expr(input)?;
+        evaluator
+            .eval(&parsed)
+            .await
+            .map(|v| v.value)
+            .map_err(|e| QueryError::convert_error(e, input))
+    };
+
+    assert_eq!(QueryValue::Integer(1), eval("let x = 1 in $x").await?);
+    assert_eq!(
+        QueryValue::Integer(2),
+        eval("let x = 1 in let x = 2 in $x").await?
+    );
+    assert_eq!(
+        QueryValue::String("a".to_owned()),
+        eval("let x = a in let y = $x in $y").await?
+    );
+    // Bound values are only evaluated if they are used.
+    assert_eq!(QueryValue::Integer(1), eval("let x = kind() in 1").await?);
+
+    // Unbound variables are words.
+    assert_eq!(
+        QueryValue::String("$y".to_owned()),
+        eval("let x = 1 in $y").await?
+    );
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_variables() -> anyhow::Result<()> {
+    let functions = DefaultQueryFunctionsModule::new();
+    let mut variables = QueryVariables::default();
+    variables.insert("x".to_owned(), QueryValue::Integer(1));
+    variables.insert("y".to_owned(), QueryValue::Integer(2));
+    variables.insert("x".to_owned(), QueryValue::Integer(3));
+    let variables = &variables;
+    let evaluator = &QueryEvaluator::new(&Env, &functions).with_variables(variables);
+
+    let eval = |input: &'static str| async move {
+        let parsed = parse_expr_with_variables(input, &variables.names())?;
+        evaluator
+            .eval(&parsed)
+            .await
+            .map(|v| v.value)
+            .map_err(|e| QueryError::convert_error(e, input))
+    };
+
+    // Inserting a variable again replaces its value.
+    assert_eq!(QueryValue::Integer(3), eval("$x").await?);
+    // The `let` bindings of the query shadow the variables.
+    assert_eq!(QueryValue::Integer(4), eval("let y = 4 in $y").await?);
+    assert_eq!(QueryValue::Integer(2), eval("let x = $y in $x").await?);
+    // Names bound by neither are still words.
+    assert_eq!(QueryValue::String("$z".to_owned()), eval("$z").await?);
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_explain() -> anyhow::Result<()> {
+    let input = "let x = 1 in let y = kind() in $x";
+    let parsed = parse_expr(input)?;
+    let functions = DefaultQueryFunctionsModule::new();
+    let explain = QueryExplain::new(ComputeCounters::default);
+    QueryEvaluator::new(&Env, &functions)
+        .with_explain(Some(&explain))
+        .eval(&parsed)
+        .await
+        .map_err(|e| QueryError::convert_error(e, input))?;
+
+    let rendered = explain.render(input)?;
+    let lines: Vec<_> = rendered.lines().skip(1).collect();
+    assert_eq!(5, lines.len(), "{}", rendered);
+    assert!(lines[0].starts_with("let x  "), "{}", rendered);
+    assert!(lines[1].starts_with("├─ = 1  "), "{}", rendered);
+    assert!(lines[2].starts_with("└─ let y  "), "{}", rendered);
+    assert_eq!("   ├─ = kind()  not evaluated", lines[3]);
+    assert!(lines[4].starts_with("   └─ x  "), "{}", rendered);
+    Ok(())
+}
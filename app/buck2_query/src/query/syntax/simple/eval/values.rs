@@ -19,6 +19,7 @@ use crate::query::environment::QueryTarget;
 use crate::query::syntax::simple::eval::error::QueryError;
 use crate::query::syntax::simple::eval::file_set::FileSet;
 use crate::query::syntax::simple::eval::multi_query::MultiQueryResult;
+use crate::query::syntax::simple::eval::paths::TargetPaths;
 use crate::query::syntax::simple::eval::set::TargetSet;
 
 pub enum QueryEvaluationResult<T: QueryTarget> {
@@ -37,12 +38,40 @@ impl<T: QueryTarget> QueryEvaluationResult<T> {
 }
 
 /// Used as a value in query evaluation, may appear in arguments to functions, results of functions etc.
-#[derive(Debug, VariantName, Eq, PartialEq)]
+#[derive(Debug, Clone, VariantName, Eq, PartialEq)]
 pub enum QueryValue<T: QueryTarget> {
     String(String),
     Integer(u64),
     TargetSet(TargetSet<T>),
     FileSet(FileSet),
+    TargetPaths(TargetPaths<T>),
+}
+
+/// Values bound to `$NAME` outside of a query, for example by the `let` statements of a query
+/// shell. The `let` bindings of the query itself shadow them.
+#[derive(Debug, Clone)]
+pub struct QueryVariables<T: QueryTarget>(Vec<(String, QueryValue<T>)>);
+
+impl<T: QueryTarget> Default for QueryVariables<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: QueryTarget> QueryVariables<T> {
+    /// Binds `name` to `value`, replacing its previous value if any.
+    pub fn insert(&mut self, name: String, value: QueryValue<T>) {
+        self.0.retain(|(n, _)| *n != name);
+        self.0.push((name, value));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&QueryValue<T>> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.0.iter().map(|(n, _)| n.as_str()).collect()
+    }
 }
 
 /// Used as a value in query evaluation where sets are valid, may appear in arguments to functions, results of functions etc.
@@ -59,12 +88,14 @@ pub enum QueryValueSet<T: QueryTarget> {
 pub enum QueryEvaluationValue<T: QueryTarget> {
     TargetSet(TargetSet<T>),
     FileSet(FileSet),
+    TargetPaths(TargetPaths<T>),
 }
 
 impl<T: QueryTarget> QueryEvaluationValue<T> {
     pub fn try_into_targets(self) -> anyhow::Result<TargetSet<T>> {
         match self {
             QueryEvaluationValue::TargetSet(targets) => Ok(targets),
+            QueryEvaluationValue::TargetPaths(paths) => Ok(paths.into_targets()),
             v => Err(QueryError::InvalidType {
                 expected: "targets",
                 actual: v.variant_name(),
@@ -76,6 +107,9 @@ impl<T: QueryTarget> QueryEvaluationValue<T> {
     pub(crate) fn targets(&self) -> impl Iterator<Item = buck2_error::Result<&T>> {
         match self {
             QueryEvaluationValue::TargetSet(targets) => Either::Left(targets.iter().map(Ok)),
+            QueryEvaluationValue::TargetPaths(paths) => {
+                Either::Left(paths.targets().iter().map(Ok))
+            }
             v => Either::Right(iter::once(Err(QueryError::InvalidType {
                 expected: "targets",
                 actual: v.variant_name(),
@@ -106,6 +140,22 @@ impl<T: QueryTarget> From<TargetSet<T>> for QueryValue<T> {
     }
 }
 
+impl<T: QueryTarget> From<TargetPaths<T>> for QueryValue<T> {
+    fn from(v: TargetPaths<T>) -> Self {
+        QueryValue::TargetPaths(v)
+    }
+}
+
+impl<T: QueryTarget> From<QueryEvaluationValue<T>> for QueryValue<T> {
+    fn from(v: QueryEvaluationValue<T>) -> Self {
+        match v {
+            QueryEvaluationValue::TargetSet(v) => Self::TargetSet(v),
+            QueryEvaluationValue::FileSet(v) => Self::FileSet(v),
+            QueryEvaluationValue::TargetPaths(v) => Self::TargetPaths(v),
+        }
+    }
+}
+
 impl<T: QueryTarget> From<FileSet> for QueryValue<T> {
     fn from(v: FileSet) -> Self {
         QueryValue::FileSet(v)
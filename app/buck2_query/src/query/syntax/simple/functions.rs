@@ -21,9 +21,11 @@ use buck2_query_parser::Expr;
 use gazebo::variants::VariantName;
 
 use crate::query::environment::QueryEnvironment;
+use crate::query::environment::QueryTarget;
 use crate::query::syntax::simple::eval::error::QueryError;
 use crate::query::syntax::simple::eval::evaluator::QueryEvaluator;
 use crate::query::syntax::simple::eval::file_set::FileSet;
+use crate::query::syntax::simple::eval::paths::TargetPaths;
 use crate::query::syntax::simple::eval::set::TargetSet;
 use crate::query::syntax::simple::eval::values::QueryResult;
 use crate::query::syntax::simple::eval::values::QueryValue;
@@ -71,10 +73,14 @@ impl<F: QueryFunctions> QueryFunctionsVisitLiterals for F {
         visitor: &mut dyn QueryLiteralVisitor,
         expr: &Spanned<Expr>,
     ) -> QueryResult<()> {
-        fn visit_literals_recurse<F: QueryFunctions>(
+        /// The `let` bindings in scope, innermost last.
+        type Scope<'a> = Vec<(&'a str, &'a Spanned<Expr<'a>>)>;
+
+        fn visit_literals_recurse<'a, F: QueryFunctions>(
             this: &F,
             visitor: &mut dyn QueryLiteralVisitor,
-            expr: &Expr,
+            expr: &'a Expr<'a>,
+            scope: &mut Scope<'a>,
         ) -> Result<(), QueryError> {
             match expr {
                 Expr::Function {
@@ -87,6 +93,7 @@ impl<F: QueryFunctions> QueryFunctionsVisitLiterals for F {
                                 this,
                                 visitor,
                                 arg,
+                                scope,
                                 matches!(
                                     func.arg_type(i)?,
                                     QueryArgType::TargetSet
@@ -102,13 +109,22 @@ impl<F: QueryFunctions> QueryFunctionsVisitLiterals for F {
                     )),
                 },
                 Expr::BinaryOpSequence(left, exprs) => {
-                    visit_literals_item(this, visitor, left, true)?;
+                    visit_literals_item(this, visitor, left, scope, true)?;
                     // All binary ops are on targetsets currently.
                     for (_, right) in exprs {
-                        visit_literals_item(this, visitor, right, true)?;
+                        visit_literals_item(this, visitor, right, scope, true)?;
                     }
                     Ok(())
                 }
+                Expr::Let { name, value, body } => {
+                    // Bound values are visited where they are referenced, as only then do we know
+                    // whether they are used as targets.
+                    scope.push((*name.fragment(), &**value));
+                    let res = visit_literals_item(this, visitor, body, scope, true);
+                    scope.pop();
+                    res?;
+                    Ok(())
+                }
                 Expr::Set(args) => {
                     for arg in args {
                         visitor.target_pattern(arg)?;
@@ -116,7 +132,7 @@ impl<F: QueryFunctions> QueryFunctionsVisitLiterals for F {
                     Ok(())
                 }
                 Expr::FileSet(_args) => Ok(()),
-                Expr::String(..) | Expr::Integer(..) => {
+                Expr::String(..) | Expr::Integer(..) | Expr::Variable(..) => {
                     panic!(
                         "This shouldn't be called with literals, they should be handled in the caller"
                     )
@@ -124,29 +140,48 @@ impl<F: QueryFunctions> QueryFunctionsVisitLiterals for F {
             }
         }
 
-        fn visit_literals_item<F: QueryFunctions>(
+        fn visit_literals_item<'a, F: QueryFunctions>(
             this: &F,
             visitor: &mut dyn QueryLiteralVisitor,
-            expr: &Spanned<Expr>,
+            expr: &'a Spanned<Expr<'a>>,
+            scope: &mut Scope<'a>,
             is_target_expr: bool,
         ) -> QueryResult<()> {
-            expr.map_res(|value| -> Result<(), QueryError> {
-                match value {
-                    Expr::String(val) => {
-                        if is_target_expr {
-                            visitor.target_pattern(val)?;
+            let res = match &expr.value {
+                Expr::String(val) if is_target_expr => {
+                    visitor.target_pattern(val).map_err(QueryError::from)
+                }
+                Expr::String(..) | Expr::Integer(..) => {
+                    // ignored
+                    Ok(())
+                }
+                Expr::Variable(name) => {
+                    match scope
+                        .iter()
+                        .rposition(|(bound, _)| bound == name.fragment())
+                    {
+                        Some(index) => {
+                            // The bound value is visited in the scope it was bound in.
+                            let (_, bound_value) = scope[index];
+                            visit_literals_item(
+                                this,
+                                visitor,
+                                bound_value,
+                                &mut scope[..index].to_vec(),
+                                is_target_expr,
+                            )
+                            .map(|_| ())
+                            .map_err(QueryError::from)
                         }
+                        None => Err(QueryError::UnboundVariable((*name.fragment()).to_owned())),
                     }
-                    Expr::Integer(..) => {
-                        // ignored
-                    }
-                    _ => visit_literals_recurse(this, visitor, value)?,
                 }
-                Ok(())
-            })
+                value => visit_literals_recurse(this, visitor, value, scope),
+            };
+            expr.span(res)
         }
 
-        visit_literals_item(self, visitor, expr, true)
+        visit_literals_item(self, visitor, expr, &mut Vec::new(), true)
     }
 }
 
@@ -174,12 +209,21 @@ impl<Env: QueryEnvironment> DefaultQueryFunctionsModule<Env> {
 type QueryFuncResult<Env> =
     std::result::Result<QueryValue<<Env as QueryEnvironment>::Target>, QueryError>;
 
+/// Paths are combined with other values as their targets.
+fn paths_as_target_set<T: QueryTarget>(val: QueryValue<T>) -> QueryValue<T> {
+    match val {
+        QueryValue::TargetPaths(paths) => QueryValue::TargetSet(paths.into_targets()),
+        val => val,
+    }
+}
+
 async fn accept_target_set<Env: QueryEnvironment>(
     env: &Env,
     val: QueryValue<Env::Target>,
 ) -> Result<TargetSet<Env::Target>, QueryError> {
     match val {
         QueryValue::TargetSet(x) => Ok(x),
+        QueryValue::TargetPaths(x) => Ok(x.into_targets()),
         QueryValue::String(literal) => Ok(env.eval_literals(&[&literal]).await?),
         _ => Err(QueryError::InvalidType {
             expected: "target_set",
@@ -209,22 +253,36 @@ impl<Env: QueryEnvironment> DefaultQueryFunctionsModule<Env> {
     /// ```
     ///
     /// Graphviz is an open-source graph-visualization software tool. Graphviz uses the dot language to describe graphs.
+    ///
+    /// The output only includes the dependencies that lie on the paths, and the default output lists each target followed by
+    /// those dependencies.
     async fn allpaths(
         &self,
         env: &Env,
         from: TargetSet<Env::Target>,
         to: TargetSet<Env::Target>,
     ) -> QueryFuncResult<Env> {
-        Ok(self.implementation.allpaths(env, &from, &to).await?.into())
+        let targets = self.implementation.allpaths(env, &from, &to).await?;
+        Ok(TargetPaths::subgraph(targets).into())
     }
 
+    /// Computes a single dependency path between the target expressions from and to, if there is one.
+    ///
+    /// The default output prints the path as a chain, from the dependent to the dependency.
     async fn somepath(
         &self,
         env: &Env,
         from: TargetSet<Env::Target>,
         to: TargetSet<Env::Target>,
     ) -> QueryFuncResult<Env> {
-        Ok(self.implementation.somepath(env, &from, &to).await?.into())
+        // The path is returned from the dependency to the dependent, but reads the other way.
+        let path: Vec<_> = self
+            .implementation
+            .somepath(env, &from, &to)
+            .await?
+            .into_iter()
+            .collect();
+        Ok(TargetPaths::single_path(path.into_iter().rev()).into())
     }
 
     /// The `attrfilter(attribute, value, targets)` operator evaluates the given target expression and filters the resulting build targets to those where the specified attribute contains the specified value.
@@ -236,6 +294,14 @@ impl<Env: QueryEnvironment> DefaultQueryFunctionsModule<Env> {
     ///
     /// For example:
     /// `buck2 query "attrfilter(deps, '//foo:bar', '//...')"` returns the build targets in the repository that depend on `//foo:bar`, or more precisely: those build targets that include `//foo:bar` in their deps argument list.
+    ///
+    /// The attribute may be followed by `[key]` or `[index]` to match only the values nested inside it,
+    /// e.g. `attrfilter('env[CC]', clang, '//...')` matches the targets whose `env` dictionary maps `CC` to `clang`,
+    /// and `attrfilter('srcs[0]', main.c, '//...')` those whose first source is `main.c`.
+    /// In `uquery` every branch of a `select()` is searched, while `cquery` matches the configured value.
+    /// The same attribute paths are accepted by `nattrfilter`, `attrregexfilter` and `attrexists`.
+    /// A malformed path, e.g. `env[CC` or `[CC]`, is an error, while an attribute which targets don't have
+    /// (including the empty attribute `''`) matches no target.
     async fn attrfilter(
         &self,
         attr: String,
@@ -272,6 +338,19 @@ impl<Env: QueryEnvironment> DefaultQueryFunctionsModule<Env> {
             .into())
     }
 
+    /// The `attrexists(attribute, targets)` operator evaluates the given target expression and filters the resulting build targets to those where
+    /// the specified attribute has a value other than `None`.
+    ///
+    /// Like `attrfilter`, the attribute may be followed by `[key]` or `[index]`, e.g. `attrexists('env[CC]', '//...')`
+    /// returns the targets whose `env` dictionary has a `CC` key.
+    async fn attrexists(
+        &self,
+        attr: String,
+        targets: TargetSet<Env::Target>,
+    ) -> QueryFuncResult<Env> {
+        Ok(self.implementation.attrexists(&attr, &targets)?.into())
+    }
+
     async fn buildfile(&self, targets: TargetSet<Env::Target>) -> QueryFuncResult<Env> {
         Ok(self.implementation.buildfile(&targets).into())
     }
@@ -378,20 +457,84 @@ impl<Env: QueryEnvironment> DefaultQueryFunctionsModule<Env> {
             .into())
     }
 
+    /// `rdeps(universe, targets, depth)` computes the reverse dependencies of `targets` within
+    /// `universe`, up to an optional `depth`.
+    ///
+    /// In uquery and cquery the universe can be omitted: `rdeps(targets)` is the same as
+    /// `allrdeps(targets)`. Since the universe comes first, the arguments are `first` and
+    /// `second`: with one argument, `first` is the targets, and with two, the universe.
     async fn rdeps(
         &self,
         env: &Env,
-        universe: TargetSet<Env::Target>,
+        first: TargetSet<Env::Target>,
+        second: Option<TargetSet<Env::Target>>,
+        depth: Option<u64>,
+    ) -> QueryFuncResult<Env> {
+        let depth = depth.map(|v| v as i32);
+        let rdeps = match second {
+            Some(targets) => {
+                let universe = first;
+                self.implementation
+                    .rdeps(env, &universe, &targets, depth)
+                    .await?
+            }
+            None => {
+                let targets = first;
+                self.implementation.allrdeps(env, &targets, depth).await?
+            }
+        };
+        Ok(rdeps.into())
+    }
+
+    /// Computes the reverse dependencies of `targets` among all the targets in the repository,
+    /// up to an optional `depth`.
+    ///
+    /// Unlike `rdeps`, no universe is needed: the daemon maintains an index of reverse
+    /// dependencies which is updated incrementally as build files change. Available in uquery
+    /// and cquery. Targets in packages which fail to load, and in cquery targets which fail to
+    /// configure, are skipped with a warning.
+    async fn allrdeps(
+        &self,
+        env: &Env,
         targets: TargetSet<Env::Target>,
         depth: Option<u64>,
     ) -> QueryFuncResult<Env> {
         Ok(self
             .implementation
-            .rdeps(env, &universe, &targets, depth.map(|v| v as i32))
+            .allrdeps(env, &targets, depth.map(|v| v as i32))
+            .await?
+            .into())
+    }
+
+    /// Filters `targets` to the targets visible to `target`, according to their `visibility`
+    /// attribute. If `target` is a set of several targets, the targets visible to all of them are
+    /// returned.
+    ///
+    /// Targets are always visible to the other targets of their package.
+    ///
+    /// Example: `buck2 uquery "visible_to(//foo:bar, //lib/...)"` returns the targets under `lib`
+    /// which `//foo:bar` is allowed to depend on.
+    async fn visible_to(
+        &self,
+        env: &Env,
+        target: TargetSet<Env::Target>,
+        targets: TargetSet<Env::Target>,
+    ) -> QueryFuncResult<Env> {
+        Ok(self
+            .implementation
+            .visible_to(env, &target, &targets)
             .await?
             .into())
     }
 
+    /// Returns all the targets declared in the same packages as `targets`, including `targets`
+    /// themselves.
+    ///
+    /// Example: `buck2 uquery "siblings(//foo:bar)"` is the same as `buck2 uquery "//foo:"`.
+    async fn siblings(&self, env: &Env, targets: TargetSet<Env::Target>) -> QueryFuncResult<Env> {
+        Ok(self.implementation.siblings(env, &targets).await?.into())
+    }
+
     async fn testsof(&self, env: &Env, targets: TargetSet<Env::Target>) -> QueryFuncResult<Env> {
         Ok(self.implementation.testsof(env, &targets).await?.into())
     }
@@ -509,6 +652,22 @@ impl<Env: QueryEnvironment> DefaultQueryFunctionsModule<Env> {
     }
 }
 
+/// The targets of `targets` which are visible to all of `to`, for implementing
+/// `QueryEnvironment::visible_to`.
+pub fn visible_to_all<T: QueryTarget>(
+    to: &TargetSet<T>,
+    targets: &TargetSet<T>,
+) -> anyhow::Result<TargetSet<T>> {
+    targets.filter(|target| {
+        for to in to.iter() {
+            if !target.is_visible_to_target(to)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    })
+}
+
 #[derive(Allocative)]
 #[allocative(bound = "")]
 pub struct DefaultQueryFunctions<Env: QueryEnvironment> {
@@ -589,6 +748,14 @@ impl<Env: QueryEnvironment> DefaultQueryFunctions<Env> {
         targets.attrregexfilter(attr, value)
     }
 
+    pub fn attrexists(
+        &self,
+        attr: &str,
+        targets: &TargetSet<Env::Target>,
+    ) -> anyhow::Result<TargetSet<Env::Target>> {
+        targets.attrexists(attr)
+    }
+
     pub fn buildfile(&self, targets: &TargetSet<Env::Target>) -> FileSet {
         targets.buildfile()
     }
@@ -676,6 +843,32 @@ impl<Env: QueryEnvironment> DefaultQueryFunctions<Env> {
         env.rdeps(universe, targets, depth).await
     }
 
+    pub async fn allrdeps(
+        &self,
+        env: &Env,
+        targets: &TargetSet<Env::Target>,
+        depth: Option<i32>,
+    ) -> anyhow::Result<TargetSet<Env::Target>> {
+        env.allrdeps(targets, depth).await
+    }
+
+    pub async fn visible_to(
+        &self,
+        env: &Env,
+        to: &TargetSet<Env::Target>,
+        targets: &TargetSet<Env::Target>,
+    ) -> anyhow::Result<TargetSet<Env::Target>> {
+        env.visible_to(to, targets).await
+    }
+
+    pub async fn siblings(
+        &self,
+        env: &Env,
+        targets: &TargetSet<Env::Target>,
+    ) -> anyhow::Result<TargetSet<Env::Target>> {
+        env.siblings(targets).await
+    }
+
     pub async fn testsof(
         &self,
         env: &Env,
@@ -722,7 +915,7 @@ impl<Env: QueryEnvironment> DefaultQueryFunctions<Env> {
     ) -> Result<QueryValue<Env::Target>, QueryError> {
         // If the operations are of the same type, which + join them.
         // If one is a string, and the other a FileSet or TargetSet, we can promote the string
-        match (left, right) {
+        match (paths_as_target_set(left), paths_as_target_set(right)) {
             (QueryValue::TargetSet(l), QueryValue::TargetSet(r)) => {
                 Ok(QueryValue::TargetSet(l.union(&r)))
             }
@@ -113,6 +113,7 @@ impl<Env: QueryEnvironment> DepsFunction<Env> {
                         match evaluator.eval_parsed_query(self.expr.expr).await {
                             Ok(v) => match v.value {
                                 QueryEvaluationValue::TargetSet(v) => Ok(v),
+                                QueryEvaluationValue::TargetPaths(v) => Ok(v.into_targets()),
                                 v => Err(QueryError::InvalidType {
                                     expected: "targets",
                                     actual: v.variant_name(),
@@ -192,6 +192,7 @@ impl<'a, Env: QueryEnvironment> QueryFunctionArg<'a, Env> for QueryValueSet<Env:
         match val {
             QueryValue::String(s) => Ok(QueryValueSet::TargetSet(env.eval_literals(&[&s]).await?)),
             QueryValue::TargetSet(x) => Ok(QueryValueSet::TargetSet(x)),
+            QueryValue::TargetPaths(x) => Ok(QueryValueSet::TargetSet(x.into_targets())),
             QueryValue::FileSet(x) => Ok(QueryValueSet::FileSet(x)),
             _ => Err(QueryError::InvalidType {
                 expected: "file or target set",
@@ -226,6 +227,7 @@ impl<'a, Env: QueryEnvironment> QueryFunctionArg<'a, Env> for TargetSet<Env::Tar
         match val {
             QueryValue::String(s) => Ok(env.eval_literals(&[&s]).await?),
             QueryValue::TargetSet(t) => Ok(t),
+            QueryValue::TargetPaths(t) => Ok(t.into_targets()),
             _ => Err(QueryError::InvalidType {
                 expected: "target_set",
                 actual: val.variant_name(),
@@ -14,15 +14,23 @@ use buck2_common::scope::scope_and_collect_with_dispatcher;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_query::query::environment::QueryEnvironment;
 use buck2_query::query::syntax::simple::eval::evaluator::QueryEvaluator;
+use buck2_query::query::syntax::simple::eval::explain::QueryExplain;
 use buck2_query::query::syntax::simple::eval::literals::extract_target_literals;
 use buck2_query::query::syntax::simple::eval::multi_query::MultiQueryResult;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationValue;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use buck2_query::query::syntax::simple::functions::QueryFunctions;
 use buck2_query_parser::multi_query::MaybeMultiQuery;
 use buck2_query_parser::multi_query::MultiQueryItem;
 use futures::Future;
 
+#[derive(Debug, buck2_error::Error)]
+enum QueryExplainError {
+    #[error("`--explain` is not supported for queries with `%s` arguments")]
+    MultiQuery,
+}
+
 pub(crate) async fn eval_query<
     F: QueryFunctions<Env = Env>,
     Env: QueryEnvironment,
@@ -32,16 +40,24 @@ pub(crate) async fn eval_query<
     functions: &F,
     query: &str,
     query_args: &[String],
+    variables: &QueryVariables<Env::Target>,
+    explain: Option<&QueryExplain>,
     environment: impl Fn(Vec<String>) -> Fut + Send + Sync,
 ) -> anyhow::Result<QueryEvaluationResult<Env::Target>> {
     let query = MaybeMultiQuery::parse(query, query_args)?;
     match query {
         MaybeMultiQuery::MultiQuery(queries) => {
-            let results = process_multi_query(dispatcher, functions, environment, &queries).await?;
+            if explain.is_some() {
+                return Err(QueryExplainError::MultiQuery.into());
+            }
+            let results =
+                process_multi_query(dispatcher, functions, environment, &queries, variables)
+                    .await?;
             Ok(QueryEvaluationResult::Multiple(results))
         }
         MaybeMultiQuery::SingleQuery(query) => {
-            let result = eval_single_query(functions, &query, environment).await?;
+            let result =
+                eval_single_query(functions, &query, environment, variables, explain).await?;
             Ok(QueryEvaluationResult::Single(result))
         }
     }
@@ -55,15 +71,25 @@ async fn eval_single_query<
     functions: &F,
     query: &str,
     environment: impl Fn(Vec<String>) -> Fut,
+    variables: &QueryVariables<Env::Target>,
+    explain: Option<&QueryExplain>,
 ) -> anyhow::Result<QueryEvaluationValue<<Env as QueryEnvironment>::Target>>
 where
     F: QueryFunctions<Env = Env>,
     Env: QueryEnvironment,
     Fut: Future<Output = anyhow::Result<Env>>,
 {
-    let literals = extract_target_literals(functions, query)?;
+    let timer = explain.map(|explain| explain.start());
+    let literals = extract_target_literals(functions, query, &variables.names())?;
     let env = environment(literals).await?;
-    QueryEvaluator::new(&env, functions).eval_query(query).await
+    if let (Some(explain), Some(timer)) = (explain, timer) {
+        explain.record_setup(timer);
+    }
+    QueryEvaluator::new(&env, functions)
+        .with_variables(variables)
+        .with_explain(explain)
+        .eval_query(query)
+        .await
 }
 
 async fn process_multi_query<Env, EnvFut, Qf>(
@@ -71,6 +97,7 @@ async fn process_multi_query<Env, EnvFut, Qf>(
     functions: &Qf,
     env: impl Fn(Vec<String>) -> EnvFut + Send + Sync,
     queries: &[MultiQueryItem],
+    variables: &QueryVariables<Env::Target>,
 ) -> anyhow::Result<MultiQueryResult<Env::Target>>
 where
     Qf: QueryFunctions<Env = Env>,
@@ -86,7 +113,8 @@ where
                 let env = &env;
                 scope.spawn_cancellable(
                     async move {
-                        let result = eval_single_query(functions, &query.query, env);
+                        let result =
+                            eval_single_query(functions, &query.query, env, variables, None);
                         let result: buck2_error::Result<_> = result.await.map_err(|e| e.into());
                         (i, arg, result)
                     },
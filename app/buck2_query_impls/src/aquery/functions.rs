@@ -13,6 +13,9 @@ use std::marker::PhantomData;
 
 use buck2_build_api::actions::query::ActionQueryNode;
 use buck2_build_api::actions::query::ActionQueryNodeData;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_query::query::environment::QueryTarget;
 use buck2_query::query::syntax::simple::eval::error::QueryError;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
 use buck2_query::query::syntax::simple::eval::values::QueryValue;
@@ -26,6 +29,12 @@ use dupe::Dupe;
 
 use crate::aquery::environment::AqueryEnvironment;
 
+/// Whether an action with the `inputs` reads `file`: either it is one of them, or it is
+/// inside one of them.
+fn consumes(file: &ProjectRelativePath, inputs: &[ProjectRelativePathBuf]) -> bool {
+    inputs.iter().any(|input| file.starts_with(input))
+}
+
 pub(crate) fn aquery_functions<'a>() -> impl QueryFunctions<Env = AqueryEnvironment<'a>> {
     struct Functions<'a> {
         defaults: DefaultQueryFunctionsModule<AqueryEnvironment<'a>>,
@@ -129,4 +138,84 @@ impl<'a> AqueryFunctions<'a> {
 
         Ok(res.into())
     }
+
+    /// Obtain the actions which produce the inputs of the given actions, including the inputs
+    /// reached through transitive sets. Source files are not produced by any action, and so
+    /// contribute nothing.
+    ///
+    /// This is `deps(actions, 1)` without the actions themselves.
+    pub(crate) async fn inputs_of(
+        &self,
+        env: &AqueryEnvironment<'a>,
+        actions: TargetSet<ActionQueryNode>,
+    ) -> Result<QueryValue<ActionQueryNode>, QueryError> {
+        let mut input_keys = Vec::new();
+        for node in &actions {
+            for dep in node.deps() {
+                input_keys.push(dep.require_action()?.dupe());
+            }
+        }
+
+        let nodes = buck2_util::future::try_join_all(
+            input_keys.iter().map(|key| env.delegate.get_node(key)),
+        )
+        .await?;
+        Ok(nodes.into_iter().collect::<TargetSet<_>>().into())
+    }
+
+    /// Filter `actions` to those which read `file`, either directly or through a transitive set.
+    /// `file` is a path relative to the project root, which may be a source file or an output of
+    /// another action (as printed by `--output-actions`). An action whose input is a directory
+    /// containing `file` also matches.
+    ///
+    /// For example, `actions_consuming(foo/bar.c, all_actions(//foo:bar))` finds the actions of
+    /// `//foo:bar` which compile `foo/bar.c`.
+    pub(crate) async fn actions_consuming(
+        &self,
+        env: &AqueryEnvironment<'a>,
+        file: String,
+        actions: TargetSet<ActionQueryNode>,
+    ) -> Result<QueryValue<ActionQueryNode>, QueryError> {
+        let file = ProjectRelativePath::new(&file)?;
+        let consuming =
+            buck2_util::future::try_join_all(actions.into_iter().map(|node| async move {
+                let matches = match node.data() {
+                    ActionQueryNodeData::Action(action) => {
+                        consumes(file, &action.input_paths(&mut env.delegate.ctx()).await?)
+                    }
+                    ActionQueryNodeData::Analysis(..) => false,
+                };
+                anyhow::Ok(matches.then_some(node))
+            }))
+            .await?;
+        let res: TargetSet<_> = consuming.into_iter().flatten().collect();
+        Ok(res.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::fs::project_rel_path::ProjectRelativePath;
+    use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+
+    use super::consumes;
+
+    #[test]
+    fn test_consumes() {
+        let inputs = vec![
+            ProjectRelativePathBuf::testing_new("buck-out/v2/gen/root/foo/out"),
+            ProjectRelativePathBuf::testing_new("foo/bar.c"),
+        ];
+        let consumes = |file| consumes(ProjectRelativePath::new(file).unwrap(), &inputs);
+
+        assert!(consumes("foo/bar.c"));
+        // A file inside a directory input.
+        assert!(consumes("buck-out/v2/gen/root/foo/out/a.o"));
+        // Path components are compared whole, not as string prefixes.
+        assert!(!consumes("foo/bar.cpp"));
+        assert!(!consumes("buck-out/v2/gen/root/foo/output"));
+        // The directory containing an input is not itself an input.
+        assert!(!consumes("foo"));
+        assert!(!consumes("baz.c"));
+    }
 }
@@ -12,13 +12,18 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::configuration::compatibility::MaybeCompatible;
+use buck2_core::configuration::pair::Configuration;
+use buck2_core::package::PackageLabel;
 use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
 use buck2_core::target::label::label::TargetLabel;
 use buck2_error::BuckErrorContext;
+use buck2_events::dispatch::console_message;
 use buck2_node::configured_universe::CqueryUniverse;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
+use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
 use buck2_node::nodes::configured_node_ref::ConfiguredTargetNodeRefNode;
 use buck2_node::nodes::configured_node_ref::ConfiguredTargetNodeRefNodeDeps;
+use buck2_node::nodes::frontend::TargetGraphCalculation;
 use buck2_query::query::environment::deps;
 use buck2_query::query::environment::QueryEnvironment;
 use buck2_query::query::environment::QueryEnvironmentAsNodeLookup;
@@ -29,17 +34,25 @@ use buck2_query::query::syntax::simple::eval::error::QueryError;
 use buck2_query::query::syntax::simple::eval::file_set::FileSet;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
 use buck2_query::query::syntax::simple::functions::docs::QueryEnvironmentDescription;
+use buck2_query::query::syntax::simple::functions::visible_to_all;
 use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
 use buck2_query::query::syntax::simple::functions::HasModuleDescription;
 use buck2_query::query::traversal::async_depth_first_postorder_traversal;
 use buck2_query::query::traversal::async_depth_limited_traversal;
+use buck2_util::future::try_join_all;
 use dice::DiceComputations;
+use dupe::Dupe;
+use dupe::IterDupedExt;
+use futures::future::join_all;
+use indexmap::IndexMap;
+use indexmap::IndexSet;
 use tracing::warn;
 
 use crate::uquery::environment::allbuildfiles;
 use crate::uquery::environment::rbuildfiles;
 use crate::uquery::environment::QueryLiterals;
 use crate::uquery::environment::UqueryDelegate;
+use crate::uquery::reverse_deps::all_reverse_deps;
 
 /// CqueryDelegate resolves information needed by the QueryEnvironment.
 #[async_trait]
@@ -116,6 +129,21 @@ impl<'c> CqueryEnvironment<'c> {
     }
 }
 
+/// The packages of `targets`, with the configurations they are used in, so that `siblings` loads
+/// each package once however many of its targets it is given.
+fn configurations_by_package(
+    targets: &TargetSet<ConfiguredTargetNode>,
+) -> IndexMap<PackageLabel, IndexSet<Configuration>> {
+    let mut packages: IndexMap<PackageLabel, IndexSet<Configuration>> = IndexMap::new();
+    for target in targets.iter() {
+        packages
+            .entry(target.label().pkg())
+            .or_default()
+            .insert(target.label().cfg_pair().dupe());
+    }
+    packages
+}
+
 #[async_trait]
 impl<'c> QueryEnvironment for CqueryEnvironment<'c> {
     type Target = ConfiguredTargetNode;
@@ -197,6 +225,114 @@ impl<'c> QueryEnvironment for CqueryEnvironment<'c> {
         Ok(result)
     }
 
+    async fn allrdeps(
+        &self,
+        targets: &TargetSet<Self::Target>,
+        depth: Option<i32>,
+    ) -> anyhow::Result<TargetSet<Self::Target>> {
+        // Every configured dependency edge is also an unconfigured one, so the configured reverse
+        // dependencies are among the configurations of the unconfigured ones. Collect those
+        // reachable from the unconfigured reverse dependencies configured for the default target
+        // platform, through configured deps (including transitioned and exec deps) which stay
+        // within the unconfigured reverse dependencies, and use them as the universe.
+        // Configurations only reached through targets not depending on `targets` are not found.
+        let labels = all_reverse_deps(
+            &mut self.delegate.ctx(),
+            targets.iter().map(|t| t.label().unconfigured().dupe()),
+            depth,
+        )
+        .await?;
+        let nodes = join_all(labels.iter().map(|label| async move {
+            (
+                label,
+                self.delegate
+                    .get_node_for_default_configured_target(label)
+                    .await,
+            )
+        }))
+        .await;
+
+        let mut stack: Vec<ConfiguredTargetNode> = targets.iter().duped().collect();
+        let mut errors = Vec::new();
+        for (label, node) in nodes {
+            match node {
+                Ok(MaybeCompatible::Compatible(node)) => stack.push(node),
+                Ok(MaybeCompatible::Incompatible(_)) => {}
+                Err(e) => errors.push((label, e)),
+            }
+        }
+        if let Some((label, error)) = errors.first() {
+            console_message(format!(
+                "{} targets failed to configure and are missing from `allrdeps()`. Error configuring `{}`: {:#}",
+                errors.len(),
+                label,
+                error,
+            ));
+        }
+
+        let mut universe = TargetSet::new();
+        while let Some(node) = stack.pop() {
+            if universe.contains(node.label()) {
+                continue;
+            }
+            for dep in node.deps() {
+                if labels.contains(dep.label().unconfigured()) && !universe.contains(dep.label()) {
+                    stack.push(dep.dupe());
+                }
+            }
+            universe.insert(node);
+        }
+        self.rdeps(&universe, targets, depth).await
+    }
+
+    async fn visible_to(
+        &self,
+        to: &TargetSet<Self::Target>,
+        targets: &TargetSet<Self::Target>,
+    ) -> anyhow::Result<TargetSet<Self::Target>> {
+        visible_to_all(to, targets)
+    }
+
+    async fn siblings(
+        &self,
+        targets: &TargetSet<Self::Target>,
+    ) -> anyhow::Result<TargetSet<Self::Target>> {
+        // Siblings are configured like the target they are the sibling of. The incompatible ones
+        // are skipped, like in target patterns.
+        let labels: IndexSet<ConfiguredTargetLabel> =
+            try_join_all(configurations_by_package(targets).into_iter().map(
+                |(package, configurations)| async move {
+                    let package = self.delegate.ctx().get_interpreter_results(package).await?;
+                    anyhow::Ok(
+                        configurations
+                            .into_iter()
+                            .flat_map(|cfg| {
+                                package
+                                    .targets()
+                                    .values()
+                                    .map(move |node| node.label().configure_pair(cfg.dupe()))
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                },
+            ))
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+        let nodes = try_join_all(labels.iter().map(|label| async move {
+            self.delegate.ctx().get_configured_target_node(label).await
+        }))
+        .await?;
+        Ok(nodes
+            .into_iter()
+            .filter_map(|node| match node {
+                MaybeCompatible::Compatible(node) => Some(node),
+                MaybeCompatible::Incompatible(_) => None,
+            })
+            .collect())
+    }
+
     async fn targets_in_buildfile(
         &self,
         _paths: &FileSet,
@@ -228,3 +364,74 @@ impl<'c> QueryEnvironment for CqueryEnvironment<'c> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::execution_types::execution::ExecutionPlatformResolution;
+    use buck2_node::attrs::coerced_attr::CoercedAttr;
+    use buck2_node::attrs::internal::internal_attrs;
+    use buck2_node::attrs::internal::VISIBILITY_ATTRIBUTE_FIELD;
+    use buck2_node::visibility::VisibilitySpecification;
+
+    use super::*;
+
+    fn node(label: &str, cfg: ConfigurationData, visibility: &[&str]) -> ConfiguredTargetNode {
+        ConfiguredTargetNode::testing_new(
+            ConfiguredTargetLabel::testing_parse(label, cfg),
+            "some_rule",
+            ExecutionPlatformResolution::new(None, Vec::new()),
+            vec![],
+            vec![(
+                VISIBILITY_ATTRIBUTE_FIELD,
+                internal_attrs()
+                    .get(VISIBILITY_ATTRIBUTE_FIELD)
+                    .unwrap()
+                    .clone(),
+                CoercedAttr::Visibility(VisibilitySpecification::testing_parse(visibility)),
+            )],
+        )
+    }
+
+    #[test]
+    fn test_visible_to_all() -> anyhow::Result<()> {
+        let targets = TargetSet::from_iter([
+            node(
+                "root//a:a",
+                ConfigurationData::testing_new(),
+                &["root//b:b"],
+            ),
+            node("root//d:d", ConfigurationData::testing_new(), &[]),
+        ]);
+        // Visibility does not depend on the configuration of `to`.
+        let to = TargetSet::from_iter([node("root//b:b", ConfigurationData::unspecified(), &[])]);
+        let visible: Vec<_> = visible_to_all(&to, &targets)?
+            .iter()
+            .map(|t| t.label().unconfigured().to_string())
+            .collect();
+        assert_eq!(vec!["root//a:a"], visible);
+        Ok(())
+    }
+
+    #[test]
+    fn test_configurations_by_package() {
+        let targets = TargetSet::from_iter([
+            node("root//a:a", ConfigurationData::testing_new(), &[]),
+            node("root//a:b", ConfigurationData::testing_new(), &[]),
+            node("root//a:b", ConfigurationData::unspecified(), &[]),
+            node("root//c:c", ConfigurationData::testing_new(), &[]),
+        ]);
+        let packages = configurations_by_package(&targets);
+
+        // Each package is loaded once, in each of the configurations its targets are in.
+        assert_eq!(
+            vec![
+                PackageLabel::testing_parse("root//a"),
+                PackageLabel::testing_parse("root//c"),
+            ],
+            packages.keys().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(2, packages[&PackageLabel::testing_parse("root//a")].len());
+        assert_eq!(1, packages[&PackageLabel::testing_parse("root//c")].len());
+    }
+}
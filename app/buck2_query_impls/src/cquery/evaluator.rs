@@ -17,7 +17,9 @@ use buck2_error::BuckErrorContext;
 use buck2_events::dispatch::console_message;
 use buck2_node::configured_universe::CqueryUniverse;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
+use buck2_query::query::syntax::simple::eval::explain::QueryExplain;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
 use dice::DiceComputations;
 use dupe::Dupe;
@@ -37,8 +39,10 @@ pub(crate) async fn eval_cquery(
     dice_query_delegate: DiceQueryDelegate<'_, '_>,
     query: &str,
     query_args: &[String],
+    variables: &QueryVariables<ConfiguredTargetNode>,
     target_universe: Option<&[String]>,
     collect_universes: bool,
+    explain: Option<&QueryExplain>,
 ) -> anyhow::Result<(
     QueryEvaluationResult<ConfiguredTargetNode>,
     Option<Vec<Arc<CqueryUniverse>>>,
@@ -91,6 +95,8 @@ pub(crate) async fn eval_cquery(
         &functions,
         query,
         query_args,
+        variables,
+        explain,
         |literals| async move {
             let (resolved_literals, universe) = match target_universe {
                 None => {
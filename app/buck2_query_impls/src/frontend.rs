@@ -19,7 +19,9 @@ use buck2_node::configured_universe::CqueryUniverse;
 use buck2_node::configured_universe::UNIVERSE_FROM_LITERALS;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
 use buck2_node::nodes::unconfigured::TargetNode;
+use buck2_query::query::syntax::simple::eval::explain::QueryExplain;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use dice::DiceComputations;
 
 use crate::aquery::evaluator::get_aquery_evaluator;
@@ -42,10 +44,14 @@ impl QueryFrontend for QueryFrontendImpl {
         working_dir: &ProjectRelativePath,
         query: &str,
         query_args: &[String],
+        variables: &QueryVariables<TargetNode>,
+        explain: Option<&QueryExplain>,
     ) -> anyhow::Result<QueryEvaluationResult<TargetNode>> {
         ctx.with_linear_recompute(|ctx| async move {
             let evaluator = get_uquery_evaluator(&ctx, working_dir).await?;
-            evaluator.eval_query(query, query_args).await
+            evaluator
+                .eval_query(query, query_args, variables, explain)
+                .await
         })
         .await
     }
@@ -61,9 +67,11 @@ impl QueryFrontend for QueryFrontendImpl {
         working_dir: &ProjectRelativePath,
         query: &str,
         query_args: &[String],
+        variables: &QueryVariables<ConfiguredTargetNode>,
         global_cfg_options: GlobalCfgOptions,
         target_universe: Option<&[String]>,
         collect_universes: bool,
+        explain: Option<&QueryExplain>,
     ) -> anyhow::Result<(
         QueryEvaluationResult<ConfiguredTargetNode>,
         Option<Vec<Arc<CqueryUniverse>>>,
@@ -82,8 +90,10 @@ impl QueryFrontend for QueryFrontendImpl {
                 dice_query_delegate,
                 query,
                 query_args,
+                variables,
                 target_universe.as_ref().map(|v| &v[..]),
                 collect_universes,
+                explain,
             )
             .await
         })
@@ -96,11 +106,15 @@ impl QueryFrontend for QueryFrontendImpl {
         working_dir: &ProjectRelativePath,
         query: &str,
         query_args: &[String],
+        variables: &QueryVariables<ActionQueryNode>,
         global_cfg_options: GlobalCfgOptions,
+        explain: Option<&QueryExplain>,
     ) -> anyhow::Result<QueryEvaluationResult<ActionQueryNode>> {
         ctx.with_linear_recompute(|ctx| async move {
             let evaluator = get_aquery_evaluator(&ctx, working_dir, global_cfg_options).await?;
-            evaluator.eval_query(query, query_args).await
+            evaluator
+                .eval_query(query, query_args, variables, explain)
+                .await
         })
         .await
     }
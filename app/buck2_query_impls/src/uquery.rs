@@ -10,3 +10,4 @@
 pub(crate) mod bxl;
 pub(crate) mod environment;
 pub(crate) mod evaluator;
+pub(crate) mod reverse_deps;
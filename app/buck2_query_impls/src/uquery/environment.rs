@@ -38,6 +38,7 @@ use buck2_query::query::syntax::simple::eval::file_set::FileNode;
 use buck2_query::query::syntax::simple::eval::file_set::FileSet;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
 use buck2_query::query::syntax::simple::functions::docs::QueryEnvironmentDescription;
+use buck2_query::query::syntax::simple::functions::visible_to_all;
 use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
 use buck2_query::query::syntax::simple::functions::HasModuleDescription;
 use buck2_query::query::traversal::async_depth_first_postorder_traversal;
@@ -58,6 +59,8 @@ use itertools::Itertools;
 use ref_cast::RefCast;
 use tracing::warn;
 
+use crate::uquery::reverse_deps::all_reverse_deps;
+
 type ArcCellPath = Arc<CellPath>;
 
 #[derive(Debug, buck2_error::Error)]
@@ -322,6 +325,44 @@ impl<'c> QueryEnvironment for UqueryEnvironment<'c> {
         Ok(result)
     }
 
+    async fn allrdeps(
+        &self,
+        targets: &TargetSet<Self::Target>,
+        depth: Option<i32>,
+    ) -> anyhow::Result<TargetSet<Self::Target>> {
+        let labels = all_reverse_deps(
+            &mut self.delegate.ctx(),
+            targets.iter().map(|t| t.label().dupe()),
+            depth,
+        )
+        .await?;
+        let nodes = try_join_all(labels.iter().map(|label| self.get_node(label))).await?;
+        Ok(nodes.into_iter().collect())
+    }
+
+    async fn visible_to(
+        &self,
+        to: &TargetSet<Self::Target>,
+        targets: &TargetSet<Self::Target>,
+    ) -> anyhow::Result<TargetSet<Self::Target>> {
+        visible_to_all(to, targets)
+    }
+
+    async fn siblings(
+        &self,
+        targets: &TargetSet<Self::Target>,
+    ) -> anyhow::Result<TargetSet<Self::Target>> {
+        let packages: IndexSet<PackageLabel> = targets.iter().map(|t| t.label().pkg()).collect();
+        let results = try_join_all(packages.into_iter().map(|package| async move {
+            self.delegate.ctx().get_interpreter_results(package).await
+        }))
+        .await?;
+        Ok(results
+            .iter()
+            .flat_map(|result| result.targets().values().map(|t| t.to_owned()))
+            .collect())
+    }
+
     /// Finds all targets in some buildfiles.
     ///
     /// todo(dbarsky): instead of having this be a trait method, this should be implemented as a uquery-specific module
@@ -716,3 +757,66 @@ pub(crate) async fn get_transitive_loads(
 
     Ok(imports)
 }
+
+#[cfg(test)]
+mod tests {
+    use buck2_node::attrs::coerced_attr::CoercedAttr;
+    use buck2_node::attrs::internal::internal_attrs;
+    use buck2_node::attrs::internal::VISIBILITY_ATTRIBUTE_FIELD;
+    use buck2_node::nodes::unconfigured::testing::TargetNodeExt;
+    use buck2_node::rule_type::RuleType;
+    use buck2_node::rule_type::StarlarkRuleType;
+    use buck2_node::visibility::VisibilitySpecification;
+
+    use super::*;
+
+    fn node(label: &str, visibility: &[&str]) -> TargetNode {
+        let rule_type = RuleType::Starlark(Arc::new(StarlarkRuleType {
+            import_path: ImportPath::testing_new("root//:rules.bzl"),
+            name: "some_rule".to_owned(),
+        }));
+        TargetNode::testing_new(
+            TargetLabel::testing_parse(label),
+            rule_type,
+            vec![],
+            vec![(
+                VISIBILITY_ATTRIBUTE_FIELD,
+                internal_attrs()
+                    .get(VISIBILITY_ATTRIBUTE_FIELD)
+                    .unwrap()
+                    .clone(),
+                CoercedAttr::Visibility(VisibilitySpecification::testing_parse(visibility)),
+            )],
+        )
+    }
+
+    #[test]
+    fn test_visible_to_all() -> anyhow::Result<()> {
+        let targets = TargetSet::from_iter([
+            node("root//a:a", &["root//b:b"]),
+            node("root//c:c", &["PUBLIC"]),
+            node("root//d:d", &[]),
+        ]);
+        let visible_to = |to: &[&str]| -> anyhow::Result<Vec<String>> {
+            let to = TargetSet::from_iter(to.iter().map(|label| node(label, &[])));
+            Ok(visible_to_all(&to, &targets)?
+                .iter()
+                .map(|t| t.label().to_string())
+                .collect())
+        };
+
+        assert_eq!(vec!["root//a:a", "root//c:c"], visible_to(&["root//b:b"])?);
+        // Targets must be visible to all of `to`.
+        assert_eq!(vec!["root//c:c"], visible_to(&["root//b:b", "root//e:e"])?);
+        // Targets are always visible to their own package.
+        assert_eq!(
+            vec!["root//c:c", "root//d:d"],
+            visible_to(&["root//d:other"])?
+        );
+        assert_eq!(
+            vec!["root//a:a", "root//c:c", "root//d:d"],
+            visible_to(&[])?
+        );
+        Ok(())
+    }
+}
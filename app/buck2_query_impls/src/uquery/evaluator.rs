@@ -14,7 +14,9 @@ use buck2_common::events::HasEvents;
 use buck2_common::global_cfg_options::GlobalCfgOptions;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_node::nodes::unconfigured::TargetNode;
+use buck2_query::query::syntax::simple::eval::explain::QueryExplain;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
 use dice::LinearRecomputeDiceComputations;
 use dupe::Dupe;
@@ -35,6 +37,8 @@ impl UqueryEvaluator<'_, '_> {
         &self,
         query: &str,
         query_args: &[String],
+        variables: &QueryVariables<TargetNode>,
+        explain: Option<&QueryExplain>,
     ) -> anyhow::Result<QueryEvaluationResult<TargetNode>> {
         eval_query(
             self.dice_query_delegate
@@ -45,6 +49,8 @@ impl UqueryEvaluator<'_, '_> {
             &self.functions,
             query,
             query_args,
+            variables,
+            explain,
             |literals| async move {
                 let resolved_literals = PreresolvedQueryLiterals::pre_resolve(
                     &**self.dice_query_delegate.query_data(),
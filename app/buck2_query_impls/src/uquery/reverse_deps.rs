@@ -0,0 +1,332 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Index of the reverse dependencies of the unconfigured target graph, used to answer
+//! `allrdeps()` without a universe.
+//!
+//! The index is stored in DICE: each package contributes the reverse edges of its own targets,
+//! grouped by the package of the dependency, so when a build file changes only that package is
+//! reevaluated. The index itself only records which packages have edges into which, and the
+//! per-package edges are looked up lazily when the reverse dependencies of a target are needed.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::pattern::resolve::ResolveTargetPatterns;
+use buck2_core::cells::cell_path::CellPath;
+use buck2_core::cells::paths::CellRelativePath;
+use buck2_core::package::PackageLabel;
+use buck2_core::pattern::pattern::ParsedPattern;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_core::target::label::label::TargetLabel;
+use buck2_events::dispatch::console_message;
+use buck2_futures::cancellation::CancellationContext;
+use buck2_node::nodes::frontend::TargetGraphCalculation;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::Key;
+use dupe::Dupe;
+use dupe::IterDupedExt;
+use futures::FutureExt;
+use indexmap::IndexSet;
+use itertools::Itertools;
+
+/// Reverse edges of the targets declared in a package: for each package of the dependencies,
+/// for each dependency in it, the targets of the package depending on it.
+#[derive(Debug, Default, PartialEq, Eq, Allocative)]
+struct PackageReverseDeps(HashMap<PackageLabel, HashMap<TargetLabel, Vec<TargetLabel>>>);
+
+impl PackageReverseDeps {
+    fn new<'a>(edges: impl IntoIterator<Item = (&'a TargetLabel, &'a TargetLabel)>) -> Self {
+        let mut rdeps = PackageReverseDeps::default();
+        for (target, dep) in edges {
+            rdeps
+                .0
+                .entry(dep.pkg())
+                .or_default()
+                .entry(dep.dupe())
+                .or_default()
+                .push(target.dupe());
+        }
+        rdeps
+    }
+}
+
+#[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "PackageReverseDeps({})", _0)]
+struct PackageReverseDepsKey(PackageLabel);
+
+#[async_trait]
+impl Key for PackageReverseDepsKey {
+    type Value = buck2_error::Result<Arc<PackageReverseDeps>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let package = ctx.get_interpreter_results(self.0.dupe()).await?;
+        Ok(Arc::new(PackageReverseDeps::new(
+            package
+                .targets()
+                .values()
+                .flat_map(|node| node.deps().map(move |dep| (node.label(), dep))),
+        )))
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        // Most build file changes do not change the deps, in which case the index does not need
+        // to be recomputed.
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+/// All the packages in the repository.
+#[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "AllPackages")]
+struct AllPackagesKey;
+
+#[async_trait]
+impl Key for AllPackagesKey {
+    type Value = buck2_error::Result<Arc<Vec<PackageLabel>>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        // External cells are not part of the repository and cannot depend on its targets.
+        let cell_resolver = ctx.get_cell_resolver().await?;
+        let patterns: Vec<ParsedPattern<TargetPatternExtra>> = cell_resolver
+            .cells()
+            .filter(|(_, cell)| cell.external().is_none())
+            .map(|(name, _)| {
+                ParsedPattern::Recursive(CellPath::new(name, CellRelativePath::empty().to_buf()))
+            })
+            .collect();
+        let packages = ResolveTargetPatterns::resolve(ctx, &patterns).await?;
+        Ok(Arc::new(packages.specs.into_keys().sorted().collect()))
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        // Most file changes do not add or remove packages.
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+/// Reverse dependencies of all the targets in the repository.
+#[derive(Debug, Allocative)]
+pub(crate) struct ReverseDepsIndex {
+    /// For each package, the reverse edges of the packages with targets depending on its targets.
+    by_dep_package: HashMap<PackageLabel, Vec<Arc<PackageReverseDeps>>>,
+    /// Packages which failed to load, and are therefore missing from the index.
+    packages_with_errors: Vec<(PackageLabel, buck2_error::Error)>,
+}
+
+impl ReverseDepsIndex {
+    fn new(
+        packages: impl IntoIterator<Item = (PackageLabel, buck2_error::Result<Arc<PackageReverseDeps>>)>,
+    ) -> Self {
+        let mut index = ReverseDepsIndex {
+            by_dep_package: HashMap::new(),
+            packages_with_errors: Vec::new(),
+        };
+        for (package, rdeps) in packages {
+            match rdeps {
+                Ok(rdeps) => {
+                    for dep_package in rdeps.0.keys() {
+                        index
+                            .by_dep_package
+                            .entry(dep_package.dupe())
+                            .or_default()
+                            .push(rdeps.dupe());
+                    }
+                }
+                Err(e) => index.packages_with_errors.push((package, e)),
+            }
+        }
+        index
+    }
+
+    /// Targets directly depending on `target`.
+    pub(crate) fn rdeps<'a>(
+        &'a self,
+        target: &'a TargetLabel,
+    ) -> impl Iterator<Item = &'a TargetLabel> + 'a {
+        self.by_dep_package
+            .get(&target.pkg())
+            .into_iter()
+            .flatten()
+            .filter_map(move |rdeps| rdeps.0.get(&target.pkg())?.get(target))
+            .flatten()
+    }
+
+    pub(crate) fn packages_with_errors(&self) -> &[(PackageLabel, buck2_error::Error)] {
+        &self.packages_with_errors
+    }
+}
+
+#[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "ReverseDepsIndex")]
+struct ReverseDepsIndexKey;
+
+#[async_trait]
+impl Key for ReverseDepsIndexKey {
+    type Value = buck2_error::Result<Arc<ReverseDepsIndex>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let packages = ctx.compute(&AllPackagesKey).await??;
+        let results = ctx
+            .compute_join(packages.iter().duped(), |ctx, package| {
+                async move {
+                    let rdeps = ctx.compute(&PackageReverseDepsKey(package.dupe())).await;
+                    (package, rdeps)
+                }
+                .boxed()
+            })
+            .await;
+
+        let mut packages = Vec::with_capacity(results.len());
+        for (package, rdeps) in results {
+            packages.push((package, rdeps?));
+        }
+        Ok(Arc::new(ReverseDepsIndex::new(packages)))
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        // The per-package edges are shared with `PackageReverseDepsKey`, so unchanged packages
+        // are the same allocation.
+        match (x, y) {
+            (Ok(x), Ok(y)) => {
+                x.by_dep_package.len() == y.by_dep_package.len()
+                    && x.by_dep_package.iter().all(|(package, x)| {
+                        y.by_dep_package.get(package).map_or(false, |y| {
+                            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| Arc::ptr_eq(x, y))
+                        })
+                    })
+                    && x.packages_with_errors
+                        .iter()
+                        .map(|(p, _)| p)
+                        .eq(y.packages_with_errors.iter().map(|(p, _)| p))
+            }
+            _ => false,
+        }
+    }
+}
+
+pub(crate) async fn get_reverse_deps_index(
+    ctx: &mut DiceComputations<'_>,
+) -> anyhow::Result<Arc<ReverseDepsIndex>> {
+    Ok(ctx.compute(&ReverseDepsIndexKey).await??)
+}
+
+/// The unconfigured targets in the repository transitively depending on `targets`, including
+/// `targets` themselves, up to `depth`.
+pub(crate) async fn all_reverse_deps(
+    ctx: &mut DiceComputations<'_>,
+    targets: impl IntoIterator<Item = TargetLabel>,
+    depth: Option<i32>,
+) -> anyhow::Result<IndexSet<TargetLabel>> {
+    let index = get_reverse_deps_index(ctx).await?;
+    if let Some((package, error)) = index.packages_with_errors().first() {
+        console_message(format!(
+            "{} packages failed to load, their targets are missing from `allrdeps()`: {}\n\
+            Error loading `{}`: {:#}",
+            index.packages_with_errors().len(),
+            index
+                .packages_with_errors()
+                .iter()
+                .map(|(package, _)| package)
+                .take(10)
+                .join(", "),
+            package,
+            error,
+        ));
+    }
+    Ok(reverse_closure(&index, targets, depth))
+}
+
+/// The unconfigured targets transitively depending on `targets`, including `targets` themselves,
+/// in breadth first order. A `depth` outside of `0..1_000_000_000` is unbounded, like in `rdeps`.
+pub(crate) fn reverse_closure(
+    index: &ReverseDepsIndex,
+    targets: impl IntoIterator<Item = TargetLabel>,
+    depth: Option<i32>,
+) -> IndexSet<TargetLabel> {
+    let max_depth = match depth {
+        Some(v) if (0..1_000_000_000).contains(&v) => Some(v as u32),
+        _ => None,
+    };
+
+    let mut visited: IndexSet<TargetLabel> = targets.into_iter().collect();
+    let mut frontier: Vec<TargetLabel> = visited.iter().duped().collect();
+    let mut level = 0;
+    while !frontier.is_empty() && max_depth.map_or(true, |max| level < max) {
+        let mut next = Vec::new();
+        for target in frontier {
+            for rdep in index.rdeps(&target) {
+                if visited.insert(rdep.dupe()) {
+                    next.push(rdep.dupe());
+                }
+            }
+        }
+        frontier = next;
+        level += 1;
+    }
+    visited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverse_closure() {
+        let t = |name: &str| TargetLabel::testing_parse(&format!("root//{}", name));
+        // a -> b -> c, d -> c, with `d` in another package.
+        let index = ReverseDepsIndex::new([
+            (
+                t(":a").pkg(),
+                Ok(Arc::new(PackageReverseDeps::new([
+                    (&t(":a"), &t(":b")),
+                    (&t(":b"), &t(":c")),
+                ]))),
+            ),
+            (
+                t("d:d").pkg(),
+                Ok(Arc::new(PackageReverseDeps::new([(&t("d:d"), &t(":c"))]))),
+            ),
+        ]);
+        let closure = |depth| {
+            reverse_closure(&index, [t(":c")], depth)
+                .into_iter()
+                .map(|t| t.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(vec!["c"], closure(Some(0)));
+        assert_eq!(vec!["c", "b", "d"], closure(Some(1)));
+        assert_eq!(vec!["c", "b", "d", "a"], closure(Some(2)));
+        assert_eq!(vec!["c", "b", "d", "a"], closure(None));
+        assert_eq!(vec!["c", "b", "d", "a"], closure(Some(-1)));
+    }
+}
@@ -25,6 +25,8 @@
 //!        | '(' EXPR ')'
 //!        | 'set(' WORD * ')'
 //!        | FUNCTION_NAME '(' EXPR ( ',' EXPR ) * ')'
+//!        | 'let' NAME '=' EXPR 'in' EXPR
+//!        | '$' NAME
 //!        | EXPR 'intersect' EXPR
 //!        | EXPR ' ^ ' EXPR
 //!        | EXPR ' union ' EXPR
@@ -41,9 +43,15 @@
 //!
 //! INTEGER ::= "0" | ("1-9" "0-9"*)
 //!
-//! FUNCTION_NAME ::= "a-zA-Z_" "a-zA-Z0-9_" *
+//! FUNCTION_NAME ::= NAME
+//!
+//! NAME ::= "a-zA-Z_" "a-zA-Z0-9_" *
 //! ```
+//!
+//! The body of a `let` extends as far to the right as possible, so `let x = a in $x + b` binds
+//! `x` in `$x + b`. Outside of the body of a `let` binding it, `$NAME` is a WORD.
 
+pub mod macros;
 pub mod multi_query;
 pub mod placeholder;
 pub mod span;
@@ -67,6 +75,7 @@ use nom::character::complete::multispace0;
 use nom::character::complete::multispace1;
 use nom::combinator::all_consuming;
 use nom::combinator::cut;
+use nom::combinator::not;
 use nom::combinator::recognize;
 use nom::error::context;
 use nom::error::convert_error;
@@ -79,13 +88,12 @@ use nom::sequence::delimited;
 use nom::sequence::pair;
 use nom::sequence::preceded;
 use nom::sequence::terminated;
+use nom::sequence::tuple;
 use nom::IResult;
 
 use crate::span::Span;
 use crate::spanned::Spanned;
 
-// TODO(cjhopman): Add `LET WORD = expr IN expr`
-
 // TODO(cjhopman): We should switch to our own error type here. VerboseError doesn't even allow us to construct
 // our own error messages (so, for example, we can't have a good error message for too large integers) and doesn't
 // support propagating anyhow or std errors (and since we can't do a custom message, we can't even capture them as a string).
@@ -124,6 +132,15 @@ pub enum Expr<'a> {
     BinaryOpSequence(Box<SpannedExpr<'a>>, Vec<(BinaryOp, SpannedExpr<'a>)>),
     Set(Vec<Span<'a>>),
     FileSet(Vec<Span<'a>>),
+    /// `let name = value in body`.
+    Let {
+        name: Span<'a>,
+        value: Box<SpannedExpr<'a>>,
+        body: Box<SpannedExpr<'a>>,
+    },
+    /// A reference to a `let`-bound value, `$name`. The span doesn't include the `$`. A `$name`
+    /// which no enclosing `let` binds is a word, as it was before `let` existed.
+    Variable(Span<'a>),
 }
 
 impl Display for Expr<'_> {
@@ -180,6 +197,11 @@ impl Display for Expr<'_> {
                 }
                 f.write_str(")")?;
             }
+            Expr::Let { name, value, body } => {
+                // Parenthesized, as the body would otherwise take any trailing binary operators.
+                write!(f, "(let {} = {} in {})", name.fragment(), value, body)?;
+            }
+            Expr::Variable(name) => write!(f, "${}", name.fragment())?,
         }
         Ok(())
     }
@@ -237,11 +259,23 @@ fn convert_to_str_error(err: VerboseError<Span>) -> VerboseError<&str> {
 
 /// Parses a query string into a SpannedExpr. Requires that the entire input is consumed.
 pub fn parse_expr(input: &str) -> anyhow::Result<SpannedExpr> {
+    parse_expr_with_variables(input, &[])
+}
+
+/// Like `parse_expr`, but `$NAME` also refers to `variables`, which are bound outside of the
+/// query.
+pub fn parse_expr_with_variables<'a>(
+    input: &'a str,
+    variables: &[&str],
+) -> anyhow::Result<SpannedExpr<'a>> {
     let span = Span::new(input);
     // Parse with fast error (`()`) first,
     // and on error reparse again with `VerboseError` to get detailed errors.
     match all_consuming(expr)(span) {
-        Ok((_, value)) => Ok(value),
+        Ok((_, mut value)) => {
+            unbound_variables_to_words(&mut value, input, &mut variables.to_vec());
+            Ok(value)
+        }
         Err(nom::Err::Failure(())) | Err(nom::Err::Error(())) => {
             match all_consuming(expr)(span) {
                 Ok(..) => unreachable!(
@@ -277,8 +311,10 @@ fn single_expr<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Spann
         preceded(char('('), cut(terminated(expr, char(')')))),
         expr_set,
         expr_fileset,
+        expr_let,
         expr_function,
         expr_int,
+        expr_variable,
         expr_word,
     ))(input)?;
 
@@ -352,9 +388,64 @@ fn expr_int<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, SpannedE
     })(input)
 }
 
+/// `$` is allowed in words, so queries predating `let` may contain words that look like
+/// variables. Those are only variables if they're bound.
+fn unbound_variables_to_words<'a: 'b, 'b>(
+    expr: &mut SpannedExpr<'a>,
+    input: &'a str,
+    bound: &mut Vec<&'b str>,
+) {
+    match &mut expr.value {
+        Expr::Variable(name) => {
+            if !bound.contains(name.fragment()) {
+                expr.value = Expr::String(&input[expr.position.clone()]);
+            }
+        }
+        Expr::Function { args, .. } => {
+            for arg in args {
+                unbound_variables_to_words(arg, input, bound);
+            }
+        }
+        Expr::BinaryOpSequence(left, exprs) => {
+            unbound_variables_to_words(left, input, bound);
+            for (_, expr) in exprs {
+                unbound_variables_to_words(expr, input, bound);
+            }
+        }
+        Expr::Let { name, value, body } => {
+            unbound_variables_to_words(value, input, bound);
+            bound.push(*name.fragment());
+            unbound_variables_to_words(body, input, bound);
+            bound.pop();
+        }
+        Expr::String(..) | Expr::Integer(..) | Expr::Set(..) | Expr::FileSet(..) => {}
+    }
+}
+
+/// Tries to parse an Expr::Variable. `$` is also allowed within words, so something like `$a.*` is
+/// still a word.
+fn expr_variable<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, SpannedExpr<'a>, E> {
+    spanned(|input| {
+        let (input, name) = preceded(char('$'), identifier)(input)?;
+        let (input, _) = not(word_chars)(input)?;
+        Ok((input, Expr::Variable(name)))
+    })(input)
+}
+
+fn identifier<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Span<'a>, E> {
+    recognize(pair(
+        alt((alpha1, tag("_"))),
+        many0(alt((alphanumeric1, tag("_")))),
+    ))(input)
+}
+
+fn word_chars<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Span<'a>, E> {
+    alt((alphanumeric1, is_a("*/@.-_:$#%")))(input)
+}
+
 fn word<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Span<'a>, E> {
     fn non_quoted_word<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Span<'a>, E> {
-        recognize(many1(alt((alphanumeric1, is_a("*/@.-_:$#%")))))(input)
+        recognize(many1(word_chars))(input)
     }
 
     alt((
@@ -428,6 +519,28 @@ fn expr_fileset<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Span
     })(input)
 }
 
+/// Tries to parse an Expr::Let. Will fail if it detects an unfinished "let NAME =", anything before
+/// that may still be a word (a target named `let`, for example).
+fn expr_let<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, SpannedExpr<'a>, E> {
+    spanned(|input| {
+        let (input, (_, _, name, _, _)) =
+            tuple((tag("let"), multispace1, identifier, multispace0, char('=')))(input)?;
+        cut(move |input| {
+            let (input, value) = expr(input)?;
+            let (input, _) = terminated(tag("in"), multispace1)(input)?;
+            let (input, body) = expr(input)?;
+            Ok((
+                input,
+                Expr::Let {
+                    name,
+                    value: Box::new(value),
+                    body: Box::new(body),
+                },
+            ))
+        })(input)
+    })(input)
+}
+
 /// Tries to parse an Expr::Function. Will fail if it detects an unfinished "func("
 // We don't need to worry about "set(" as the outermost expr() ensures that never gets to here.
 fn expr_function<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, SpannedExpr<'a>, E> {
@@ -438,10 +551,7 @@ fn expr_function<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Spa
     }
 
     spanned(|input| {
-        let (input, function_name) = recognize(pair(
-            alt((alpha1, tag("_"))),
-            many0(alt((alphanumeric1, tag("_")))),
-        ))(input)?;
+        let (input, function_name) = identifier(input)?;
         let (input, _) = char('(')(input)?;
         cut(move |input| {
             let (input, args) = terminated(function_args, char(')'))(input)?;
@@ -622,6 +732,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_let() -> anyhow::Result<()> {
+        run_tests(
+            expr_let,
+            &[
+                "let x = a in $x",
+                "let x=deps(a) in $x + b",
+                "let x = a in let y = $x in ($x ^ $y)",
+            ],
+            // Until we see "let NAME =", it may be a word
+            &["let", "let x", "letx = a in $x", "let + b"],
+            &[
+                "let x = a",
+                "let x = a in",
+                "let x = a inb",
+                "let x = in $x",
+            ],
+        );
+
+        match parse_expr("let x = a in $x + b") {
+            Ok(Spanned {
+                value: Expr::Let { name, body, .. },
+                ..
+            }) => {
+                assert_eq!("x", *name.fragment());
+                assert!(matches!(body.value, Expr::BinaryOpSequence(..)));
+            }
+            v => panic!("expected let expr, got `{:?}`", v),
+        }
+
+        match parse_expr("let + b") {
+            Ok(Spanned {
+                value: Expr::BinaryOpSequence(..),
+                ..
+            }) => {}
+            v => panic!("expected union expr, got `{:?}`", v),
+        }
+
+        let parsed = parse_expr("let x = a in let y = $x in $y + $x")?;
+        assert_eq!(
+            "(let x = 'a' in (let y = $x in ( $y + $x)))",
+            parsed.to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_variable() -> anyhow::Result<()> {
+        run_tests(
+            expr_variable,
+            &["$x", "$_x1"],
+            // Anything that isn't exactly `$NAME` is a word
+            &["x", "$", "$1", "$x.*", "$x$", "a$x"],
+            &[],
+        );
+        match parse_expr("$x.*") {
+            Ok(Spanned {
+                value: Expr::String("$x.*"),
+                ..
+            }) => {}
+            v => panic!("expected '$x.*', got `{:?}`", v),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_unbound_variables_are_words() -> anyhow::Result<()> {
+        // As they were before `let` was added.
+        match parse_expr("$x") {
+            Ok(Spanned {
+                value: Expr::String("$x"),
+                ..
+            }) => {}
+            v => panic!("expected '$x', got `{:?}`", v),
+        }
+        assert_eq!(
+            "attrfilter('name', '$x', '//foo:')",
+            parse_expr("attrfilter(name, $x, //foo:)")?.to_string()
+        );
+        // Only the body of a `let` sees its binding.
+        assert_eq!(
+            "( '$a' + (let a = '$a' in $a))",
+            parse_expr("$a + let a = $a in $a")?.to_string()
+        );
+        // Unless they are bound outside of the query.
+        assert_eq!(
+            "( $x + '$y')",
+            parse_expr_with_variables("$x + $y", &["x"])?.to_string()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_integer() -> anyhow::Result<()> {
         run_tests(expr_int, &["0", "1234"], &["w123", ".1", ""], &["0123"]);
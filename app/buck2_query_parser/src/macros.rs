@@ -0,0 +1,172 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Named query macros, as loaded by `buck2 query --query-macros`.
+//!
+//! A macros file is a sequence of definitions, each of which may refer to the ones before it:
+//!
+//! ```text
+//! # Lines starting with `#` are comments.
+//! lib_deps = deps(//lib/...)
+//! external = $lib_deps - //lib/...
+//! ```
+//!
+//! Macros are bound around a query as `let` expressions, so a query can refer to them as `$NAME`,
+//! and only the macros the query uses are evaluated.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use nom::branch::alt;
+use nom::bytes::complete::take_till;
+use nom::character::complete::char;
+use nom::character::complete::multispace0;
+use nom::character::complete::multispace1;
+use nom::combinator::all_consuming;
+use nom::combinator::cut;
+use nom::combinator::recognize;
+use nom::error::convert_error;
+use nom::error::VerboseError;
+use nom::multi::many0;
+use nom::sequence::delimited;
+use nom::sequence::pair;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
+
+use crate::convert_to_str_error;
+use crate::expr;
+use crate::identifier;
+use crate::parse_expr;
+use crate::span::Span;
+use crate::NomParseError;
+use crate::NomResult;
+use crate::ParseError;
+use crate::SpannedExpr;
+
+#[derive(Debug, buck2_error::Error)]
+enum QueryMacrosError {
+    #[error("Query macro `{0}` is defined more than once")]
+    #[buck2(input)]
+    Duplicate(String),
+}
+
+/// Query macro definitions, in the order they were defined.
+#[derive(Debug, Default)]
+pub struct QueryMacros {
+    /// Macro names and the text of their definitions.
+    macros: Vec<(String, String)>,
+}
+
+impl QueryMacros {
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        let definitions = match all_consuming(definitions::<VerboseError<Span>>)(Span::new(input)) {
+            Ok((_, definitions)) => definitions,
+            Err(nom::Err::Failure(err)) | Err(nom::Err::Error(err)) => {
+                return Err(
+                    ParseError::NomError(convert_error(input, convert_to_str_error(err))).into(),
+                );
+            }
+            Err(nom::Err::Incomplete(..)) => unreachable!(),
+        };
+
+        let mut names = HashSet::new();
+        let mut macros = Vec::with_capacity(definitions.len());
+        for (name, value) in definitions {
+            let name = *name.fragment();
+            if !names.insert(name) {
+                return Err(QueryMacrosError::Duplicate(name.to_owned()).into());
+            }
+            macros.push((name.to_owned(), input[value.position].to_owned()));
+        }
+        Ok(Self { macros })
+    }
+
+    /// The names of the macros and the text of their definitions, in the order they were defined.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.macros
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Wraps `query` in a `let` for each macro, making them available to it as `$NAME`.
+    ///
+    /// `query` is parsed on its own first (the macros were when they were loaded), so syntax
+    /// errors point into the text the user wrote rather than into the rewritten query.
+    pub fn bind(&self, query: &str) -> anyhow::Result<String> {
+        parse_expr(query)?;
+
+        let mut bound = String::new();
+        for (name, value) in &self.macros {
+            write!(bound, "let {} = {} in ", name, value).unwrap();
+        }
+        bound.push_str(query);
+        Ok(bound)
+    }
+}
+
+fn definitions<'a, E: NomParseError<'a>>(
+    input: Span<'a>,
+) -> NomResult<'a, Vec<(Span<'a>, SpannedExpr<'a>)>, E> {
+    fn separator<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, (), E> {
+        let comment = recognize(pair(char('#'), take_till(|c| c == '\n')));
+        let (input, _) = many0(alt((multispace1, comment)))(input)?;
+        Ok((input, ()))
+    }
+
+    fn definition<'a, E: NomParseError<'a>>(
+        input: Span<'a>,
+    ) -> NomResult<'a, (Span<'a>, SpannedExpr<'a>), E> {
+        let (input, name) =
+            terminated(identifier, delimited(multispace0, char('='), multispace0))(input)?;
+        let (input, value) = cut(expr)(input)?;
+        Ok((input, (name, value)))
+    }
+
+    terminated(many0(preceded(separator, definition)), separator)(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> anyhow::Result<()> {
+        let macros = QueryMacros::parse(
+            "
+# The libraries.
+libs = //lib/...
+
+lib_deps = deps($libs)
+  # Anything outside of lib.
+external = $lib_deps
+    - $libs
+",
+        )?;
+        assert_eq!(
+            "let libs = //lib/... in let lib_deps = deps($libs) in let external = $lib_deps\n    - $libs in $external",
+            macros.bind("$external")?
+        );
+        parse_expr(&macros.bind("$external")?)?;
+
+        assert_eq!("//foo:bar", QueryMacros::parse("")?.bind("//foo:bar")?);
+        assert!(QueryMacros::parse("a = b\na = c").is_err());
+        assert!(QueryMacros::parse("a = ").is_err());
+        assert!(QueryMacros::parse("a = b c").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bind_syntax_error() -> anyhow::Result<()> {
+        let macros = QueryMacros::parse("libs = //lib/...")?;
+        let msg = format!("{:#}", macros.bind("deps($libs").unwrap_err());
+        assert!(msg.contains("deps($libs"), "{}", msg);
+        assert!(!msg.contains("let libs"), "{}", msg);
+        Ok(())
+    }
+}
@@ -392,10 +392,13 @@ mod tests {
                                     check_deps_finished: 0,
                                     compute_started: 0,
                                     compute_finished: 0,
+                                    early_cutoffs: 0,
+                                    recompute_changes: 0,
                                 },
                             );
                             map
                         },
+                        concurrency_class_states: HashMap::new(),
                     }
                     .into(),
                 ),
@@ -11,6 +11,7 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::io::BufWriter;
 use std::marker::PhantomData;
+use std::ops::Deref;
 use std::sync::Arc;
 
 use allocative::Allocative;
@@ -88,6 +89,7 @@ use buck2_interpreter::starlark_profiler::config::StarlarkProfilerConfiguration;
 use buck2_interpreter_for_build::interpreter::configuror::BuildInterpreterConfiguror;
 use buck2_interpreter_for_build::interpreter::cycles::LoadCycleDescriptor;
 use buck2_interpreter_for_build::interpreter::interpreter_setup::setup_interpreter;
+use buck2_node::compute_counters::SetComputeCounters;
 use buck2_server_ctx::concurrency::DiceUpdater;
 use buck2_server_ctx::ctx::DiceAccessor;
 use buck2_server_ctx::ctx::PrivateStruct;
@@ -142,10 +144,28 @@ pub struct BaseServerCommandContext {
     pub spawner: Arc<BuckSpawner>,
 }
 
+/// The `BaseServerCommandContext` of a `ServerCommandContext`: owned by the context of a command,
+/// and borrowed by the contexts of the requests of a long-running command.
+pub enum BaseContext<'a> {
+    Owned(BaseServerCommandContext),
+    Borrowed(&'a BaseServerCommandContext),
+}
+
+impl Deref for BaseContext<'_> {
+    type Target = BaseServerCommandContext;
+
+    fn deref(&self) -> &BaseServerCommandContext {
+        match self {
+            BaseContext::Owned(base_context) => base_context,
+            BaseContext::Borrowed(base_context) => base_context,
+        }
+    }
+}
+
 /// ServerCommandContext provides access to the global daemon state and information about the calling client for
 /// the implementation of DaemonApi endpoints (ex. targets, query, build).
 pub struct ServerCommandContext<'a> {
-    pub base_context: BaseServerCommandContext,
+    pub base_context: BaseContext<'a>,
 
     /// The working directory of the client. This is used for resolving things in the request in a
     /// working-dir relative way. For example, it's common to resolve target patterns relative to
@@ -218,6 +238,33 @@ impl<'a> ServerCommandContext<'a> {
         paths: &InvocationPaths,
         snapshot_collector: SnapshotCollector,
         cancellations: &'a ExplicitCancellationContext,
+    ) -> anyhow::Result<Self> {
+        let heartbeat_guard_handle =
+            HeartbeatGuard::new(base_context.events.dupe(), snapshot_collector);
+
+        let mut context = Self::with_client_context(
+            BaseContext::Owned(base_context),
+            client_context,
+            starlark_profiler_instrumentation_override,
+            build_options.cloned(),
+            paths.buck_out_dir(),
+            paths.isolation.clone(),
+            cancellations,
+        )?;
+        context.heartbeat_guard_handle = Some(heartbeat_guard_handle);
+        Ok(context)
+    }
+
+    /// Does not emit heartbeats: that is left to `new`, so that the contexts of the requests of a
+    /// command don't emit them a second time.
+    fn with_client_context(
+        base_context: BaseContext<'a>,
+        client_context: &ClientContext,
+        starlark_profiler_instrumentation_override: StarlarkProfilerConfiguration,
+        build_options: Option<CommonBuildOptions>,
+        buck_out_dir: ProjectRelativePathBuf,
+        isolation_prefix: FileNameBuf,
+        cancellations: &'a ExplicitCancellationContext,
     ) -> anyhow::Result<Self> {
         let working_dir = AbsNormPath::new(&client_context.working_dir)?;
 
@@ -285,9 +332,6 @@ impl<'a> ServerCommandContext<'a> {
             .find(|m| m.key == "id")
             .map(|m| m.value.clone());
 
-        let heartbeat_guard_handle =
-            HeartbeatGuard::new(base_context.events.dupe(), snapshot_collector);
-
         let debugger_handle = create_debugger_handle(base_context.events.dupe());
 
         Ok(ServerCommandContext {
@@ -303,14 +347,14 @@ impl<'a> ServerCommandContext<'a> {
             client_id_from_client_metadata,
             _re_connection_handle: re_connection_handle,
             starlark_profiler_instrumentation_override,
-            buck_out_dir: paths.buck_out_dir(),
-            isolation_prefix: paths.isolation.clone(),
-            build_options: build_options.cloned(),
+            buck_out_dir,
+            isolation_prefix,
+            build_options,
             record_target_call_stacks: client_context.target_call_stacks,
             skip_targets_with_duplicate_names: client_context.skip_targets_with_duplicate_names,
             disable_starlark_types: client_context.disable_starlark_types,
             unstable_typecheck: client_context.unstable_typecheck,
-            heartbeat_guard_handle: Some(heartbeat_guard_handle),
+            heartbeat_guard_handle: None,
             daemon_uuid_from_client: client_context.daemon_uuid.clone(),
             command_name: client_context.command_name.clone(),
             sanitized_argv: client_context.sanitized_argv.clone(),
@@ -702,6 +746,7 @@ impl<'a, 's> DiceCommandUpdater<'a, 's> {
         )));
         data.set_blocking_executor(self.cmd_ctx.base_context.daemon.blocking_executor.dupe());
         data.set_http_client(self.cmd_ctx.base_context.daemon.http_client.dupe());
+        data.set_compute_counters();
         data.set_materializer(self.cmd_ctx.base_context.daemon.materializer.dupe());
         data.set_build_signals(self.build_signals.build_signals.dupe());
         data.set_run_action_knobs(run_action_knobs);
@@ -976,4 +1021,19 @@ impl<'a> ServerCommandContextTrait for ServerCommandContext<'a> {
     fn cancellation_context(&self) -> &ExplicitCancellationContext {
         self.cancellations
     }
+
+    fn for_request<'s>(
+        &'s self,
+        client_context: &ClientContext,
+    ) -> anyhow::Result<Box<dyn ServerCommandContextTrait + 's>> {
+        Ok(Box::new(ServerCommandContext::with_client_context(
+            BaseContext::Borrowed(&*self.base_context),
+            client_context,
+            self.starlark_profiler_instrumentation_override.clone(),
+            self.build_options.clone(),
+            self.buck_out_dir.clone(),
+            self.isolation_prefix.clone(),
+            self.cancellations,
+        )?))
+    }
 }
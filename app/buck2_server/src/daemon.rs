@@ -11,11 +11,14 @@ pub mod check_working_dir;
 pub mod common;
 pub mod daemon_tcp;
 pub mod dice_dump;
+mod dice_eviction;
+mod dice_snapshot;
 pub mod disk_state;
 pub mod forkserver;
 pub(crate) mod io_provider;
 mod multi_event_stream;
 pub mod panic;
+mod prometheus;
 pub mod server;
 pub(crate) mod server_allocative;
 pub mod state;
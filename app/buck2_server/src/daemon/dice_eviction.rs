@@ -0,0 +1,48 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `buck2.dice_memory_budget_bytes`: periodically evict the least recently used DICE values so
+//! that long-lived daemons don't keep every value they ever computed.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use dice::Dice;
+
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spawns a task evicting DICE values above `memory_budget` bytes whenever no transaction is
+/// active. The task stops once DICE is dropped.
+pub(crate) fn spawn_dice_eviction(dice: &Arc<Dice>, memory_budget: u64) {
+    let dice = Arc::downgrade(dice);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EVICTION_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let Some(dice) = dice.upgrade() else {
+                break;
+            };
+            // Measuring the graph isn't free, don't slow down running commands.
+            if dice.metrics().active_transaction_count != 0 {
+                continue;
+            }
+            let stats = dice.evict_cold_values(memory_budget).await;
+            if stats.evicted_count != 0 {
+                tracing::info!(
+                    "Evicted {} of {} DICE values ({} of {} bytes)",
+                    stats.evicted_count,
+                    stats.candidate_count,
+                    stats.evicted_bytes,
+                    stats.candidate_bytes
+                );
+            }
+        }
+    });
+}
@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `buck2.dice_snapshot`: save the DICE graph when the daemon shuts down or has been idle for a
+//! while, and restore it in the next daemon, so that its first command only recomputes what
+//! changed in between.
+//!
+//! Only the key types registered in `dice_persistence` are saved. The snapshot records where the
+//! file watcher was at, and is only restored if the new file watcher can resume from there, so
+//! that every file changed since gets invalidated on the first sync.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::ErrorKind;
+
+use allocative::Allocative;
+use anyhow::Context as _;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_execute::execute::blocking::BlockingExecutor;
+use buck2_file_watcher::file_watcher::FileWatcher;
+use dice::Dice;
+use dice::DicePersistence;
+use dice::DiceSnapshot;
+use serde::Deserialize;
+use serde::Serialize;
+
+const SNAPSHOT_FILE_NAME: &str = "snapshot";
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotMetadata {
+    /// Values are only valid for the daemon binary that computed them.
+    daemon_version: String,
+    file_watcher_cursor: String,
+}
+
+/// The key types which are saved. A key is only saved along with everything it depends on, so a
+/// key type is only useful here once the key types it depends on are registered too.
+///
+/// Keys computed through transitions, configuration constructors, anon targets or external cells
+/// aren't registered, so nothing depending on them is saved.
+fn dice_persistence() -> anyhow::Result<DicePersistence> {
+    let mut persistence = DicePersistence::new();
+    buck2_common::dice::register_persistent_keys(&mut persistence)?;
+    buck2_interpreter::dice::register_persistent_keys(&mut persistence)?;
+    buck2_interpreter_for_build::interpreter::register_persistent_keys(&mut persistence)?;
+    buck2_configured::register_persistent_keys(&mut persistence)?;
+    buck2_build_api::context::register_persistent_keys(&mut persistence)?;
+    buck2_analysis::register_persistent_keys(&mut persistence)?;
+    Ok(persistence)
+}
+
+/// Where the DICE snapshot is saved, and for which daemon.
+#[derive(Allocative)]
+pub(crate) struct DiceSnapshotLocation {
+    dir: AbsNormPathBuf,
+    /// The build id of the daemon binary, as in its `DaemonConstraints`.
+    daemon_version: String,
+}
+
+impl DiceSnapshotLocation {
+    pub(crate) fn new(dir: AbsNormPathBuf, daemon_version: String) -> Self {
+        Self {
+            dir,
+            daemon_version,
+        }
+    }
+
+    /// Writes the snapshot, replacing the previous one. Nothing is written if the file watcher
+    /// can't be resumed.
+    pub(crate) async fn save(
+        &self,
+        dice: &Dice,
+        file_watcher: &dyn FileWatcher,
+        blocking_executor: &dyn BlockingExecutor,
+    ) -> anyhow::Result<()> {
+        let path = self.dir.as_path().join(SNAPSHOT_FILE_NAME);
+        let Some(file_watcher_cursor) = file_watcher.cursor().await? else {
+            // Don't leave an older snapshot to be restored instead.
+            return blocking_executor
+                .execute_io_inline(|| match std::fs::remove_file(&path) {
+                    Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+                    _ => Ok(()),
+                })
+                .await;
+        };
+        let metadata = serde_json::to_string(&SnapshotMetadata {
+            daemon_version: self.daemon_version.clone(),
+            file_watcher_cursor,
+        })?;
+        let snapshot = dice.snapshot(&dice_persistence()?, metadata).await?;
+
+        blocking_executor
+            .execute_io_inline(|| {
+                std::fs::create_dir_all(&self.dir).context("Failed to create directory")?;
+                let tmp_path = self
+                    .dir
+                    .as_path()
+                    .join(format!("{}.tmp", SNAPSHOT_FILE_NAME));
+                let file = File::create(&tmp_path)
+                    .with_context(|| format!("Failed to open `{}`", tmp_path.display()))?;
+                snapshot.write(BufWriter::new(file))?;
+                std::fs::rename(&tmp_path, &path)
+                    .with_context(|| format!("Failed to write `{}`", path.display()))
+            })
+            .await?;
+
+        tracing::info!("Saved {} DICE nodes", snapshot.len());
+        Ok(())
+    }
+
+    /// Restores the snapshot into a DICE graph that has no nodes yet, and resumes the file watcher
+    /// from where it was when the snapshot was taken. Must be called before the file watcher
+    /// first syncs. Returns the number of restored nodes.
+    pub(crate) async fn restore(
+        &self,
+        dice: &Dice,
+        file_watcher: &dyn FileWatcher,
+        blocking_executor: &dyn BlockingExecutor,
+    ) -> anyhow::Result<usize> {
+        let path = self.dir.as_path().join(SNAPSHOT_FILE_NAME);
+        let snapshot = blocking_executor
+            .execute_io_inline(|| {
+                let file = match File::open(&path) {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+                    Err(e) => {
+                        return Err(e)
+                            .with_context(|| format!("Failed to open `{}`", path.display()));
+                    }
+                };
+                Ok(Some(DiceSnapshot::read(BufReader::new(file))?))
+            })
+            .await?;
+        let Some(snapshot) = snapshot else {
+            return Ok(0);
+        };
+
+        let metadata: SnapshotMetadata = serde_json::from_str(snapshot.metadata())?;
+        if metadata.daemon_version != self.daemon_version {
+            tracing::info!("Not restoring DICE snapshot written by another daemon version");
+            return Ok(0);
+        }
+        if !file_watcher.resume(&metadata.file_watcher_cursor).await? {
+            tracing::info!("Not restoring DICE snapshot: the file watcher cannot resume from it");
+            return Ok(0);
+        }
+        dice.restore(&snapshot, &dice_persistence()?).await
+    }
+}
@@ -0,0 +1,441 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Serves the daemon's metrics in the Prometheus text format, when `buck2.metrics_address` is
+//! set. The metrics are the ones we already collect for snapshots, plus DICE concurrency classes
+//! and early cutoffs, active commands and forkserver processes.
+
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::Context;
+use buck2_forkserver::client::ForkserverStats;
+use dupe::Dupe;
+use hyper::header::HeaderValue;
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::Body;
+use hyper::Method;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
+use tokio::net::TcpListener;
+
+use crate::active_commands::active_commands;
+use crate::daemon::state::DaemonState;
+use crate::snapshot::SnapshotCollector;
+
+const METRICS_PATH: &str = "/metrics";
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4";
+
+/// Binds `address` and serves metrics from it until the daemon exits.
+pub(crate) async fn spawn_metrics_server(
+    address: &str,
+    daemon_state: Arc<DaemonState>,
+) -> anyhow::Result<()> {
+    let address: SocketAddr = address
+        .parse()
+        .with_context(|| format!("Invalid `buck2.metrics_address`: `{}`", address))?;
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Error binding metrics server to `{}`", address))?;
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::debug!("Error accepting metrics connection: {:#}", e);
+                    continue;
+                }
+            };
+            let daemon_state = daemon_state.dupe();
+            tokio::spawn(async move {
+                let service = service_fn(move |req| handle(req, daemon_state.dupe()));
+                if let Err(e) = Http::new().serve_connection(stream, service).await {
+                    tracing::debug!("Error serving metrics: {:#}", e);
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle(
+    req: Request<Body>,
+    daemon_state: Arc<DaemonState>,
+) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::GET || req.uri().path() != METRICS_PATH {
+        return Ok(response(StatusCode::NOT_FOUND, "Not found\n".to_owned()));
+    }
+
+    // Not available until the daemon has finished initializing.
+    let data = match daemon_state.data() {
+        Ok(data) => data,
+        Err(e) => {
+            return Ok(response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("{:#}\n", e),
+            ));
+        }
+    };
+
+    let dice = data.dice_manager.unsafe_dice().metrics();
+    let forkserver = data.forkserver.as_ref().map(|f| f.stats());
+    let snapshot =
+        SnapshotCollector::new(data, daemon_state.paths.buck_out_path()).create_snapshot();
+    let active_commands = active_commands().len();
+
+    Ok(response(
+        StatusCode::OK,
+        render_metrics(&snapshot, &dice, active_commands, forkserver),
+    ))
+}
+
+fn response(status: StatusCode, body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(TEXT_FORMAT));
+    response
+}
+
+fn render_metrics(
+    snapshot: &buck2_data::Snapshot,
+    dice: &dice::Metrics,
+    active_commands: usize,
+    forkserver: Option<ForkserverStats>,
+) -> String {
+    let mut out = MetricsWriter::default();
+
+    out.gauge(
+        "buck2_daemon_uptime_seconds",
+        "Time since the daemon started.",
+        snapshot.daemon_uptime_s,
+    );
+    out.gauge(
+        "buck2_active_commands",
+        "Commands currently running on the daemon.",
+        active_commands as u64,
+    );
+
+    if let Some(rss) = snapshot.buck2_rss {
+        out.gauge("buck2_rss_bytes", "Resident set size of the daemon.", rss);
+    }
+    out.gauge(
+        "buck2_max_rss_bytes",
+        "Maximum resident set size of the daemon.",
+        snapshot.buck2_max_rss,
+    );
+    if let Some(active) = snapshot.malloc_bytes_active {
+        out.gauge(
+            "buck2_malloc_active_bytes",
+            "Bytes in pages allocated by the allocator.",
+            active,
+        );
+    }
+    if let Some(allocated) = snapshot.malloc_bytes_allocated {
+        out.gauge(
+            "buck2_malloc_allocated_bytes",
+            "Bytes allocated by the daemon.",
+            allocated,
+        );
+    }
+
+    out.gauge(
+        "buck2_dice_keys",
+        "Keys stored in DICE.",
+        dice.key_count as u64,
+    );
+    out.gauge(
+        "buck2_dice_active_keys",
+        "Keys in the caches of active DICE transactions.",
+        dice.currently_active_key_count as u64,
+    );
+    out.gauge(
+        "buck2_dice_active_transactions",
+        "Active DICE transactions.",
+        dice.active_transaction_count as u64,
+    );
+    out.family(
+        "buck2_dice_running_computations",
+        "gauge",
+        "DICE computations currently running, by concurrency class.",
+        dice.concurrency_classes
+            .iter()
+            .map(|c| (vec![("class", c.name)], c.running as u64)),
+    );
+    out.family(
+        "buck2_dice_queued_computations",
+        "gauge",
+        "DICE computations waiting to start, by concurrency class.",
+        dice.concurrency_classes
+            .iter()
+            .map(|c| (vec![("class", c.name)], c.queued as u64)),
+    );
+    out.family(
+        "buck2_dice_recomputations_total",
+        "counter",
+        "DICE recomputations, by key type and whether their value changed or was cut off.",
+        dice.early_cutoffs.iter().flat_map(|e| {
+            [
+                (
+                    vec![("key_type", e.key_type), ("result", "cutoff")],
+                    e.cutoffs,
+                ),
+                (
+                    vec![("key_type", e.key_type), ("result", "changed")],
+                    e.changes,
+                ),
+            ]
+        }),
+    );
+
+    out.gauge(
+        "buck2_materializer_queue_size",
+        "Commands queued in the deferred materializer.",
+        snapshot.deferred_materializer_queue_size,
+    );
+    out.counter(
+        "buck2_materializer_declares_total",
+        "Artifacts declared to the deferred materializer.",
+        snapshot.deferred_materializer_declares,
+    );
+    out.counter(
+        "buck2_materializer_declares_reused_total",
+        "Artifacts declared to the deferred materializer which were already materialized.",
+        snapshot.deferred_materializer_declares_reused,
+    );
+    out.gauge(
+        "buck2_blocking_executor_io_queue_size",
+        "IO operations queued in the blocking executor.",
+        snapshot.blocking_executor_io_queue_size,
+    );
+
+    out.counter(
+        "buck2_re_upload_bytes_total",
+        "Bytes uploaded to RE.",
+        snapshot.re_upload_bytes,
+    );
+    out.counter(
+        "buck2_re_download_bytes_total",
+        "Bytes downloaded from RE.",
+        snapshot.re_download_bytes,
+    );
+    let re_requests = [
+        (
+            "upload",
+            snapshot.re_uploads_started,
+            snapshot.re_uploads_finished_successfully,
+            snapshot.re_uploads_finished_with_error,
+        ),
+        (
+            "download",
+            snapshot.re_downloads_started,
+            snapshot.re_downloads_finished_successfully,
+            snapshot.re_downloads_finished_with_error,
+        ),
+        (
+            "action_cache",
+            snapshot.re_action_cache_started,
+            snapshot.re_action_cache_finished_successfully,
+            snapshot.re_action_cache_finished_with_error,
+        ),
+        (
+            "execute",
+            snapshot.re_executes_started,
+            snapshot.re_executes_finished_successfully,
+            snapshot.re_executes_finished_with_error,
+        ),
+        (
+            "materialize",
+            snapshot.re_materializes_started,
+            snapshot.re_materializes_finished_successfully,
+            snapshot.re_materializes_finished_with_error,
+        ),
+        (
+            "write_action_result",
+            snapshot.re_write_action_results_started,
+            snapshot.re_write_action_results_finished_successfully,
+            snapshot.re_write_action_results_finished_with_error,
+        ),
+        (
+            "get_digest_expirations",
+            snapshot.re_get_digest_expirations_started,
+            snapshot.re_get_digest_expirations_finished_successfully,
+            snapshot.re_get_digest_expirations_finished_with_error,
+        ),
+    ];
+    out.family(
+        "buck2_re_requests_total",
+        "counter",
+        "RE requests, by operation and status.",
+        re_requests
+            .iter()
+            .flat_map(|(operation, started, succeeded, failed)| {
+                [
+                    (
+                        vec![("operation", *operation), ("status", "started")],
+                        *started,
+                    ),
+                    (
+                        vec![("operation", *operation), ("status", "succeeded")],
+                        *succeeded,
+                    ),
+                    (
+                        vec![("operation", *operation), ("status", "failed")],
+                        *failed,
+                    ),
+                ]
+            }),
+    );
+    out.counter(
+        "buck2_http_download_bytes_total",
+        "Bytes downloaded over HTTP.",
+        snapshot.http_download_bytes,
+    );
+
+    if let Some(forkserver) = forkserver {
+        out.gauge(
+            "buck2_forkserver_running_processes",
+            "Processes currently running through the forkserver.",
+            forkserver.running,
+        );
+        out.counter(
+            "buck2_forkserver_processes_total",
+            "Processes started through the forkserver.",
+            forkserver.started,
+        );
+    }
+
+    out.out
+}
+
+#[derive(Default)]
+struct MetricsWriter {
+    out: String,
+}
+
+impl MetricsWriter {
+    fn gauge(&mut self, name: &str, help: &str, value: u64) {
+        self.family(name, "gauge", help, [(vec![], value)]);
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: u64) {
+        self.family(name, "counter", help, [(vec![], value)]);
+    }
+
+    /// Writes a metric with one sample per set of labels.
+    fn family<'a>(
+        &mut self,
+        name: &str,
+        kind: &str,
+        help: &str,
+        samples: impl IntoIterator<Item = (Vec<(&'a str, &'a str)>, u64)>,
+    ) {
+        // Writing to a `String` can't fail.
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+        for (labels, value) in samples {
+            self.out.push_str(name);
+            if !labels.is_empty() {
+                self.out.push('{');
+                for (i, (label, label_value)) in labels.iter().enumerate() {
+                    if i != 0 {
+                        self.out.push(',');
+                    }
+                    let _ = write!(self.out, "{}=\"{}\"", label, escape(label_value));
+                }
+                self.out.push('}');
+            }
+            let _ = writeln!(self.out, " {}", value);
+        }
+    }
+}
+
+fn escape(label_value: &str) -> String {
+    label_value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let snapshot = buck2_data::Snapshot {
+            buck2_rss: Some(100),
+            re_upload_bytes: 7,
+            deferred_materializer_queue_size: 3,
+            ..Default::default()
+        };
+        let dice = dice::Metrics {
+            key_count: 10,
+            currently_active_key_count: 2,
+            active_transaction_count: 1,
+            concurrency_classes: vec![dice::ConcurrencyClassMetrics {
+                name: "build",
+                limit: 8,
+                priority: 0,
+                running: 4,
+                queued: 5,
+            }],
+            early_cutoffs: vec![dice::EarlyCutoffMetrics {
+                key_type: "Parse\"Key",
+                cutoffs: 6,
+                changes: 8,
+            }],
+        };
+
+        let rendered = render_metrics(
+            &snapshot,
+            &dice,
+            2,
+            Some(ForkserverStats {
+                started: 9,
+                running: 1,
+            }),
+        );
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        for expected in [
+            "# TYPE buck2_rss_bytes gauge",
+            "buck2_rss_bytes 100",
+            "buck2_active_commands 2",
+            "buck2_dice_keys 10",
+            "buck2_dice_running_computations{class=\"build\"} 4",
+            "buck2_dice_queued_computations{class=\"build\"} 5",
+            "# TYPE buck2_dice_recomputations_total counter",
+            "buck2_dice_recomputations_total{key_type=\"Parse\\\"Key\",result=\"cutoff\"} 6",
+            "buck2_dice_recomputations_total{key_type=\"Parse\\\"Key\",result=\"changed\"} 8",
+            "buck2_materializer_queue_size 3",
+            "buck2_re_upload_bytes_total 7",
+            "buck2_re_requests_total{operation=\"upload\",status=\"started\"} 0",
+            "buck2_forkserver_running_processes 1",
+            "buck2_forkserver_processes_total 9",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "Missing `{}` in:\n{}",
+                expected,
+                rendered
+            );
+        }
+        // Not reported when the allocator doesn't give us stats.
+        assert!(!rendered.contains("buck2_malloc_active_bytes"));
+    }
+}
@@ -97,11 +97,13 @@ use tonic::Request;
 use tonic::Response;
 use tonic::Status;
 
+use crate::active_commands::active_commands;
 use crate::active_commands::ActiveCommand;
 use crate::active_commands::ActiveCommandStateWriter;
 use crate::clean_stale::clean_stale_command;
 use crate::ctx::ServerCommandContext;
 use crate::daemon::multi_event_stream::MultiEventStream;
+use crate::daemon::prometheus::spawn_metrics_server;
 use crate::daemon::server_allocative::spawn_allocative;
 use crate::daemon::state::DaemonState;
 use crate::file_status::file_status_command;
@@ -117,6 +119,10 @@ static DEFAULT_KILL_TIMEOUT: Duration = Duration::from_millis(500);
 
 static DEFAULT_INACTIVITY_TIMEOUT: Duration = Duration::from_secs(4 * 86400);
 
+/// How long the daemon waits without new commands before it saves the DICE snapshot, so that it
+/// is there even if the daemon is killed without shutting down.
+static DICE_SNAPSHOT_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 pub trait BuckdServerDelegate: Allocative + Send + Sync {
     fn force_shutdown_with_timeout(&self, reason: String, timeout: Duration);
 }
@@ -279,6 +285,7 @@ impl BuckdServer {
         let materializations = MaterializationMethod::try_new_from_config_value(
             init_ctx.daemon_startup_config.materializations.as_deref(),
         )?;
+        let metrics_address = init_ctx.daemon_startup_config.metrics_address.clone();
 
         // Create buck-out and potentially chdir to there.
         fs_util::create_dir_all(paths.buck_out_path()).context("Error creating buck_out_path")?;
@@ -291,9 +298,25 @@ impl BuckdServer {
         };
 
         let daemon_state = Arc::new(
-            DaemonState::new(fb, paths, init_ctx, rt.clone(), materializations, cwd).await,
+            DaemonState::new(
+                fb,
+                paths,
+                init_ctx,
+                rt.clone(),
+                materializations,
+                cwd,
+                base_daemon_constraints.version.clone(),
+            )
+            .await,
         );
 
+        if let Some(metrics_address) = metrics_address {
+            // Metrics are optional, don't fail the daemon if e.g. the port is taken.
+            if let Err(e) = spawn_metrics_server(&metrics_address, daemon_state.dupe()).await {
+                tracing::warn!("Not serving metrics: {:#}", e);
+            }
+        }
+
         let auth_token = process_info.auth_token.clone();
         let api_server = BuckdServer(Arc::new(BuckdServerData {
             stop_accepting_requests: AtomicBool::new(false),
@@ -315,7 +338,11 @@ impl BuckdServer {
             rt,
         }));
 
-        let shutdown = server_shutdown_signal(command_receiver, shutdown_receiver)?;
+        let shutdown = server_shutdown_signal(
+            command_receiver,
+            shutdown_receiver,
+            api_server.0.daemon_state.dupe(),
+        )?;
         let server = Server::builder()
             .layer(interceptor(BuckCheckAuthTokenInterceptor { auth_token }))
             .add_service(
@@ -809,6 +836,8 @@ impl DaemonApi for BuckdServer {
                 callers: req.callers,
             };
 
+            self.0.daemon_state.save_dice_snapshot().await;
+
             self.0.daemon_shutdown.start_shutdown(reason, timeout);
             Ok(KillResponse {})
         })
@@ -1245,6 +1274,85 @@ impl DaemonApi for BuckdServer {
             .map_err(|e| Status::internal(format!("{:#}", e)))
     }
 
+    async fn unstable_dice_why(
+        &self,
+        req: Request<UnstableDiceWhyRequest>,
+    ) -> Result<Response<UnstableDiceWhyResponse>, Status> {
+        self.check_if_accepting_requests()?;
+
+        let UnstableDiceWhyRequest {
+            key,
+            contains,
+            limit,
+        } = req.into_inner();
+        let res: anyhow::Result<_> = try {
+            let limit = usize::try_from(limit)?;
+            let paths = self
+                .0
+                .daemon_state
+                .data()?
+                .dice_manager
+                .unsafe_dice()
+                .invalidation_paths(move |k| if contains { k.contains(&key) } else { k == key })
+                .await;
+
+            UnstableDiceWhyResponse {
+                omitted: paths.len().saturating_sub(limit) as u64,
+                paths: paths
+                    .into_iter()
+                    .take(limit)
+                    .map(|path| unstable_dice_why_response::Path {
+                        version: path.version.to_string(),
+                        keys: path.keys,
+                    })
+                    .collect(),
+            }
+        };
+
+        res.map(Response::new)
+            .map_err(|e| Status::internal(format!("{:#}", e)))
+    }
+
+    async fn unstable_dice_record(
+        &self,
+        req: Request<UnstableDiceRecordRequest>,
+    ) -> Result<Response<UnstableDiceRecordResponse>, Status> {
+        self.check_if_accepting_requests()?;
+
+        let action = req.into_inner().action;
+        let res: anyhow::Result<_> = try {
+            let dice = self
+                .0
+                .daemon_state
+                .data()?
+                .dice_manager
+                .unsafe_dice()
+                .dupe();
+            match action.context("Missing DICE recording action")? {
+                unstable_dice_record_request::Action::Start(_) => {
+                    dice.start_recording();
+                    UnstableDiceRecordResponse { transactions: 0 }
+                }
+                unstable_dice_record_request::Action::Stop(stop) => {
+                    let recording = dice
+                        .stop_recording()
+                        .context("DICE isn't recording, start recording first")?;
+                    let path = Path::new(&stop.output_path);
+                    let json = serde_json::to_vec(&recording)?;
+                    tokio::fs::write(path, json)
+                        .await
+                        .with_context(|| format!("Failed to write `{}`", path.display()))?;
+                    UnstableDiceRecordResponse {
+                        transactions: recording.transactions.len() as u64,
+                    }
+                }
+            }
+        };
+
+        res.map(Response::new)
+            .map_err(|e| Status::internal(format!("{:#}", e)))
+    }
+
     type AllocativeStream = ResponseStream;
     async fn allocative(
         &self,
@@ -1432,6 +1540,29 @@ impl DaemonApi for BuckdServer {
         .await
     }
 
+    type QueryShellStream = ResponseStream;
+    async fn query_shell(
+        &self,
+        req: Request<tonic::Streaming<StreamingRequest>>,
+    ) -> Result<Response<Self::QueryShellStream>, Status> {
+        self.run_bidirectional(
+            req,
+            DefaultCommandOptions,
+            |ctx,
+             partial_result_dispatcher,
+             _client_ctx,
+             req: StreamingRequestHandler<QueryShellRequest>| {
+                Box::pin(async {
+                    OTHER_SERVER_COMMANDS
+                        .get()?
+                        .query_shell(ctx, partial_result_dispatcher, req)
+                        .await
+                })
+            },
+        )
+        .await
+    }
+
     async fn set_log_filter(
         &self,
         req: Request<SetLogFilterRequest>,
@@ -1497,6 +1628,7 @@ trait StreamingCommandOptions<Req>: OneshotCommandOptions {
 fn server_shutdown_signal(
     command_receiver: UnboundedReceiver<()>,
     mut shutdown_receiver: UnboundedReceiver<()>,
+    daemon_state: Arc<DaemonState>,
 ) -> anyhow::Result<impl Future<Output = ()>> {
     let mut duration = DEFAULT_INACTIVITY_TIMEOUT;
     if buck2_env!(
@@ -1508,29 +1640,45 @@ fn server_shutdown_signal(
     }
 
     Ok(async move {
-        let timeout = inactivity_timeout(command_receiver, duration);
+        let timeout = inactivity_timeout(command_receiver, duration, &daemon_state);
         let shutdown = shutdown_receiver.next();
 
         futures::pin_mut!(shutdown);
         futures::pin_mut!(timeout);
 
-        futures::future::select(timeout, shutdown).await;
+        match futures::future::select(timeout, shutdown).await {
+            // `kill` saves the DICE snapshot before it requests the shutdown, idle daemons don't go
+            // through it.
+            futures::future::Either::Left(_) => daemon_state.save_dice_snapshot().await,
+            futures::future::Either::Right(_) => {}
+        }
     })
 }
 
-async fn inactivity_timeout(mut command_receiver: UnboundedReceiver<()>, duration: Duration) {
+async fn inactivity_timeout(
+    mut command_receiver: UnboundedReceiver<()>,
+    duration: Duration,
+    daemon_state: &DaemonState,
+) {
     // this restarts the timer everytime there is a new command
+    let mut deadline = tokio::time::Instant::now() + duration;
+    // Whether commands ran since the DICE snapshot was last saved.
+    let mut snapshot_outdated = false;
     loop {
-        let command = command_receiver.next();
-        let timer = tokio::time::sleep(duration);
-
-        futures::pin_mut!(command);
-        futures::pin_mut!(timer);
-
-        match futures::future::select(command, timer).await {
-            futures::future::Either::Left(_) => continue,
-            futures::future::Either::Right(_) => break,
-        };
+        tokio::select! {
+            _ = command_receiver.next() => {
+                deadline = tokio::time::Instant::now() + duration;
+                snapshot_outdated = true;
+            }
+            _ = tokio::time::sleep_until(deadline) => break,
+            _ = tokio::time::sleep(DICE_SNAPSHOT_IDLE_TIMEOUT), if snapshot_outdated => {
+                // Try again after another idle period if commands are still running.
+                if active_commands().is_empty() {
+                    daemon_state.save_dice_snapshot().await;
+                    snapshot_outdated = false;
+                }
+            }
+        }
     }
 }
 
@@ -77,6 +77,8 @@ use tokio::sync::Mutex;
 use crate::active_commands::ActiveCommandDropGuard;
 use crate::ctx::BaseServerCommandContext;
 use crate::daemon::check_working_dir;
+use crate::daemon::dice_eviction::spawn_dice_eviction;
+use crate::daemon::dice_snapshot::DiceSnapshotLocation;
 use crate::daemon::disk_state::delete_unknown_disk_state;
 use crate::daemon::disk_state::maybe_initialize_materializer_sqlite_db;
 use crate::daemon::disk_state::DiskStateOptions;
@@ -116,6 +118,9 @@ pub struct DaemonStateData {
     /// Synced every time we run a command.
     pub(crate) file_watcher: Arc<dyn FileWatcher>,
 
+    /// Where to save the DICE graph on shutdown, if `buck2.dice_snapshot` is enabled.
+    pub(crate) dice_snapshot: Option<DiceSnapshotLocation>,
+
     /// Settled every time we run a command.
     pub io: Arc<dyn IoProvider>,
 
@@ -212,10 +217,18 @@ impl DaemonState {
         rt: Handle,
         materializations: MaterializationMethod,
         working_directory: Option<WorkingDirectory>,
+        daemon_version: String,
     ) -> Self {
-        let data = Self::init_data(fb, paths.clone(), init_ctx, rt.clone(), materializations)
-            .await
-            .context("Error initializing DaemonStateData");
+        let data = Self::init_data(
+            fb,
+            paths.clone(),
+            init_ctx,
+            rt.clone(),
+            materializations,
+            daemon_version,
+        )
+        .await
+        .context("Error initializing DaemonStateData");
 
         if let Ok(data) = &data {
             crate::daemon::panic::initialize(data.dupe());
@@ -242,6 +255,7 @@ impl DaemonState {
         init_ctx: BuckdServerInitPreferences,
         rt: Handle,
         materializations: MaterializationMethod,
+        daemon_version: String,
     ) -> anyhow::Result<Arc<DaemonStateData>> {
         let daemon_state_data_rt = rt.clone();
         rt.spawn(async move {
@@ -524,6 +538,44 @@ impl DaemonState {
                 )
             })?;
 
+            let dice_snapshot = if root_config
+                .parse(BuckconfigKeyRef {
+                    section: "buck2",
+                    property: "dice_snapshot",
+                })?
+                .unwrap_or(false)
+            {
+                let location =
+                    DiceSnapshotLocation::new(paths.dice_snapshot_path(), daemon_version);
+                match location
+                    .restore(&dice, &*file_watcher, &*blocking_executor)
+                    .await
+                {
+                    Ok(restored) => tracing::info!("Restored {} DICE nodes", restored),
+                    Err(e) => tracing::warn!("Error restoring DICE snapshot: {:#}", e),
+                }
+                Some(location)
+            } else {
+                None
+            };
+
+            if let Some(memory_budget) = root_config.parse(BuckconfigKeyRef {
+                section: "buck2",
+                property: "dice_memory_budget_bytes",
+            })? {
+                spawn_dice_eviction(&dice, memory_budget);
+            }
+
+            if root_config
+                .parse(BuckconfigKeyRef {
+                    section: "buck2",
+                    property: "dice_invalidation_tracing",
+                })?
+                .unwrap_or(false)
+            {
+                dice.set_invalidation_tracing(true);
+            }
+
             let hash_all_commands = root_config
                 .parse::<RolloutPercentage>(BuckconfigKeyRef {
                     section: "buck2",
@@ -597,6 +649,7 @@ impl DaemonState {
             Ok(Arc::new(DaemonStateData {
                 dice_manager: ConcurrencyHandler::new(dice),
                 file_watcher,
+                dice_snapshot,
                 io,
                 re_client_manager,
                 blocking_executor,
@@ -749,6 +802,27 @@ impl DaemonState {
         Ok(self.data.dupe()?)
     }
 
+    /// Saves the DICE graph if `buck2.dice_snapshot` is enabled. Called on every shutdown path,
+    /// errors are only logged.
+    pub(crate) async fn save_dice_snapshot(&self) {
+        let Ok(data) = self.data() else {
+            return;
+        };
+        let Some(location) = &data.dice_snapshot else {
+            return;
+        };
+        if let Err(e) = location
+            .save(
+                data.dice_manager.unsafe_dice(),
+                &*data.file_watcher,
+                &*data.blocking_executor,
+            )
+            .await
+        {
+            tracing::warn!("Error saving DICE snapshot: {:#}", e);
+        }
+    }
+
     pub fn validate_cwd(&self) -> anyhow::Result<()> {
         if let Some(working_directory) = &self.working_directory {
             let res = working_directory.is_stale().and_then(|stale| {
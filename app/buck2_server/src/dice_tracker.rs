@@ -15,6 +15,7 @@ use buck2_data::*;
 use buck2_events::dispatch::with_dispatcher_async;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_util::threads::thread_spawn;
+use dice::ConcurrencyClass;
 use dice::DiceEvent;
 use dice::DiceEventListener;
 use dupe::Dupe;
@@ -61,6 +62,7 @@ impl BuckDiceTracker {
     async fn run_task(events: EventDispatcher, mut receiver: UnboundedReceiver<DiceEvent>) {
         let mut needs_update = false;
         let mut states = HashMap::new();
+        let mut class_states = HashMap::new();
         let mut interval = tokio::time::interval(DICE_SNAPSHOT_INTERVAL);
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         // This will loop until the sender side of the channel is dropped.
@@ -87,6 +89,24 @@ impl BuckDiceTracker {
                         Some(DiceEvent::ComputeFinished{key_type}) => {
                             states.entry(key_type).or_insert_with(DiceKeyState::default).compute_finished += 1;
                         }
+                        Some(DiceEvent::RecomputeUnchanged{key_type}) => {
+                            states.entry(key_type).or_insert_with(DiceKeyState::default).early_cutoffs += 1;
+                        }
+                        Some(DiceEvent::RecomputeChanged{key_type}) => {
+                            states.entry(key_type).or_insert_with(DiceKeyState::default).recompute_changes += 1;
+                        }
+                        Some(DiceEvent::ConcurrencyQueued{class}) => {
+                            Self::class_state(&mut class_states, class).queued += 1;
+                        }
+                        Some(DiceEvent::ConcurrencyDequeued{class}) => {
+                            Self::class_state(&mut class_states, class).dequeued += 1;
+                        }
+                        Some(DiceEvent::ConcurrencyStarted{class}) => {
+                            Self::class_state(&mut class_states, class).started += 1;
+                        }
+                        Some(DiceEvent::ConcurrencyFinished{class}) => {
+                            Self::class_state(&mut class_states, class).finished += 1;
+                        }
                         None => {
                             // This indicates that the sender side has been dropped and we can exit.
                             break;
@@ -101,6 +121,10 @@ impl BuckDiceTracker {
                                 .iter()
                                 .map(|(k, v)| ((*k).to_owned(), v.clone()))
                                 .collect(),
+                            concurrency_class_states: class_states
+                                .iter()
+                                .map(|(k, v)| ((*k).to_owned(), v.clone()))
+                                .collect(),
                         });
                     }
                 }
@@ -109,6 +133,21 @@ impl BuckDiceTracker {
     }
 }
 
+impl BuckDiceTracker {
+    fn class_state(
+        class_states: &mut HashMap<&'static str, DiceConcurrencyClassState>,
+        class: ConcurrencyClass,
+    ) -> &mut DiceConcurrencyClassState {
+        class_states
+            .entry(class.name)
+            .or_insert_with(|| DiceConcurrencyClassState {
+                limit: class.limit as u64,
+                priority: class.priority,
+                ..Default::default()
+            })
+    }
+}
+
 impl DiceEventListener for BuckDiceTracker {
     fn event(&self, event: DiceEvent) {
         let _ = self.event_forwarder.unbounded_send(event);
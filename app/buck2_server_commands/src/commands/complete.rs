@@ -10,6 +10,7 @@
 use buck2_cli_proto::new_generic::CompleteRequest;
 use buck2_cli_proto::new_generic::CompleteResponse;
 use buck2_common::pattern::parse_from_cli::parse_patterns_from_cli_args;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_node::load_patterns::load_patterns;
 use buck2_node::load_patterns::MissingTargetBehavior;
@@ -18,6 +19,7 @@ use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
+use dice::DiceComputations;
 use dice::DiceTransaction;
 
 pub(crate) async fn complete_command(
@@ -50,28 +52,13 @@ impl ServerCommandTemplate for CompleteServerCommand {
         _partial_result_dispatcher: PartialResultDispatcher<Self::PartialResult>,
         mut dice: DiceTransaction,
     ) -> anyhow::Result<Self::Response> {
-        let cwd = server_ctx.working_dir();
-        let parsed_target_patterns = parse_patterns_from_cli_args::<TargetPatternExtra>(
+        let completions = complete_target(
             &mut dice,
-            &[self.req.partial_target.clone()],
-            cwd,
+            server_ctx.working_dir(),
+            &self.req.partial_target,
         )
         .await?;
-
-        let results = &load_patterns(
-            &mut dice,
-            parsed_target_patterns,
-            MissingTargetBehavior::Fail,
-        )
-        .await?;
-
-        let mut output: Vec<String> = vec![];
-        for node in results.iter_loaded_targets() {
-            output.push(format!("{}", node?.label()));
-        }
-        Ok(CompleteResponse {
-            completions: output,
-        })
+        Ok(CompleteResponse { completions })
     }
 
     fn is_success(&self, _response: &Self::Response) -> bool {
@@ -82,3 +69,22 @@ impl ServerCommandTemplate for CompleteServerCommand {
         Some("complete".to_owned())
     }
 }
+
+/// Labels of the targets matched by the `partial_target` pattern, typically `cell//package:`.
+pub(crate) async fn complete_target(
+    dice: &mut DiceComputations<'_>,
+    cwd: &ProjectRelativePath,
+    partial_target: &str,
+) -> anyhow::Result<Vec<String>> {
+    let parsed_target_patterns =
+        parse_patterns_from_cli_args::<TargetPatternExtra>(dice, &[partial_target.to_owned()], cwd)
+            .await?;
+
+    let results = &load_patterns(dice, parsed_target_patterns, MissingTargetBehavior::Fail).await?;
+
+    let mut output: Vec<String> = vec![];
+    for node in results.iter_loaded_targets() {
+        output.push(format!("{}", node?.label()));
+    }
+    Ok(output)
+}
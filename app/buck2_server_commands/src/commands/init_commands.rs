@@ -21,6 +21,7 @@ use buck2_server_ctx::other_server_commands::OtherServerCommands;
 use buck2_server_ctx::other_server_commands::OTHER_SERVER_COMMANDS;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::streaming_request_handler::StreamingRequestHandler;
 
 use crate::commands::build::build_command;
 use crate::commands::complete::complete_command;
@@ -31,6 +32,7 @@ use crate::commands::explain::explain_command;
 use crate::commands::install::install_command;
 use crate::commands::query::aquery::aquery_command;
 use crate::commands::query::cquery::cquery_command;
+use crate::commands::query::shell::query_shell_command;
 use crate::commands::query::uquery::uquery_command;
 use crate::commands::targets::targets_command;
 use crate::commands::targets_show_outputs::targets_show_outputs_command;
@@ -79,6 +81,14 @@ impl OtherServerCommands for OtherServerCommandsInstance {
     ) -> anyhow::Result<buck2_cli_proto::AqueryResponse> {
         aquery_command(ctx, partial_result_dispatcher, req).await
     }
+    async fn query_shell(
+        &self,
+        ctx: &dyn ServerCommandContextTrait,
+        partial_result_dispatcher: PartialResultDispatcher<buck2_cli_proto::QueryShellMessage>,
+        req: StreamingRequestHandler<buck2_cli_proto::QueryShellRequest>,
+    ) -> anyhow::Result<buck2_cli_proto::QueryShellResponse> {
+        query_shell_command(ctx, partial_result_dispatcher, req).await
+    }
     async fn targets(
         &self,
         ctx: &dyn ServerCommandContextTrait,
@@ -9,8 +9,12 @@
 
 pub mod aquery;
 pub mod cquery;
+pub(crate) mod explain;
+pub(crate) mod output_actions;
+pub(crate) mod platform_diff;
 pub mod printer;
 pub(crate) mod query_target_ext;
+pub(crate) mod shell;
 pub(crate) mod starlark_profile;
 pub mod uquery;
 
@@ -20,4 +24,6 @@ enum QueryCommandError {
         "query result was a set of files and one or more --output-attribute was requested, but files have not attributes"
     )]
     FileSetHasNoAttributes,
+    #[error("query result was a set of files, which cannot be printed as a {0} graph")]
+    FileSetHasNoGraph(&'static str),
 }
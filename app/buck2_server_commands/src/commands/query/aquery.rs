@@ -16,6 +16,7 @@ use buck2_common::dice::cells::HasCellResolver;
 use buck2_error::BuckErrorContext;
 use buck2_query::query::environment::AttrFmtOptions;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::global_cfg_options::global_cfg_options_from_client_context;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
@@ -23,6 +24,9 @@ use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
 use dice::DiceTransaction;
 
+use crate::commands::query::explain::query_explain;
+use crate::commands::query::explain::report_query_explain;
+use crate::commands::query::output_actions::print_actions;
 use crate::commands::query::printer::QueryResultPrinter;
 use crate::commands::query::printer::ShouldPrintProviders;
 use crate::commands::query::query_target_ext::QueryCommandTarget;
@@ -84,6 +88,7 @@ impl ServerCommandTemplate for AqueryServerCommand {
             partial_result_dispatcher.as_writer(),
             ctx,
             &self.req,
+            &QueryVariables::default(),
         )
         .await
     }
@@ -93,11 +98,12 @@ impl ServerCommandTemplate for AqueryServerCommand {
     }
 }
 
-async fn aquery(
+pub(crate) async fn aquery(
     server_ctx: &dyn ServerCommandContextTrait,
     mut stdout: impl Write,
     mut ctx: DiceTransaction,
     request: &buck2_cli_proto::AqueryRequest,
+    variables: &QueryVariables<ActionQueryNode>,
 ) -> anyhow::Result<buck2_cli_proto::AqueryResponse> {
     let cell_resolver = ctx.get_cell_resolver().await?;
 
@@ -107,6 +113,41 @@ async fn aquery(
         request.unstable_output_format,
     )?;
 
+    let query_result = eval_aquery_request(server_ctx, &mut ctx, request, variables).await?;
+
+    if request.output_actions {
+        print_actions(
+            &mut stdout,
+            &mut ctx,
+            query_result,
+            request.unstable_output_format,
+        )
+        .await?;
+        return Ok(buck2_cli_proto::AqueryResponse {});
+    }
+
+    match query_result {
+        QueryEvaluationResult::Single(targets) => {
+            output_configuration
+                .print_single_output(&mut stdout, targets, false, ShouldPrintProviders::No)
+                .await?
+        }
+        QueryEvaluationResult::Multiple(results) => {
+            output_configuration
+                .print_multi_output(&mut stdout, results, false, ShouldPrintProviders::No)
+                .await?
+        }
+    };
+    Ok(buck2_cli_proto::AqueryResponse {})
+}
+
+/// Evaluates the query of `request`, without printing it.
+pub(crate) async fn eval_aquery_request(
+    server_ctx: &dyn ServerCommandContextTrait,
+    ctx: &mut DiceTransaction,
+    request: &buck2_cli_proto::AqueryRequest,
+    variables: &QueryVariables<ActionQueryNode>,
+) -> anyhow::Result<QueryEvaluationResult<ActionQueryNode>> {
     let buck2_cli_proto::AqueryRequest {
         query, query_args, ..
     } = request;
@@ -117,32 +158,24 @@ async fn aquery(
             .as_ref()
             .internal_error("target_cfg must be set")?,
         server_ctx,
-        &mut ctx,
+        ctx,
     )
     .await?;
 
+    let explain = query_explain(request.explain, ctx);
     let query_result = QUERY_FRONTEND
         .get()?
         .eval_aquery(
-            &mut ctx,
+            ctx,
             server_ctx.working_dir(),
             query,
             query_args,
+            variables,
             global_cfg_options,
+            explain.as_ref(),
         )
         .await?;
+    report_query_explain(explain.as_ref(), query)?;
 
-    match query_result {
-        QueryEvaluationResult::Single(targets) => {
-            output_configuration
-                .print_single_output(&mut stdout, targets, false, ShouldPrintProviders::No)
-                .await?
-        }
-        QueryEvaluationResult::Multiple(results) => {
-            output_configuration
-                .print_multi_output(&mut stdout, results, false, ShouldPrintProviders::No)
-                .await?
-        }
-    };
-    Ok(buck2_cli_proto::AqueryResponse {})
+    Ok(query_result)
 }
@@ -28,6 +28,7 @@ use buck2_node::attrs::serialize::AttrSerializeWithContext;
 use buck2_node::nodes::configured::ConfiguredTargetNode;
 use buck2_query::query::environment::AttrFmtOptions;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::global_cfg_options::global_cfg_options_from_client_context;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
@@ -38,6 +39,9 @@ use dice::DiceTransaction;
 use dice::LinearRecomputeDiceComputations;
 use dupe::Dupe;
 
+use crate::commands::query::explain::query_explain;
+use crate::commands::query::explain::report_query_explain;
+use crate::commands::query::platform_diff::cquery_platform_diff;
 use crate::commands::query::printer::ProviderLookUp;
 use crate::commands::query::printer::QueryResultPrinter;
 use crate::commands::query::printer::ShouldPrintProviders;
@@ -128,6 +132,7 @@ impl ServerCommandTemplate for CqueryServerCommand {
             partial_result_dispatcher.as_writer(),
             ctx,
             &self.req,
+            &QueryVariables::default(),
         )
         .await
     }
@@ -137,12 +142,18 @@ impl ServerCommandTemplate for CqueryServerCommand {
     }
 }
 
-async fn cquery(
+pub(crate) async fn cquery(
     server_ctx: &dyn ServerCommandContextTrait,
     mut stdout: impl Write,
     mut ctx: DiceTransaction,
     request: &CqueryRequest,
+    variables: &QueryVariables<ConfiguredTargetNode>,
 ) -> anyhow::Result<CqueryResponse> {
+    if !request.diff_target_platforms.is_empty() {
+        cquery_platform_diff(server_ctx, stdout, &mut ctx, request).await?;
+        return Ok(CqueryResponse {});
+    }
+
     let cell_resolver = ctx.get_cell_resolver().await?;
     let output_configuration = QueryResultPrinter::from_request_options(
         &cell_resolver,
@@ -150,12 +161,64 @@ async fn cquery(
         request.unstable_output_format,
     )?;
 
+    let client_ctx = request
+        .context
+        .as_ref()
+        .internal_error("No client context")?;
+
+    let target_call_stacks = client_ctx.target_call_stacks;
+    let show_providers = request.show_providers;
+
+    let query_result = eval_cquery_request(server_ctx, &mut ctx, request, variables).await?;
+
+    ctx.with_linear_recompute(|ctx| async move {
+        let should_print_providers = if show_providers {
+            ShouldPrintProviders::Yes(&ctx as &dyn ProviderLookUp<ConfiguredTargetNode>)
+        } else {
+            ShouldPrintProviders::No
+        };
+
+        match query_result {
+            QueryEvaluationResult::Single(targets) => {
+                output_configuration
+                    .print_single_output(
+                        &mut stdout,
+                        targets,
+                        target_call_stacks,
+                        should_print_providers,
+                    )
+                    .await?
+            }
+            QueryEvaluationResult::Multiple(results) => {
+                output_configuration
+                    .print_multi_output(
+                        &mut stdout,
+                        results,
+                        target_call_stacks,
+                        should_print_providers,
+                    )
+                    .await?
+            }
+        };
+        anyhow::Ok(())
+    })
+    .await?;
+
+    Ok(CqueryResponse {})
+}
+
+/// Evaluates the query of `request`, writing a profile if one was requested, without printing
+/// the result.
+pub(crate) async fn eval_cquery_request(
+    server_ctx: &dyn ServerCommandContextTrait,
+    ctx: &mut DiceTransaction,
+    request: &CqueryRequest,
+    variables: &QueryVariables<ConfiguredTargetNode>,
+) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
     let CqueryRequest {
         query,
         query_args,
         target_universe,
-        context,
-        show_providers,
         target_cfg,
         ..
     } = request;
@@ -165,16 +228,13 @@ async fn cquery(
     } else {
         Some(target_universe)
     };
-    let client_ctx = context.as_ref().internal_error("No client context")?;
-
-    let target_call_stacks = client_ctx.target_call_stacks;
 
     let global_cfg_options = global_cfg_options_from_client_context(
         target_cfg
             .as_ref()
             .internal_error("target_cfg must be set")?,
         server_ctx,
-        &mut ctx,
+        ctx,
     )
     .await?;
 
@@ -183,18 +243,22 @@ async fn cquery(
         .map(|i| buck2_cli_proto::ProfileMode::from_i32(i).internal_error("Invalid profile mode"))
         .transpose()?;
 
+    let explain = query_explain(request.explain, ctx);
     let (query_result, universes) = QUERY_FRONTEND
         .get()?
         .eval_cquery(
-            &mut ctx,
+            ctx,
             server_ctx.working_dir(),
             query,
             query_args,
+            variables,
             global_cfg_options,
             target_universe,
             profile_mode.is_some(),
+            explain.as_ref(),
         )
         .await?;
+    report_query_explain(explain.as_ref(), query)?;
 
     if let Some(profile_mode) = profile_mode {
         let universes = universes.internal_error("No universes")?;
@@ -204,7 +268,7 @@ async fn cquery(
         }
 
         write_query_profile_for_targets(
-            &mut ctx,
+            ctx,
             profile_mode,
             request.profile_output.as_deref(),
             universes.iter().flat_map(|u| {
@@ -221,40 +285,7 @@ async fn cquery(
         }
     }
 
-    ctx.with_linear_recompute(|ctx| async move {
-        let should_print_providers = if *show_providers {
-            ShouldPrintProviders::Yes(&ctx as &dyn ProviderLookUp<ConfiguredTargetNode>)
-        } else {
-            ShouldPrintProviders::No
-        };
-
-        match query_result {
-            QueryEvaluationResult::Single(targets) => {
-                output_configuration
-                    .print_single_output(
-                        &mut stdout,
-                        targets,
-                        target_call_stacks,
-                        should_print_providers,
-                    )
-                    .await?
-            }
-            QueryEvaluationResult::Multiple(results) => {
-                output_configuration
-                    .print_multi_output(
-                        &mut stdout,
-                        results,
-                        target_call_stacks,
-                        should_print_providers,
-                    )
-                    .await?
-            }
-        };
-        anyhow::Ok(())
-    })
-    .await?;
-
-    Ok(CqueryResponse {})
+    Ok(query_result)
 }
 
 #[async_trait]
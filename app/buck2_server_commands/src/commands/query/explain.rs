@@ -0,0 +1,41 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `--explain` for query commands: a per-expression breakdown of where evaluation time went.
+
+use buck2_events::dispatch::console_message;
+use buck2_node::compute_counters::HasComputeCounters;
+use buck2_query::query::syntax::simple::eval::explain::ComputeCounters;
+use buck2_query::query::syntax::simple::eval::explain::QueryExplain;
+use dice::DiceComputations;
+
+/// Computations are attributed using the counters of the transaction of `ctx`, or not at all if
+/// it has none.
+pub(crate) fn query_explain(explain: bool, ctx: &DiceComputations) -> Option<QueryExplain> {
+    explain.then(|| {
+        let counters = ctx.compute_counters();
+        QueryExplain::new(move || {
+            counters
+                .as_ref()
+                .map_or(ComputeCounters::default(), |counters| counters.get())
+        })
+    })
+}
+
+/// Send the annotated expression tree to the client's console (stderr), so it doesn't mix
+/// with the query output.
+pub(crate) fn report_query_explain(
+    explain: Option<&QueryExplain>,
+    query: &str,
+) -> anyhow::Result<()> {
+    if let Some(explain) = explain {
+        console_message(explain.render(query)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,274 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `aquery --output-actions`: print the actions of a query result as JSON, with everything
+//! needed to reproduce them.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use buck2_build_api::actions::query::ActionQueryNode;
+use buck2_build_api::actions::query::ActionQueryNodeData;
+use buck2_cli_proto::QueryOutputFormat;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_directory::directory::entry::DirectoryEntry;
+use buck2_directory::directory::fingerprinted_directory::FingerprintedDirectory;
+use buck2_execute::digest_config::DigestConfig;
+use buck2_execute::digest_config::HasDigestConfig;
+use buck2_execute::directory::insert_entry;
+use buck2_execute::directory::ActionDirectoryBuilder;
+use buck2_execute::directory::ActionDirectoryEntry;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_query::query::environment::QueryTarget;
+use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryEvaluationValue;
+use dice::DiceComputations;
+use futures::FutureExt;
+use serde::Serialize;
+
+#[derive(Debug, buck2_error::Error)]
+enum OutputActionsError {
+    #[error("`--output-actions` cannot be used with a multi-query")]
+    MultiQuery,
+    #[error("`--output-actions` prints JSON and cannot be combined with another output format")]
+    UnsupportedOutputFormat,
+    #[error("`--output-actions` requires the query to return actions, but it returned files")]
+    FileSet,
+}
+
+#[derive(Serialize)]
+struct CommandLineJson {
+    exe: Vec<String>,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct ActionJson {
+    kind: String,
+    category: String,
+    identifier: Option<String>,
+    owner: String,
+    inputs: Vec<String>,
+    /// The digest of each input, or `unknown` for build outputs which are not materialized,
+    /// source directories, and symlinks.
+    input_digests: BTreeMap<String, String>,
+    /// The digest of the directory of all inputs, as the action sees it, or `unknown` unless the
+    /// contents of every input are known.
+    input_directory_digest: String,
+    outputs: Vec<String>,
+    /// Only set for actions which run a command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cmd: Option<CommandLineJson>,
+}
+
+const UNKNOWN_DIGEST: &str = "unknown";
+
+type InputEntry = (
+    ProjectRelativePathBuf,
+    Option<ActionDirectoryEntry<ActionDirectoryBuilder>>,
+);
+
+fn input_digests(inputs: &[InputEntry], digest_config: DigestConfig) -> BTreeMap<String, String> {
+    inputs
+        .iter()
+        .map(|(path, entry)| {
+            let digest = match entry {
+                Some(DirectoryEntry::Leaf(ActionDirectoryMember::File(meta))) => {
+                    meta.digest.to_string()
+                }
+                Some(DirectoryEntry::Dir(dir)) => dir
+                    .clone()
+                    .fingerprint(digest_config.as_directory_serializer())
+                    .fingerprint()
+                    .to_string(),
+                // Symlinks have no digest of their own.
+                Some(DirectoryEntry::Leaf(..)) | None => UNKNOWN_DIGEST.to_owned(),
+            };
+            (path.to_string(), digest)
+        })
+        .collect()
+}
+
+/// The digest of the directory an action sees its inputs in, if the contents of all of them are
+/// known.
+fn input_directory_digest(
+    inputs: Vec<InputEntry>,
+    digest_config: DigestConfig,
+) -> anyhow::Result<Option<String>> {
+    let mut builder = ActionDirectoryBuilder::empty();
+    for (path, entry) in inputs {
+        match entry {
+            Some(entry) => insert_entry(&mut builder, &path, entry)?,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(
+        builder
+            .fingerprint(digest_config.as_directory_serializer())
+            .fingerprint()
+            .to_string(),
+    ))
+}
+
+pub(crate) async fn print_actions(
+    mut stdout: impl Write,
+    ctx: &mut DiceComputations<'_>,
+    result: QueryEvaluationResult<ActionQueryNode>,
+    output_format: i32,
+) -> anyhow::Result<()> {
+    let output_format = QueryOutputFormat::from_i32(output_format)
+        .expect("cli should send a valid output_format enum");
+    if !matches!(
+        output_format,
+        QueryOutputFormat::Default | QueryOutputFormat::Json
+    ) {
+        return Err(OutputActionsError::UnsupportedOutputFormat.into());
+    }
+    let nodes = match result {
+        QueryEvaluationResult::Single(QueryEvaluationValue::TargetSet(nodes)) => nodes,
+        QueryEvaluationResult::Single(QueryEvaluationValue::TargetPaths(paths)) => {
+            paths.into_targets()
+        }
+        QueryEvaluationResult::Single(QueryEvaluationValue::FileSet(_)) => {
+            return Err(OutputActionsError::FileSet.into());
+        }
+        QueryEvaluationResult::Multiple(_) => return Err(OutputActionsError::MultiQuery.into()),
+    };
+
+    let actions: BTreeMap<String, ActionJson> = ctx
+        .try_compute_join(nodes.into_iter(), |ctx, node| {
+            async move {
+                let data = match node.data() {
+                    ActionQueryNodeData::Action(data) => data,
+                    // Target literals evaluate to analysis nodes, which aren't actions.
+                    ActionQueryNodeData::Analysis(..) => return anyhow::Ok(None),
+                };
+                let action = data.action();
+                let cmd = data.command_line()?.map(|cmd| CommandLineJson {
+                    exe: cmd.exe,
+                    args: cmd.args,
+                    env: cmd.env.into_iter().collect(),
+                });
+                let digest_config = ctx.global_data().get_digest_config();
+                let inputs = data.input_entries(ctx).await?;
+                Ok(Some((
+                    node.key().to_string(),
+                    ActionJson {
+                        kind: node.rule_type().into_owned(),
+                        category: action.category().as_str().to_owned(),
+                        identifier: action.identifier().map(|i| i.to_owned()),
+                        owner: action.owner().to_string(),
+                        inputs: inputs.iter().map(|(p, _)| p.to_string()).collect(),
+                        input_digests: input_digests(&inputs, digest_config),
+                        input_directory_digest: input_directory_digest(inputs, digest_config)?
+                            .unwrap_or_else(|| UNKNOWN_DIGEST.to_owned()),
+                        outputs: data.output_paths().iter().map(|p| p.to_string()).collect(),
+                        cmd,
+                    },
+                )))
+            }
+            .boxed()
+        })
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    serde_json::to_writer_pretty(&mut stdout, &actions)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_common::file_ops::FileMetadata;
+    use buck2_common::file_ops::TrackedFileDigest;
+
+    use super::*;
+
+    fn file(content: &str) -> ActionDirectoryEntry<ActionDirectoryBuilder> {
+        DirectoryEntry::Leaf(ActionDirectoryMember::File(FileMetadata {
+            digest: TrackedFileDigest::from_content(
+                content.as_bytes(),
+                DigestConfig::testing_default().cas_digest_config(),
+            ),
+            is_executable: false,
+        }))
+    }
+
+    fn path(p: &str) -> ProjectRelativePathBuf {
+        ProjectRelativePathBuf::testing_new(p)
+    }
+
+    #[test]
+    fn test_input_digests() {
+        let digest_config = DigestConfig::testing_default();
+        let mut dir = ActionDirectoryBuilder::empty();
+        insert_entry(&mut dir, &path("c.txt"), file("c")).unwrap();
+        let inputs = vec![
+            (path("a.txt"), Some(file("a"))),
+            (path("b"), Some(DirectoryEntry::Dir(dir.clone()))),
+            (path("buck-out/v2/gen/x"), None),
+        ];
+        let digests = input_digests(&inputs, digest_config);
+        assert_eq!(
+            digests.keys().map(|p| p.as_str()).collect::<Vec<_>>(),
+            vec!["a.txt", "b", "buck-out/v2/gen/x"]
+        );
+        let Some(DirectoryEntry::Leaf(ActionDirectoryMember::File(a))) = &inputs[0].1 else {
+            unreachable!()
+        };
+        assert_eq!(digests["a.txt"], a.digest.to_string());
+        assert_eq!(
+            digests["b"],
+            dir.fingerprint(digest_config.as_directory_serializer())
+                .fingerprint()
+                .to_string()
+        );
+        assert_eq!(digests["buck-out/v2/gen/x"], UNKNOWN_DIGEST);
+    }
+
+    #[test]
+    fn test_input_directory_digest() -> anyhow::Result<()> {
+        let digest_config = DigestConfig::testing_default();
+        let known = vec![
+            (path("a/b.txt"), Some(file("b"))),
+            (path("c.txt"), Some(file("c"))),
+        ];
+
+        let mut expected = ActionDirectoryBuilder::empty();
+        insert_entry(&mut expected, &path("a/b.txt"), file("b"))?;
+        insert_entry(&mut expected, &path("c.txt"), file("c"))?;
+        let expected = expected
+            .fingerprint(digest_config.as_directory_serializer())
+            .fingerprint()
+            .to_string();
+        assert_eq!(
+            input_directory_digest(known.clone(), digest_config)?,
+            Some(expected.clone())
+        );
+
+        // Any other content gives a different digest.
+        let changed = vec![
+            (path("a/b.txt"), Some(file("b"))),
+            (path("c.txt"), Some(file("changed"))),
+        ];
+        assert_ne!(
+            input_directory_digest(changed, digest_config)?,
+            Some(expected)
+        );
+
+        // An input which is not materialized makes the whole directory unknown.
+        let mut unknown = known;
+        unknown.push((path("buck-out/v2/gen/x"), None));
+        assert_eq!(input_directory_digest(unknown, digest_config)?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `cquery --diff-target-platforms`: evaluate the same query under two target platforms and
+//! report the targets and dependency edges found under only one of them. Configured targets are
+//! matched by their unconfigured label.
+
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use buck2_build_api::query::oneshot::QUERY_FRONTEND;
+use buck2_cli_proto::CqueryRequest;
+use buck2_cli_proto::QueryOutputFormat;
+use buck2_cli_proto::TargetCfg;
+use buck2_core::target::label::label::TargetLabel;
+use buck2_error::internal_error;
+use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryEvaluationValue;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::global_cfg_options::global_cfg_options_from_client_context;
+use dice::DiceComputations;
+use dupe::Dupe;
+use serde::Serialize;
+
+#[derive(Debug, buck2_error::Error)]
+enum PlatformDiffError {
+    #[error("`--diff-target-platforms` expects exactly two platforms, got {0}")]
+    WrongPlatformCount(usize),
+    #[error("`--diff-target-platforms` cannot be combined with `--target-platforms`")]
+    TargetPlatformSpecified,
+    #[error("`--diff-target-platforms` cannot be combined with `--explain`")]
+    ExplainSpecified,
+    #[error(
+        "`--diff-target-platforms` cannot be combined with `--profile-mode` or `--profile-output`"
+    )]
+    ProfileSpecified,
+    #[error("`--diff-target-platforms` cannot be used with a multi-query")]
+    MultiQuery,
+    #[error("`--diff-target-platforms` can only be printed as a list or as JSON")]
+    UnsupportedOutputFormat,
+    #[error(
+        "`--diff-target-platforms` cannot be combined with `--output-attribute` or `--output-all-attributes`"
+    )]
+    OutputAttributesSpecified,
+    #[error("`--diff-target-platforms` cannot be combined with `--show-providers`")]
+    ShowProvidersSpecified,
+    #[error(
+        "`--diff-target-platforms` requires the query to return targets, but it returned files"
+    )]
+    FileSet,
+}
+
+/// The targets and dependency edges of a query result, by unconfigured label.
+#[derive(Default)]
+struct PlatformGraph {
+    targets: BTreeSet<TargetLabel>,
+    edges: BTreeSet<(TargetLabel, TargetLabel)>,
+}
+
+#[derive(Serialize)]
+struct PlatformOnly<'a> {
+    platform: &'a str,
+    targets: Vec<String>,
+    edges: Vec<(String, String)>,
+}
+
+impl<'a> PlatformOnly<'a> {
+    fn new(platform: &'a str, this: &PlatformGraph, other: &PlatformGraph) -> Self {
+        Self {
+            platform,
+            targets: this
+                .targets
+                .difference(&other.targets)
+                .map(|t| t.to_string())
+                .collect(),
+            edges: this
+                .edges
+                .difference(&other.edges)
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect(),
+        }
+    }
+
+    fn write_text(&self, mut stdout: impl Write) -> anyhow::Result<()> {
+        writeln!(stdout, "Targets only in {}:", self.platform)?;
+        for target in &self.targets {
+            writeln!(stdout, "  {}", target)?;
+        }
+        writeln!(stdout, "Dependencies only in {}:", self.platform)?;
+        for (from, to) in &self.edges {
+            writeln!(stdout, "  {} -> {}", from, to)?;
+        }
+        Ok(())
+    }
+}
+
+async fn eval_for_platform(
+    server_ctx: &dyn ServerCommandContextTrait,
+    ctx: &mut DiceComputations<'_>,
+    request: &CqueryRequest,
+    platform: &str,
+) -> anyhow::Result<PlatformGraph> {
+    let target_cfg = TargetCfg {
+        target_platform: platform.to_owned(),
+        cli_modifiers: request
+            .target_cfg
+            .as_ref()
+            .map(|cfg| cfg.cli_modifiers.clone())
+            .unwrap_or_default(),
+    };
+    let global_cfg_options =
+        global_cfg_options_from_client_context(&target_cfg, server_ctx, ctx).await?;
+    let target_universe = if request.target_universe.is_empty() {
+        None
+    } else {
+        Some(&request.target_universe[..])
+    };
+
+    let (result, _) = QUERY_FRONTEND
+        .get()?
+        .eval_cquery(
+            ctx,
+            server_ctx.working_dir(),
+            &request.query,
+            &[],
+            &QueryVariables::default(),
+            global_cfg_options,
+            target_universe,
+            false,
+            None,
+        )
+        .await?;
+    let targets = match result {
+        QueryEvaluationResult::Single(QueryEvaluationValue::TargetSet(targets)) => targets,
+        QueryEvaluationResult::Single(QueryEvaluationValue::TargetPaths(paths)) => {
+            paths.into_targets()
+        }
+        QueryEvaluationResult::Single(QueryEvaluationValue::FileSet(_)) => {
+            return Err(PlatformDiffError::FileSet.into());
+        }
+        QueryEvaluationResult::Multiple(_) => {
+            return Err(internal_error!("Single query returned multiple results"));
+        }
+    };
+
+    let mut graph = PlatformGraph::default();
+    for target in targets.iter() {
+        let from = target.label().unconfigured();
+        graph.targets.insert(from.dupe());
+        for dep in target.deps() {
+            graph
+                .edges
+                .insert((from.dupe(), dep.label().unconfigured().dupe()));
+        }
+    }
+    Ok(graph)
+}
+
+pub(crate) async fn cquery_platform_diff(
+    server_ctx: &dyn ServerCommandContextTrait,
+    mut stdout: impl Write,
+    ctx: &mut DiceComputations<'_>,
+    request: &CqueryRequest,
+) -> anyhow::Result<()> {
+    let [a, b] = &request.diff_target_platforms[..] else {
+        return Err(
+            PlatformDiffError::WrongPlatformCount(request.diff_target_platforms.len()).into(),
+        );
+    };
+    if request
+        .target_cfg
+        .as_ref()
+        .is_some_and(|cfg| !cfg.target_platform.is_empty())
+    {
+        return Err(PlatformDiffError::TargetPlatformSpecified.into());
+    }
+    if request.explain {
+        return Err(PlatformDiffError::ExplainSpecified.into());
+    }
+    if request.profile_mode.is_some() || request.profile_output.is_some() {
+        return Err(PlatformDiffError::ProfileSpecified.into());
+    }
+    if !request.query_args.is_empty() {
+        return Err(PlatformDiffError::MultiQuery.into());
+    }
+    let output_format = QueryOutputFormat::from_i32(request.unstable_output_format)
+        .expect("cli should send a valid output_format enum");
+    if !matches!(
+        output_format,
+        QueryOutputFormat::Default | QueryOutputFormat::Json
+    ) {
+        return Err(PlatformDiffError::UnsupportedOutputFormat.into());
+    }
+    if !request.output_attributes.is_empty() {
+        return Err(PlatformDiffError::OutputAttributesSpecified.into());
+    }
+    if request.show_providers {
+        return Err(PlatformDiffError::ShowProvidersSpecified.into());
+    }
+
+    let graph_a = eval_for_platform(server_ctx, ctx, request, a).await?;
+    let graph_b = eval_for_platform(server_ctx, ctx, request, b).await?;
+    let only_a = PlatformOnly::new(a, &graph_a, &graph_b);
+    let only_b = PlatformOnly::new(b, &graph_b, &graph_a);
+
+    match output_format {
+        QueryOutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut stdout, &[only_a, only_b])?;
+            writeln!(stdout)?;
+        }
+        _ => {
+            only_a.write_text(&mut stdout)?;
+            only_b.write_text(&mut stdout)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_only() {
+        let t = |name: &str| TargetLabel::testing_parse(&format!("root//pkg:{}", name));
+        let graph = |targets: &[&str], edges: &[(&str, &str)]| PlatformGraph {
+            targets: targets.iter().map(|name| t(name)).collect(),
+            edges: edges.iter().map(|(from, to)| (t(from), t(to))).collect(),
+        };
+        // `a` depends on `b` on both platforms, and on `c` or `d` depending on the platform.
+        let linux = graph(&["a", "b", "c"], &[("a", "b"), ("a", "c")]);
+        let mac = graph(&["a", "b", "d"], &[("a", "b"), ("a", "d")]);
+
+        let only_linux = PlatformOnly::new("linux", &linux, &mac);
+        assert_eq!("linux", only_linux.platform);
+        assert_eq!(vec!["root//pkg:c"], only_linux.targets);
+        assert_eq!(
+            vec![("root//pkg:a".to_owned(), "root//pkg:c".to_owned())],
+            only_linux.edges
+        );
+
+        let only_mac = PlatformOnly::new("mac", &mac, &linux);
+        assert_eq!(vec!["root//pkg:d"], only_mac.targets);
+        assert_eq!(
+            vec![("root//pkg:a".to_owned(), "root//pkg:d".to_owned())],
+            only_mac.edges
+        );
+
+        // An edge between targets found on both platforms can still differ.
+        let without_edge = graph(&["a", "b", "c"], &[("a", "c")]);
+        let only = PlatformOnly::new("linux", &linux, &without_edge);
+        assert!(only.targets.is_empty());
+        assert_eq!(
+            vec![("root//pkg:a".to_owned(), "root//pkg:b".to_owned())],
+            only.edges
+        );
+    }
+}
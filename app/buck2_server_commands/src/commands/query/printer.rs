@@ -26,12 +26,16 @@ use buck2_query::query::environment::QueryTarget;
 use buck2_query::query::environment::QueryTargets;
 use buck2_query::query::syntax::simple::eval::file_set::FileSet;
 use buck2_query::query::syntax::simple::eval::multi_query::MultiQueryResult;
+use buck2_query::query::syntax::simple::eval::paths::TargetPaths;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationValue;
 use buck2_util::indent::indent;
 use dupe::Clone_;
 use dupe::Copy_;
 use dupe::Dupe_;
+use futures::FutureExt;
+use futures::StreamExt;
+use futures::TryStreamExt;
 use gazebo::variants::UnpackVariants;
 use indent_write::fmt::IndentWriter;
 use indent_write::io::IndentWriter as IoIndentWriter;
@@ -46,6 +50,12 @@ use crate::commands::query::QueryCommandError;
 use crate::dot::targets::DotTargetGraph;
 use crate::dot::Dot;
 use crate::dot::DotCompact;
+use crate::dot::GraphMl;
+use crate::dot::Mermaid;
+
+/// How many targets ahead of the one being written have their providers looked up in ndjson
+/// output.
+const NDJSON_CONCURRENT_LOOKUPS: usize = 64;
 
 #[derive(Copy_, Dupe_, Clone_, UnpackVariants)]
 pub enum ShouldPrintProviders<'a, T> {
@@ -133,13 +143,8 @@ impl<'a, T: QueryCommandTarget> Display for PrintableQueryTarget<'a, T> {
     }
 }
 
-impl<'a, T: QueryCommandTarget> Serialize for PrintableQueryTarget<'a, T> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut map = serializer.serialize_map(None)?;
-
+impl<'a, T: QueryCommandTarget> PrintableQueryTarget<'a, T> {
+    fn serialize_entries<M: SerializeMap>(&self, map: &mut M) -> Result<(), M::Error> {
         QueryTargets::for_all_attrs(self.value, |attr_name, attr_value| {
             if let Some(attr_regex) = self.attributes {
                 if attr_regex.is_match(attr_name) {
@@ -177,6 +182,32 @@ impl<'a, T: QueryCommandTarget> Serialize for PrintableQueryTarget<'a, T> {
             map.serialize_entry("buck.providers", providers)?;
         }
 
+        Ok(())
+    }
+}
+
+impl<'a, T: QueryCommandTarget> Serialize for PrintableQueryTarget<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        self.serialize_entries(&mut map)?;
+        map.end()
+    }
+}
+
+/// A target as a standalone JSON object, for output with one target per line.
+struct NdjsonQueryTarget<'a, 'b, T: QueryTarget>(&'b PrintableQueryTarget<'a, T>);
+
+impl<'a, 'b, T: QueryCommandTarget> Serialize for NdjsonQueryTarget<'a, 'b, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("buck.label", &self.0.label())?;
+        self.0.serialize_entries(&mut map)?;
         map.end()
     }
 }
@@ -194,6 +225,36 @@ impl<'a, T: QueryCommandTarget> Serialize for TargetSetJsonPrinter<'a, T> {
     }
 }
 
+/// Prints a single path as `{"path": [...]}`, and other paths as the targets on them along with
+/// their dependencies on the paths.
+struct TargetPathsJsonPrinter<'a, T: QueryTarget>(&'a TargetPaths<T>);
+
+impl<'a, T: QueryTarget> Serialize for TargetPathsJsonPrinter<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let paths = self.0;
+        let labels = || paths.targets().iter_names().map(|t| t.to_string());
+        let mut map = serializer.serialize_map(None)?;
+        if paths.is_single_path() {
+            map.serialize_entry("path", &labels().collect::<Vec<_>>())?;
+        } else {
+            map.serialize_entry("targets", &labels().collect::<Vec<_>>())?;
+            let deps: BTreeMap<_, Vec<_>> = paths
+                .targets()
+                .iter_names()
+                .map(|t| {
+                    let deps = paths.deps(t).iter().map(|d| d.to_string()).collect();
+                    (t.to_string(), deps)
+                })
+                .collect();
+            map.serialize_entry("deps", &deps)?;
+        }
+        map.end()
+    }
+}
+
 struct FileSetJsonPrinter<'a> {
     value: &'a FileSet,
     resolver: &'a CellResolver,
@@ -295,6 +356,22 @@ impl<'a> QueryResultPrinter<'a> {
                                     value: &files,
                                 },
                             )?,
+                            QueryEvaluationValue::TargetPaths(paths)
+                                if !target_call_stacks
+                                    && print_providers.unpack_yes().is_none() =>
+                            {
+                                seq.serialize_entry(&arg, &TargetPathsJsonPrinter(&paths))?
+                            }
+                            QueryEvaluationValue::TargetPaths(paths) => seq.serialize_entry(
+                                &arg,
+                                &TargetSetJsonPrinter::new(
+                                    target_call_stacks,
+                                    print_providers,
+                                    &self.attributes,
+                                    paths.targets(),
+                                )
+                                .await?,
+                            )?,
                         },
                         Err(e) => {
                             seq.serialize_entry(
@@ -330,6 +407,35 @@ impl<'a> QueryResultPrinter<'a> {
         call_stack: bool,
         print_providers: ShouldPrintProviders<'b, T>,
     ) -> anyhow::Result<()> {
+        // Paths are printed with their edges when that's all we're asked for, otherwise they're
+        // printed as their targets (keeping their edges for dot output).
+        let (result, paths) = match result {
+            QueryEvaluationValue::TargetPaths(paths) => {
+                let is_complex = self.attributes.is_some()
+                    || call_stack
+                    || print_providers.unpack_yes().is_some();
+                match self.output_format {
+                    QueryOutputFormat::Default if !is_complex => {
+                        write_target_paths(&mut output, &paths)?;
+                        return Ok(());
+                    }
+                    QueryOutputFormat::Json if !is_complex => {
+                        let mut ser = serde_json::Serializer::pretty(&mut output);
+                        TargetPathsJsonPrinter(&paths).serialize(&mut ser)?;
+                        std::mem::drop(ser);
+                        // need to add a newline to flush the output.
+                        writeln!(&mut output)?;
+                        return Ok(());
+                    }
+                    _ => (
+                        QueryEvaluationValue::TargetSet(paths.targets().clone()),
+                        Some(paths),
+                    ),
+                }
+            }
+            result => (result, None),
+        };
+
         match result {
             QueryEvaluationValue::TargetSet(targets) => match self.output_format {
                 QueryOutputFormat::Default => {
@@ -394,6 +500,7 @@ impl<'a> QueryResultPrinter<'a> {
                     Dot::render(
                         &DotTargetGraph {
                             targets,
+                            paths,
                             attributes: self.attributes.clone(),
                         },
                         &mut output,
@@ -403,6 +510,53 @@ impl<'a> QueryResultPrinter<'a> {
                     DotCompact::render(
                         &DotTargetGraph {
                             targets,
+                            paths,
+                            attributes: self.attributes.clone(),
+                        },
+                        &mut output,
+                    )?;
+                }
+                QueryOutputFormat::Ndjson => {
+                    // Providers are looked up concurrently, and each target is written as soon
+                    // as it and the targets before it are ready, so that output starts while the
+                    // rest is still being analyzed. Whatever has been written is flushed whenever
+                    // the next target is not ready yet, rather than held back behind it.
+                    let mut printable = futures::stream::iter(targets.iter())
+                        .map(|target| {
+                            printable_target(target, print_providers, &self.attributes, call_stack)
+                        })
+                        .buffered(NDJSON_CONCURRENT_LOOKUPS);
+                    loop {
+                        let next = match printable.try_next().now_or_never() {
+                            Some(next) => next?,
+                            None => {
+                                output.flush()?;
+                                printable.try_next().await?
+                            }
+                        };
+                        let Some(target) = next else {
+                            break;
+                        };
+                        serde_json::to_writer(&mut output, &NdjsonQueryTarget(&target))?;
+                        writeln!(&mut output)?;
+                    }
+                    output.flush()?;
+                }
+                QueryOutputFormat::Graphml => {
+                    GraphMl::render(
+                        &DotTargetGraph {
+                            targets,
+                            paths,
+                            attributes: self.attributes.clone(),
+                        },
+                        &mut output,
+                    )?;
+                }
+                QueryOutputFormat::Mermaid => {
+                    Mermaid::render(
+                        &DotTargetGraph {
+                            targets,
+                            paths,
                             attributes: self.attributes.clone(),
                         },
                         &mut output,
@@ -440,33 +594,82 @@ impl<'a> QueryResultPrinter<'a> {
                     QueryOutputFormat::DotCompact => {
                         unimplemented!("dot_compact output for files not implemented yet")
                     }
+                    QueryOutputFormat::Ndjson => {
+                        for file in files.iter() {
+                            serde_json::to_writer(
+                                &mut output,
+                                &self.resolver.resolve_path(file.as_ref())?.to_string(),
+                            )?;
+                            writeln!(&mut output)?;
+                        }
+                    }
+                    QueryOutputFormat::Graphml => {
+                        return Err(QueryCommandError::FileSetHasNoGraph("graphml").into());
+                    }
+                    QueryOutputFormat::Mermaid => {
+                        return Err(QueryCommandError::FileSetHasNoGraph("mermaid").into());
+                    }
                 }
             }
+            QueryEvaluationValue::TargetPaths(..) => unreachable!("paths are handled above"),
         }
 
         Ok(())
     }
 }
 
+/// Prints a single path as a chain from the dependent to the dependency, and other paths as each
+/// target followed by its dependencies on the paths.
+fn write_target_paths<T: QueryTarget>(
+    mut output: impl std::io::Write,
+    paths: &TargetPaths<T>,
+) -> anyhow::Result<()> {
+    if paths.is_single_path() {
+        for (i, target) in paths.targets().iter_names().enumerate() {
+            let arrow = if i == 0 { "" } else { "-> " };
+            writeln!(&mut output, "{}{}", arrow, target)?;
+        }
+    } else {
+        for target in paths.targets().iter_names() {
+            writeln!(&mut output, "{}", target)?;
+            for dep in paths.deps(target) {
+                writeln!(&mut output, "  -> {}", dep)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn printable_target<'a, T: QueryTarget>(
+    target: &'a T,
+    print_providers: ShouldPrintProviders<'a, T>,
+    attributes: &'a Option<RegexSet>,
+    target_call_stacks: bool,
+) -> anyhow::Result<PrintableQueryTarget<'a, T>> {
+    Ok(PrintableQueryTarget {
+        value: target,
+        attributes,
+        target_call_stacks,
+        providers: match print_providers {
+            ShouldPrintProviders::No => None,
+            ShouldPrintProviders::Yes(lookup) => {
+                Some(lookup.lookup(target).await?.require_compatible()?)
+            }
+        },
+    })
+}
+
 async fn printable_targets<'a, T: QueryTarget>(
     targets: &'a TargetSet<T>,
     print_providers: ShouldPrintProviders<'a, T>,
     attributes: &'a Option<RegexSet>,
     target_call_stacks: bool,
 ) -> anyhow::Result<Vec<PrintableQueryTarget<'a, T>>> {
-    futures::future::join_all(targets.iter().map(|t| async move {
-        Ok(PrintableQueryTarget {
-            value: t,
-            attributes,
-            target_call_stacks,
-            providers: match print_providers {
-                ShouldPrintProviders::No => None,
-                ShouldPrintProviders::Yes(lookup) => {
-                    Some(lookup.lookup(t).await?.require_compatible()?)
-                }
-            },
-        })
-    }))
+    futures::future::join_all(
+        targets
+            .iter()
+            .map(|t| printable_target(t, print_providers, attributes, target_call_stacks)),
+    )
     .await
     .into_iter()
     .collect::<anyhow::Result<_>>()
@@ -0,0 +1,215 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Server side of `buck2 query-shell`: evaluates a stream of queries, each in its own DICE
+//! transaction with the working directory and configuration of its request, so that file
+//! changes made while the shell is open are picked up by the next query. The values bound by
+//! `let` are kept for the whole session.
+
+use buck2_build_api::actions::query::ActionQueryNode;
+use buck2_cli_proto::query_shell_let;
+use buck2_cli_proto::query_shell_message;
+use buck2_cli_proto::query_shell_request;
+use buck2_cli_proto::ClientContext;
+use buck2_cli_proto::QueryShellBound;
+use buck2_cli_proto::QueryShellCompletions;
+use buck2_cli_proto::QueryShellLet;
+use buck2_cli_proto::QueryShellMessage;
+use buck2_cli_proto::QueryShellRequest;
+use buck2_cli_proto::QueryShellResponse;
+use buck2_error::internal_error;
+use buck2_error::BuckErrorContext;
+use buck2_events::dispatch::span_async;
+use buck2_node::nodes::configured::ConfiguredTargetNode;
+use buck2_node::nodes::unconfigured::TargetNode;
+use buck2_query::query::environment::QueryTarget;
+use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryEvaluationValue;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
+use buck2_server_ctx::command_end::command_end;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::streaming_request_handler::StreamingRequestHandler;
+use futures::StreamExt;
+
+use crate::commands::complete::complete_target;
+use crate::commands::query::aquery::aquery;
+use crate::commands::query::aquery::eval_aquery_request;
+use crate::commands::query::cquery::cquery;
+use crate::commands::query::cquery::eval_cquery_request;
+use crate::commands::query::uquery::eval_uquery_request;
+use crate::commands::query::uquery::uquery;
+
+pub(crate) async fn query_shell_command(
+    ctx: &dyn ServerCommandContextTrait,
+    partial_result_dispatcher: PartialResultDispatcher<QueryShellMessage>,
+    req: StreamingRequestHandler<QueryShellRequest>,
+) -> anyhow::Result<QueryShellResponse> {
+    let start_event = buck2_data::CommandStart {
+        metadata: ctx.request_metadata().await?,
+        data: Some(buck2_data::QueryShellCommandStart {}.into()),
+    };
+    span_async(start_event, async move {
+        let result = run_query_shell(ctx, partial_result_dispatcher, req)
+            .await
+            .map_err(Into::into);
+        let end_event = command_end(&result, buck2_data::QueryShellCommandEnd {});
+        (result.map_err(Into::into), end_event)
+    })
+    .await
+}
+
+/// The values bound by `let` so far. Each kind of query has its own, as their targets differ.
+#[derive(Default)]
+struct ShellVariables {
+    uquery: QueryVariables<TargetNode>,
+    cquery: QueryVariables<ConfiguredTargetNode>,
+    aquery: QueryVariables<ActionQueryNode>,
+}
+
+async fn run_query_shell(
+    server_ctx: &dyn ServerCommandContextTrait,
+    mut partial_result_dispatcher: PartialResultDispatcher<QueryShellMessage>,
+    mut requests: StreamingRequestHandler<QueryShellRequest>,
+) -> anyhow::Result<QueryShellResponse> {
+    let mut variables = ShellVariables::default();
+    // The shell ends when the client closes its side of the stream.
+    while let Some(request) = requests.next().await {
+        let message = match run_request(server_ctx, &mut variables, request?).await {
+            Ok(message) => message,
+            // A failing query should not end the session.
+            Err(e) => query_shell_message::Message::Error(format!("{:#}", e)),
+        };
+        partial_result_dispatcher.emit(QueryShellMessage {
+            message: Some(message),
+        });
+    }
+    Ok(QueryShellResponse {})
+}
+
+/// The context of a single request, which sees the configuration as of that request.
+fn request_ctx<'s>(
+    server_ctx: &'s dyn ServerCommandContextTrait,
+    context: &Option<ClientContext>,
+) -> anyhow::Result<Box<dyn ServerCommandContextTrait + 's>> {
+    server_ctx.for_request(context.as_ref().internal_error("No client context")?)
+}
+
+async fn run_request(
+    server_ctx: &dyn ServerCommandContextTrait,
+    variables: &mut ShellVariables,
+    request: QueryShellRequest,
+) -> anyhow::Result<query_shell_message::Message> {
+    match request
+        .request
+        .internal_error("Empty query shell request")?
+    {
+        query_shell_request::Request::Uquery(req) => {
+            let variables = &variables.uquery;
+            let mut stdout = Vec::new();
+            request_ctx(server_ctx, &req.context)?
+                .with_dice_ctx(|server_ctx, ctx| async move {
+                    uquery(server_ctx, &mut stdout, ctx, &req, variables).await?;
+                    Ok(query_shell_message::Message::Stdout(stdout))
+                })
+                .await
+        }
+        query_shell_request::Request::Cquery(req) => {
+            let variables = &variables.cquery;
+            let mut stdout = Vec::new();
+            request_ctx(server_ctx, &req.context)?
+                .with_dice_ctx(|server_ctx, ctx| async move {
+                    cquery(server_ctx, &mut stdout, ctx, &req, variables).await?;
+                    Ok(query_shell_message::Message::Stdout(stdout))
+                })
+                .await
+        }
+        query_shell_request::Request::Aquery(req) => {
+            let variables = &variables.aquery;
+            let mut stdout = Vec::new();
+            request_ctx(server_ctx, &req.context)?
+                .with_dice_ctx(|server_ctx, ctx| async move {
+                    aquery(server_ctx, &mut stdout, ctx, &req, variables).await?;
+                    Ok(query_shell_message::Message::Stdout(stdout))
+                })
+                .await
+        }
+        query_shell_request::Request::Complete(req) => {
+            request_ctx(server_ctx, &req.context)?
+                .with_dice_ctx(|server_ctx, mut ctx| async move {
+                    let completions =
+                        complete_target(&mut ctx, server_ctx.working_dir(), &req.partial_target)
+                            .await?;
+                    Ok(query_shell_message::Message::Completions(
+                        QueryShellCompletions { completions },
+                    ))
+                })
+                .await
+        }
+        query_shell_request::Request::Let(QueryShellLet { name, query }) => {
+            let size = match query.internal_error("Empty query shell let")? {
+                query_shell_let::Query::Uquery(req) => {
+                    let bound = &variables.uquery;
+                    let result = request_ctx(server_ctx, &req.context)?
+                        .with_dice_ctx(|server_ctx, mut ctx| async move {
+                            eval_uquery_request(server_ctx, &mut ctx, &req, bound).await
+                        })
+                        .await?;
+                    bind(&mut variables.uquery, name.clone(), result)?
+                }
+                query_shell_let::Query::Cquery(req) => {
+                    let bound = &variables.cquery;
+                    let result = request_ctx(server_ctx, &req.context)?
+                        .with_dice_ctx(|server_ctx, mut ctx| async move {
+                            eval_cquery_request(server_ctx, &mut ctx, &req, bound).await
+                        })
+                        .await?;
+                    bind(&mut variables.cquery, name.clone(), result)?
+                }
+                query_shell_let::Query::Aquery(req) => {
+                    let bound = &variables.aquery;
+                    let result = request_ctx(server_ctx, &req.context)?
+                        .with_dice_ctx(|server_ctx, mut ctx| async move {
+                            eval_aquery_request(server_ctx, &mut ctx, &req, bound).await
+                        })
+                        .await?;
+                    bind(&mut variables.aquery, name.clone(), result)?
+                }
+            };
+            Ok(query_shell_message::Message::Bound(QueryShellBound {
+                name,
+                size,
+            }))
+        }
+    }
+}
+
+/// Binds the evaluated value of a `let` to `name`, returning its number of targets or files.
+fn bind<T: QueryTarget>(
+    variables: &mut QueryVariables<T>,
+    name: String,
+    result: QueryEvaluationResult<T>,
+) -> anyhow::Result<u64> {
+    let value = match result {
+        QueryEvaluationResult::Single(value) => value,
+        QueryEvaluationResult::Multiple(_) => {
+            return Err(internal_error!(
+                "Query shell `let` returned multiple results"
+            ));
+        }
+    };
+    let size = match &value {
+        QueryEvaluationValue::TargetSet(targets) => targets.len(),
+        QueryEvaluationValue::FileSet(files) => files.len(),
+        QueryEvaluationValue::TargetPaths(paths) => paths.targets().len(),
+    };
+    variables.insert(name, value.into());
+    Ok(size as u64)
+}
@@ -23,6 +23,7 @@ use buck2_node::nodes::unconfigured::TargetNode;
 use buck2_node::nodes::unconfigured::TargetNodeData;
 use buck2_query::query::environment::AttrFmtOptions;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
+use buck2_query::query::syntax::simple::eval::values::QueryVariables;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 use buck2_server_ctx::template::run_server_command;
@@ -30,6 +31,8 @@ use buck2_server_ctx::template::ServerCommandTemplate;
 use dice::DiceTransaction;
 use dupe::Dupe;
 
+use crate::commands::query::explain::query_explain;
+use crate::commands::query::explain::report_query_explain;
 use crate::commands::query::printer::QueryResultPrinter;
 use crate::commands::query::printer::ShouldPrintProviders;
 use crate::commands::query::query_target_ext::QueryCommandTarget;
@@ -110,6 +113,7 @@ impl ServerCommandTemplate for UqueryServerCommand {
             partial_result_dispatcher.as_writer(),
             ctx,
             &self.req,
+            &QueryVariables::default(),
         )
         .await
     }
@@ -119,11 +123,12 @@ impl ServerCommandTemplate for UqueryServerCommand {
     }
 }
 
-async fn uquery(
+pub(crate) async fn uquery(
     server_ctx: &dyn ServerCommandContextTrait,
     mut stdout: impl Write,
     mut ctx: DiceTransaction,
     request: &UqueryRequest,
+    variables: &QueryVariables<TargetNode>,
 ) -> anyhow::Result<UqueryResponse> {
     let cell_resolver = ctx.get_cell_resolver().await?;
     let output_configuration = QueryResultPrinter::from_request_options(
@@ -132,21 +137,14 @@ async fn uquery(
         request.unstable_output_format,
     )?;
 
-    let UqueryRequest {
-        query,
-        query_args,
-        context,
-        ..
-    } = request;
-
-    let client_ctx = context.as_ref().internal_error("No client context")?;
+    let client_ctx = request
+        .context
+        .as_ref()
+        .internal_error("No client context")?;
 
     let target_call_stacks = client_ctx.target_call_stacks;
 
-    let query_result = QUERY_FRONTEND
-        .get()?
-        .eval_uquery(&mut ctx, server_ctx.working_dir(), query, query_args)
-        .await?;
+    let query_result = eval_uquery_request(server_ctx, &mut ctx, request, variables).await?;
 
     match query_result {
         QueryEvaluationResult::Single(targets) => {
@@ -173,3 +171,30 @@ async fn uquery(
 
     Ok(UqueryResponse {})
 }
+
+/// Evaluates the query of `request`, without printing it.
+pub(crate) async fn eval_uquery_request(
+    server_ctx: &dyn ServerCommandContextTrait,
+    ctx: &mut DiceTransaction,
+    request: &UqueryRequest,
+    variables: &QueryVariables<TargetNode>,
+) -> anyhow::Result<QueryEvaluationResult<TargetNode>> {
+    let UqueryRequest {
+        query, query_args, ..
+    } = request;
+
+    let explain = query_explain(request.explain, ctx);
+    let query_result = QUERY_FRONTEND
+        .get()?
+        .eval_uquery(
+            ctx,
+            server_ctx.working_dir(),
+            query,
+            query_args,
+            variables,
+            explain.as_ref(),
+        )
+        .await?;
+    report_query_explain(explain.as_ref(), query)?;
+    Ok(query_result)
+}
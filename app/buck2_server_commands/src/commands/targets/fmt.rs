@@ -8,8 +8,10 @@
  */
 
 use std::collections::BTreeSet;
+use std::collections::HashSet;
 use std::fmt::Write;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 use buck2_cli_proto::targets_request;
 use buck2_cli_proto::targets_request::OutputFormat;
@@ -19,6 +21,7 @@ use buck2_cli_proto::TargetsRequest;
 use buck2_core::bzl::ImportPath;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::package::PackageLabel;
+use buck2_core::target::label::label::TargetLabel;
 use buck2_error::internal_error;
 use buck2_error::BuckErrorContext;
 use buck2_node::attrs::hacks::value_to_json;
@@ -34,9 +37,17 @@ use buck2_node::nodes::attributes::TYPE;
 use buck2_node::nodes::unconfigured::TargetNodeRef;
 use buck2_node::super_package::SuperPackage;
 use buck2_util::indent::indent;
+use dupe::Dupe;
+use dupe::IterDupedExt;
 use gazebo::prelude::SliceExt;
 use regex::RegexSet;
 
+use crate::dot::DotDigraph;
+use crate::dot::DotEdge;
+use crate::dot::DotNode;
+use crate::dot::DotNodeAttrs;
+use crate::dot::GraphMl;
+use crate::dot::Mermaid;
 use crate::json::QuotedJson;
 use crate::target_hash::BuckTargetHash;
 
@@ -394,6 +405,103 @@ impl TargetFormatter for TargetNameFormat {
     }
 }
 
+#[derive(Clone, Copy)]
+enum GraphKind {
+    GraphMl,
+    Mermaid,
+}
+
+/// Prints the targets and the dependencies between them as a graph. Targets are collected as
+/// they are printed and the graph is rendered at the end, since only the dependencies on other
+/// printed targets are drawn.
+struct GraphFormat {
+    kind: GraphKind,
+    targets: Mutex<Vec<(TargetLabel, Vec<TargetLabel>)>>,
+}
+
+impl TargetFormatter for GraphFormat {
+    fn end(&self, _stats: &Stats, buffer: &mut String) {
+        let targets = std::mem::take(&mut *self.targets.lock().unwrap());
+        let graph = LabelGraph::new(targets);
+        let mut out = Vec::new();
+        // Writing to a `Vec` does not fail.
+        match self.kind {
+            GraphKind::GraphMl => GraphMl::render(&graph, &mut out).unwrap(),
+            GraphKind::Mermaid => Mermaid::render(&graph, &mut out).unwrap(),
+        }
+        buffer.push_str(&String::from_utf8_lossy(&out));
+    }
+
+    fn target(&self, target_info: TargetInfo<'_>, _buffer: &mut String) {
+        let label = target_info.node.label().dupe();
+        let deps = target_info.node.deps().duped().collect();
+        self.targets.lock().unwrap().push((label, deps));
+    }
+}
+
+struct LabelGraphNode {
+    label: String,
+    deps: Vec<String>,
+}
+
+struct LabelGraph {
+    nodes: Vec<LabelGraphNode>,
+}
+
+impl LabelGraph {
+    fn new(targets: Vec<(TargetLabel, Vec<TargetLabel>)>) -> Self {
+        let labels: HashSet<TargetLabel> = targets.iter().map(|(t, _)| t.dupe()).collect();
+        let nodes = targets
+            .into_iter()
+            .map(|(label, deps)| LabelGraphNode {
+                label: label.to_string(),
+                deps: deps
+                    .iter()
+                    .filter(|d| labels.contains(*d))
+                    .map(|d| d.to_string())
+                    .collect(),
+            })
+            .collect();
+        Self { nodes }
+    }
+}
+
+impl DotNode for LabelGraphNode {
+    fn attrs(&self) -> anyhow::Result<DotNodeAttrs> {
+        Ok(DotNodeAttrs::default())
+    }
+
+    fn id(&self) -> String {
+        self.label.clone()
+    }
+}
+
+impl<'a> DotDigraph<'a> for LabelGraph {
+    type Node = LabelGraphNode;
+
+    fn name(&self) -> &str {
+        "targets"
+    }
+
+    fn for_each_node<F: FnMut(&Self::Node) -> anyhow::Result<()>>(
+        &'a self,
+        f: F,
+    ) -> anyhow::Result<()> {
+        self.nodes.iter().try_for_each(f)
+    }
+
+    fn for_each_edge<F: FnMut(&DotEdge) -> anyhow::Result<()>>(
+        &'a self,
+        node: &Self::Node,
+        mut f: F,
+    ) -> anyhow::Result<()> {
+        for dep in &node.deps {
+            f(&DotEdge::new(&node.label, dep))?;
+        }
+        Ok(())
+    }
+}
+
 pub(crate) fn print_target_call_stack_after_target(out: &mut String, call_stack: Option<&str>) {
     if let Some(call_stack) = call_stack {
         write!(out, "{}", indent("  ", call_stack)).unwrap();
@@ -429,6 +537,14 @@ pub(crate) fn create_formatter(
             target_hash_graph_type: TargetHashGraphType::from_i32(other.target_hash_graph_type)
                 .expect("buck cli should send valid target hash graph type"),
         })),
+        OutputFormat::Graphml => Ok(Arc::new(GraphFormat {
+            kind: GraphKind::GraphMl,
+            targets: Mutex::new(Vec::new()),
+        })),
+        OutputFormat::Mermaid => Ok(Arc::new(GraphFormat {
+            kind: GraphKind::Mermaid,
+            targets: Mutex::new(Vec::new()),
+        })),
         OutputFormat::Json | OutputFormat::JsonLines => Ok(Arc::new(JsonFormat {
             attributes: if other.output_attributes.is_empty() {
                 None
@@ -13,6 +13,8 @@
 enum ResolveAliasError {
     #[error("`--stat` format is not supported by `--resolve-alias`")]
     StatFormatNotSupported,
+    #[error("Graph formats are not supported by `--resolve-alias`")]
+    GraphFormatNotSupported,
 }
 
 use std::collections::HashMap;
@@ -161,6 +163,9 @@ pub(crate) async fn targets_resolve_aliases(
             &json_writer as &dyn ResolveAliasFormatter
         }
         OutputFormat::Stats => return Err(ResolveAliasError::StatFormatNotSupported.into()),
+        OutputFormat::Graphml | OutputFormat::Mermaid => {
+            return Err(ResolveAliasError::GraphFormatNotSupported.into());
+        }
     };
 
     let mut needs_separator = false;
@@ -16,9 +16,13 @@
 // It looks like we could use that, but it mostly would just handle the actual writing of the
 // data in the right format and maybe escaping. It's not been imported to tp2 so we implement it
 // ourselves for now.
+//!
+//! The same graphs can also be rendered as GraphML (see <http://graphml.graphdrawing.org/>) and
+//! as Mermaid flowcharts (see <https://mermaid.js.org/syntax/flowchart.html>).
 
 use std::collections::hash_map::Entry::Occupied;
 use std::collections::hash_map::Entry::Vacant;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::io::Write;
@@ -71,6 +75,12 @@ pub struct DotEdge<'a> {
     to: &'a str,
 }
 
+impl<'a> DotEdge<'a> {
+    pub fn new(from: &'a str, to: &'a str) -> Self {
+        Self { from, to }
+    }
+}
+
 pub trait DotDigraph<'a> {
     type Node: DotNode;
 
@@ -174,3 +184,223 @@ impl DotCompact {
         Ok(())
     }
 }
+
+/// Escapes text for use in XML content and attribute values.
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub struct GraphMl {}
+
+impl GraphMl {
+    pub fn render<'a, T: DotDigraph<'a>, W: Write>(graph: &'a T, mut w: W) -> anyhow::Result<()> {
+        // GraphML declares the node attributes before the graph, so collect everything first.
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut keys = BTreeSet::new();
+        graph.for_each_node(|node| {
+            let id = node.id();
+            let attrs = node.attrs()?;
+            keys.extend(attrs.extra.keys().cloned());
+            graph.for_each_edge(node, |edge| {
+                edges.push((edge.from.to_owned(), edge.to.to_owned()));
+                Ok(())
+            })?;
+            nodes.push((id, attrs));
+            Ok(())
+        })?;
+
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        // Key ids are namespaced so that an attribute named `label` does not clash with the
+        // label of the node.
+        writeln!(
+            w,
+            r#"  <key id="buck.label" for="node" attr.name="buck.label" attr.type="string"/>"#
+        )?;
+        for key in &keys {
+            let key = escape_xml(key);
+            writeln!(
+                w,
+                r#"  <key id="attr.{key}" for="node" attr.name="{key}" attr.type="string"/>"#
+            )?;
+        }
+        writeln!(
+            w,
+            r#"  <graph id="{}" edgedefault="directed">"#,
+            escape_xml(graph.name())
+        )?;
+        for (id, attrs) in &nodes {
+            let id = escape_xml(id);
+            writeln!(w, r#"    <node id="{id}">"#)?;
+            writeln!(w, r#"      <data key="buck.label">{id}</data>"#)?;
+            for (key, value) in &attrs.extra {
+                writeln!(
+                    w,
+                    r#"      <data key="attr.{}">{}</data>"#,
+                    escape_xml(key),
+                    escape_xml(value)
+                )?;
+            }
+            writeln!(w, "    </node>")?;
+        }
+        for (from, to) in &edges {
+            writeln!(
+                w,
+                r#"    <edge source="{}" target="{}"/>"#,
+                escape_xml(from),
+                escape_xml(to)
+            )?;
+        }
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")?;
+        Ok(())
+    }
+}
+
+pub struct Mermaid {}
+
+impl Mermaid {
+    pub fn render<'a, T: DotDigraph<'a>, W: Write>(graph: &'a T, mut w: W) -> anyhow::Result<()> {
+        writeln!(w, "flowchart LR")?;
+
+        // Mermaid ids can't contain most of the characters in labels, so number the nodes like
+        // `DotCompact` does and use the labels as the node text.
+        let mut lookup_numeric_id: HashMap<String, u32> = HashMap::new();
+        let mut name_to_number = |node_name: &str| -> u32 {
+            let next_id = lookup_numeric_id.len() as u32 + 1;
+            *lookup_numeric_id
+                .entry(node_name.to_owned())
+                .or_insert(next_id)
+        };
+
+        let mut edges = Vec::new();
+        graph.for_each_node(|node| {
+            let id = node.id();
+            writeln!(
+                w,
+                "  n{}[\"{}\"]",
+                name_to_number(&id),
+                id.replace('"', "#quot;")
+            )?;
+            graph.for_each_edge(node, |edge| {
+                edges.push((name_to_number(edge.from), name_to_number(edge.to)));
+                Ok(())
+            })?;
+            Ok(())
+        })?;
+        for (from, to) in edges {
+            writeln!(w, "  n{} --> n{}", from, to)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestNode(&'static str, Vec<&'static str>);
+
+    impl DotNode for TestNode {
+        fn attrs(&self) -> anyhow::Result<DotNodeAttrs> {
+            let mut attrs = DotNodeAttrs::default();
+            attrs.extra.insert("label".to_owned(), "ok".to_owned());
+            Ok(attrs)
+        }
+
+        fn id(&self) -> String {
+            self.0.to_owned()
+        }
+    }
+
+    struct TestGraph(Vec<TestNode>);
+
+    impl<'a> DotDigraph<'a> for TestGraph {
+        type Node = TestNode;
+
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn for_each_node<F: FnMut(&Self::Node) -> anyhow::Result<()>>(
+            &'a self,
+            f: F,
+        ) -> anyhow::Result<()> {
+            self.0.iter().try_for_each(f)
+        }
+
+        fn for_each_edge<F: FnMut(&DotEdge) -> anyhow::Result<()>>(
+            &'a self,
+            node: &Self::Node,
+            mut f: F,
+        ) -> anyhow::Result<()> {
+            node.1
+                .iter()
+                .try_for_each(|to| f(&DotEdge { from: node.0, to }))
+        }
+    }
+
+    fn test_graph() -> TestGraph {
+        TestGraph(vec![
+            TestNode("//a:a", vec!["//b:<b>"]),
+            TestNode("//b:<b>", vec![]),
+        ])
+    }
+
+    #[test]
+    fn test_graphml() -> anyhow::Result<()> {
+        let mut out = Vec::new();
+        GraphMl::render(&test_graph(), &mut out)?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains(r#"<node id="//b:&lt;b&gt;">"#), "{}", out);
+        assert!(
+            out.contains(
+                r#"<key id="attr.label" for="node" attr.name="label" attr.type="string"/>"#
+            ),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains(r#"<data key="buck.label">//a:a</data>"#),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains(r#"<data key="attr.label">ok</data>"#),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains(r#"<edge source="//a:a" target="//b:&lt;b&gt;"/>"#),
+            "{}",
+            out
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_mermaid() -> anyhow::Result<()> {
+        let mut out = Vec::new();
+        Mermaid::render(&test_graph(), &mut out)?;
+        assert_eq!(
+            "flowchart LR\n  n1[\"//a:a\"]\n  n2[\"//b:<b>\"]\n  n1 --> n2\n",
+            String::from_utf8(out)?
+        );
+        Ok(())
+    }
+}
@@ -10,6 +10,7 @@
 use buck2_query::query::environment::AttrFmtOptions;
 use buck2_query::query::environment::QueryTarget;
 use buck2_query::query::environment::QueryTargets;
+use buck2_query::query::syntax::simple::eval::paths::TargetPaths;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
 use regex::RegexSet;
 use starlark_map::small_map::SmallMap;
@@ -25,6 +26,9 @@ pub struct DotTargetGraphNode<'a, T: QueryTarget>(&'a T, &'a DotTargetGraph<T>);
 /// A simple adapter for creating a DotDiGraph for a TargetSet.
 pub struct DotTargetGraph<T: QueryTarget> {
     pub targets: TargetSet<T>,
+    /// If set, only the dependencies on these paths are drawn, rather than all the dependencies
+    /// between `targets`.
+    pub paths: Option<TargetPaths<T>>,
     pub attributes: Option<RegexSet>,
 }
 
@@ -50,6 +54,16 @@ impl<'a, T: QueryCommandTarget> DotDigraph<'a> for DotTargetGraph<T> {
         node: &Self::Node,
         mut f: F,
     ) -> anyhow::Result<()> {
+        if let Some(paths) = &self.paths {
+            for dep in paths.deps(node.0.node_key()) {
+                f(&DotEdge {
+                    from: &node.0.node_key().to_string(),
+                    to: &dep.to_string(),
+                })?;
+            }
+            return Ok(());
+        }
+
         for dep in node.0.deps() {
             // Only include edges to other nodes within the subgraph.
             if self.targets.contains(dep) {
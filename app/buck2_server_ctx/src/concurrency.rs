@@ -26,6 +26,7 @@ use buck2_core::soft_error;
 use buck2_data::DiceBlockConcurrentCommandEnd;
 use buck2_data::DiceBlockConcurrentCommandStart;
 use buck2_data::DiceEqualityCheck;
+use buck2_data::DiceInvalidationTrace;
 use buck2_data::DiceSynchronizeSectionEnd;
 use buck2_data::DiceSynchronizeSectionStart;
 use buck2_data::ExclusiveCommandWaitEnd;
@@ -57,6 +58,9 @@ use tokio::sync::oneshot::error::RecvError;
 use tokio::sync::Mutex;
 use tokio::sync::MutexGuard;
 
+/// How many invalidation paths to report per update, the rest are only counted.
+const MAX_REPORTED_INVALIDATION_PATHS: usize = 20;
+
 #[derive(buck2_error::Error, Debug)]
 enum ConcurrencyHandlerError {
     #[error(
@@ -119,6 +123,8 @@ struct ConcurrencyHandlerData {
     cleanup_epoch: usize,
     /// Whether this has been tainted previously.
     previously_tainted: bool,
+    /// The last version whose invalidations were reported, so each update is only reported once.
+    last_traced_version: Option<DiceEquality>,
 }
 
 #[derive(Allocative, Display, Copy, Clone, Dupe, PartialEq, Eq, Hash)]
@@ -320,6 +326,7 @@ impl ConcurrencyHandler {
                 next_command_id: CommandId(0),
                 cleanup_epoch: 0,
                 previously_tainted: false,
+                last_traced_version: None,
             })),
             cond: Default::default(),
             dice,
@@ -486,6 +493,9 @@ impl ConcurrencyHandler {
                     }
                     .await?;
 
+                    self.report_invalidations(&mut data, &transaction, &event_dispatcher)
+                        .await;
+
                     if let Some(active) = active {
                         let is_same_state = transaction.equivalent(&active.version);
 
@@ -628,6 +638,37 @@ impl ConcurrencyHandler {
         }
     }
 
+    /// Reports what the update that created `transaction` dirtied, if DICE traced it. Nothing is
+    /// reported when invalidation tracing isn't enabled.
+    async fn report_invalidations(
+        &self,
+        data: &mut ConcurrencyHandlerData,
+        transaction: &DiceTransaction,
+        event_dispatcher: &EventDispatcher,
+    ) {
+        let version = transaction.equality_token();
+        if data.last_traced_version == Some(version) {
+            return;
+        }
+        data.last_traced_version = Some(version);
+
+        if let Some(invalidations) = self
+            .dice
+            .invalidations_at(version, MAX_REPORTED_INVALIDATION_PATHS)
+            .await
+        {
+            event_dispatcher.instant_event(DiceInvalidationTrace {
+                version: version.to_string(),
+                dirtied_count: invalidations.dirtied_count as u64,
+                paths: invalidations
+                    .paths
+                    .into_iter()
+                    .map(|path| buck2_data::DiceInvalidationTracePath { keys: path.keys })
+                    .collect(),
+            });
+        }
+    }
+
     fn emit_logs(
         &self,
         state: RunState,
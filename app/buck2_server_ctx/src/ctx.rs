@@ -16,6 +16,7 @@ use buck2_build_signals::BuildSignalsContext;
 use buck2_build_signals::DeferredBuildSignals;
 use buck2_build_signals::HasCriticalPathBackend;
 use buck2_cli_proto::client_context::PreemptibleWhen;
+use buck2_cli_proto::ClientContext;
 use buck2_core::fs::paths::file_name::FileName;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
@@ -74,6 +75,14 @@ pub trait ServerCommandContextTrait: Send + Sync {
     );
 
     fn cancellation_context(&self) -> &ExplicitCancellationContext;
+
+    /// A context for one request of a long-running command (like `query-shell`), which takes its
+    /// working directory and configuration from `client_context` rather than from the command.
+    /// Each call to `with_dice_ctx` on it picks up the configuration as of that call.
+    fn for_request<'s>(
+        &'s self,
+        client_context: &ClientContext,
+    ) -> anyhow::Result<Box<dyn ServerCommandContextTrait + 's>>;
 }
 
 pub struct PrivateStruct(());
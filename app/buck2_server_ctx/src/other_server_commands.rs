@@ -21,6 +21,7 @@ use buck2_util::late_binding::LateBinding;
 use crate::ctx::ServerCommandContextTrait;
 use crate::partial_result_dispatcher::NoPartialResult;
 use crate::partial_result_dispatcher::PartialResultDispatcher;
+use crate::streaming_request_handler::StreamingRequestHandler;
 
 #[async_trait]
 pub trait OtherServerCommands: Send + Sync + 'static {
@@ -54,6 +55,12 @@ pub trait OtherServerCommands: Send + Sync + 'static {
         partial_result_dispatcher: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
         req: buck2_cli_proto::AqueryRequest,
     ) -> anyhow::Result<buck2_cli_proto::AqueryResponse>;
+    async fn query_shell(
+        &self,
+        ctx: &dyn ServerCommandContextTrait,
+        partial_result_dispatcher: PartialResultDispatcher<buck2_cli_proto::QueryShellMessage>,
+        req: StreamingRequestHandler<buck2_cli_proto::QueryShellRequest>,
+    ) -> anyhow::Result<buck2_cli_proto::QueryShellResponse>;
     async fn targets(
         &self,
         ctx: &dyn ServerCommandContextTrait,
@@ -0,0 +1,210 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Selection of the tests affected by a set of changed files (`buck2 test --changed-since`).
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+use buck2_core::cells::cell_path::CellPath;
+use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+use buck2_core::target::label::label::TargetLabel;
+use buck2_node::configured_universe::CqueryUniverse;
+use dupe::Dupe;
+
+/// Why a target was found to be affected by the change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Reason {
+    /// The target owns one of the changed files.
+    Owns(CellPath),
+    /// The target depends on another affected target.
+    DependsOn(TargetLabel),
+}
+
+/// The targets affected by a set of changed files, within a universe.
+#[derive(Debug, Default)]
+pub(crate) struct ChangedFilesSelection {
+    reasons: HashMap<TargetLabel, Reason>,
+}
+
+impl ChangedFilesSelection {
+    /// Find owners of `changed` in `universe`, and walk their reverse dependencies within the
+    /// universe. Each target records the shortest path to a changed file.
+    pub(crate) fn compute(universe: &CqueryUniverse, changed: &[CellPath]) -> Self {
+        let mut rdeps: HashMap<&ConfiguredTargetLabel, Vec<&ConfiguredTargetLabel>> =
+            HashMap::new();
+        for node in universe.iter() {
+            for dep in node.deps() {
+                rdeps.entry(dep.label()).or_default().push(node.label());
+            }
+        }
+
+        let mut reasons = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        for path in changed {
+            for owner in universe.owners(path) {
+                let label = owner.label().dupe();
+                reasons
+                    .entry(label.unconfigured().dupe())
+                    .or_insert_with(|| Reason::Owns(path.clone()));
+                if visited.insert(label.dupe()) {
+                    queue.push_back(label);
+                }
+            }
+        }
+
+        while let Some(label) = queue.pop_front() {
+            for rdep in rdeps.get(&label).into_iter().flatten() {
+                if !visited.insert((*rdep).dupe()) {
+                    continue;
+                }
+                reasons
+                    .entry(rdep.unconfigured().dupe())
+                    .or_insert_with(|| Reason::DependsOn(label.unconfigured().dupe()));
+                queue.push_back((*rdep).dupe());
+            }
+        }
+
+        Self { reasons }
+    }
+
+    pub(crate) fn contains(&self, label: &TargetLabel) -> bool {
+        self.reasons.contains_key(label)
+    }
+
+    /// Describe why `label` was selected, e.g.
+    /// ``//a:test depends on `//b:lib`, which owns `root//b/lib.rs` ``.
+    pub(crate) fn explain(&self, label: &TargetLabel) -> Option<String> {
+        let mut explanation = label.to_string();
+        let mut current = label;
+        let mut first = true;
+        // Bounded by the number of targets, in case of a cycle in the reasons.
+        for _ in 0..=self.reasons.len() {
+            let sep = if first { " " } else { ", which " };
+            first = false;
+            match self.reasons.get(current)? {
+                Reason::Owns(path) => {
+                    explanation.push_str(&format!("{}owns `{}`", sep, path));
+                    return Some(explanation);
+                }
+                Reason::DependsOn(dep) => {
+                    explanation.push_str(&format!("{}depends on `{}`", sep, dep));
+                    current = dep;
+                }
+            }
+        }
+        Some(explanation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::cells::cell_path::CellPath;
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::execution_types::execution::ExecutionPlatformResolution;
+    use buck2_core::package::package_relative_path::PackageRelativePath;
+    use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+    use buck2_core::target::label::label::TargetLabel;
+    use buck2_node::attrs::attr::Attribute;
+    use buck2_node::attrs::attr_type::list::ListLiteral;
+    use buck2_node::attrs::attr_type::AttrType;
+    use buck2_node::attrs::coerced_attr::CoercedAttr;
+    use buck2_node::attrs::coerced_path::CoercedPath;
+    use buck2_node::configured_universe::CqueryUniverse;
+    use buck2_node::nodes::configured::ConfiguredTargetNode;
+    use buck2_query::query::syntax::simple::eval::set::TargetSet;
+
+    use super::ChangedFilesSelection;
+    use super::Reason;
+
+    fn node(label: &str, srcs: &[&str], deps: Vec<ConfiguredTargetNode>) -> ConfiguredTargetNode {
+        let srcs = srcs
+            .iter()
+            .map(|src| {
+                CoercedAttr::SourceFile(CoercedPath::File(
+                    PackageRelativePath::new(src).unwrap().to_arc(),
+                ))
+            })
+            .collect::<Vec<_>>();
+        ConfiguredTargetNode::testing_new_with_deps(
+            ConfiguredTargetLabel::testing_parse(label, ConfigurationData::testing_new()),
+            "rust_library",
+            ExecutionPlatformResolution::new(None, Vec::new()),
+            vec![(
+                "srcs",
+                Attribute::new(None, "", AttrType::list(AttrType::source(false))),
+                CoercedAttr::List(ListLiteral(srcs.into())),
+            )],
+            vec![],
+            deps,
+        )
+    }
+
+    #[test]
+    fn test_compute() {
+        let lib = node("root//b:lib", &["lib.rs"], vec![]);
+        let test = node("root//a:test", &["test.rs"], vec![lib]);
+        let top = node("root//d:top", &[], vec![test]);
+        let other = node("root//c:other", &["other.rs"], vec![]);
+        let universe = CqueryUniverse::build(&TargetSet::from_iter([top, other])).unwrap();
+
+        let selection =
+            ChangedFilesSelection::compute(&universe, &[CellPath::testing_new("root//b/lib.rs")]);
+
+        assert!(selection.contains(&TargetLabel::testing_parse("root//b:lib")));
+        assert!(selection.contains(&TargetLabel::testing_parse("root//a:test")));
+        assert!(!selection.contains(&TargetLabel::testing_parse("root//c:other")));
+        assert_eq!(
+            Some(
+                "root//d:top depends on `root//a:test`, which depends on `root//b:lib`, \
+                 which owns `root//b/lib.rs`"
+                    .to_owned()
+            ),
+            selection.explain(&TargetLabel::testing_parse("root//d:top"))
+        );
+
+        let selection =
+            ChangedFilesSelection::compute(&universe, &[CellPath::testing_new("root//c/lib.rs")]);
+        assert!(!selection.contains(&TargetLabel::testing_parse("root//c:other")));
+    }
+
+    #[test]
+    fn test_explain() {
+        let lib = TargetLabel::testing_parse("root//b:lib");
+        let test = TargetLabel::testing_parse("root//a:test");
+        let path = CellPath::testing_new("root//b/lib.rs");
+
+        let selection = ChangedFilesSelection {
+            reasons: [
+                (lib.clone(), Reason::Owns(path)),
+                (test.clone(), Reason::DependsOn(lib.clone())),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        assert!(selection.contains(&test));
+        assert!(!selection.contains(&TargetLabel::testing_parse("root//c:other")));
+        assert_eq!(
+            Some("root//a:test depends on `root//b:lib`, which owns `root//b/lib.rs`".to_owned()),
+            selection.explain(&test)
+        );
+        assert_eq!(
+            Some("root//b:lib owns `root//b/lib.rs`".to_owned()),
+            selection.explain(&lib)
+        );
+        assert_eq!(
+            None,
+            selection.explain(&TargetLabel::testing_parse("root//c:other"))
+        );
+    }
+}
@@ -7,14 +7,17 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::io::BufWriter;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Context;
 use async_trait::async_trait;
+use buck2_build_api::actions::artifact::get_artifact_fs::GetArtifactFs;
 use buck2_build_api::analysis::calculation::RuleAnalysisCalculation;
 use buck2_build_api::artifact_groups::calculation::ArtifactGroupCalculation;
 use buck2_build_api::artifact_groups::ArtifactGroup;
@@ -39,9 +42,13 @@ use buck2_common::pattern::resolve::ResolvedPattern;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
 use buck2_core::configuration::compatibility::MaybeCompatible;
+use buck2_core::fs::buck_out_path::BuckOutTestPath;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::paths::abs_path::AbsPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::package::PackageLabel;
 use buck2_core::pattern::pattern::PackageSpec;
@@ -57,6 +64,7 @@ use buck2_events::dispatch::console_message;
 use buck2_events::dispatch::with_dispatcher_async;
 use buck2_events::errors::create_error_report;
 use buck2_futures::cancellation::CancellationContext;
+use buck2_node::configured_universe::UNIVERSE_FROM_LITERALS;
 use buck2_node::load_patterns::MissingTargetBehavior;
 use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
 use buck2_node::nodes::eval_result::EvaluationResult;
@@ -88,6 +96,9 @@ use indexmap::IndexSet;
 use itertools::Itertools;
 use serde::Serialize;
 
+use crate::changed_files::ChangedFilesSelection;
+use crate::coverage::merge_coverage;
+use crate::coverage::COVERAGE_REPORT_NAME;
 use crate::downward_api::BuckTestDownwardApi;
 use crate::executor_launcher::ExecutorLaunch;
 use crate::executor_launcher::ExecutorLauncher;
@@ -101,10 +112,12 @@ use crate::session::TestSessionOptions;
 use crate::translations::build_configured_target_handle;
 
 #[derive(Debug, Serialize)]
-#[allow(dead_code)]
 pub(crate) struct TestReport {
     project_root: AbsNormPathBuf,
+    /// Undeclared outputs directories collected for each target.
     outputs: HashMap<TargetLabel, Vec<ProjectRelativePathBuf>>,
+    /// The merged coverage report, if coverage was collected.
+    coverage_report: Option<AbsPathBuf>,
 }
 
 struct TestOutcome {
@@ -112,6 +125,7 @@ struct TestOutcome {
     executor_report: ExecutorReport,
     executor_stdout: String,
     executor_stderr: String,
+    undeclared_outputs: BTreeMap<ConfiguredProvidersLabel, Vec<ProjectRelativePathBuf>>,
 }
 
 impl TestOutcome {
@@ -267,6 +281,10 @@ impl ServerCommandTemplate for TestServerCommand {
     }
 }
 
+/// Where the internal test runner records test durations, relative to the test directory in
+/// buck-out.
+const TEST_DURATIONS_FILE: &str = "durations.json";
+
 async fn test(
     server_ctx: &dyn ServerCommandContextTrait,
     mut ctx: DiceTransaction,
@@ -313,7 +331,24 @@ async fn test(
         None => {
             // If no v2_test_executor config was set, fall back to the internal test runner.
             let test_executor = std::env::current_exe()?;
-            let test_executor_args = vec!["internal-test-runner".to_owned()];
+            // The internal test runner keeps track of how long tests take in buck-out, so that
+            // it can start the longest tests first in later runs.
+            let durations_file = ctx
+                .get_artifact_fs()
+                .await?
+                .buck_out_path_resolver()
+                .resolve_test(&BuckOutTestPath::new(
+                    ForwardRelativePathBuf::empty(),
+                    ForwardRelativePathBuf::unchecked_new(TEST_DURATIONS_FILE.to_owned()),
+                ));
+            let test_executor_args = vec![
+                "internal-test-runner".to_owned(),
+                "--durations-file".to_owned(),
+                server_ctx
+                    .project_root()
+                    .resolve(&durations_file)
+                    .to_string(),
+            ];
             (test_executor, test_executor_args)
         }
     };
@@ -324,6 +359,27 @@ async fn test(
 
     let resolved_pattern = ResolveTargetPatterns::resolve(&mut ctx, &parsed_patterns).await?;
 
+    let changed_files = match &request.changed_files {
+        Some(changed_files) => {
+            // The patterns being tested are the universe in which we look for owners and reverse
+            // dependencies of the changed files.
+            let universe = (UNIVERSE_FROM_LITERALS.get()?)(
+                &mut ctx,
+                cwd,
+                &request.target_patterns,
+                global_cfg_options.dupe(),
+            )
+            .await?;
+            let paths = changed_files
+                .paths
+                .iter()
+                .map(|p| cell_resolver.get_cell_path(ProjectRelativePath::new(p)?))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Some(Arc::new(ChangedFilesSelection::compute(&universe, &paths)))
+        }
+        None => None,
+    };
+
     let launcher: Box<dyn ExecutorLauncher> = Box::new(OutOfProcessTestExecutor {
         executable: test_executor,
         args: test_executor_args,
@@ -335,16 +391,76 @@ async fn test(
         .as_ref()
         .context("Missing `options`")?;
 
+    let build_opts = request
+        .build_opts
+        .as_ref()
+        .expect("should have build options");
+
+    // A command to run against local tests that time out, before they get killed, e.g.
+    // `gdb -p {pid} -batch -ex "thread apply all bt"`.
+    let timeout_hook = ctx
+        .get_legacy_config_property(
+            cell_resolver.root_cell(),
+            BuckconfigKeyRef {
+                section: "test",
+                property: "timeout_hook",
+            },
+        )
+        .await?
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            shlex::split(&s)
+                .filter(|argv| !argv.is_empty())
+                .with_context(|| format!("Invalid `test.timeout_hook`: {}", s))
+        })
+        .transpose()?;
+
     let session = TestSession::new(TestSessionOptions {
         allow_re: options.allow_re,
         force_use_project_relative_paths: options.force_use_project_relative_paths,
         force_run_from_project_root: options.force_run_from_project_root,
-    });
+        coverage: options.coverage,
+    })
+    .with_timeout_hook(timeout_hook);
+
+    let coverage_report = if options.coverage {
+        let path = if build_opts.unstable_print_build_report
+            && !build_opts.unstable_build_report_filename.is_empty()
+        {
+            // Put the coverage report next to the build report.
+            server_ctx
+                .project_root()
+                .resolve(cwd)
+                .as_abs_path()
+                .join(&build_opts.unstable_build_report_filename)
+                .parent()
+                .context("Build report path has no parent")?
+                .join(COVERAGE_REPORT_NAME)
+        } else {
+            let artifact_fs = ctx.get_artifact_fs().await?;
+            let path = artifact_fs
+                .buck_out_path_resolver()
+                .resolve_test(&BuckOutTestPath::new(
+                    session.prefix().to_buf(),
+                    ForwardRelativePathBuf::unchecked_new(COVERAGE_REPORT_NAME.to_owned()),
+                ));
+            server_ctx.project_root().resolve(&path).into_abs_path_buf()
+        };
+        Some(path)
+    } else {
+        None
+    };
 
-    let build_opts = request
-        .build_opts
-        .as_ref()
-        .expect("should have build options");
+    let coverage_merger = ctx
+        .get_legacy_config_property(
+            cell_resolver.root_cell(),
+            BuckconfigKeyRef {
+                section: "test",
+                property: "coverage_merger",
+            },
+        )
+        .await?
+        .filter(|s| !s.is_empty());
 
     let timeout = request
         .timeout
@@ -372,6 +488,7 @@ async fn test(
         MissingTargetBehavior::from_skip(build_opts.skip_missing_targets),
         timeout,
         request.ignore_tests_attribute,
+        changed_files,
     )
     .await?;
 
@@ -423,6 +540,68 @@ async fn test(
         ),
     };
 
+    let coverage_report = match coverage_report {
+        Some(output) => {
+            match merge_coverage(
+                coverage_merger.as_deref(),
+                server_ctx.project_root(),
+                test_outcome
+                    .undeclared_outputs
+                    .values()
+                    .flatten()
+                    .map(|p| p.as_ref()),
+                output,
+            )
+            .await
+            {
+                Ok(report) => report,
+                Err(e) => {
+                    // Coverage is best effort, it should not change the outcome of the tests.
+                    console_message(format!(
+                        "Error merging coverage data, no coverage report was written: {:#}",
+                        e
+                    ));
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    let serialized_build_report = if build_opts.unstable_print_build_report {
+        let mut outputs = HashMap::<TargetLabel, Vec<ProjectRelativePathBuf>>::new();
+        for (label, paths) in &test_outcome.undeclared_outputs {
+            outputs
+                .entry(label.target().unconfigured().dupe())
+                .or_default()
+                .extend(paths.iter().cloned());
+        }
+        let report = TestReport {
+            project_root: server_ctx.project_root().root().to_owned(),
+            outputs,
+            coverage_report: coverage_report.clone(),
+        };
+        write_test_report(
+            &report,
+            server_ctx.project_root(),
+            cwd,
+            &build_opts.unstable_build_report_filename,
+        )?
+    } else {
+        None
+    };
+
+    let undeclared_outputs = test_outcome
+        .undeclared_outputs
+        .into_iter()
+        .map(
+            |(label, paths)| buck2_cli_proto::test_response::UndeclaredOutputs {
+                target: label.to_string(),
+                paths: paths.into_iter().map(|p| p.to_string()).collect(),
+            },
+        )
+        .collect();
+
     Ok(TestResponse {
         exit_code,
         errors: test_outcome.errors,
@@ -430,9 +609,30 @@ async fn test(
         executor_stdout: test_outcome.executor_stdout,
         executor_stderr: test_outcome.executor_stderr,
         executor_info_messages: test_outcome.executor_report.info_messages,
+        undeclared_outputs,
+        serialized_build_report,
+        coverage_report: coverage_report.map(|p| p.to_string()),
     })
 }
 
+/// Write the report to `filename` (relative to the working directory) if provided, otherwise
+/// return it serialized so the client can print it.
+fn write_test_report(
+    report: &TestReport,
+    project_root: &ProjectRoot,
+    cwd: &ProjectRelativePath,
+    filename: &str,
+) -> anyhow::Result<Option<String>> {
+    if filename.is_empty() {
+        return Ok(Some(serde_json::to_string(report)?));
+    }
+
+    let file = fs_util::create_file(project_root.resolve(cwd).as_abs_path().join(filename))
+        .context("Error writing build report")?;
+    serde_json::to_writer_pretty(BufWriter::new(file), report)?;
+    Ok(None)
+}
+
 async fn test_targets(
     ctx: DiceTransaction,
     pattern: ResolvedPattern<ConfiguredProvidersPatternExtra>,
@@ -447,6 +647,7 @@ async fn test_targets(
     missing_target_behavior: MissingTargetBehavior,
     timeout: Option<Duration>,
     ignore_tests_attribute: bool,
+    changed_files: Option<Arc<ChangedFilesSelection>>,
 ) -> anyhow::Result<TestOutcome> {
     let session = Arc::new(session);
 
@@ -530,6 +731,7 @@ async fn test_targets(
                     working_dir_cell,
                     missing_target_behavior,
                     ignore_tests_attribute,
+                    changed_files: changed_files.as_deref(),
                 });
 
                 driver.push_pattern(
@@ -582,7 +784,11 @@ async fn test_targets(
 
                 // And finally return our results;
 
-                anyhow::Ok((driver.build_errors, test_statuses))
+                anyhow::Ok((
+                    driver.build_errors,
+                    test_statuses,
+                    session.undeclared_outputs(),
+                ))
             },
         )
     });
@@ -606,7 +812,7 @@ async fn test_targets(
     )));
 
     // TODO(bobyf, torozco) we can use cancellation handle here instead of liveliness observer
-    let (build_errors, executor_report) = test_server
+    let (build_errors, executor_report, undeclared_outputs) = test_server
         .await
         .context("Failed to collect executor report")??;
 
@@ -627,6 +833,7 @@ async fn test_targets(
         executor_stdout: executor_output.stdout,
         executor_stderr: executor_output.stderr,
         executor_report,
+        undeclared_outputs,
     })
 }
 
@@ -656,6 +863,8 @@ pub(crate) struct TestDriverState<'a, 'e> {
     working_dir_cell: CellName,
     missing_target_behavior: MissingTargetBehavior,
     ignore_tests_attribute: bool,
+    /// If set, only targets affected by the changed files are tested.
+    changed_files: Option<&'a ChangedFilesSelection>,
 }
 
 /// Maintains the state of an ongoing test execution.
@@ -748,10 +957,20 @@ impl<'a, 'e> TestDriver<'a, 'e> {
                     state.missing_target_behavior,
                 )?;
 
-                let labels = labels.into_map(|(target_name, providers_pattern)| {
+                let mut labels = labels.into_map(|(target_name, providers_pattern)| {
                     providers_pattern.into_providers_label(package.dupe(), target_name.as_ref())
                 });
 
+                if let Some(changed_files) = state.changed_files {
+                    labels.retain(|label| match changed_files.explain(label.target()) {
+                        Some(explanation) => {
+                            console_message(format!("Selected test target: {}", explanation));
+                            true
+                        }
+                        None => false,
+                    });
+                }
+
                 let work = labels
                     .into_iter()
                     .map(|label| TestDriverTask::ConfigureTarget { label, skippable })
@@ -807,6 +1026,13 @@ impl<'a, 'e> TestDriver<'a, 'e> {
             // Look up `tests` in the the target we're testing, and if we find any tests, add them to the test backlog.
             if !state.ignore_tests_attribute {
                 for test in node.tests() {
+                    if state.changed_files.is_some() {
+                        console_message(format!(
+                            "Selected test target: {} is in the `tests` attribute of `{}`",
+                            test.target().unconfigured(),
+                            node.label().unconfigured(),
+                        ));
+                    }
                     work.push(TestDriverTask::ConfigureTarget {
                         label: test.unconfigured(),
                         // Historically `skippable: false` is what we enforced here, perhaps that
@@ -0,0 +1,181 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Collection of native coverage data for `buck2 test --coverage`.
+//!
+//! Tests are pointed at their undeclared outputs directory via `LLVM_PROFILE_FILE`, so raw
+//! profiles are collected along with the rest of the undeclared outputs. Once all tests have
+//! finished, the raw profiles are passed to a user-provided merger tool (`test.coverage_merger`),
+//! which is invoked as:
+//!
+//! ```text
+//! <merger> --output <report.lcov> <raw profile>...
+//! ```
+
+use anyhow::Context;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::abs_path::AbsPath;
+use buck2_core::fs::paths::abs_path::AbsPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_events::dispatch::console_message;
+use buck2_util::process::async_background_command;
+
+/// The environment variable LLVM's profiling runtime reads to know where to write raw profiles.
+pub const LLVM_PROFILE_FILE_ENV_VAR: &str = "LLVM_PROFILE_FILE";
+
+/// Where raw profiles go, relative to the undeclared outputs directory. `%p` and `%m` keep
+/// profiles from concurrent processes and different binaries from clobbering each other.
+const RAW_PROFILES_DIR: &str = "coverage";
+const RAW_PROFILE_PATTERN: &str = "%p-%m.profraw";
+
+/// Name of the merged report.
+pub(crate) const COVERAGE_REPORT_NAME: &str = "coverage.lcov";
+
+/// The value of `LLVM_PROFILE_FILE` for a test with the given undeclared outputs directory.
+pub(crate) fn raw_profile_path(
+    undeclared_outputs: &ProjectRelativePath,
+) -> anyhow::Result<ProjectRelativePathBuf> {
+    Ok(undeclared_outputs
+        .join(ForwardRelativePath::new(RAW_PROFILES_DIR)?)
+        .join(ForwardRelativePath::new(RAW_PROFILE_PATTERN)?))
+}
+
+/// Find all the raw profiles written to the given undeclared outputs directories.
+pub(crate) fn find_raw_profiles<'a>(
+    project_root: &ProjectRoot,
+    undeclared_outputs: impl IntoIterator<Item = &'a ProjectRelativePath>,
+) -> anyhow::Result<Vec<AbsNormPathBuf>> {
+    let mut profiles = Vec::new();
+    for dir in undeclared_outputs {
+        let dir = project_root
+            .resolve(dir)
+            .join(ForwardRelativePath::new(RAW_PROFILES_DIR)?);
+        if !fs_util::try_exists(&dir)? {
+            continue;
+        }
+        for entry in fs_util::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |e| e == "profraw") {
+                profiles.push(path);
+            }
+        }
+    }
+    profiles.sort();
+    Ok(profiles)
+}
+
+/// Run the merger tool to produce a single LCOV report at `output`.
+pub(crate) async fn merge_raw_profiles(
+    merger: &str,
+    project_root: &ProjectRoot,
+    profiles: &[AbsNormPathBuf],
+    output: &AbsPath,
+) -> anyhow::Result<()> {
+    let result = async_background_command(merger)
+        .current_dir(project_root.root().as_path())
+        .arg("--output")
+        .arg(output.as_path())
+        .args(profiles.iter().map(|p| p.as_path()))
+        .output()
+        .await
+        .with_context(|| format!("Error running coverage merger `{}`", merger))?;
+
+    if !result.status.success() {
+        return Err(anyhow::anyhow!(
+            "Coverage merger `{}` failed with exit code {:?}, stderr:\n{}",
+            merger,
+            result.status.code(),
+            String::from_utf8_lossy(&result.stderr),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Merge the raw profiles found in `undeclared_outputs` into `output`. Returns `None` if there
+/// was nothing to merge or no merger is configured.
+pub(crate) async fn merge_coverage<'a>(
+    merger: Option<&str>,
+    project_root: &ProjectRoot,
+    undeclared_outputs: impl IntoIterator<Item = &'a ProjectRelativePath>,
+    output: AbsPathBuf,
+) -> anyhow::Result<Option<AbsPathBuf>> {
+    let profiles = find_raw_profiles(project_root, undeclared_outputs)?;
+    if profiles.is_empty() {
+        console_message("Coverage was requested, but no test wrote coverage data".to_owned());
+        return Ok(None);
+    }
+
+    let Some(merger) = merger else {
+        console_message(format!(
+            "Coverage was requested, but `test.coverage_merger` is not set. {} raw profiles were left in the test outputs",
+            profiles.len()
+        ));
+        return Ok(None);
+    };
+
+    merge_raw_profiles(merger, project_root, &profiles, &output).await?;
+    Ok(Some(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::fs::project::ProjectRootTemp;
+
+    use super::*;
+
+    #[test]
+    fn test_find_raw_profiles() -> anyhow::Result<()> {
+        let temp = ProjectRootTemp::new()?;
+        temp.write_file("test-outputs/a/coverage/2-x.profraw", "");
+        temp.write_file("test-outputs/a/coverage/notes.txt", "");
+        temp.write_file("test-outputs/b/coverage/1-y.profraw", "");
+        // Written outside of the coverage directory.
+        temp.write_file("test-outputs/b/3-z.profraw", "");
+
+        let root = temp.path();
+        let profiles = find_raw_profiles(
+            root,
+            [
+                ProjectRelativePath::new("test-outputs/a")?,
+                ProjectRelativePath::new("test-outputs/b")?,
+                // Its test wrote no outputs.
+                ProjectRelativePath::new("test-outputs/c")?,
+            ],
+        )?;
+
+        let mut expected = vec![
+            root.resolve(ProjectRelativePath::new(
+                "test-outputs/a/coverage/2-x.profraw",
+            )?),
+            root.resolve(ProjectRelativePath::new(
+                "test-outputs/b/coverage/1-y.profraw",
+            )?),
+        ];
+        expected.sort();
+        assert_eq!(expected, profiles);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_profile_path() -> anyhow::Result<()> {
+        assert_eq!(
+            ProjectRelativePathBuf::unchecked_new(
+                "test-outputs/a/coverage/%p-%m.profraw".to_owned()
+            ),
+            raw_profile_path(ProjectRelativePath::new("test-outputs/a")?)?
+        );
+        Ok(())
+    }
+}
@@ -12,7 +12,9 @@
 
 //! Implementation of test running.
 
+pub(crate) mod changed_files;
 pub mod command;
+pub mod coverage;
 pub mod downward_api;
 pub mod executor_launcher;
 pub(crate) mod local_resource_api;
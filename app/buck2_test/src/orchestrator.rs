@@ -111,9 +111,11 @@ use buck2_test_api::data::ExecutorConfigOverride;
 use buck2_test_api::data::ExternalRunnerSpecValue;
 use buck2_test_api::data::LocalExecutionCommand;
 use buck2_test_api::data::Output;
+use buck2_test_api::data::OutputName;
 use buck2_test_api::data::PrepareForLocalExecutionResult;
 use buck2_test_api::data::RequiredLocalResources;
 use buck2_test_api::data::TestResult;
+use buck2_test_api::data::TestSchedulingSummary;
 use buck2_test_api::protocol::TestOrchestrator;
 use derive_more::From;
 use dice::DiceTransaction;
@@ -130,6 +132,8 @@ use sorted_vector_map::SortedVectorMap;
 use starlark::values::FrozenRef;
 use uuid::Uuid;
 
+use crate::coverage::raw_profile_path;
+use crate::coverage::LLVM_PROFILE_FILE_ENV_VAR;
 use crate::local_resource_api::LocalResourcesSetupResult;
 use crate::local_resource_registry::LocalResourceRegistry;
 use crate::local_resource_setup::required_local_resources_setup_contexts;
@@ -140,6 +144,20 @@ use crate::translations;
 
 const MAX_SUFFIX_LEN: usize = 1024;
 
+/// Environment variable pointing tests at a directory where they can write arbitrary files
+/// (screenshots, logs, coverage data, ...) that will be collected once the test finishes.
+pub const UNDECLARED_OUTPUTS_ENV_VAR: &str = "TEST_UNDECLARED_OUTPUTS_DIR";
+
+/// The name under which the undeclared outputs directory is reported to the test executor.
+pub const UNDECLARED_OUTPUTS_OUTPUT_NAME: &str = "undeclared_outputs";
+
+#[derive(Debug, buck2_error_derive::Error)]
+#[buck2(input)]
+enum TestEnvError {
+    #[error("`{0}` is set by buck2 for every test and cannot be set in the test's `env`")]
+    Reserved(&'static str),
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum ExecutorMessage {
     TestResult(TestResult),
@@ -253,6 +271,7 @@ impl<'a> BuckTestOrchestrator<'a> {
             supports_re,
             declared_outputs,
             worker,
+            undeclared_outputs,
         } = test_executable_expanded;
 
         let executor_preference = self.executor_preference(supports_re)?;
@@ -320,6 +339,18 @@ impl<'a> BuckTestOrchestrator<'a> {
 
         for (test_path, artifact) in outputs {
             let project_relative_path = fs.buck_out_path_resolver().resolve_test(&test_path);
+            if test_path == undeclared_outputs {
+                // Undeclared outputs are meant to be inspected by users, so we always bring them
+                // back locally, regardless of where the test ran.
+                paths_to_materialize.push(project_relative_path.clone());
+                output_map.insert(
+                    OutputName::unchecked_new(UNDECLARED_OUTPUTS_OUTPUT_NAME.to_owned()),
+                    Output::LocalPath(fs.fs().resolve(&project_relative_path)),
+                );
+                self.session
+                    .record_undeclared_outputs(test_target.clone(), project_relative_path);
+                continue;
+            }
             let output_name = test_path.into_path().into();
             // It's OK to search iteratively here because there will be few entries in `pre_create_dirs`
             let remote_storage_config = pre_create_dirs
@@ -546,8 +577,16 @@ impl<'a> TestOrchestrator for BuckTestOrchestrator<'a> {
             supports_re: _,
             declared_outputs,
             worker: _,
+            undeclared_outputs,
         } = test_executable_expanded;
 
+        // Whoever runs the test locally will find its undeclared outputs there.
+        self.session.record_undeclared_outputs(
+            test_target.clone(),
+            fs.buck_out_path_resolver()
+                .resolve_test(&undeclared_outputs),
+        );
+
         let execution_request = self
             .create_command_execution_request(
                 cwd,
@@ -610,6 +649,22 @@ impl<'a> TestOrchestrator for BuckTestOrchestrator<'a> {
             .map_err(|_| anyhow::Error::msg("Message received after end-of-tests"))?;
         Ok(())
     }
+
+    async fn report_test_scheduling(&self, summary: TestSchedulingSummary) -> anyhow::Result<()> {
+        let TestSchedulingSummary {
+            estimated_wall_time,
+            actual_wall_time,
+            tests_with_history,
+            tests,
+        } = summary;
+        self.events.instant_event(buck2_data::TestScheduling {
+            estimated_wall_time: estimated_wall_time.and_then(|d| d.try_into().ok()),
+            actual_wall_time: actual_wall_time.try_into().ok(),
+            tests_with_history,
+            tests,
+        });
+        Ok(())
+    }
 }
 
 struct ExecuteData {
@@ -936,12 +991,12 @@ impl<'b> BuckTestOrchestrator<'b> {
         pre_create_dirs: Vec<DeclaredOutput>,
         executor_fs: &ExecutorFs<'_>,
     ) -> anyhow::Result<ExpandedTestExecutable> {
-        let output_root = self
-            .session
-            .prefix()
-            .join(ForwardRelativePathBuf::unchecked_new(
-                Uuid::new_v4().to_string(),
-            ));
+        let execution_id = ForwardRelativePathBuf::unchecked_new(Uuid::new_v4().to_string());
+        let output_root = self.session.prefix().join(&execution_id);
+        let undeclared_outputs = BuckOutTestPath::new(
+            self.session.undeclared_outputs_base(test_target.target())?,
+            execution_id,
+        );
 
         let mut declared_outputs = IndexMap::<BuckOutTestPath, OutputCreationBehavior>::new();
 
@@ -967,6 +1022,8 @@ impl<'b> BuckTestOrchestrator<'b> {
                 test_info,
                 output_root: &output_root,
                 declared_outputs: &mut declared_outputs,
+                undeclared_outputs: &undeclared_outputs,
+                coverage: opts.coverage,
                 fs: executor_fs,
                 cmd,
                 env,
@@ -997,6 +1054,7 @@ impl<'b> BuckTestOrchestrator<'b> {
             declared_outputs,
             supports_re,
             worker: expanded_worker,
+            undeclared_outputs,
         })
     }
 
@@ -1045,6 +1103,9 @@ impl<'b> BuckTestOrchestrator<'b> {
         if let Some(timeout) = timeout {
             request = request.with_timeout(timeout)
         }
+        if let Some(timeout_hook) = self.session.timeout_hook() {
+            request = request.with_timeout_hook(timeout_hook.to_vec());
+        }
         if let Some(host_sharing_requirements) = host_sharing_requirements {
             request = request.with_host_sharing_requirements(host_sharing_requirements);
         }
@@ -1232,6 +1293,8 @@ struct Execute2RequestExpander<'a> {
     test_info: &'a FrozenExternalRunnerTestInfo,
     output_root: &'a ForwardRelativePath,
     declared_outputs: &'a mut IndexMap<BuckOutTestPath, OutputCreationBehavior>,
+    undeclared_outputs: &'a BuckOutTestPath,
+    coverage: bool,
     fs: &'a ExecutorFs<'a>,
     cmd: Vec<ArgValue>,
     env: SortedVectorMap<String, ArgValue>,
@@ -1323,7 +1386,7 @@ impl<'a> Execute2RequestExpander<'a> {
             )?;
         }
 
-        let expanded_env = self
+        let mut expanded_env = self
             .env
             .into_iter()
             .map(|(k, v)| {
@@ -1340,6 +1403,38 @@ impl<'a> Execute2RequestExpander<'a> {
             })
             .collect::<Result<SortedVectorMap<_, _>, _>>()?;
 
+        {
+            let undeclared_outputs = self
+                .fs
+                .fs()
+                .buck_out_path_resolver()
+                .resolve_test(self.undeclared_outputs);
+            if self.coverage {
+                let profile = B::new(self.fs)
+                    .resolve_project_path(raw_profile_path(&undeclared_outputs)?)?
+                    .into_string();
+                if expanded_env
+                    .insert(LLVM_PROFILE_FILE_ENV_VAR.to_owned(), profile)
+                    .is_some()
+                {
+                    return Err(TestEnvError::Reserved(LLVM_PROFILE_FILE_ENV_VAR).into());
+                }
+            }
+            let path = B::new(self.fs)
+                .resolve_project_path(undeclared_outputs)?
+                .into_string();
+            if expanded_env
+                .insert(UNDECLARED_OUTPUTS_ENV_VAR.to_owned(), path)
+                .is_some()
+            {
+                return Err(TestEnvError::Reserved(UNDECLARED_OUTPUTS_ENV_VAR).into());
+            }
+            self.declared_outputs.insert(
+                self.undeclared_outputs.clone(),
+                OutputCreationBehavior::Create,
+            );
+        }
+
         let expanded_worker = match self.test_info.worker() {
             Some(worker) => {
                 let mut worker_rendered = Vec::<String>::new();
@@ -1402,6 +1497,8 @@ struct ExpandedTestExecutable {
     supports_re: bool,
     declared_outputs: IndexMap<BuckOutTestPath, OutputCreationBehavior>,
     worker: Option<WorkerSpec>,
+    /// Directory exposed to the test via `UNDECLARED_OUTPUTS_ENV_VAR`.
+    undeclared_outputs: BuckOutTestPath,
 }
 
 fn create_prepare_for_local_execution_result(
@@ -1695,6 +1792,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_result_has_undeclared_outputs() -> anyhow::Result<()> {
+        let session = TestSession::new(Default::default());
+        let label = ConfiguredProvidersLabel::new(
+            ConfiguredTargetLabel::testing_parse("cell//pkg:foo", ConfigurationData::testing_new()),
+            Default::default(),
+        );
+        let target = session.register(label.clone());
+        session.record_undeclared_outputs(
+            label,
+            ProjectRelativePathBuf::unchecked_new("buck-out/v2/test/outputs".to_owned()),
+        );
+
+        let result = translations::convert_test_result(
+            TestResult {
+                target,
+                status: TestStatus::PASS,
+                msg: None,
+                name: "test".to_owned(),
+                duration: None,
+                details: String::new(),
+            },
+            &session,
+        )?;
+        assert_eq!(
+            vec!["buck-out/v2/test/outputs".to_owned()],
+            result.undeclared_outputs
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn orchestrator_attach_info_messages() -> anyhow::Result<()> {
         let (orchestrator, channel) = make().await?;
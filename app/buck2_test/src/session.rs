@@ -7,13 +7,16 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering;
 
 use anyhow::Context as _;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
 use buck2_test_api::data::ConfiguredTargetHandle;
 use chrono::Local;
 use dashmap::DashMap;
@@ -25,6 +28,8 @@ pub struct TestSessionOptions {
     pub allow_re: bool,
     pub force_use_project_relative_paths: bool,
     pub force_run_from_project_root: bool,
+    /// Whether tests should write native coverage data to their undeclared outputs.
+    pub coverage: bool,
 }
 
 /// The state of a buck2 test command.
@@ -41,6 +46,11 @@ pub struct TestSession {
     /// Options overriding the behavior of tests executed in this session. This is primarily
     /// intended for unstable or debugging features.
     options: TestSessionOptions,
+    /// Undeclared output directories that were collected for each target once its executions
+    /// finished.
+    undeclared_outputs: DashMap<ConfiguredProvidersLabel, Vec<ProjectRelativePathBuf>>,
+    /// Command to run against local tests that time out, before they get killed.
+    timeout_hook: Option<Vec<String>>,
 }
 
 impl TestSession {
@@ -57,9 +67,20 @@ impl TestSession {
             labels: DashMap::new(),
             prefix,
             options,
+            undeclared_outputs: DashMap::new(),
+            timeout_hook: None,
         }
     }
 
+    pub fn with_timeout_hook(mut self, timeout_hook: Option<Vec<String>>) -> Self {
+        self.timeout_hook = timeout_hook;
+        self
+    }
+
+    pub fn timeout_hook(&self) -> Option<&[String]> {
+        self.timeout_hook.as_deref()
+    }
+
     pub fn options(&self) -> TestSessionOptions {
         self.options
     }
@@ -68,6 +89,52 @@ impl TestSession {
         self.prefix.as_ref()
     }
 
+    /// The base path (relative to the test output root) under which undeclared outputs for
+    /// executions of this target are placed.
+    pub fn undeclared_outputs_base(
+        &self,
+        target: &ConfiguredTargetLabel,
+    ) -> anyhow::Result<ForwardRelativePathBuf> {
+        let pkg = target.pkg();
+        Ok(ForwardRelativePathBuf::concat([
+            self.prefix.as_ref(),
+            ForwardRelativePath::unchecked_new("test-outputs"),
+            ForwardRelativePath::new(pkg.cell_name().as_str())?,
+            pkg.cell_relative_path().as_forward_relative_path(),
+            ForwardRelativePath::new(target.name().as_str())?,
+        ]))
+    }
+
+    /// Record an undeclared outputs directory that was collected for this target.
+    pub fn record_undeclared_outputs(
+        &self,
+        label: ConfiguredProvidersLabel,
+        path: ProjectRelativePathBuf,
+    ) {
+        self.undeclared_outputs.entry(label).or_default().push(path);
+    }
+
+    /// The undeclared outputs directories collected for this target so far.
+    pub fn undeclared_outputs_for(
+        &self,
+        label: &ConfiguredProvidersLabel,
+    ) -> Vec<ProjectRelativePathBuf> {
+        self.undeclared_outputs
+            .get(label)
+            .map(|paths| paths.clone())
+            .unwrap_or_default()
+    }
+
+    /// All the undeclared outputs directories collected in this session so far.
+    pub fn undeclared_outputs(
+        &self,
+    ) -> BTreeMap<ConfiguredProvidersLabel, Vec<ProjectRelativePathBuf>> {
+        self.undeclared_outputs
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect()
+    }
+
     /// Insert a new provider and retrieve the matching handle.
     pub fn register(&self, label: ConfiguredProvidersLabel) -> ConfiguredTargetHandle {
         let id = self.next_id.fetch_add(1, Ordering::Relaxed).into();
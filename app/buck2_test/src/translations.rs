@@ -69,6 +69,11 @@ pub(crate) fn convert_test_result(
         duration: duration.and_then(|d| d.try_into().ok()),
         details,
         target_label: Some(test_target.target().as_proto()),
+        undeclared_outputs: session
+            .undeclared_outputs_for(&test_target)
+            .into_iter()
+            .map(|path| path.to_string())
+            .collect(),
     })
 }
 
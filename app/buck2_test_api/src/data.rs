@@ -98,6 +98,20 @@ pub struct TestResult {
     pub details: String,
 }
 
+/// How a test runner scheduled the tests of a run, compared with what it expected.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TestSchedulingSummary {
+    // the wall time predicted from historical test durations, if the runner limited how many
+    // tests ran at a time
+    pub estimated_wall_time: Option<Duration>,
+    // the wall time the tests actually took
+    pub actual_wall_time: Duration,
+    // the number of tests that had a historical duration
+    pub tests_with_history: u64,
+    // the number of tests that were run
+    pub tests: u64,
+}
+
 /// different possible test results
 #[derive(PartialEq, Eq, Debug, Clone, Dupe)]
 #[allow(non_camel_case_types)]
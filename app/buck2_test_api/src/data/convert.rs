@@ -45,6 +45,7 @@ use crate::data::RemoteFile;
 use crate::data::RemoteObject;
 use crate::data::TestExecutable;
 use crate::data::TestResult;
+use crate::data::TestSchedulingSummary;
 use crate::data::TestStatus;
 use crate::protocol::convert::host_sharing_requirements_from_grpc;
 use crate::protocol::convert::host_sharing_requirements_to_grpc;
@@ -294,6 +295,48 @@ impl TryInto<buck2_test_proto::TestResult> for TestResult {
     }
 }
 
+impl TryFrom<buck2_test_proto::TestSchedulingSummary> for TestSchedulingSummary {
+    type Error = anyhow::Error;
+
+    fn try_from(s: buck2_test_proto::TestSchedulingSummary) -> Result<Self, Self::Error> {
+        let buck2_test_proto::TestSchedulingSummary {
+            estimated_wall_time,
+            actual_wall_time,
+            tests_with_history,
+            tests,
+        } = s;
+
+        Ok(Self {
+            estimated_wall_time: estimated_wall_time
+                .map(convert::to_std_duration)
+                .transpose()
+                .context("Invalid `estimated_wall_time`")?,
+            actual_wall_time: convert::to_std_duration(
+                actual_wall_time.context("Missing `actual_wall_time`")?,
+            )
+            .context("Invalid `actual_wall_time`")?,
+            tests_with_history,
+            tests,
+        })
+    }
+}
+
+impl TryInto<buck2_test_proto::TestSchedulingSummary> for TestSchedulingSummary {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<buck2_test_proto::TestSchedulingSummary, Self::Error> {
+        Ok(buck2_test_proto::TestSchedulingSummary {
+            estimated_wall_time: self
+                .estimated_wall_time
+                .map(TryInto::try_into)
+                .transpose()?,
+            actual_wall_time: Some(self.actual_wall_time.try_into()?),
+            tests_with_history: self.tests_with_history,
+            tests: self.tests,
+        })
+    }
+}
+
 impl TryFrom<buck2_test_proto::ExternalRunnerSpec> for ExternalRunnerSpec {
     type Error = anyhow::Error;
 
@@ -1132,6 +1175,19 @@ mod tests {
         assert_roundtrips::<buck2_test_proto::ExecutionResult2, ExecutionResult2>(&result);
     }
 
+    #[test]
+    fn test_scheduling_summary_roundtrip() {
+        let summary = TestSchedulingSummary {
+            estimated_wall_time: Some(Duration::from_millis(1500)),
+            actual_wall_time: Duration::from_secs(2),
+            tests_with_history: 3,
+            tests: 4,
+        };
+        assert_roundtrips::<buck2_test_proto::TestSchedulingSummary, TestSchedulingSummary>(
+            &summary,
+        );
+    }
+
     fn dummy_local_execution_command() -> LocalExecutionCommand {
         let cmd = vec![
             "my_cmd".to_owned(),
@@ -33,6 +33,7 @@ use buck2_test_proto::EndOfTestResultsRequest;
 use buck2_test_proto::ExecuteResponse2;
 use buck2_test_proto::PrepareForLocalExecutionResponse;
 use buck2_test_proto::ReportTestResultRequest;
+use buck2_test_proto::ReportTestSchedulingRequest;
 use buck2_test_proto::ReportTestSessionRequest;
 use buck2_test_proto::ReportTestsDiscoveredRequest;
 use buck2_test_proto::Testing;
@@ -59,6 +60,7 @@ use crate::data::PrepareForLocalExecutionResult;
 use crate::data::RequiredLocalResources;
 use crate::data::TestExecutable;
 use crate::data::TestResult;
+use crate::data::TestSchedulingSummary;
 use crate::protocol::TestOrchestrator;
 
 /// Test runner client to buck2 test orchestrator.
@@ -277,6 +279,21 @@ impl TestOrchestratorClient {
             .await?;
         Ok(())
     }
+
+    pub async fn report_test_scheduling(
+        &self,
+        summary: TestSchedulingSummary,
+    ) -> anyhow::Result<()> {
+        let summary = summary.try_into().context("Invalid `summary`")?;
+
+        self.test_orchestrator_client
+            .clone()
+            .report_test_scheduling(ReportTestSchedulingRequest {
+                summary: Some(summary),
+            })
+            .await?;
+        Ok(())
+    }
 }
 
 struct TestOrchestratorService<T: TestOrchestrator> {
@@ -480,6 +497,28 @@ where
         })
         .await
     }
+
+    async fn report_test_scheduling(
+        &self,
+        request: tonic::Request<ReportTestSchedulingRequest>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        to_tonic(async move {
+            let ReportTestSchedulingRequest { summary } = request.into_inner();
+
+            let summary = summary
+                .context("Missing `summary`")?
+                .try_into()
+                .context("Invalid `summary`")?;
+
+            self.inner
+                .report_test_scheduling(summary)
+                .await
+                .context("Failed to report test scheduling")?;
+
+            Ok(Empty {})
+        })
+        .await
+    }
 }
 
 struct DownwardApiService<T: DownwardApi> {
@@ -30,6 +30,7 @@ use crate::data::ExternalRunnerSpec;
 use crate::data::PrepareForLocalExecutionResult;
 use crate::data::RequiredLocalResources;
 use crate::data::TestResult;
+use crate::data::TestSchedulingSummary;
 
 /// available to buck to interact with the test executor
 #[async_trait::async_trait]
@@ -104,6 +105,10 @@ pub trait TestOrchestrator: Send + Sync {
     /// attach a message containing information that the executor wants to be surfaced
     /// to the user
     async fn attach_info_message(&self, message: String) -> anyhow::Result<()>;
+
+    /// report how the tests were scheduled, comparing the wall time the executor estimated with
+    /// the wall time the tests actually took
+    async fn report_test_scheduling(&self, summary: TestSchedulingSummary) -> anyhow::Result<()>;
 }
 
 // TODO need to figure out what this is. we can go without it for now
@@ -19,10 +19,16 @@ pub struct Config {
     #[clap(long)]
     pub env: Vec<EnvValue>,
 
-    /// Max number of seconds allowed to run a test.
+    /// Max number of seconds allowed to run a test. Tests can override this with a
+    /// `test_timeout=<seconds>` label.
     #[clap(long, default_value = "600", value_parser = try_parse_timeout_from_str)]
     pub timeout: Duration,
 
+    /// Max number of tests to run at a time. Tests expected to take the longest are started
+    /// first. Defaults to the number of CPUs of the machine.
+    #[clap(long)]
+    pub jobs: Option<usize>,
+
     /// Ignored arg included for backwards compatibility.
     #[clap(long, hide = true)]
     buck_test_info: String,
@@ -70,3 +76,38 @@ fn try_parse_timeout_from_str(input: &str) -> anyhow::Result<Duration> {
     let seconds = input.parse().context("Could not parse provided timeout")?;
     Ok(Duration::from_secs(seconds))
 }
+
+/// Prefix of test labels setting a per-target timeout, in seconds.
+const TIMEOUT_LABEL_PREFIX: &str = "test_timeout=";
+
+/// The timeout set by a `test_timeout=<seconds>` label, if any.
+pub fn timeout_from_labels(labels: &[String]) -> anyhow::Result<Option<Duration>> {
+    labels
+        .iter()
+        .find_map(|label| label.strip_prefix(TIMEOUT_LABEL_PREFIX))
+        .map(|seconds| {
+            try_parse_timeout_from_str(seconds)
+                .with_context(|| format!("Invalid label `{}{}`", TIMEOUT_LABEL_PREFIX, seconds))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_from_labels() {
+        let labels = |l: &[&str]| l.iter().map(|l| (*l).to_owned()).collect::<Vec<_>>();
+
+        assert_eq!(
+            None,
+            timeout_from_labels(&labels(&["slow", "timeout"])).unwrap()
+        );
+        assert_eq!(
+            Some(Duration::from_secs(30)),
+            timeout_from_labels(&labels(&["slow", "test_timeout=30"])).unwrap()
+        );
+        assert!(timeout_from_labels(&labels(&["test_timeout=long"])).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Historical test durations, used to start the longest tests first.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+
+/// Durations of the last run of each test, keyed by test name. Persisted as a JSON object
+/// mapping test names to milliseconds.
+#[derive(Debug, Default)]
+pub struct TestDurations {
+    path: Option<PathBuf>,
+    durations: BTreeMap<String, u64>,
+}
+
+impl TestDurations {
+    /// Load the durations stored at `path`. The store only informs scheduling, so a missing or
+    /// malformed store is treated as empty.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let durations = path
+            .as_ref()
+            .and_then(|path| serde_json::from_slice(&std::fs::read(path).ok()?).ok())
+            .unwrap_or_default();
+        Self { path, durations }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.durations.get(name).copied().map(Duration::from_millis)
+    }
+
+    /// The estimate to use for tests that have never run: the mean of the known durations.
+    pub fn default_estimate(&self) -> Duration {
+        if self.durations.is_empty() {
+            return Duration::ZERO;
+        }
+        let total: u64 = self.durations.values().sum();
+        Duration::from_millis(total / self.durations.len() as u64)
+    }
+
+    pub fn record(&mut self, name: String, duration: Duration) {
+        let millis = duration.as_millis().try_into().unwrap_or(u64::MAX);
+        self.durations.insert(name, millis);
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Error creating `{}`", parent.display()))?;
+        }
+        // Write then rename, so that a concurrent run never reads a partial store.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&self.durations)?)
+            .with_context(|| format!("Error writing `{}`", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Error writing `{}`", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Estimate the wall time of running tests taking `durations` with `jobs` tests running at a
+/// time, longest first.
+pub fn estimate_wall_time(durations: impl IntoIterator<Item = Duration>, jobs: usize) -> Duration {
+    let mut durations: Vec<_> = durations.into_iter().collect();
+    durations.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut slots: BinaryHeap<Reverse<Duration>> =
+        (0..jobs.max(1)).map(|_| Reverse(Duration::ZERO)).collect();
+    for duration in durations {
+        if let Some(Reverse(busy)) = slots.pop() {
+            slots.push(Reverse(busy + duration));
+        }
+    }
+    slots
+        .into_iter()
+        .map(|Reverse(busy)| busy)
+        .max()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_estimate_wall_time() {
+        let secs = |s: &[u64]| {
+            s.iter()
+                .map(|s| Duration::from_secs(*s))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(Duration::ZERO, estimate_wall_time(Vec::new(), 4));
+        assert_eq!(
+            Duration::from_secs(10),
+            estimate_wall_time(secs(&[1, 2, 3, 4]), 1)
+        );
+        // Longest first: 5 | 3 + 1 | 2 + 2, then the last 1 goes after one of the 4s.
+        assert_eq!(
+            Duration::from_secs(5),
+            estimate_wall_time(secs(&[1, 2, 5, 1, 3, 2]), 3)
+        );
+        assert_eq!(Duration::from_secs(7), estimate_wall_time(secs(&[7]), 0));
+    }
+
+    #[test]
+    fn test_default_estimate() {
+        let mut durations = TestDurations::default();
+        assert_eq!(Duration::ZERO, durations.default_estimate());
+
+        durations.record("a".to_owned(), Duration::from_secs(1));
+        durations.record("b".to_owned(), Duration::from_secs(3));
+        assert_eq!(Duration::from_secs(2), durations.default_estimate());
+        assert_eq!(Some(Duration::from_secs(3)), durations.get("b"));
+        assert_eq!(None, durations.get("c"));
+    }
+}
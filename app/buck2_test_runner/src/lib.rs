@@ -10,6 +10,7 @@
 #![feature(error_generic_member_access)]
 
 mod config;
+mod durations;
 mod executor;
 mod runner;
 mod service;
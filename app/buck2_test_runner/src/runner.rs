@@ -7,6 +7,14 @@
  * of this source tree.
  */
 
+use std::cmp::Ordering;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
 use anyhow::Context;
 use buck2_test_api::data::ArgValue;
 use buck2_test_api::data::ArgValueContent;
@@ -19,16 +27,21 @@ use buck2_test_api::data::ExternalRunnerSpec;
 use buck2_test_api::data::ExternalRunnerSpecValue;
 use buck2_test_api::data::RequiredLocalResources;
 use buck2_test_api::data::TestResult;
+use buck2_test_api::data::TestSchedulingSummary;
 use buck2_test_api::data::TestStatus;
 use buck2_test_api::grpc::TestOrchestratorClient;
 use clap::Parser;
 use futures::channel::mpsc::UnboundedReceiver;
+use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use host_sharing::HostSharingRequirements;
 use parking_lot::Mutex;
 
+use crate::config::timeout_from_labels;
 use crate::config::Config;
 use crate::config::EnvValue;
+use crate::durations::estimate_wall_time;
+use crate::durations::TestDurations;
 
 pub type SpecReceiver = UnboundedReceiver<ExternalRunnerSpec>;
 
@@ -43,6 +56,8 @@ pub struct Buck2TestRunner {
     orchestrator_client: TestOrchestratorClient,
     spec_receiver: Mutex<Option<SpecReceiver>>,
     config: Config,
+    jobs: usize,
+    durations: Mutex<TestDurations>,
 }
 
 impl Buck2TestRunner {
@@ -50,17 +65,24 @@ impl Buck2TestRunner {
         orchestrator_client: TestOrchestratorClient,
         spec_receiver: SpecReceiver,
         args: Vec<String>,
+        durations_file: Option<PathBuf>,
     ) -> anyhow::Result<Self> {
         let config = Config::try_parse_from(args).context("Error parsing test runner arguments")?;
+        let jobs = match config.jobs {
+            Some(jobs) => jobs.max(1),
+            None => std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        };
         Ok(Self {
             orchestrator_client,
             spec_receiver: Mutex::new(Some(spec_receiver)),
             config,
+            jobs,
+            durations: Mutex::new(TestDurations::load(durations_file)),
         })
     }
 
     pub async fn run_all_tests(&self) -> anyhow::Result<()> {
-        let receiver;
+        let mut receiver;
         {
             let mut maybe_receiver = self.spec_receiver.lock();
             receiver = maybe_receiver
@@ -68,57 +90,130 @@ impl Buck2TestRunner {
                 .context("Spec channel has already been consumed")?;
             drop(maybe_receiver);
         }
-        let run_verdict = receiver
-            .map(|spec| async move {
-                let name = format!(
-                    "{}//{}:{}",
-                    spec.target.cell, spec.target.package, spec.target.target
-                );
-                let target_handle = spec.target.handle.to_owned();
-
-                let execution_response = self
-                    .execute_test_from_spec(spec)
-                    .await
-                    .expect("Test execution request failed");
-
-                let execution_result = match execution_response {
-                    ExecuteResponse::Result(r) => r,
-                    ExecuteResponse::Cancelled => return TestStatus::OMITTED,
-                };
 
-                let test_result = get_test_result(name, target_handle, execution_result);
-                let test_status = test_result.status.clone();
+        let default_estimate = self.durations.lock().default_estimate();
 
-                self.report_test_result(test_result)
-                    .await
-                    .expect("Test result reporting failed");
+        // Tests that were discovered but not started yet, longest first. Tests that never ran
+        // before are assumed to take as long as an average test.
+        let mut pending = BinaryHeap::new();
+        let mut running = FuturesUnordered::new();
+        let mut receiving = true;
+        let mut estimates = Vec::new();
+        let mut tests_with_history = 0;
+        let mut started = None;
+        // If any individual test failed, consider the entire run to have failed.
+        let mut run_verdict = RunVerdict::Pass;
 
-                test_status
-            })
-            // Use an arbitrarily large buffer -- execution throttling will be handled by the Buck2
-            // executor, so no need to hold back on requests here.
-            .buffer_unordered(10000)
-            // If any individual test failed, consider the entire run to have failed.
-            .fold(
-                RunVerdict::Pass,
-                |mut run_verdict, test_status| async move {
+        loop {
+            while running.len() < self.jobs {
+                let Some(PendingTest { spec, .. }) = pending.pop() else {
+                    break;
+                };
+                started.get_or_insert_with(Instant::now);
+                running.push(self.run_test(spec));
+            }
+
+            if !receiving && running.is_empty() {
+                break;
+            }
+
+            tokio::select! {
+                spec = receiver.next(), if receiving => match spec {
+                    Some(spec) => {
+                        let history = self.durations.lock().get(&test_name(&spec));
+                        if history.is_some() {
+                            tests_with_history += 1;
+                        }
+                        let estimate = history.unwrap_or(default_estimate);
+                        estimates.push(estimate);
+                        pending.push(PendingTest {
+                            estimate,
+                            index: estimates.len(),
+                            spec,
+                        });
+                    }
+                    None => receiving = false,
+                },
+                Some(test_status) = running.next(), if !running.is_empty() => {
                     if test_status != TestStatus::PASS {
                         run_verdict = RunVerdict::Fail;
                     }
-                    run_verdict
-                },
-            )
-            .await;
+                }
+            }
+        }
+
+        let actual_wall_time = started.map_or(Duration::ZERO, |started| started.elapsed());
+
+        let saved = self.durations.lock().save();
+        if let Err(e) = saved {
+            self.orchestrator_client
+                .attach_info_message(format!("Failed to save test durations: {:#}", e))
+                .await?;
+        }
+
+        self.orchestrator_client
+            .report_test_scheduling(TestSchedulingSummary {
+                // With no more tests than jobs, the jobs never limited anything and the wall time
+                // only depends on how the executors scheduled the tests.
+                estimated_wall_time: (estimates.len() > self.jobs)
+                    .then(|| estimate_wall_time(estimates.iter().copied(), self.jobs)),
+                actual_wall_time,
+                tests_with_history,
+                tests: estimates.len() as u64,
+            })
+            .await?;
 
         self.orchestrator_client
             .end_of_test_results(run_verdict.exit_code())
             .await
     }
 
+    async fn run_test(&self, spec: ExternalRunnerSpec) -> TestStatus {
+        let name = test_name(&spec);
+        let target_handle = spec.target.handle.to_owned();
+
+        let execution_response = self
+            .execute_test_from_spec(spec)
+            .await
+            .expect("Test execution request failed");
+
+        let execution_result = match execution_response {
+            ExecuteResponse::Result(r) => r,
+            ExecuteResponse::Cancelled => return TestStatus::OMITTED,
+        };
+
+        self.durations
+            .lock()
+            .record(name.clone(), execution_result.execution_time);
+
+        let test_result = get_test_result(name, target_handle, execution_result);
+        let test_status = test_result.status.clone();
+
+        self.report_test_result(test_result)
+            .await
+            .expect("Test result reporting failed");
+
+        test_status
+    }
+
     async fn execute_test_from_spec(
         &self,
         spec: ExternalRunnerSpec,
     ) -> anyhow::Result<ExecuteResponse> {
+        let timeout = match timeout_from_labels(&spec.labels) {
+            Ok(timeout) => timeout.unwrap_or(self.config.timeout),
+            Err(e) => {
+                self.orchestrator_client
+                    .attach_info_message(format!(
+                        "Using the default timeout for {}: {:#}",
+                        test_name(&spec),
+                        e
+                    ))
+                    .await?;
+                self.config.timeout
+            }
+        };
+
         let display_metadata = DisplayMetadata::Testing {
             suite: spec.target.target,
             testcases: Vec::new(),
@@ -179,7 +274,7 @@ impl Buck2TestRunner {
                 target_handle,
                 command,
                 env,
-                self.config.timeout,
+                timeout,
                 host_sharing_requirements,
                 pre_create_dirs,
                 executor_override,
@@ -195,6 +290,46 @@ impl Buck2TestRunner {
     }
 }
 
+fn test_name(spec: &ExternalRunnerSpec) -> String {
+    format!(
+        "{}//{}:{}",
+        spec.target.cell, spec.target.package, spec.target.target
+    )
+}
+
+/// A test waiting for a free slot. Ordered by estimated duration, then by discovery order.
+struct PendingTest {
+    estimate: Duration,
+    index: usize,
+    spec: ExternalRunnerSpec,
+}
+
+impl PendingTest {
+    fn key(&self) -> (Duration, Reverse<usize>) {
+        (self.estimate, Reverse(self.index))
+    }
+}
+
+impl PartialEq for PendingTest {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for PendingTest {}
+
+impl PartialOrd for PendingTest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingTest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
 fn get_test_result(
     name: String,
     target: ConfiguredTargetHandle,
@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+use std::path::PathBuf;
+
 use anyhow::Context;
 use buck2_grpc::DuplexChannel;
 use buck2_test_api::grpc::spawn_executor_server;
@@ -21,6 +23,7 @@ pub async fn run<OC, ER, EW>(
     orchestrator_channel: OC,
     executor_channel: DuplexChannel<ER, EW>,
     args: Vec<String>,
+    durations_file: Option<PathBuf>,
 ) -> anyhow::Result<()>
 where
     OC: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static,
@@ -36,7 +39,7 @@ where
         .await
         .context("Failed to TestOrchestratorClient")?;
 
-    let runner = Buck2TestRunner::new(orchestrator_client, spec_receiver, args)?;
+    let runner = Buck2TestRunner::new(orchestrator_client, spec_receiver, args, durations_file)?;
 
     runner.run_all_tests().await?;
 
@@ -8,6 +8,7 @@
  */
 
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
 use buck2_grpc::DuplexChannel;
 use clap::Parser;
@@ -21,6 +22,10 @@ pub struct Buck2TestRunnerTcp {
     #[clap(long)]
     orchestrator_addr: String,
 
+    /// Where to read and record how long each test took, to start the longest tests first.
+    #[clap(long)]
+    durations_file: Option<PathBuf>,
+
     args: Vec<String>,
 }
 
@@ -58,6 +63,6 @@ impl Buck2TestRunnerTcp {
             DuplexChannel::new(read, write)
         };
 
-        crate::service::run(orchestrator_io, executor_io, self.args).await
+        crate::service::run(orchestrator_io, executor_io, self.args, self.durations_file).await
     }
 }
@@ -10,6 +10,7 @@
 use std::os::unix::io::FromRawFd;
 use std::os::unix::io::RawFd;
 use std::os::unix::net::UnixStream as StdUnixStream;
+use std::path::PathBuf;
 
 use buck2_grpc::DuplexChannel;
 use clap::Parser;
@@ -23,6 +24,10 @@ pub struct Buck2TestRunnerUnix {
     #[clap(long)]
     orchestrator_fd: RawFd,
 
+    /// Where to read and record how long each test took, to start the longest tests first.
+    #[clap(long)]
+    durations_file: Option<PathBuf>,
+
     args: Vec<String>,
 }
 
@@ -51,6 +56,6 @@ impl Buck2TestRunnerUnix {
             DuplexChannel::new(read, write)
         };
 
-        crate::service::run(orchestrator_io, executor_io, self.args).await
+        crate::service::run(orchestrator_io, executor_io, self.args, self.durations_file).await
     }
 }
@@ -11,15 +11,20 @@
 
 pub mod activation_tracker;
 pub mod computations;
+pub mod concurrency;
 pub mod cycles;
 pub mod data;
 pub mod dice;
 pub mod error;
 pub mod events;
+pub mod eviction;
 pub mod injected;
+pub mod invalidations;
 pub mod key;
 pub mod opaque;
+pub mod persistence;
 pub mod projection;
+pub mod recording;
 pub mod storage_type;
 pub mod transaction;
 pub mod user_data;
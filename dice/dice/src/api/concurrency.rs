@@ -0,0 +1,48 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Concurrency classes, to stop wide fan-outs of cheap keys from starving important computations.
+//!
+//! A `Key` can declare a [`ConcurrencyClass`] through `Key::concurrency_class`. At most `limit`
+//! computations of keys of a class run at once, the others wait in a queue. Checking whether the
+//! deps of a previous value changed isn't limited, only running `Key::compute` is.
+//!
+//! When a limit across all classes is set with
+//! [`DiceDataBuilder::set_concurrency_limit`](crate::DiceDataBuilder::set_concurrency_limit),
+//! the queued computations of higher priority classes start first once computations finish.
+//!
+//! A computation gives back its slot while it waits on deps that aren't computed yet, and queues
+//! for one again before resuming, ahead of the computations that haven't started. So keys can
+//! depend on keys of any class, including their own, without deadlocking once the limits are
+//! reached. When a compute waits on deps from parallel computes, the computes that keep running
+//! meanwhile aren't counted against the limits.
+
+use allocative::Allocative;
+use dupe::Dupe;
+
+/// A class of keys whose computations are limited and scheduled together. Classes are told apart
+/// by name, so all keys of a class should use the same limit and priority.
+#[derive(Allocative, Debug, Clone, Copy, Dupe, PartialEq, Eq, Hash)]
+pub struct ConcurrencyClass {
+    pub name: &'static str,
+    /// How many computations of this class can run at once.
+    pub limit: usize,
+    /// Queued computations of classes with a higher priority start first.
+    pub priority: u32,
+}
+
+impl ConcurrencyClass {
+    pub const fn new(name: &'static str, limit: usize, priority: u32) -> Self {
+        Self {
+            name,
+            limit,
+            priority,
+        }
+    }
+}
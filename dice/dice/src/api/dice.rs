@@ -171,6 +171,13 @@ use futures::future::Future;
 use serde::Serializer;
 
 use crate::api::cycles::DetectCycles;
+use crate::api::eviction::EvictionStats;
+use crate::api::invalidations::DiceInvalidationPath;
+use crate::api::invalidations::DiceInvalidations;
+use crate::api::persistence::DicePersistence;
+use crate::api::persistence::DiceSnapshot;
+use crate::api::recording::DiceRecording;
+use crate::api::transaction::DiceEquality;
 use crate::api::transaction::DiceTransactionUpdater;
 use crate::api::user_data::UserComputationData;
 use crate::metrics::Metrics;
@@ -249,6 +256,72 @@ impl Dice {
     pub async fn is_idle(&self) -> bool {
         self.implementation.is_idle().await
     }
+
+    /// Saves the nodes of the keys registered in `persistence` which are valid at the latest
+    /// version, along with `metadata`. See `api::persistence`.
+    pub async fn snapshot(
+        &self,
+        persistence: &DicePersistence,
+        metadata: String,
+    ) -> anyhow::Result<DiceSnapshot> {
+        self.implementation.snapshot(persistence, metadata).await
+    }
+
+    /// Loads a snapshot into a graph which has no nodes yet, e.g. right after it was built.
+    /// Nodes of key types which aren't registered in `persistence` are skipped, along with the
+    /// nodes that depend on them. Returns the number of restored nodes.
+    pub async fn restore(
+        &self,
+        snapshot: &DiceSnapshot,
+        persistence: &DicePersistence,
+    ) -> anyhow::Result<usize> {
+        self.implementation.restore(snapshot, persistence).await
+    }
+
+    /// Evicts the values of the least recently used computed nodes until the remaining ones use
+    /// at most `memory_budget` bytes, as measured by `allocative`. Evicted values are recomputed
+    /// when requested again. See `api::eviction`.
+    pub async fn evict_cold_values(&self, memory_budget: u64) -> EvictionStats {
+        self.implementation.evict_cold_values(memory_budget).await
+    }
+
+    /// Starts or stops recording why nodes get invalidated. Stopping drops what was recorded.
+    /// See `api::invalidations`.
+    pub fn set_invalidation_tracing(&self, enabled: bool) {
+        self.implementation.set_invalidation_tracing(enabled)
+    }
+
+    /// The recorded paths to the dirtied keys whose `Display` is accepted by `matches`, most
+    /// recent updates first, and shortest paths first within an update.
+    pub async fn invalidation_paths(
+        &self,
+        matches: impl Fn(&str) -> bool + Send + 'static,
+    ) -> Vec<DiceInvalidationPath> {
+        self.implementation
+            .invalidation_paths(Box::new(matches))
+            .await
+    }
+
+    /// The number of nodes dirtied by the update that created `version`, along with the shortest
+    /// `limit` paths to them. `None` if that update wasn't traced, or its trace was dropped.
+    pub async fn invalidations_at(
+        &self,
+        version: DiceEquality,
+        limit: usize,
+    ) -> Option<DiceInvalidations> {
+        self.implementation.invalidations_at(version, limit).await
+    }
+
+    /// Starts recording the transactions committed from now on, dropping anything recorded
+    /// before. See `api::recording`.
+    pub fn start_recording(&self) {
+        self.implementation.start_recording()
+    }
+
+    /// Stops recording and returns the recording, or `None` if it wasn't recording.
+    pub fn stop_recording(&self) -> Option<DiceRecording> {
+        self.implementation.stop_recording()
+    }
 }
 
 pub struct DiceDataBuilder(DiceDataBuilderImpl);
@@ -258,6 +331,12 @@ impl DiceDataBuilder {
         self.0.set(val);
     }
 
+    /// Limits how many computations of keys with a concurrency class run at once, across all
+    /// classes. Queued computations then start in priority order. See `api::concurrency`.
+    pub fn set_concurrency_limit(&mut self, limit: usize) {
+        self.0.set_concurrency_limit(limit);
+    }
+
     pub fn build(self, detect_cycles: DetectCycles) -> Arc<Dice> {
         self.0.build(detect_cycles)
     }
@@ -9,6 +9,8 @@
 
 use allocative::Allocative;
 
+use crate::api::concurrency::ConcurrencyClass;
+
 #[derive(Allocative, PartialEq, Eq, Debug)]
 pub enum DiceEvent {
     /// Key evaluation started.
@@ -28,6 +30,25 @@ pub enum DiceEvent {
 
     /// Compute has finished.
     ComputeFinished { key_type: &'static str },
+
+    /// A recomputation produced a value equal to the previous one, so the keys depending on it
+    /// don't need to be recomputed.
+    RecomputeUnchanged { key_type: &'static str },
+
+    /// A recomputation produced a value different from the previous one.
+    RecomputeChanged { key_type: &'static str },
+
+    /// Compute is waiting for its concurrency class to have room, see `api::concurrency`.
+    ConcurrencyQueued { class: ConcurrencyClass },
+
+    /// Compute has stopped waiting for its concurrency class, either starting or being cancelled.
+    ConcurrencyDequeued { class: ConcurrencyClass },
+
+    /// Compute has taken a slot of its concurrency class.
+    ConcurrencyStarted { class: ConcurrencyClass },
+
+    /// Compute has given back its slot, either finishing or waiting on its deps.
+    ConcurrencyFinished { class: ConcurrencyClass },
 }
 
 pub trait DiceEventListener: Allocative + Send + Sync + 'static {
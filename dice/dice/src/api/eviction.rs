@@ -0,0 +1,34 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Bounding the memory used by the graph by evicting the values of the least recently used
+//! nodes.
+//!
+//! Evicting a node only drops its value: its deps, rdeps and history are kept, so it still
+//! propagates invalidations. The value is recomputed when the node is requested again. There's
+//! nothing left to compare it with, so it counts as changed: dependents checked against it are
+//! recomputed too.
+//!
+//! Only values of keys opting in with [`Key::evictable`](crate::Key::evictable) are evicted.
+//! Injected values can't be recomputed, and are never evicted.
+
+/// The outcome of [`Dice::evict_cold_values`](crate::Dice::evict_cold_values).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EvictionStats {
+    /// The number of values that could be evicted.
+    pub candidate_count: usize,
+    /// The memory used by those values, as measured by `allocative`, before eviction. Data shared
+    /// by several values is only counted once.
+    pub candidate_bytes: u64,
+    /// The number of evicted values.
+    pub evicted_count: usize,
+    /// The memory used by the values selected for eviction. Values used again while they were
+    /// measured are kept, so this can be more than what was actually evicted.
+    pub evicted_bytes: u64,
+}
@@ -0,0 +1,39 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Tracing why nodes were invalidated, to answer "why was this recomputed?".
+//!
+//! When enabled with [`Dice::set_invalidation_tracing`](crate::Dice::set_invalidation_tracing),
+//! every update that changes keys records, for each node it dirties, the dep through which it
+//! was dirtied. Following those back gives the dependency path from a changed key to the node.
+//!
+//! A node that is reachable from several changed keys only records the first path found. A node
+//! that was already dirty isn't dirtied again, so its cause is found in the trace of the update
+//! that first dirtied it. Only the traces of the most recent updates are kept.
+
+use crate::api::transaction::DiceEquality;
+
+/// The dependency path through which a change dirtied a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceInvalidationPath {
+    /// The version created by the update that made the change.
+    pub version: DiceEquality,
+    /// The changed key first, then each key depending on the previous one, ending with the
+    /// dirtied key. A changed key has a path to itself only.
+    pub keys: Vec<String>,
+}
+
+/// What an update dirtied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceInvalidations {
+    /// The number of dirtied nodes, including the changed keys themselves.
+    pub dirtied_count: usize,
+    /// The paths to some of the dirtied nodes, shortest first.
+    pub paths: Vec<DiceInvalidationPath>,
+}
@@ -17,6 +17,7 @@ use buck2_futures::cancellation::CancellationContext;
 use dupe::Dupe;
 
 use crate::api::computations::DiceComputations;
+use crate::api::concurrency::ConcurrencyClass;
 use crate::api::storage_type::StorageType;
 use crate::introspection::graph::short_type_name;
 
@@ -65,4 +66,18 @@ pub trait Key: Allocative + Debug + Display + Clone + Eq + Hash + Send + Sync +
     fn storage_type() -> StorageType {
         StorageType::Normal
     }
+
+    /// Limits how many computations of this key type run at once, see `api::concurrency`. By
+    /// default computations start as soon as they're requested.
+    fn concurrency_class() -> Option<ConcurrencyClass> {
+        None
+    }
+
+    /// Whether values of this key can be evicted to save memory, see `api::eviction`. Only opt
+    /// in when recomputing the value is deterministic in the key's deps and cheaper to redo than
+    /// to keep: an evicted value is recomputed from scratch, and dependents checked against it
+    /// then see it as changed.
+    fn evictable() -> bool {
+        false
+    }
 }
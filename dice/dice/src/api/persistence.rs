@@ -0,0 +1,450 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Saving the graph to disk, so that a new DICE instance (e.g. after a restart) can start from
+//! the values computed by an older one.
+//!
+//! Only keys whose type is registered in a [`DicePersistence`] are saved, and only if every key
+//! they transitively depend on is saved too. Key types whose values can't be serialized can be
+//! registered without their values: their nodes are restored like evicted ones, keeping their
+//! edges, and the value is recomputed when it's first needed. Projection keys are saved with
+//! their values, as the opaque key they are derived from is usually saved without its value.
+//!
+//! A restored graph behaves as if all its values were computed at the first version of the new
+//! instance: the caller is responsible for invalidating everything that changed since the
+//! snapshot was taken (e.g. files changed while no daemon was running) in its first transaction.
+
+use std::any::Any;
+use std::any::TypeId;
+use std::io::Read;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use dupe::Dupe;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::api::data::DiceData;
+use crate::api::key::Key;
+use crate::api::projection::ProjectionKey;
+use crate::api::storage_type::StorageType;
+use crate::impls::core::graph::storage::PersistedNode;
+use crate::impls::key::CowDiceKeyHashed;
+use crate::impls::key::DiceKey;
+use crate::impls::key_index::DiceKeyIndex;
+use crate::impls::value::DiceKeyValue;
+use crate::impls::value::DiceProjectValue;
+use crate::impls::value::DiceValidValue;
+use crate::impls::value::DiceValidity;
+use crate::impls::value::MaybeValidDiceValue;
+use crate::HashMap;
+use crate::HashSet;
+
+/// A `Key` that can be saved to a snapshot.
+pub trait PersistentKey: Key + Serialize + DeserializeOwned {
+    /// Identifies the key type in snapshots. Must be unique among the registered key types, and
+    /// should change whenever the serialized form of the key or its value changes.
+    const PERSISTENT_ID: &'static str;
+}
+
+/// A `PersistentKey` whose values are saved along with it, see [`DicePersistence::register`].
+pub trait PersistentValue: PersistentKey {
+    /// The serialized form of the values.
+    type Persisted: Serialize + DeserializeOwned;
+
+    /// Returns `None` for values which can't be saved (e.g. errors), in which case neither the
+    /// key nor anything depending on it is saved.
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted>;
+
+    /// `data` is the global data of the DICE instance the value is restored into.
+    fn from_persisted(value: Self::Persisted, data: &DiceData) -> anyhow::Result<Self::Value>;
+}
+
+/// A `ProjectionKey` that can be saved to a snapshot along with its values, see
+/// [`DicePersistence::register_projection`].
+pub trait PersistentProjection: ProjectionKey + Serialize + DeserializeOwned {
+    /// Same as [`PersistentKey::PERSISTENT_ID`].
+    const PERSISTENT_ID: &'static str;
+
+    type Persisted: Serialize + DeserializeOwned;
+
+    /// Same as [`PersistentValue::to_persisted`].
+    fn to_persisted(value: &Self::Value) -> Option<Self::Persisted>;
+
+    fn from_persisted(value: Self::Persisted, data: &DiceData) -> anyhow::Result<Self::Value>;
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum PersistenceError {
+    #[error("Key type `{0}` is registered for persistence more than once")]
+    DuplicateId(&'static str),
+    #[error("Not a DICE snapshot, or a snapshot written by an incompatible version")]
+    InvalidHeader,
+    #[error("Snapshot node refers to a dependency which does not precede it")]
+    InvalidDep,
+    #[error("Snapshot node refers to an unknown key type index `{0}`")]
+    InvalidKeyType(u32),
+    #[error("Value of key `{0}` is not valid and cannot be restored")]
+    InvalidValue(String),
+    #[error("Cannot restore a snapshot into a DICE graph which already has nodes")]
+    GraphNotEmpty,
+    #[error("Snapshot node of projection key `{0}` has no base key")]
+    MissingProjectionBase(String),
+    #[error("Injected key type `{0}` cannot be restored without its values")]
+    InjectedWithoutValue(&'static str),
+}
+
+/// Type erased (de)serialization of a registered `PersistentKey` and its value.
+pub(crate) trait PersistentKeyCodec: Send + Sync + 'static {
+    fn id(&self) -> &'static str;
+
+    /// Returns `None` if the node can't be saved. `value` is `None` for evicted nodes.
+    fn encode(
+        &self,
+        key: &dyn Any,
+        value: Option<&DiceValidValue>,
+    ) -> anyhow::Result<Option<(Vec<u8>, Option<Vec<u8>>)>>;
+
+    /// `deps` are the already restored deps of the node: for projections, the first one is the
+    /// key it is derived from.
+    fn decode(
+        &self,
+        key_index: &DiceKeyIndex,
+        data: &DiceData,
+        key: &[u8],
+        value: Option<&[u8]>,
+        deps: &[DiceKey],
+    ) -> anyhow::Result<(DiceKey, Option<DiceValidValue>, StorageType)>;
+}
+
+/// Saves keys along with their values.
+struct Codec<K>(PhantomData<fn() -> K>);
+
+impl<K: PersistentValue> PersistentKeyCodec for Codec<K> {
+    fn id(&self) -> &'static str {
+        K::PERSISTENT_ID
+    }
+
+    fn encode(
+        &self,
+        key: &dyn Any,
+        value: Option<&DiceValidValue>,
+    ) -> anyhow::Result<Option<(Vec<u8>, Option<Vec<u8>>)>> {
+        let key = key
+            .downcast_ref::<K>()
+            .expect("key type was matched by TypeId");
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        let value = value
+            .downcast_ref::<K::Value>()
+            .expect("value type should match its key");
+        match K::to_persisted(value) {
+            Some(value) => Ok(Some((
+                bincode::serialize(key)?,
+                Some(bincode::serialize(&value)?),
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    fn decode(
+        &self,
+        key_index: &DiceKeyIndex,
+        data: &DiceData,
+        key: &[u8],
+        value: Option<&[u8]>,
+        _deps: &[DiceKey],
+    ) -> anyhow::Result<(DiceKey, Option<DiceValidValue>, StorageType)> {
+        let key: K = bincode::deserialize(key)?;
+        let value = value.ok_or_else(|| PersistenceError::InvalidValue(key.to_string()))?;
+        let value = K::from_persisted(bincode::deserialize(value)?, data)?;
+        let value =
+            MaybeValidDiceValue::new(Arc::new(DiceKeyValue::<K>::new(value)), DiceValidity::Valid)
+                .into_valid_value()
+                .map_err(|_| PersistenceError::InvalidValue(key.to_string()))?;
+        Ok((key_index.index_key(key), Some(value), K::storage_type()))
+    }
+}
+
+/// Saves keys without their values, which are recomputed after restoring.
+struct KeyCodec<K>(PhantomData<fn() -> K>);
+
+impl<K: PersistentKey> PersistentKeyCodec for KeyCodec<K> {
+    fn id(&self) -> &'static str {
+        K::PERSISTENT_ID
+    }
+
+    fn encode(
+        &self,
+        key: &dyn Any,
+        _value: Option<&DiceValidValue>,
+    ) -> anyhow::Result<Option<(Vec<u8>, Option<Vec<u8>>)>> {
+        let key = key
+            .downcast_ref::<K>()
+            .expect("key type was matched by TypeId");
+        Ok(Some((bincode::serialize(key)?, None)))
+    }
+
+    fn decode(
+        &self,
+        key_index: &DiceKeyIndex,
+        _data: &DiceData,
+        key: &[u8],
+        _value: Option<&[u8]>,
+        _deps: &[DiceKey],
+    ) -> anyhow::Result<(DiceKey, Option<DiceValidValue>, StorageType)> {
+        let key: K = bincode::deserialize(key)?;
+        Ok((key_index.index_key(key), None, K::storage_type()))
+    }
+}
+
+/// Saves projection keys along with their values. The key they are derived from isn't part of
+/// the encoded key: it's the first dep of the node.
+struct ProjectionCodec<P>(PhantomData<fn() -> P>);
+
+impl<P: PersistentProjection> PersistentKeyCodec for ProjectionCodec<P> {
+    fn id(&self) -> &'static str {
+        P::PERSISTENT_ID
+    }
+
+    fn encode(
+        &self,
+        key: &dyn Any,
+        value: Option<&DiceValidValue>,
+    ) -> anyhow::Result<Option<(Vec<u8>, Option<Vec<u8>>)>> {
+        let key = key
+            .downcast_ref::<P>()
+            .expect("key type was matched by TypeId");
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        let value = value
+            .downcast_ref::<P::Value>()
+            .expect("value type should match its key");
+        match P::to_persisted(value) {
+            Some(value) => Ok(Some((
+                bincode::serialize(key)?,
+                Some(bincode::serialize(&value)?),
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    fn decode(
+        &self,
+        key_index: &DiceKeyIndex,
+        data: &DiceData,
+        key: &[u8],
+        value: Option<&[u8]>,
+        deps: &[DiceKey],
+    ) -> anyhow::Result<(DiceKey, Option<DiceValidValue>, StorageType)> {
+        let key: P = bincode::deserialize(key)?;
+        let base = *deps
+            .first()
+            .ok_or_else(|| PersistenceError::MissingProjectionBase(key.to_string()))?;
+        let value = value.ok_or_else(|| PersistenceError::InvalidValue(key.to_string()))?;
+        let value = P::from_persisted(bincode::deserialize(value)?, data)?;
+        let value = MaybeValidDiceValue::new(
+            Arc::new(DiceProjectValue::<P>::new(value)),
+            DiceValidity::Valid,
+        )
+        .into_valid_value()
+        .map_err(|_| PersistenceError::InvalidValue(key.to_string()))?;
+        Ok((
+            key_index.index(CowDiceKeyHashed::proj_ref(base, &key)),
+            Some(value),
+            P::storage_type(),
+        ))
+    }
+}
+
+/// The key types which are saved to, and restored from, snapshots.
+#[derive(Default)]
+pub struct DicePersistence {
+    by_type: HashMap<TypeId, Arc<dyn PersistentKeyCodec>>,
+    by_id: HashMap<&'static str, Arc<dyn PersistentKeyCodec>>,
+}
+
+impl DicePersistence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Saves the keys of type `K` along with their values.
+    pub fn register<K: PersistentValue>(&mut self) -> anyhow::Result<()> {
+        self.register_codec::<K>(K::PERSISTENT_ID, Arc::new(Codec::<K>(PhantomData)))
+    }
+
+    /// Saves the keys of type `K` without their values, for values which can't be serialized
+    /// but are deterministic given the values of the key's deps. This lets the nodes that depend
+    /// on them be saved. A restored key is recomputed when it's first needed, and the nodes that
+    /// depend on it stay valid if it still has the same deps.
+    pub fn register_without_value<K: PersistentKey>(&mut self) -> anyhow::Result<()> {
+        if let StorageType::Injected = K::storage_type() {
+            // Injected values can't be recomputed.
+            return Err(PersistenceError::InjectedWithoutValue(K::PERSISTENT_ID).into());
+        }
+        self.register_codec::<K>(K::PERSISTENT_ID, Arc::new(KeyCodec::<K>(PhantomData)))
+    }
+
+    /// Saves the projection keys of type `P` along with their values. They are only saved if the
+    /// key they are derived from is registered too, with or without its values.
+    pub fn register_projection<P: PersistentProjection>(&mut self) -> anyhow::Result<()> {
+        self.register_codec::<P>(
+            P::PERSISTENT_ID,
+            Arc::new(ProjectionCodec::<P>(PhantomData)),
+        )
+    }
+
+    fn register_codec<K: 'static>(
+        &mut self,
+        id: &'static str,
+        codec: Arc<dyn PersistentKeyCodec>,
+    ) -> anyhow::Result<()> {
+        if self.by_id.insert(id, codec.dupe()).is_some() {
+            return Err(PersistenceError::DuplicateId(id).into());
+        }
+        self.by_type.insert(TypeId::of::<K>(), codec);
+        Ok(())
+    }
+
+    pub(crate) fn key_types(&self) -> HashSet<TypeId> {
+        self.by_type.keys().copied().collect()
+    }
+
+    pub(crate) fn codec_for_key(&self, key: &dyn Any) -> Option<&Arc<dyn PersistentKeyCodec>> {
+        self.by_type.get(&key.type_id())
+    }
+
+    pub(crate) fn codec_for_id(&self, id: &str) -> Option<&Arc<dyn PersistentKeyCodec>> {
+        self.by_id.get(id)
+    }
+}
+
+/// A saved DICE graph, along with caller provided metadata (e.g. the version of the program
+/// which wrote it and what it needs to find out what changed since).
+#[derive(Serialize, Deserialize)]
+pub struct DiceSnapshot {
+    metadata: String,
+    /// The `PERSISTENT_ID`s of the key types in the snapshot, referred to by index in `nodes`.
+    key_types: Vec<String>,
+    /// Nodes in dependency order: every node comes after all of its deps.
+    nodes: Vec<SnapshotNode>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SnapshotNode {
+    pub(crate) key_type: u32,
+    pub(crate) key: Vec<u8>,
+    /// `None` for key types registered without their values.
+    pub(crate) value: Option<Vec<u8>>,
+    /// Indices into the snapshot nodes.
+    pub(crate) deps: Vec<u32>,
+}
+
+impl DiceSnapshot {
+    /// Written before the serialized snapshot, so that files from other formats are rejected
+    /// instead of misinterpreted.
+    const HEADER: &'static [u8; 12] = b"DICESNAP\x00\x00\x00\x02";
+
+    pub(crate) fn new(metadata: String, key_types: Vec<String>, nodes: Vec<SnapshotNode>) -> Self {
+        Self {
+            metadata,
+            key_types,
+            nodes,
+        }
+    }
+
+    pub fn metadata(&self) -> &str {
+        &self.metadata
+    }
+
+    /// The number of nodes in the snapshot.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn write(&self, mut w: impl Write) -> anyhow::Result<()> {
+        w.write_all(Self::HEADER)?;
+        bincode::serialize_into(&mut w, self)?;
+        w.flush()?;
+        Ok(())
+    }
+
+    pub fn read(mut r: impl Read) -> anyhow::Result<Self> {
+        let mut header = *Self::HEADER;
+        r.read_exact(&mut header)
+            .map_err(|_| PersistenceError::InvalidHeader)?;
+        if &header != Self::HEADER {
+            return Err(PersistenceError::InvalidHeader.into());
+        }
+        Ok(bincode::deserialize_from(r)?)
+    }
+
+    /// Decodes the nodes, skipping the ones whose key type isn't registered and, transitively,
+    /// the ones that depend on them. The resulting nodes are still in dependency order.
+    pub(crate) fn decode(
+        &self,
+        key_index: &DiceKeyIndex,
+        data: &DiceData,
+        persistence: &DicePersistence,
+    ) -> anyhow::Result<Vec<PersistedNode>> {
+        let codecs: Vec<_> = self
+            .key_types
+            .iter()
+            .map(|id| persistence.codec_for_id(id))
+            .collect();
+
+        let mut decoded: Vec<Option<DiceKey>> = Vec::with_capacity(self.nodes.len());
+        let mut res = Vec::new();
+        for node in &self.nodes {
+            let codec = *codecs
+                .get(node.key_type as usize)
+                .ok_or(PersistenceError::InvalidKeyType(node.key_type))?;
+            let deps = node
+                .deps
+                .iter()
+                .map(|dep| {
+                    decoded
+                        .get(*dep as usize)
+                        .copied()
+                        .ok_or(PersistenceError::InvalidDep)
+                })
+                .collect::<Result<Option<Vec<_>>, _>>()?;
+            match (codec, deps) {
+                (Some(codec), Some(deps)) => {
+                    let (key, value, storage) = codec.decode(
+                        key_index,
+                        data,
+                        &node.key,
+                        node.value.as_deref(),
+                        &deps,
+                    )?;
+                    decoded.push(Some(key));
+                    res.push(PersistedNode {
+                        key,
+                        value,
+                        storage,
+                        deps,
+                    });
+                }
+                _ => decoded.push(None),
+            }
+        }
+        Ok(res)
+    }
+}
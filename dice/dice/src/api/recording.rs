@@ -0,0 +1,68 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Recording the transactions run against a DICE, to replay them when chasing incrementality
+//! bugs.
+//!
+//! While recording, started with [`Dice::start_recording`](crate::Dice::start_recording), each
+//! committed transaction records the keys it changed, the keys requested from it by its user, and
+//! the deps of the keys it computed. Keys are recorded by their type name and `Display`, so the
+//! recording can be replayed with mocked computations (see `fuzzy_dice replay-recording`),
+//! without the key types that produced it. Keys of the same type with the same `Display` are
+//! indistinguishable in a recording.
+//!
+//! Transactions which don't change anything share the version of the previous one, and so are
+//! recorded as part of it.
+
+use std::fmt;
+use std::fmt::Display;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiceRecording {
+    /// In the order they were committed.
+    pub transactions: Vec<RecordedTransaction>,
+}
+
+/// What happened at a version. All keys are sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedTransaction {
+    /// The keys passed to `DiceTransactionUpdater::changed`.
+    pub invalidated: Vec<RecordedKey>,
+    /// The keys passed to `DiceTransactionUpdater::changed_to`. Their values aren't recorded.
+    pub changed_to: Vec<RecordedKey>,
+    /// The keys requested directly from the transaction, rather than by another key.
+    pub requested: Vec<RecordedKey>,
+    /// The keys computed at this version, projections included.
+    pub computed: Vec<RecordedComputation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedComputation {
+    pub key: RecordedKey,
+    /// In the order they were first requested.
+    pub deps: Vec<RecordedKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Serialize, Deserialize)]
+pub struct RecordedKey {
+    /// As returned by `Key::key_type_name`, which defaults to the short name of the key's type.
+    pub key_type: String,
+    /// The key's `Display`.
+    pub key: String,
+}
+
+impl Display for RecordedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.key, self.key_type)
+    }
+}
@@ -110,9 +110,9 @@ impl DiceTransaction {
     }
 }
 
-#[derive(Allocative, Eq, PartialEq, Copy, Clone, derive_more::Display)]
+#[derive(Allocative, Eq, PartialEq, Copy, Clone, Debug, derive_more::Display)]
 #[repr(transparent)]
-pub struct DiceEquality(VersionNumber);
+pub struct DiceEquality(pub(crate) VersionNumber);
 
 mod private {
     use super::*;
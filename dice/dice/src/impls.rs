@@ -14,10 +14,14 @@ mod deps;
 pub(crate) mod dice;
 pub(crate) mod evaluator;
 pub(crate) mod events;
+mod eviction;
 mod hash;
+mod invalidations;
 pub(crate) mod key;
-mod key_index;
+pub(crate) mod key_index;
 pub(crate) mod opaque;
+mod persistence;
+pub(crate) mod recording;
 pub(crate) mod task;
 #[cfg(test)]
 mod tests;
@@ -7,8 +7,10 @@
  * of this source tree.
  */
 
+pub(crate) mod early_cutoffs;
 pub(crate) mod graph;
 mod internals;
+pub(crate) mod invalidations;
 mod processor;
 pub(crate) mod state;
 pub(crate) mod versions;
@@ -0,0 +1,51 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Counts how often recomputations are cut off because they produced a value equal to the
+//! previous one, so that nothing depending on them has to be recomputed.
+
+use std::collections::BTreeMap;
+
+use crate::metrics::EarlyCutoffMetrics;
+
+/// How a recomputation, rather than the first computation, of a key ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RecomputeOutcome {
+    /// The value is equal to the previous one, which is kept.
+    Unchanged,
+    /// The value changed, so the keys depending on it need to be recomputed.
+    Changed,
+}
+
+#[derive(Default)]
+pub(super) struct EarlyCutoffStats {
+    by_key_type: BTreeMap<&'static str, EarlyCutoffMetrics>,
+}
+
+impl EarlyCutoffStats {
+    pub(super) fn record(&mut self, key_type: &'static str, outcome: RecomputeOutcome) {
+        let counts = self
+            .by_key_type
+            .entry(key_type)
+            .or_insert_with(|| EarlyCutoffMetrics {
+                key_type,
+                cutoffs: 0,
+                changes: 0,
+            });
+        match outcome {
+            RecomputeOutcome::Unchanged => counts.cutoffs += 1,
+            RecomputeOutcome::Changed => counts.changes += 1,
+        }
+    }
+
+    /// Sorted by key type.
+    pub(super) fn metrics(&self) -> Vec<EarlyCutoffMetrics> {
+        self.by_key_type.values().cloned().collect()
+    }
+}
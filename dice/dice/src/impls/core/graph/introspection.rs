@@ -70,10 +70,15 @@ impl VersionedGraph {
 
             edges.insert(
                 *k,
-                versioned_node.unpack_occupied().map_or_else(
-                    || Arc::new(Vec::new()),
-                    |node| Arc::new(node.deps().iter_keys().collect()),
-                ),
+                match versioned_node {
+                    VersionedGraphNode::Occupied(node) => {
+                        Arc::new(node.deps().iter_keys().collect())
+                    }
+                    VersionedGraphNode::Evicted(node) => {
+                        Arc::new(node.deps().iter_keys().collect())
+                    }
+                    _ => Arc::new(Vec::new()),
+                },
             );
         }
 
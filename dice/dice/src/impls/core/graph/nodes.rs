@@ -44,6 +44,29 @@ use crate::versions::VersionRange;
 use crate::versions::VersionRanges;
 use crate::HashSet;
 
+/// How a newly computed value was stored by `VersionedGraphNode::on_computed`.
+#[derive(Copy, Clone, Dupe, Debug, PartialEq, Eq)]
+pub(crate) enum ComputedUpdate {
+    /// The value was equal to the occupied node's, whose value is kept.
+    Reused,
+    /// The node was restored from a snapshot without its value and its deps were unchanged, so
+    /// it was reinstated without comparing values.
+    Reinstated,
+    /// The value was not equal to the occupied node's, and replaced it.
+    Replaced,
+    /// The node had no value to compare against, being vacant, evicted or restored without one.
+    Filled,
+    /// The node has a value valid at a newer version, so the value was not stored.
+    Stale,
+}
+
+impl ComputedUpdate {
+    /// Whether the update counts as a change, as `VersionedGraph::update` reports it.
+    pub(crate) fn changed(self) -> bool {
+        !matches!(self, ComputedUpdate::Reused | ComputedUpdate::Reinstated)
+    }
+}
+
 /// Actual entries as seen when querying the VersionedGraph.
 ///
 /// This is responsible for tracking the information related to a single
@@ -53,6 +76,7 @@ pub(crate) enum VersionedGraphNode {
     Occupied(OccupiedGraphNode),
     Injected(InjectedGraphNode),
     Vacant(VacantGraphNode),
+    Evicted(EvictedGraphNode),
 }
 
 impl VersionedGraphNode {
@@ -69,18 +93,14 @@ impl VersionedGraphNode {
             VersionedGraphNode::Injected(e) => {
                 panic!("injected keys don't get invalidated (`{:?}`)", e)
             }
+            VersionedGraphNode::Evicted(e) => e.metadata.force_dirty(v),
         }
     }
 
     pub(crate) fn mark_invalidated(&mut self, v: VersionNumber) -> InvalidateResult {
         match self {
-            VersionedGraphNode::Occupied(e) => {
-                if e.mark_invalidated(v) {
-                    InvalidateResult::Changed(Some(e.metadata.rdeps.drain()))
-                } else {
-                    InvalidateResult::NoChange
-                }
-            }
+            VersionedGraphNode::Occupied(e) => e.metadata.invalidate(v),
+            VersionedGraphNode::Evicted(e) => e.metadata.invalidate(v),
             VersionedGraphNode::Vacant(e) => {
                 panic!("vacant nodes shouldn't get invalidated (`{:?}`)", e)
             }
@@ -96,13 +116,18 @@ impl VersionedGraphNode {
             VersionedGraphNode::Occupied(entry) => entry.at_version(v),
             VersionedGraphNode::Vacant(_) => VersionedGraphResult::Compute,
             VersionedGraphNode::Injected(entry) => entry.at_version(v),
+            // The value is gone, so even if the node is verified at `v` it needs to be recomputed.
+            // `on_computed` then reuses the node's history if the recomputed value must be the
+            // same as the missing one.
+            VersionedGraphNode::Evicted(_) => VersionedGraphResult::Compute,
         }
     }
 
     pub(crate) fn add_rdep_at(&mut self, v: VersionNumber, k: DiceKey) {
         match self {
-            VersionedGraphNode::Occupied(occ) => occ.add_rdep_at(v, k),
+            VersionedGraphNode::Occupied(occ) => occ.metadata.add_rdep_at(v, k),
             VersionedGraphNode::Injected(inj) => inj.add_rdep_at(v, k),
+            VersionedGraphNode::Evicted(ev) => ev.metadata.add_rdep_at(v, k),
             VersionedGraphNode::Vacant(_) => {
                 unreachable!("we can't have an rdep on something that has never seen a value")
             }
@@ -117,6 +142,14 @@ impl VersionedGraphNode {
         match self {
             VersionedGraphNode::Occupied(occ) => occ.on_injected(version, value),
             VersionedGraphNode::Injected(inj) => inj.on_injected(version, value),
+            VersionedGraphNode::Evicted(_) => {
+                // There's no value to compare with, so assume it changed.
+                let occ = self.reinstate(value);
+                occ.metadata.deps = Arc::new(SeriesParallelDeps::None);
+                occ.metadata.verified_ranges =
+                    Arc::new(VersionRange::begins_with(version).into_ranges());
+                InvalidateResult::Changed(Some(occ.metadata.rdeps.drain()))
+            }
             VersionedGraphNode::Vacant(vac) => {
                 let entry = OccupiedGraphNode::new(
                     vac.key,
@@ -140,13 +173,13 @@ impl VersionedGraphNode {
         mut valid_deps_versions: VersionRanges,
         reusable: super::storage::ValueReusable,
         deps: Arc<SeriesParallelDeps>,
-    ) -> (DiceComputedValue, bool) {
+    ) -> (DiceComputedValue, ComputedUpdate) {
         let (dirtied_history, overwrite_entry) = match self {
             VersionedGraphNode::Occupied(entry) if reusable.is_reusable(&value, &deps, entry) => {
                 debug!("marking graph entry as unchanged");
                 entry.mark_unchanged(key.v, valid_deps_versions);
                 let ret = entry.computed_val();
-                return (ret, false);
+                return (ret, ComputedUpdate::Reused);
             }
             VersionedGraphNode::Occupied(entry) => {
                 // TODO(cjhopman): Should this consider the max version in valid_deps_version rather than just key.v?
@@ -155,6 +188,18 @@ impl VersionedGraphNode {
                     !entry.metadata.ever_valid_after(key.v),
                 )
             }
+            VersionedGraphNode::Evicted(entry)
+                if reusable.is_reusable_evicted(&deps, &valid_deps_versions, key.v, entry) =>
+            {
+                debug!("reinstating evicted graph entry");
+                let entry = self.reinstate(value);
+                entry.mark_unchanged(key.v, valid_deps_versions);
+                return (entry.computed_val(), ComputedUpdate::Reinstated);
+            }
+            VersionedGraphNode::Evicted(entry) => (
+                &entry.metadata.dirtied_history,
+                !entry.metadata.ever_valid_after(key.v),
+            ),
             VersionedGraphNode::Vacant(entry) => (&entry.dirtied_history, true),
             _ => unreachable!("injected nodes are never computed"),
         };
@@ -164,32 +209,83 @@ impl VersionedGraphNode {
         valid_deps_versions.insert(computed_version);
 
         if !overwrite_entry {
-            // TODO(cjhopman): Returning `true` here matches previous behavior, but it seems odd
-            // that we claim something changed when we don't change anything. It's likely that the
-            // the return value actually is used to mean something different than that we changed
-            // something.
+            // TODO(cjhopman): This is reported as changed to match previous behavior, but it seems
+            // odd that we claim something changed when we don't change anything. It's likely that
+            // the the return value actually is used to mean something different than that we
+            // changed something.
             debug!("skipping new graph entry because value is older than current entry");
             return (
                 DiceComputedValue::new(
                     MaybeValidDiceValue::valid(value),
                     Arc::new(valid_deps_versions),
                 ),
-                true,
+                ComputedUpdate::Stale,
             );
         }
 
         debug!("making new graph entry because value not reusable");
-        let new = OccupiedGraphNode::new(
-            key.k,
-            value,
-            deps,
-            valid_deps_versions,
-            dirtied_history.clone(),
-        );
+        let dirtied_history = dirtied_history.clone();
+        let update = match *self {
+            VersionedGraphNode::Occupied(_) => ComputedUpdate::Replaced,
+            _ => ComputedUpdate::Filled,
+        };
+        let mut new =
+            OccupiedGraphNode::new(key.k, value, deps, valid_deps_versions, dirtied_history);
+        // Evicted nodes keep their rdeps so that they still get invalidated, and so must the node
+        // replacing them.
+        if let VersionedGraphNode::Evicted(entry) = self {
+            new.metadata.rdeps = std::mem::replace(&mut entry.metadata.rdeps, LazyDepsSet::new());
+        }
         let ret = new.computed_val();
         *self = VersionedGraphNode::Occupied(new);
 
-        (ret, true)
+        (ret, update)
+    }
+
+    /// Drops the value of an occupied node, keeping its edges and history. Returns whether the
+    /// node was evicted.
+    pub(crate) fn evict(&mut self) -> bool {
+        match self {
+            VersionedGraphNode::Occupied(occ) => {
+                let key = occ.key;
+                match std::mem::replace(self, VersionedGraphNode::Vacant(VacantGraphNode::new(key)))
+                {
+                    VersionedGraphNode::Occupied(occ) => {
+                        *self = VersionedGraphNode::Evicted(EvictedGraphNode {
+                            key,
+                            metadata: occ.metadata,
+                            restored: false,
+                        });
+                        true
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Turns an evicted node back into an occupied one with the given value.
+    fn reinstate(&mut self, value: DiceValidValue) -> &mut OccupiedGraphNode {
+        let key = match self {
+            VersionedGraphNode::Evicted(ev) => ev.key,
+            _ => unreachable!("only evicted nodes are reinstated"),
+        };
+        match std::mem::replace(self, VersionedGraphNode::Vacant(VacantGraphNode::new(key))) {
+            VersionedGraphNode::Evicted(ev) => {
+                *self = VersionedGraphNode::Occupied(OccupiedGraphNode {
+                    key,
+                    res: value,
+                    metadata: ev.metadata,
+                    last_used: 0,
+                });
+            }
+            _ => unreachable!(),
+        }
+        match self {
+            VersionedGraphNode::Occupied(occ) => occ,
+            _ => unreachable!(),
+        }
     }
 
     pub(crate) fn to_introspectable(&self) -> Option<SerializedGraphNode> {
@@ -216,6 +312,16 @@ impl VersionedGraphNode {
                 // TODO(bobyf) should probably write the metadata of vacant
                 None
             }
+            VersionedGraphNode::Evicted(ev) => Some(SerializedGraphNode {
+                node_id: NodeID(ev.key.index as usize),
+                kind: GraphNodeKind::Evicted,
+                history: crate::introspection::graph::CellHistory {
+                    valid_ranges: ev.metadata.verified_ranges.to_introspectable(),
+                    force_dirtied_at: ev.metadata.dirtied_history.to_introspectable(),
+                },
+                deps: Some(visit_deps(ev.metadata.deps.iter_keys())),
+                rdeps: Some(visit_rdeps(ev.metadata.rdeps.iter())),
+            }),
             VersionedGraphNode::Injected(inj) => {
                 let latest = inj.latest();
                 Some(SerializedGraphNode {
@@ -249,9 +355,10 @@ impl VersionedGraphNode {
 
     fn valid_versions_at(&self, v: VersionNumber) -> Option<&VersionRanges> {
         match self {
-            VersionedGraphNode::Occupied(occ) => {
-                if occ.metadata.verified_ranges.contains(v) {
-                    Some(&occ.metadata.verified_ranges)
+            VersionedGraphNode::Occupied(OccupiedGraphNode { metadata, .. })
+            | VersionedGraphNode::Evicted(EvictedGraphNode { metadata, .. }) => {
+                if metadata.verified_ranges.contains(v) {
+                    Some(&metadata.verified_ranges)
                 } else {
                     None
                 }
@@ -276,6 +383,20 @@ pub(crate) struct OccupiedGraphNode {
     key: DiceKey,
     res: DiceValidValue,
     metadata: NodeMetadata,
+    /// The transaction in which the node was last looked up or computed, for eviction.
+    last_used: u64,
+}
+
+/// An occupied node whose value was evicted to save memory, or restored from a snapshot without
+/// it. Its edges and history are kept, so that it still propagates invalidations, and so that
+/// recomputing it can reuse its history.
+#[derive(Allocative, Debug)]
+pub(crate) struct EvictedGraphNode {
+    key: DiceKey,
+    metadata: NodeMetadata,
+    /// Whether the node was restored without its value, rather than evicted. Only then is its
+    /// recomputed value assumed to be the missing one, see `ValueReusable::is_reusable_evicted`.
+    restored: bool,
 }
 
 /// Meta data about a DICE node, which are its edges and history information
@@ -287,6 +408,34 @@ pub(crate) struct NodeMetadata {
     dirtied_history: ForceDirtyHistory,
 }
 impl NodeMetadata {
+    fn add_rdep_at(&mut self, v: VersionNumber, k: DiceKey) {
+        if self.should_add_rdep_at(v) {
+            self.rdeps.insert(v, k);
+        }
+    }
+
+    fn mark_invalidated(&mut self, v: VersionNumber) -> bool {
+        Arc::make_mut(&mut self.verified_ranges)
+            .intersect_range(VersionRange::bounded(VersionNumber::ZERO, v))
+    }
+
+    fn invalidate(&mut self, v: VersionNumber) -> InvalidateResult {
+        if self.mark_invalidated(v) {
+            InvalidateResult::Changed(Some(self.rdeps.drain()))
+        } else {
+            InvalidateResult::NoChange
+        }
+    }
+
+    fn force_dirty(&mut self, v: VersionNumber) -> InvalidateResult {
+        self.mark_invalidated(v);
+        if self.dirtied_history.force_dirty(v) {
+            InvalidateResult::Changed(Some(self.rdeps.drain()))
+        } else {
+            InvalidateResult::NoChange
+        }
+    }
+
     fn should_add_rdep_at(&self, v: VersionNumber) -> bool {
         match self.verified_ranges.last() {
             Some(last) => match (last.begin(), last.end()) {
@@ -397,6 +546,7 @@ impl OccupiedGraphNode {
                 verified_ranges: Arc::new(verified_ranges),
                 dirtied_history,
             },
+            last_used: 0,
         }
     }
 
@@ -471,34 +621,72 @@ impl OccupiedGraphNode {
         }
     }
 
-    fn add_rdep_at(&mut self, v: VersionNumber, k: DiceKey) {
-        if self.metadata.should_add_rdep_at(v) {
-            self.metadata.rdeps.insert(v, k);
-        }
+    fn force_dirty(&mut self, v: VersionNumber) -> InvalidateResult {
+        self.metadata.force_dirty(v)
     }
 
-    fn force_dirty(&mut self, v: VersionNumber) -> InvalidateResult {
-        self.mark_invalidated(v);
-        if self.metadata.dirtied_history.force_dirty(v) {
-            InvalidateResult::Changed(Some(self.metadata.rdeps.drain()))
-        } else {
-            InvalidateResult::NoChange
-        }
+    fn rdeps(&self) -> impl Iterator<Item = DiceKey> + '_ {
+        self.metadata.rdeps.iter()
     }
 
-    fn mark_invalidated(&mut self, v: VersionNumber) -> bool {
-        Arc::make_mut(&mut self.metadata.verified_ranges)
-            .intersect_range(VersionRange::bounded(VersionNumber::ZERO, v))
+    pub(crate) fn deps(&self) -> &Arc<SeriesParallelDeps> {
+        &self.metadata.deps
     }
 
-    fn rdeps(&self) -> impl Iterator<Item = DiceKey> + '_ {
-        self.metadata.rdeps.iter()
+    pub(crate) fn is_verified_at(&self, version: VersionNumber) -> bool {
+        self.metadata.verified_ranges.contains(version)
+    }
+
+    pub(crate) fn last_used(&self) -> u64 {
+        self.last_used
+    }
+
+    pub(crate) fn mark_used(&mut self, transaction: u64) {
+        self.last_used = transaction;
+    }
+}
+
+impl EvictedGraphNode {
+    /// A node restored from a snapshot without its value.
+    pub(crate) fn restored(
+        key: DiceKey,
+        deps: Arc<SeriesParallelDeps>,
+        verified_ranges: VersionRanges,
+    ) -> Self {
+        Self {
+            key,
+            metadata: NodeMetadata {
+                deps,
+                rdeps: LazyDepsSet::new(),
+                verified_ranges: Arc::new(verified_ranges),
+                dirtied_history: ForceDirtyHistory::new(),
+            },
+            restored: true,
+        }
+    }
+
+    pub(crate) fn is_restored(&self) -> bool {
+        self.restored
     }
 
     pub(crate) fn deps(&self) -> &Arc<SeriesParallelDeps> {
         &self.metadata.deps
     }
 
+    /// Whether the node's value is known to have been valid at some version at which its deps had
+    /// the same values as at `v`, given the versions `valid_deps_versions` at which they do.
+    /// Across force-dirtied versions that says nothing about the value.
+    pub(crate) fn was_valid_with_deps_at(
+        &self,
+        v: VersionNumber,
+        valid_deps_versions: &VersionRanges,
+    ) -> bool {
+        let mut versions = valid_deps_versions.clone();
+        versions.intersect_range(self.metadata.dirtied_history.restricted_range(v));
+        versions.intersect_in_place(&self.metadata.verified_ranges);
+        !versions.is_empty()
+    }
+
     pub(crate) fn is_verified_at(&self, version: VersionNumber) -> bool {
         self.metadata.verified_ranges.contains(version)
     }
@@ -596,6 +784,11 @@ impl InjectedGraphNode {
         }
     }
 
+    /// The value injected at or before `v`, if any.
+    pub(crate) fn value_at(&self, v: VersionNumber) -> Option<&DiceValidValue> {
+        self.data_at(v).map(|(_, data)| &data.value)
+    }
+
     pub(crate) fn latest(&self) -> &InjectedNodeData {
         // We don't ever create an empty values map
         self.values.values().next_back().unwrap()
@@ -158,9 +158,12 @@
 //! value-based dep checks.
 
 use allocative::Allocative;
+use dupe::Dupe;
 
 use crate::api::storage_type::StorageType;
 use crate::arc::Arc;
+use crate::impls::core::graph::nodes::ComputedUpdate;
+use crate::impls::core::graph::nodes::EvictedGraphNode;
 use crate::impls::core::graph::nodes::ForceDirtyHistory;
 use crate::impls::core::graph::nodes::InjectedGraphNode;
 use crate::impls::core::graph::nodes::InvalidateResult;
@@ -169,6 +172,7 @@ use crate::impls::core::graph::nodes::VacantGraphNode;
 use crate::impls::core::graph::nodes::VersionedGraphNode;
 use crate::impls::core::graph::types::VersionedGraphKey;
 use crate::impls::core::graph::types::VersionedGraphResult;
+use crate::impls::core::invalidations::InvalidationCauses;
 use crate::impls::deps::graph::SeriesParallelDeps;
 use crate::impls::key::DiceKey;
 use crate::impls::value::DiceComputedValue;
@@ -220,6 +224,19 @@ impl VersionedGraph {
         deps: Arc<SeriesParallelDeps>,
         storage_type: StorageType,
     ) -> (DiceComputedValue, bool) {
+        let (res, update) = self.update_classified(key, value, reusable, deps, storage_type);
+        (res, update.changed())
+    }
+
+    /// Like `update`, also telling how the value was stored.
+    pub(crate) fn update_classified(
+        &mut self,
+        key: VersionedGraphKey,
+        value: DiceValidValue,
+        reusable: ValueReusable,
+        deps: Arc<SeriesParallelDeps>,
+        storage_type: StorageType,
+    ) -> (DiceComputedValue, ComputedUpdate) {
         if let StorageType::Injected = storage_type {
             unreachable!(
                 "Injected keys should not receive update calls, as those are only from a compute() finishing and InjectedKeys have no compute()"
@@ -241,7 +258,7 @@ impl VersionedGraph {
 
             None => (
                 self.update_empty(key.k, key.v, value, valid_deps_versions, deps),
-                true,
+                ComputedUpdate::Filled,
             ),
         }
     }
@@ -252,6 +269,16 @@ impl VersionedGraph {
         &mut self,
         key: VersionedGraphKey,
         invalidate: InvalidateKind,
+    ) -> bool {
+        self.invalidate_traced(key, invalidate, None)
+    }
+
+    /// Like `invalidate`, also recording in `causes` why each node got dirtied.
+    pub(crate) fn invalidate_traced(
+        &mut self,
+        key: VersionedGraphKey,
+        invalidate: InvalidateKind,
+        mut causes: Option<&mut InvalidationCauses>,
     ) -> bool {
         let entry = match self.nodes.get_mut(&key.k) {
             Some(entry) => entry,
@@ -278,6 +305,9 @@ impl VersionedGraph {
                 };
 
                 self.nodes.insert(key.k, new_entry);
+                if let Some(causes) = causes {
+                    causes.entry(key.k).or_insert(None);
+                }
                 return true;
             }
         };
@@ -296,10 +326,145 @@ impl VersionedGraph {
             }
         };
 
-        self.invalidate_rdeps(key.v, queue);
+        if let Some(causes) = causes.as_deref_mut() {
+            causes.entry(key.k).or_insert(None);
+        }
+        self.invalidate_rdeps(key.v, key.k, queue, causes);
         true
     }
 
+    /// The nodes accepted by `filter` whose value is known to be valid at `v`, including evicted
+    /// ones, without their value. Nodes that are dirty at `v`, or only have values for older
+    /// versions, are skipped.
+    pub(crate) fn valid_nodes_at(
+        &self,
+        v: VersionNumber,
+        filter: &dyn Fn(DiceKey) -> bool,
+    ) -> Vec<PersistedNode> {
+        let mut res = Vec::new();
+        for (key, node) in self.nodes.iter() {
+            if !filter(*key) {
+                continue;
+            }
+            match node {
+                VersionedGraphNode::Occupied(node) if node.is_verified_at(v) => {
+                    res.push(PersistedNode {
+                        key: *key,
+                        value: Some(node.val().dupe()),
+                        storage: StorageType::Normal,
+                        deps: node.deps().iter_keys().collect(),
+                    })
+                }
+                VersionedGraphNode::Evicted(node) if node.is_verified_at(v) => {
+                    res.push(PersistedNode {
+                        key: *key,
+                        value: None,
+                        storage: StorageType::Normal,
+                        deps: node.deps().iter_keys().collect(),
+                    })
+                }
+                VersionedGraphNode::Injected(node) => {
+                    if let Some(value) = node.value_at(v) {
+                        res.push(PersistedNode {
+                            key: *key,
+                            value: Some(value.dupe()),
+                            storage: StorageType::Injected,
+                            deps: Vec::new(),
+                        })
+                    }
+                }
+                _ => {}
+            }
+        }
+        res
+    }
+
+    /// Inserts nodes loaded from a snapshot, valid from `v` onwards. Nodes must come after all
+    /// of their deps. Nodes without a value are inserted as evicted.
+    pub(crate) fn restore(&mut self, v: VersionNumber, nodes: Vec<PersistedNode>) {
+        for node in nodes {
+            if self.nodes.contains_key(&node.key) {
+                continue;
+            }
+            let entry = match (node.storage, node.value) {
+                (StorageType::Injected, Some(value)) => {
+                    VersionedGraphNode::Injected(InjectedGraphNode::new(node.key, v, value))
+                }
+                (StorageType::Injected, None) => {
+                    unreachable!("injected keys are always saved with their values")
+                }
+                (StorageType::Normal, value) => {
+                    for dep in &node.deps {
+                        self.nodes
+                            .get_mut(dep)
+                            .expect("dependency should be restored first")
+                            .add_rdep_at(v, node.key);
+                    }
+                    let deps = Arc::new(SeriesParallelDeps::serial_from_vec(node.deps));
+                    let verified_ranges = VersionRange::begins_with(v).into_ranges();
+                    match value {
+                        Some(value) => VersionedGraphNode::Occupied(OccupiedGraphNode::new(
+                            node.key,
+                            value,
+                            deps,
+                            verified_ranges,
+                            ForceDirtyHistory::new(),
+                        )),
+                        None => VersionedGraphNode::Evicted(EvictedGraphNode::restored(
+                            node.key,
+                            deps,
+                            verified_ranges,
+                        )),
+                    }
+                }
+            };
+            self.nodes.insert(node.key, entry);
+        }
+    }
+
+    /// Records that the node of `key` was looked up or computed in `transaction`.
+    pub(crate) fn mark_used(&mut self, key: DiceKey, transaction: u64) {
+        if let Some(VersionedGraphNode::Occupied(node)) = self.nodes.get_mut(&key) {
+            node.mark_used(transaction);
+        }
+    }
+
+    /// The nodes whose value can be evicted. Values of injected keys can't be recomputed, so
+    /// only computed nodes of keys opting into eviction are candidates.
+    pub(crate) fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        self.nodes
+            .iter()
+            .filter_map(|(key, node)| match node {
+                VersionedGraphNode::Occupied(node) if node.val().evictable() => {
+                    Some(EvictionCandidate {
+                        key: *key,
+                        last_used: node.last_used(),
+                        value: node.val().dupe(),
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Evicts the values of the given nodes, unless they were used again since the transaction
+    /// they were selected at. Returns the number of evicted nodes.
+    pub(crate) fn evict(&mut self, keys: impl IntoIterator<Item = (DiceKey, u64)>) -> usize {
+        let mut evicted = 0;
+        for (key, last_used) in keys {
+            if let Some(node) = self.nodes.get_mut(&key) {
+                let used_since = match node {
+                    VersionedGraphNode::Occupied(occ) => occ.last_used() != last_used,
+                    _ => true,
+                };
+                if !used_since && node.evict() {
+                    evicted += 1;
+                }
+            }
+        }
+        evicted
+    }
+
     // -----------------------------------------------------------------------------
     // ------------------------- Implementation functions below --------------------
     // -----------------------------------------------------------------------------
@@ -328,15 +493,25 @@ impl VersionedGraph {
         res
     }
 
-    fn invalidate_rdeps(&mut self, version: VersionNumber, mut queued: HashSet<DiceKey>) {
-        let mut queue: Vec<_> = queued.iter().copied().collect();
-
-        while let Some(rdep) = queue.pop() {
+    /// Invalidates `queued`, the rdeps of `changed`, and their transitive rdeps.
+    fn invalidate_rdeps(
+        &mut self,
+        version: VersionNumber,
+        changed: DiceKey,
+        mut queued: HashSet<DiceKey>,
+        mut causes: Option<&mut InvalidationCauses>,
+    ) {
+        let mut queue: Vec<_> = queued.iter().map(|rdep| (*rdep, changed)).collect();
+
+        while let Some((rdep, cause)) = queue.pop() {
             if let Some(node) = self.nodes.get_mut(&rdep) {
                 if let InvalidateResult::Changed(Some(rdeps)) = node.mark_invalidated(version) {
+                    if let Some(causes) = causes.as_deref_mut() {
+                        causes.entry(rdep).or_insert(Some(cause));
+                    }
                     for dep in rdeps.into_iter() {
                         if queued.insert(dep) {
-                            queue.push(dep);
+                            queue.push((dep, rdep));
                         }
                     }
                 }
@@ -370,6 +545,46 @@ impl ValueReusable {
             ValueReusable::VersionBased(version) => value.is_verified_at(*version),
         }
     }
+
+    /// A node restored without its value has no value to compare with. Recomputing it gives the
+    /// same value as the saved one if it has the same deps, and they have the same values as at a
+    /// version where the saved value was valid. Only the deps themselves are compared, not whether
+    /// they were computed serially or in parallel, which restored nodes don't know.
+    ///
+    /// Values evicted to save memory are never reused: keys opting into eviction aren't required
+    /// to be deterministic enough for that, so their recomputed value counts as changed.
+    pub(crate) fn is_reusable_evicted(
+        &self,
+        new_deps: &SeriesParallelDeps,
+        valid_deps_versions: &VersionRanges,
+        v: VersionNumber,
+        node: &EvictedGraphNode,
+    ) -> bool {
+        match self {
+            _ if !node.is_restored() => false,
+            ValueReusable::EqualityBased => {
+                new_deps.iter_keys().eq(node.deps().iter_keys())
+                    && node.was_valid_with_deps_at(v, valid_deps_versions)
+            }
+            ValueReusable::VersionBased(version) => node.is_verified_at(*version),
+        }
+    }
+}
+
+/// A node whose value could be evicted, along with the transaction it was last used in.
+pub(crate) struct EvictionCandidate {
+    pub(crate) key: DiceKey,
+    pub(crate) last_used: u64,
+    pub(crate) value: DiceValidValue,
+}
+
+/// A node of the graph as saved to, or restored from, a snapshot.
+pub(crate) struct PersistedNode {
+    pub(crate) key: DiceKey,
+    /// `None` for evicted nodes.
+    pub(crate) value: Option<DiceValidValue>,
+    pub(crate) storage: StorageType,
+    pub(crate) deps: Vec<DiceKey>,
 }
 
 pub(crate) enum InvalidateKind {
@@ -449,6 +664,7 @@ mod tests {
     use crate::api::computations::DiceComputations;
     use crate::api::key::Key;
     use crate::arc::Arc;
+    use crate::impls::core::graph::nodes::ComputedUpdate;
     use crate::impls::core::graph::storage::testing::VersionedCacheResultAssertsExt;
     use crate::impls::core::graph::storage::InvalidateKind;
     use crate::impls::core::graph::storage::StorageType;
@@ -479,6 +695,10 @@ mod tests {
         fn equality(x: &Self::Value, y: &Self::Value) -> bool {
             x == y
         }
+
+        fn evictable() -> bool {
+            true
+        }
     }
 
     fn inject(graph: &mut VersionedGraph, v: usize, key: DiceKey, value: usize) {
@@ -1464,4 +1684,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn update_classified_reports_how_values_are_stored() {
+        let mut cache = VersionedGraph::new();
+        let dep_key = DiceKey { index: 1 };
+        let key_at = |v| VersionedGraphKey::new(VersionNumber::new(v), DiceKey { index: 0 });
+        let update = |cache: &mut VersionedGraph, v, value| {
+            cache
+                .update_classified(
+                    key_at(v),
+                    DiceValidValue::testing_new(DiceKeyValue::<K>::new(value)),
+                    ValueReusable::EqualityBased,
+                    Arc::new(SeriesParallelDeps::serial_from_vec(vec![dep_key])),
+                    StorageType::Normal,
+                )
+                .1
+        };
+
+        inject(&mut cache, 0, dep_key, 0);
+        assert_eq!(ComputedUpdate::Filled, update(&mut cache, 0, 100));
+
+        inject(&mut cache, 1, dep_key, 1);
+        assert_eq!(ComputedUpdate::Reused, update(&mut cache, 1, 100));
+
+        inject(&mut cache, 2, dep_key, 2);
+        assert_eq!(ComputedUpdate::Replaced, update(&mut cache, 2, 200));
+
+        // The node already has a value at a newer version.
+        assert_eq!(ComputedUpdate::Stale, update(&mut cache, 1, 300));
+
+        let candidates = cache.eviction_candidates();
+        assert_eq!(
+            1,
+            cache.evict(candidates.iter().map(|c| (c.key, c.last_used)))
+        );
+        // There's nothing to compare the evicted value 200 with, so 400 counts as a change.
+        assert_eq!(ComputedUpdate::Filled, update(&mut cache, 2, 400));
+    }
 }
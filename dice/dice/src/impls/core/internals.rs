@@ -15,12 +15,19 @@ use super::graph::types::RejectedReason;
 use crate::api::storage_type::StorageType;
 use crate::arc::Arc;
 use crate::impls::cache::SharedCache;
+use crate::impls::core::early_cutoffs::EarlyCutoffStats;
+use crate::impls::core::early_cutoffs::RecomputeOutcome;
 use crate::impls::core::graph::introspection::VersionedGraphIntrospectable;
+use crate::impls::core::graph::nodes::ComputedUpdate;
+use crate::impls::core::graph::storage::EvictionCandidate;
 use crate::impls::core::graph::storage::InvalidateKind;
+use crate::impls::core::graph::storage::PersistedNode;
 use crate::impls::core::graph::storage::ValueReusable;
 use crate::impls::core::graph::storage::VersionedGraph;
 use crate::impls::core::graph::types::VersionedGraphKey;
 use crate::impls::core::graph::types::VersionedGraphResult;
+use crate::impls::core::invalidations::InvalidationCauses;
+use crate::impls::core::invalidations::InvalidationTraces;
 use crate::impls::core::versions::introspection::VersionIntrospectable;
 use crate::impls::core::versions::VersionEpoch;
 use crate::impls::core::versions::VersionTracker;
@@ -41,6 +48,11 @@ pub(super) struct CoreState {
     version_tracker: VersionTracker,
     graph: VersionedGraph,
     pending_termination_tasks: Vec<DiceTask>,
+    /// Counts the transactions started, to track when nodes were last used for eviction.
+    transactions: u64,
+    /// Set when invalidation tracing is enabled.
+    invalidation_traces: Option<InvalidationTraces>,
+    early_cutoffs: EarlyCutoffStats,
 }
 
 impl CoreState {
@@ -49,6 +61,9 @@ impl CoreState {
             version_tracker: VersionTracker::new(),
             graph: VersionedGraph::new(),
             pending_termination_tasks: Vec::new(),
+            transactions: 0,
+            invalidation_traces: None,
+            early_cutoffs: EarlyCutoffStats::default(),
         }
     }
 
@@ -59,9 +74,13 @@ impl CoreState {
         let version_update = self.version_tracker.write();
         let v = version_update.version();
 
+        let mut causes = self
+            .invalidation_traces
+            .as_ref()
+            .map(|_| InvalidationCauses::default());
         let mut changes_recorded = false;
         for (key, change) in updates {
-            changes_recorded |= self.graph.invalidate(
+            changes_recorded |= self.graph.invalidate_traced(
                 VersionedGraphKey::new(v, key),
                 match change {
                     ChangeType::Invalidate => InvalidateKind::ForceDirty,
@@ -69,16 +88,22 @@ impl CoreState {
                     #[cfg(test)]
                     ChangeType::TestingSoftDirty => InvalidateKind::Invalidate,
                 },
+                causes.as_mut(),
             );
         }
         if changes_recorded {
-            version_update.commit()
+            let v = version_update.commit();
+            if let (Some(traces), Some(causes)) = (&mut self.invalidation_traces, causes) {
+                traces.record(v, causes);
+            }
+            v
         } else {
             version_update.undo()
         }
     }
 
     pub(super) fn ctx_at_version(&mut self, v: VersionNumber) -> (VersionEpoch, SharedCache) {
+        self.transactions += 1;
         self.version_tracker.at(v)
     }
 
@@ -99,6 +124,7 @@ impl CoreState {
         if self.version_tracker.should_reject(key.v) {
             VersionedGraphResult::Rejected(RejectedReason::RejectedDueToGraphClear)
         } else {
+            self.graph.mark_used(key.k, self.transactions);
             self.graph.get(key)
         }
     }
@@ -112,9 +138,58 @@ impl CoreState {
         reusability: ValueReusable,
         deps: Arc<SeriesParallelDeps>,
     ) -> CancellableResult<DiceComputedValue> {
+        self.update_graph(key, epoch, storage, value, reusability, deps)
+            .map(|(res, _update)| res)
+    }
+
+    /// Like `update_computed`, for a value that was just computed. If the key had a value before,
+    /// counts whether the new one was equal to it. Values that were not compared, because the old
+    /// one was evicted or the new one is stale, are not counted.
+    pub(super) fn update_recomputed(
+        &mut self,
+        key: VersionedGraphKey,
+        epoch: VersionEpoch,
+        storage: StorageType,
+        value: DiceValidValue,
+        deps: Arc<SeriesParallelDeps>,
+        key_type: &'static str,
+    ) -> CancellableResult<(DiceComputedValue, Option<RecomputeOutcome>)> {
+        let (res, update) = self.update_graph(
+            key,
+            epoch,
+            storage,
+            value,
+            ValueReusable::EqualityBased,
+            deps,
+        )?;
+
+        let outcome = match update {
+            ComputedUpdate::Reused => Some(RecomputeOutcome::Unchanged),
+            ComputedUpdate::Replaced => Some(RecomputeOutcome::Changed),
+            ComputedUpdate::Reinstated | ComputedUpdate::Filled | ComputedUpdate::Stale => None,
+        };
+        if let Some(outcome) = outcome {
+            self.early_cutoffs.record(key_type, outcome);
+        }
+        Ok((res, outcome))
+    }
+
+    fn update_graph(
+        &mut self,
+        key: VersionedGraphKey,
+        epoch: VersionEpoch,
+        storage: StorageType,
+        value: DiceValidValue,
+        reusability: ValueReusable,
+        deps: Arc<SeriesParallelDeps>,
+    ) -> CancellableResult<(DiceComputedValue, ComputedUpdate)> {
         if self.version_tracker.is_relevant(key.v, epoch) {
             debug!(msg = "update graph entry", k = ?key.k, v = %key.v, v_epoch = %epoch);
-            Ok(self.graph.update(key, value, reusability, deps, storage).0)
+            let res = self
+                .graph
+                .update_classified(key, value, reusability, deps, storage);
+            self.graph.mark_used(key.k, self.transactions);
+            Ok(res)
         } else {
             debug!(msg = "update is rejected due to outdated epoch", k = ?key.k, v = %key.v, v_epoch = %epoch);
             Err(Cancelled)
@@ -155,9 +230,62 @@ impl CoreState {
             key_count: self.graph.nodes.len(),
             currently_active_key_count: currently_running_key_count,
             active_transaction_count: active_transaction_count as u32, // probably won't support more than u32 transactions
+            // filled in by `DiceModern`, which owns the concurrency limiter
+            concurrency_classes: Vec::new(),
+            early_cutoffs: self.early_cutoffs.metrics(),
+        }
+    }
+
+    pub(super) fn valid_nodes(
+        &self,
+        filter: &dyn Fn(DiceKey) -> bool,
+    ) -> (VersionNumber, Vec<PersistedNode>) {
+        let v = self.version_tracker.current();
+        (v, self.graph.valid_nodes_at(v, filter))
+    }
+
+    /// Restores the nodes at the current version. Returns `None` without doing anything if the
+    /// graph isn't empty, since the restored nodes could conflict with the existing ones.
+    pub(super) fn restore(&mut self, nodes: Vec<PersistedNode>) -> Option<VersionNumber> {
+        if !self.graph.nodes.is_empty() {
+            return None;
+        }
+        let v = self.version_tracker.current();
+        self.graph.restore(v, nodes);
+        Some(v)
+    }
+
+    pub(super) fn eviction_candidates(&self) -> Vec<EvictionCandidate> {
+        self.graph.eviction_candidates()
+    }
+
+    pub(super) fn evict(&mut self, keys: Vec<(DiceKey, u64)>) -> usize {
+        self.graph.evict(keys)
+    }
+
+    /// Enables or disables invalidation tracing. Disabling it drops the recorded traces.
+    pub(super) fn set_invalidation_tracing(&mut self, enabled: bool) {
+        match (enabled, &self.invalidation_traces) {
+            (true, None) => self.invalidation_traces = Some(InvalidationTraces::default()),
+            (false, Some(_)) => self.invalidation_traces = None,
+            _ => {}
         }
     }
 
+    pub(super) fn invalidation_traces(&self) -> Vec<(VersionNumber, Arc<InvalidationCauses>)> {
+        match &self.invalidation_traces {
+            Some(traces) => traces.traces(),
+            None => Vec::new(),
+        }
+    }
+
+    pub(super) fn invalidation_trace_at(
+        &self,
+        v: VersionNumber,
+    ) -> Option<Arc<InvalidationCauses>> {
+        self.invalidation_traces.as_ref()?.trace_at(v)
+    }
+
     pub(super) fn introspection(&self) -> (VersionedGraphIntrospectable, VersionIntrospectable) {
         let graph = self.graph.introspect();
         let version_data = self.version_tracker.introspect();
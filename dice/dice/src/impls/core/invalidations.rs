@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Records why nodes were invalidated, see `api::invalidations`.
+
+use std::collections::VecDeque;
+
+use dupe::Dupe;
+
+use crate::arc::Arc;
+use crate::impls::key::DiceKey;
+use crate::versions::VersionNumber;
+use crate::HashMap;
+
+/// How many updates to keep the traces of.
+const MAX_TRACED_VERSIONS: usize = 16;
+
+/// For each node dirtied by an update, the dep through which it was dirtied, or `None` for the
+/// changed keys.
+pub(crate) type InvalidationCauses = HashMap<DiceKey, Option<DiceKey>>;
+
+#[derive(Default)]
+pub(super) struct InvalidationTraces {
+    traces: VecDeque<(VersionNumber, Arc<InvalidationCauses>)>,
+}
+
+impl InvalidationTraces {
+    pub(super) fn record(&mut self, v: VersionNumber, causes: InvalidationCauses) {
+        if self.traces.len() == MAX_TRACED_VERSIONS {
+            self.traces.pop_front();
+        }
+        self.traces.push_back((v, Arc::new(causes)));
+    }
+
+    /// The recorded traces, most recent updates first.
+    pub(super) fn traces(&self) -> Vec<(VersionNumber, Arc<InvalidationCauses>)> {
+        self.traces
+            .iter()
+            .rev()
+            .map(|(v, causes)| (*v, causes.dupe()))
+            .collect()
+    }
+
+    /// The trace of the update that created `v`. `None` if that update wasn't traced.
+    pub(super) fn trace_at(&self, v: VersionNumber) -> Option<Arc<InvalidationCauses>> {
+        self.traces
+            .iter()
+            .find(|(traced, _)| *traced == v)
+            .map(|(_, causes)| causes.dupe())
+    }
+}
+
+/// The paths to the dirtied nodes accepted by `filter`, shortest first, then ordered by the
+/// changed key they start from.
+pub(crate) fn invalidation_paths(
+    causes: &InvalidationCauses,
+    mut filter: impl FnMut(DiceKey) -> bool,
+) -> Vec<Vec<DiceKey>> {
+    let mut paths: Vec<_> = causes
+        .keys()
+        .filter(|key| filter(**key))
+        .map(|key| path(causes, *key))
+        .collect();
+    paths.sort_by(|x, y| x.len().cmp(&y.len()).then_with(|| x.cmp(y)));
+    paths
+}
+
+/// The path from the changed key to `key`.
+fn path(causes: &InvalidationCauses, key: DiceKey) -> Vec<DiceKey> {
+    let mut path = vec![key];
+    let mut current = key;
+    while let Some(Some(cause)) = causes.get(&current) {
+        path.push(*cause);
+        current = *cause;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::impls::core::invalidations::invalidation_paths;
+    use crate::impls::core::invalidations::InvalidationCauses;
+    use crate::impls::key::DiceKey;
+
+    #[test]
+    fn test_invalidation_paths_are_ordered() {
+        let key = |index| DiceKey { index };
+        let causes: InvalidationCauses = [
+            (key(3), Some(key(1))),
+            (key(0), None),
+            (key(4), Some(key(2))),
+            (key(1), None),
+            (key(2), Some(key(0))),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            vec![
+                vec![key(0)],
+                vec![key(1)],
+                vec![key(0), key(2)],
+                vec![key(1), key(3)],
+                vec![key(0), key(2), key(4)],
+            ],
+            invalidation_paths(&causes, |_| true)
+        );
+        assert_eq!(
+            vec![vec![key(1), key(3)]],
+            invalidation_paths(&causes, |k| k == key(3))
+        );
+    }
+}
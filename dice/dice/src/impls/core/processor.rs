@@ -77,18 +77,14 @@ impl StateProcessor {
                 storage,
                 value,
                 deps,
+                key_type,
                 resp,
-                ..
             } => {
+                let res = self
+                    .state
+                    .update_recomputed(key, epoch, storage, value, deps, key_type);
                 // ignore error if the requester dropped it.
-                drop(resp.send(self.state.update_computed(
-                    key,
-                    epoch,
-                    storage,
-                    value,
-                    ValueReusable::EqualityBased,
-                    deps,
-                )));
+                drop(resp.send(res));
             }
             StateRequest::UpdateMismatchAsUnchanged {
                 key,
@@ -115,6 +111,27 @@ impl StateProcessor {
             StateRequest::Metrics { resp } => {
                 let _ignored = resp.send(self.state.metrics());
             }
+            StateRequest::ValidNodes { filter, resp } => {
+                let _ignored = resp.send(self.state.valid_nodes(&*filter));
+            }
+            StateRequest::Restore { nodes, resp } => {
+                let _ignored = resp.send(self.state.restore(nodes));
+            }
+            StateRequest::EvictionCandidates { resp } => {
+                let _ignored = resp.send(self.state.eviction_candidates());
+            }
+            StateRequest::Evict { keys, resp } => {
+                let _ignored = resp.send(self.state.evict(keys));
+            }
+            StateRequest::SetInvalidationTracing { enabled } => {
+                self.state.set_invalidation_tracing(enabled)
+            }
+            StateRequest::InvalidationTraces { resp } => {
+                let _ignored = resp.send(self.state.invalidation_traces());
+            }
+            StateRequest::InvalidationTraceAt { version, resp } => {
+                let _ignored = resp.send(self.state.invalidation_trace_at(version));
+            }
             StateRequest::Introspection { resp } => {
                 let _ignored = resp.send(self.state.introspection());
             }
@@ -19,10 +19,14 @@ use tokio::sync::oneshot::{self};
 
 use crate::api::storage_type::StorageType;
 use crate::arc::Arc;
+use crate::impls::core::early_cutoffs::RecomputeOutcome;
 use crate::impls::core::graph::introspection::VersionedGraphIntrospectable;
+use crate::impls::core::graph::storage::EvictionCandidate;
+use crate::impls::core::graph::storage::PersistedNode;
 use crate::impls::core::graph::types::VersionedGraphKey;
 use crate::impls::core::graph::types::VersionedGraphResult;
 use crate::impls::core::graph::types::VersionedGraphResultMismatch;
+use crate::impls::core::invalidations::InvalidationCauses;
 use crate::impls::core::processor::StateProcessor;
 use crate::impls::core::versions::introspection::VersionIntrospectable;
 use crate::impls::core::versions::VersionEpoch;
@@ -106,7 +110,8 @@ impl CoreStateHandle {
         self.call(StateRequest::LookupKey { key, resp }, recv)
     }
 
-    /// Report that a value has been computed
+    /// Report that a value has been computed. If the key had a value before, also returns
+    /// whether the new one was equal to it.
     pub(crate) fn update_computed(
         &self,
         key: VersionedGraphKey,
@@ -114,7 +119,9 @@ impl CoreStateHandle {
         storage: StorageType,
         value: DiceValidValue,
         deps: Arc<SeriesParallelDeps>,
-    ) -> impl Future<Output = CancellableResult<DiceComputedValue>> {
+        key_type: &'static str,
+    ) -> impl Future<Output = CancellableResult<(DiceComputedValue, Option<RecomputeOutcome>)>>
+    {
         let (resp, recv) = oneshot::channel();
         self.call(
             StateRequest::UpdateComputed {
@@ -123,6 +130,7 @@ impl CoreStateHandle {
                 storage,
                 value,
                 deps,
+                key_type,
                 resp,
             },
             recv,
@@ -173,6 +181,58 @@ impl CoreStateHandle {
         tokio::task::block_in_place(|| recv.blocking_recv().unwrap())
     }
 
+    /// Collects the nodes accepted by `filter` that are valid at the current version
+    pub(crate) fn valid_nodes(
+        &self,
+        filter: Box<dyn Fn(DiceKey) -> bool + Send>,
+    ) -> impl Future<Output = (VersionNumber, Vec<PersistedNode>)> {
+        let (resp, recv) = oneshot::channel();
+        self.call(StateRequest::ValidNodes { filter, resp }, recv)
+    }
+
+    /// Inserts nodes restored from a snapshot at the current version, if the graph is empty
+    pub(crate) fn restore(
+        &self,
+        nodes: Vec<PersistedNode>,
+    ) -> impl Future<Output = Option<VersionNumber>> {
+        let (resp, recv) = oneshot::channel();
+        self.call(StateRequest::Restore { nodes, resp }, recv)
+    }
+
+    /// Collects the computed nodes whose values could be evicted
+    pub(crate) fn eviction_candidates(&self) -> impl Future<Output = Vec<EvictionCandidate>> {
+        let (resp, recv) = oneshot::channel();
+        self.call(StateRequest::EvictionCandidates { resp }, recv)
+    }
+
+    /// Evicts the values of the given nodes that weren't used since the given transaction
+    pub(crate) fn evict(&self, keys: Vec<(DiceKey, u64)>) -> impl Future<Output = usize> {
+        let (resp, recv) = oneshot::channel();
+        self.call(StateRequest::Evict { keys, resp }, recv)
+    }
+
+    /// Enables or disables recording why nodes get invalidated
+    pub(crate) fn set_invalidation_tracing(&self, enabled: bool) {
+        self.request(StateRequest::SetInvalidationTracing { enabled })
+    }
+
+    /// Collects the recorded invalidation traces, most recent first
+    pub(crate) fn invalidation_traces(
+        &self,
+    ) -> impl Future<Output = Vec<(VersionNumber, Arc<InvalidationCauses>)>> {
+        let (resp, recv) = oneshot::channel();
+        self.call(StateRequest::InvalidationTraces { resp }, recv)
+    }
+
+    /// Collects the invalidation trace of the update that created the given version
+    pub(crate) fn invalidation_trace_at(
+        &self,
+        version: VersionNumber,
+    ) -> impl Future<Output = Option<Arc<InvalidationCauses>>> {
+        let (resp, recv) = oneshot::channel();
+        self.call(StateRequest::InvalidationTraceAt { version, resp }, recv)
+    }
+
     /// Collects the introspectable dice state
     pub(crate) fn introspection(&self) -> (VersionedGraphIntrospectable, VersionIntrospectable) {
         let (resp, recv) = oneshot::channel();
@@ -227,9 +287,12 @@ pub(super) enum StateRequest {
         value: DiceValidValue,
         /// The deps accessed during the computation of newly computed value
         deps: Arc<SeriesParallelDeps>,
+        /// The type of the key, to count its early cutoffs under
+        key_type: &'static str,
         /// Response of the new value to use. This could be a different instance that is `Eq` to the
         /// given computed value if the state already stores an instance of value that is equal.
-        resp: Sender<CancellableResult<DiceComputedValue>>,
+        /// Along with whether it was equal, if the key had a value before.
+        resp: Sender<CancellableResult<(DiceComputedValue, Option<RecomputeOutcome>)>>,
     },
     /// Report that a value has been verified to be unchanged due to its deps
     UpdateMismatchAsUnchanged {
@@ -252,6 +315,40 @@ pub(super) enum StateRequest {
     UnstableDropEverything,
     /// Collect metrics
     Metrics { resp: Sender<Metrics> },
+    /// Collects the nodes accepted by `filter` that are valid at the current version
+    ValidNodes {
+        #[derivative(Debug = "ignore")]
+        filter: Box<dyn Fn(DiceKey) -> bool + Send>,
+        #[derivative(Debug = "ignore")]
+        resp: Sender<(VersionNumber, Vec<PersistedNode>)>,
+    },
+    /// Inserts nodes restored from a snapshot at the current version, if the graph is empty
+    Restore {
+        #[derivative(Debug = "ignore")]
+        nodes: Vec<PersistedNode>,
+        resp: Sender<Option<VersionNumber>>,
+    },
+    /// Collects the computed nodes whose values could be evicted
+    EvictionCandidates {
+        #[derivative(Debug = "ignore")]
+        resp: Sender<Vec<EvictionCandidate>>,
+    },
+    /// Evicts the values of the given nodes that weren't used since the given transaction
+    Evict {
+        keys: Vec<(DiceKey, u64)>,
+        resp: Sender<usize>,
+    },
+    /// Enables or disables recording why nodes get invalidated
+    SetInvalidationTracing { enabled: bool },
+    /// Collects the recorded invalidation traces, most recent first
+    InvalidationTraces {
+        resp: Sender<Vec<(VersionNumber, Arc<InvalidationCauses>)>>,
+    },
+    /// Collects the invalidation trace of the update that created the given version
+    InvalidationTraceAt {
+        version: VersionNumber,
+        resp: Sender<Option<Arc<InvalidationCauses>>>,
+    },
     /// Collects the introspectable dice state
     Introspection {
         #[derivative(Debug = "ignore")]
@@ -57,6 +57,7 @@ use crate::impls::user_cycle::KeyComputingUserCycleDetectorData;
 use crate::impls::user_cycle::UserCycleDetectorData;
 use crate::impls::value::DiceComputedValue;
 use crate::impls::value::MaybeValidDiceValue;
+use crate::impls::worker::concurrency::ConcurrencySlot;
 use crate::impls::worker::project_for_key;
 use crate::impls::worker::DiceTaskWorker;
 use crate::result::CancellableResult;
@@ -105,6 +106,7 @@ impl BaseComputeCtx {
                     user_data,
                     dice,
                 },
+                None,
             ))),
             live_version_guard,
         }
@@ -119,6 +121,7 @@ impl BaseComputeCtx {
                 ParentKey::None,
                 KeyComputingUserCycleDetectorData::Untracked,
                 modern.ctx_data().async_evaluator.clone(),
+                None,
             ))),
             live_version_guard,
         }
@@ -411,6 +414,9 @@ pub(crate) struct CoreCtx {
     // data for the entire compute of a Key, including parallel computes
     #[allocative(skip)]
     evaluation_data: Mutex<EvaluationData>,
+    // the slot of the concurrency class of the Key, given back while waiting on deps
+    #[allocative(skip)]
+    concurrency: Option<Arc<ConcurrencySlot>>,
 }
 
 impl ModernComputeCtx<'static> {
@@ -455,6 +461,7 @@ impl ModernComputeCtx<'_> {
         parent_key: ParentKey,
         cycles: KeyComputingUserCycleDetectorData,
         async_evaluator: AsyncEvaluator,
+        concurrency: Option<ConcurrencySlot>,
     ) -> ModernComputeCtx<'static> {
         ModernComputeCtx::Owned {
             dep_trackers: RecordingDepsTracker::new(),
@@ -463,6 +470,7 @@ impl ModernComputeCtx<'_> {
                 parent_key,
                 cycles,
                 evaluation_data: Mutex::new(EvaluationData::none()),
+                concurrency: concurrency.map(Arc::new),
             },
         }
     }
@@ -589,7 +597,15 @@ impl CoreCtx {
             .key_index
             .index(CowDiceKeyHashed::key_ref(key));
 
-        self.async_evaluator
+        if let ParentKey::None = self.parent_key {
+            self.async_evaluator
+                .dice
+                .recorder
+                .requested(self.get_version(), dice_key);
+        }
+
+        let fut = self
+            .async_evaluator
             .per_live_version_ctx
             .compute_opaque(
                 dice_key,
@@ -598,7 +614,12 @@ impl CoreCtx {
                 self.cycles
                     .subrequest(dice_key, &self.async_evaluator.dice.key_index),
             )
-            .map_ok(move |res| (dice_key, res))
+            .map_ok(move |res| (dice_key, res));
+
+        match self.concurrency.dupe() {
+            Some(concurrency) => async move { concurrency.wait_on(fut).await }.left_future(),
+            None => fut.right_future(),
+        }
     }
 
     /// Compute "projection" based on deriving value
@@ -20,7 +20,9 @@ use crate::api::user_data::UserComputationData;
 use crate::impls::core::state::init_state;
 use crate::impls::core::state::CoreStateHandle;
 use crate::impls::key_index::DiceKeyIndex;
+use crate::impls::recording::DiceRecorder;
 use crate::impls::transaction::TransactionUpdater;
+use crate::impls::worker::concurrency::ConcurrencyLimiter;
 use crate::introspection::graph::GraphIntrospectable;
 use crate::introspection::graph::ModernIntrospectable;
 use crate::metrics::Metrics;
@@ -30,6 +32,8 @@ pub(crate) struct DiceModern {
     pub(crate) key_index: DiceKeyIndex,
     pub(crate) state_handle: CoreStateHandle,
     pub(crate) global_data: DiceData,
+    pub(crate) concurrency: ConcurrencyLimiter,
+    pub(crate) recorder: DiceRecorder,
 }
 
 impl Debug for DiceModern {
@@ -38,30 +42,49 @@ impl Debug for DiceModern {
     }
 }
 
-pub(crate) struct DiceModernDataBuilder(DiceData);
+pub(crate) struct DiceModernDataBuilder {
+    data: DiceData,
+    concurrency_limit: Option<usize>,
+}
 
 impl DiceModernDataBuilder {
     pub(crate) fn new() -> Self {
-        Self(DiceData::new())
+        Self {
+            data: DiceData::new(),
+            concurrency_limit: None,
+        }
     }
 
     pub fn set<K: Send + Sync + 'static>(&mut self, val: K) {
-        self.0.set(val);
+        self.data.set(val);
+    }
+
+    pub fn set_concurrency_limit(&mut self, limit: usize) {
+        self.concurrency_limit = Some(limit);
     }
 
     pub fn build(self, _detect_cycles: DetectCycles) -> Arc<DiceModern> {
-        DiceModern::new(self.0)
+        DiceModern::new_with_concurrency_limit(self.data, self.concurrency_limit)
     }
 }
 
 impl DiceModern {
     pub(crate) fn new(global_data: DiceData) -> Arc<Self> {
+        Self::new_with_concurrency_limit(global_data, None)
+    }
+
+    pub(crate) fn new_with_concurrency_limit(
+        global_data: DiceData,
+        concurrency_limit: Option<usize>,
+    ) -> Arc<Self> {
         let state_handle = init_state();
 
         Arc::new(DiceModern {
             key_index: Default::default(),
             state_handle,
             global_data,
+            concurrency: ConcurrencyLimiter::new(concurrency_limit),
+            recorder: DiceRecorder::default(),
         })
     }
 
@@ -79,7 +102,9 @@ impl DiceModern {
     }
 
     pub fn metrics(&self) -> Metrics {
-        self.state_handle.metrics()
+        let mut metrics = self.state_handle.metrics();
+        metrics.concurrency_classes = self.concurrency.metrics();
+        metrics
     }
 
     pub fn to_introspectable(&self) -> GraphIntrospectable {
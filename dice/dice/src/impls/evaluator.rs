@@ -13,6 +13,7 @@ use allocative::Allocative;
 use dupe::Dupe;
 
 use crate::api::computations::DiceComputations;
+use crate::api::concurrency::ConcurrencyClass;
 use crate::api::projection::DiceProjectionComputations;
 use crate::api::storage_type::StorageType;
 use crate::api::user_data::UserComputationData;
@@ -26,6 +27,7 @@ use crate::impls::key::DiceKeyErased;
 use crate::impls::key::ParentKey;
 use crate::impls::user_cycle::KeyComputingUserCycleDetectorData;
 use crate::impls::value::MaybeValidDiceValue;
+use crate::impls::worker::concurrency::ConcurrencySlot;
 use crate::impls::worker::state::DiceWorkerStateEvaluating;
 use crate::impls::worker::state::DiceWorkerStateFinishedEvaluating;
 use crate::result::CancellableResult;
@@ -48,11 +50,19 @@ impl AsyncEvaluator {
         }
     }
 
+    pub(crate) fn concurrency_class(&self, key: DiceKey) -> Option<ConcurrencyClass> {
+        match self.dice.key_index.get(key) {
+            DiceKeyErased::Key(k) => k.concurrency_class(),
+            DiceKeyErased::Projection(_) => None,
+        }
+    }
+
     pub(crate) async fn evaluate<'a, 'b>(
         &self,
         key: DiceKey,
         state: DiceWorkerStateEvaluating<'a, 'b>,
         cycles: KeyComputingUserCycleDetectorData,
+        concurrency: Option<ConcurrencySlot>,
     ) -> CancellableResult<DiceWorkerStateFinishedEvaluating<'a, 'b>> {
         let key_erased = self.dice.key_index.get(key);
 
@@ -63,6 +73,7 @@ impl AsyncEvaluator {
                         ParentKey::Some(key), // within this key's compute, this key is the parent
                         cycles,
                         self.dupe(),
+                        concurrency,
                     )));
 
                 let value = key_dyn
@@ -118,7 +129,7 @@ impl AsyncEvaluator {
 #[derive(Clone, Dupe)]
 pub(crate) struct SyncEvaluator {
     user_data: Arc<UserComputationData>,
-    dice: Arc<DiceModern>,
+    pub(super) dice: Arc<DiceModern>,
     base: MaybeValidDiceValue,
 }
 
@@ -11,8 +11,10 @@ use std::sync::Arc;
 
 use dupe::Dupe;
 
+use crate::api::concurrency::ConcurrencyClass;
 use crate::api::events::DiceEvent;
 use crate::api::events::DiceEventListener;
+use crate::impls::core::early_cutoffs::RecomputeOutcome;
 use crate::impls::dice::DiceModern;
 use crate::impls::key::DiceKey;
 
@@ -66,4 +68,29 @@ impl DiceEventDispatcher {
         self.tracker
             .event(DiceEvent::ComputeFinished { key_type: desc })
     }
+
+    pub(crate) fn recomputed(&self, k: DiceKey, outcome: RecomputeOutcome) {
+        let desc = self.dice.key_index.get(k).key_type_name();
+
+        self.tracker.event(match outcome {
+            RecomputeOutcome::Unchanged => DiceEvent::RecomputeUnchanged { key_type: desc },
+            RecomputeOutcome::Changed => DiceEvent::RecomputeChanged { key_type: desc },
+        })
+    }
+
+    pub(crate) fn concurrency_queued(&self, class: ConcurrencyClass) {
+        self.tracker.event(DiceEvent::ConcurrencyQueued { class })
+    }
+
+    pub(crate) fn concurrency_dequeued(&self, class: ConcurrencyClass) {
+        self.tracker.event(DiceEvent::ConcurrencyDequeued { class })
+    }
+
+    pub(crate) fn concurrency_started(&self, class: ConcurrencyClass) {
+        self.tracker.event(DiceEvent::ConcurrencyStarted { class })
+    }
+
+    pub(crate) fn concurrency_finished(&self, class: ConcurrencyClass) {
+        self.tracker.event(DiceEvent::ConcurrencyFinished { class })
+    }
 }
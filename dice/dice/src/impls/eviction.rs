@@ -0,0 +1,66 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Evicting the least recently used values of the graph, see `api::eviction`.
+
+use crate::api::eviction::EvictionStats;
+use crate::impls::core::graph::storage::EvictionCandidate;
+use crate::impls::dice::DiceModern;
+use crate::impls::key::DiceKey;
+
+impl DiceModern {
+    pub(crate) async fn evict_cold_values(&self, memory_budget: u64) -> EvictionStats {
+        let candidates = self.state_handle.eviction_candidates().await;
+        // Measuring can take a while for large graphs, so it's done here rather than on the core
+        // state thread. Nodes used in the meantime won't be evicted.
+        let (keys, mut stats) = select_for_eviction(candidates, memory_budget);
+        if !keys.is_empty() {
+            stats.evicted_count = self.state_handle.evict(keys).await;
+        }
+        stats
+    }
+}
+
+/// Picks the least recently used candidates to evict so that the remaining ones fit in
+/// `memory_budget` bytes.
+fn select_for_eviction(
+    mut candidates: Vec<EvictionCandidate>,
+    memory_budget: u64,
+) -> (Vec<(DiceKey, u64)>, EvictionStats) {
+    candidates.sort_by_key(|c| c.last_used);
+    // All the values are measured in one go, so that data they share is counted once. It's
+    // counted for the most recently used value sharing it: evicting older values doesn't free it.
+    let mut sizes: Vec<u64> = allocative::size_of_allocated_data_per_root(
+        candidates
+            .iter()
+            .rev()
+            .map(|c| &c.value as &dyn allocative::Allocative),
+    )
+    .into_iter()
+    .map(|size| size as u64)
+    .collect();
+    sizes.reverse();
+
+    let mut stats = EvictionStats {
+        candidate_count: candidates.len(),
+        candidate_bytes: sizes.iter().sum(),
+        ..Default::default()
+    };
+    let mut remaining = stats.candidate_bytes;
+    let mut keys = Vec::new();
+    for (candidate, size) in candidates.iter().zip(sizes) {
+        if remaining <= memory_budget {
+            break;
+        }
+        remaining -= size;
+        stats.evicted_bytes += size;
+        keys.push((candidate.key, candidate.last_used));
+    }
+    (keys, stats)
+}
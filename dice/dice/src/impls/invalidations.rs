@@ -0,0 +1,65 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Querying the recorded invalidation traces, see `api::invalidations`. The traces are copied out
+//! of the core state, so that the keys are formatted on the caller's task.
+
+use crate::api::invalidations::DiceInvalidationPath;
+use crate::api::invalidations::DiceInvalidations;
+use crate::api::transaction::DiceEquality;
+use crate::impls::core::invalidations::invalidation_paths;
+use crate::impls::dice::DiceModern;
+use crate::impls::key::DiceKey;
+use crate::versions::VersionNumber;
+
+impl DiceModern {
+    pub(crate) fn set_invalidation_tracing(&self, enabled: bool) {
+        self.state_handle.set_invalidation_tracing(enabled)
+    }
+
+    pub(crate) async fn invalidation_paths(
+        &self,
+        matches: Box<dyn Fn(&str) -> bool + Send>,
+    ) -> Vec<DiceInvalidationPath> {
+        let traces = self.state_handle.invalidation_traces().await;
+        let mut res = Vec::new();
+        for (v, causes) in traces {
+            let paths =
+                invalidation_paths(&causes, |k| matches(&self.key_index.get(k).to_string()));
+            res.extend(paths.into_iter().map(|keys| self.path_to_strings(v, keys)));
+        }
+        res
+    }
+
+    pub(crate) async fn invalidations_at(
+        &self,
+        version: DiceEquality,
+        limit: usize,
+    ) -> Option<DiceInvalidations> {
+        let causes = self.state_handle.invalidation_trace_at(version.0).await?;
+        Some(DiceInvalidations {
+            dirtied_count: causes.len(),
+            paths: invalidation_paths(&causes, |_| true)
+                .into_iter()
+                .take(limit)
+                .map(|keys| self.path_to_strings(version.0, keys))
+                .collect(),
+        })
+    }
+
+    fn path_to_strings(&self, v: VersionNumber, keys: Vec<DiceKey>) -> DiceInvalidationPath {
+        DiceInvalidationPath {
+            version: DiceEquality(v),
+            keys: keys
+                .into_iter()
+                .map(|k| self.key_index.get(k).to_string())
+                .collect(),
+        }
+    }
+}
@@ -23,6 +23,7 @@ use dupe::Dupe;
 use fxhash::FxHasher;
 
 use crate::api::computations::DiceComputations;
+use crate::api::concurrency::ConcurrencyClass;
 use crate::api::key::Key;
 use crate::api::projection::DiceProjectionComputations;
 use crate::api::projection::ProjectionKey;
@@ -271,6 +272,8 @@ pub(crate) trait DiceKeyDyn: Allocative + Display + Send + Sync + 'static {
     fn key_type_name(&self) -> &'static str;
 
     fn storage_type(&self) -> StorageType;
+
+    fn concurrency_class(&self) -> Option<ConcurrencyClass>;
 }
 
 #[async_trait]
@@ -310,6 +313,10 @@ where
     fn storage_type(&self) -> StorageType {
         K::storage_type()
     }
+
+    fn concurrency_class(&self) -> Option<ConcurrencyClass> {
+        K::concurrency_class()
+    }
 }
 
 pub(crate) trait DiceProjectionDyn: Allocative + Display + Send + Sync + 'static {
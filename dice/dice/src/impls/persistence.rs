@@ -0,0 +1,158 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Taking and restoring snapshots of the graph, see `api::persistence`.
+
+use std::sync::Arc;
+
+use dupe::Dupe;
+
+use crate::api::persistence::DicePersistence;
+use crate::api::persistence::DiceSnapshot;
+use crate::api::persistence::PersistenceError;
+use crate::api::persistence::SnapshotNode;
+use crate::impls::core::graph::storage::PersistedNode;
+use crate::impls::dice::DiceModern;
+use crate::impls::key::DiceKey;
+use crate::HashMap;
+
+impl DiceModern {
+    pub(crate) async fn snapshot(
+        self: &Arc<Self>,
+        persistence: &DicePersistence,
+        metadata: String,
+    ) -> anyhow::Result<DiceSnapshot> {
+        let key_types = persistence.key_types();
+        let dice = self.dupe();
+        let (_, nodes) = self
+            .state_handle
+            .valid_nodes(Box::new(move |k| {
+                // For projections, this is the type of the projection key.
+                key_types.contains(&dice.key_index.get(k).as_any().type_id())
+            }))
+            .await;
+
+        // Encode first, as nodes that can't be encoded are dropped along with their rdeps.
+        let mut encoded = Vec::with_capacity(nodes.len());
+        let mut encodable = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let key = self.key_index.get(node.key).as_any();
+            let codec = persistence
+                .codec_for_key(key)
+                .expect("only registered key types are collected");
+            if let Some((key, value)) = codec.encode(key, node.value.as_ref())? {
+                encoded.push((codec.id(), key, value));
+                encodable.push(node);
+            }
+        }
+        let nodes = encodable;
+
+        let order = persistable_order(&nodes);
+
+        let mut key_types = Vec::new();
+        let mut key_type_indices = HashMap::default();
+        let mut snapshot_indices = HashMap::default();
+        let mut snapshot_nodes = Vec::with_capacity(order.len());
+        for i in order {
+            let node = &nodes[i];
+            let (id, key, value) = std::mem::take(&mut encoded[i]);
+            let key_type = *key_type_indices.entry(id).or_insert_with(|| {
+                key_types.push(id.to_owned());
+                (key_types.len() - 1) as u32
+            });
+            snapshot_indices.insert(node.key, snapshot_nodes.len() as u32);
+            snapshot_nodes.push(SnapshotNode {
+                key_type,
+                key,
+                value,
+                deps: node.deps.iter().map(|dep| snapshot_indices[dep]).collect(),
+            });
+        }
+
+        Ok(DiceSnapshot::new(metadata, key_types, snapshot_nodes))
+    }
+
+    /// Returns the number of restored nodes.
+    pub(crate) async fn restore(
+        &self,
+        snapshot: &DiceSnapshot,
+        persistence: &DicePersistence,
+    ) -> anyhow::Result<usize> {
+        let nodes = snapshot.decode(&self.key_index, &self.global_data, persistence)?;
+        let count = nodes.len();
+        match self.state_handle.restore(nodes).await {
+            Some(_) => Ok(count),
+            None => Err(PersistenceError::GraphNotEmpty.into()),
+        }
+    }
+}
+
+/// The indices of the nodes which can be saved, i.e. the ones whose deps can all be saved, with
+/// every node after its deps.
+fn persistable_order(nodes: &[PersistedNode]) -> Vec<usize> {
+    let indices: HashMap<DiceKey, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.key, i))
+        .collect();
+
+    // Drop the nodes with a dep that wasn't collected, and everything that depends on them.
+    let mut rdeps: HashMap<usize, Vec<usize>> = HashMap::default();
+    let mut dropped = vec![false; nodes.len()];
+    let mut queue = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        for dep in &node.deps {
+            match indices.get(dep) {
+                Some(dep) => rdeps.entry(*dep).or_default().push(i),
+                None => {
+                    if !dropped[i] {
+                        dropped[i] = true;
+                        queue.push(i);
+                    }
+                }
+            }
+        }
+    }
+    while let Some(i) = queue.pop() {
+        for rdep in rdeps.get(&i).into_iter().flatten() {
+            if !dropped[*rdep] {
+                dropped[*rdep] = true;
+                queue.push(*rdep);
+            }
+        }
+    }
+
+    // Post-order depth first traversal, so that deps come first.
+    let mut visited = dropped;
+    let mut order = Vec::new();
+    for root in 0..nodes.len() {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some((i, next_dep)) = stack.last_mut() {
+            match nodes[*i].deps.get(*next_dep) {
+                Some(dep) => {
+                    *next_dep += 1;
+                    let dep = indices[dep];
+                    if !visited[dep] {
+                        visited[dep] = true;
+                        stack.push((dep, 0));
+                    }
+                }
+                None => {
+                    order.push(*i);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    order
+}
@@ -0,0 +1,153 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Records transactions while enabled, see `api::recording`.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use allocative::Allocative;
+use parking_lot::Mutex;
+
+use crate::api::recording::DiceRecording;
+use crate::api::recording::RecordedComputation;
+use crate::api::recording::RecordedKey;
+use crate::api::recording::RecordedTransaction;
+use crate::impls::dice::DiceModern;
+use crate::impls::key::DiceKey;
+use crate::impls::key_index::DiceKeyIndex;
+use crate::versions::VersionNumber;
+use crate::HashSet;
+
+#[derive(Allocative, Default)]
+pub(crate) struct DiceRecorder {
+    /// Checked before locking, so that computations don't contend on the lock when not recording.
+    #[allocative(skip)]
+    enabled: AtomicBool,
+    #[allocative(skip)]
+    transactions: Mutex<BTreeMap<VersionNumber, VersionRecording>>,
+}
+
+#[derive(Default)]
+struct VersionRecording {
+    invalidated: HashSet<DiceKey>,
+    changed_to: HashSet<DiceKey>,
+    requested: HashSet<DiceKey>,
+    computed: Vec<(DiceKey, Vec<DiceKey>)>,
+}
+
+impl DiceRecorder {
+    fn record(&self, v: VersionNumber, f: impl FnOnce(&mut VersionRecording)) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut transactions = self.transactions.lock();
+        // Checked again under the lock, so nothing is recorded once `stop` has taken the
+        // recording.
+        if self.enabled.load(Ordering::Relaxed) {
+            f(transactions.entry(v).or_default())
+        }
+    }
+
+    pub(crate) fn changed(&self, v: VersionNumber, key: DiceKey, invalidated: bool) {
+        self.record(v, |r| {
+            if invalidated {
+                r.invalidated.insert(key);
+            } else {
+                r.changed_to.insert(key);
+            }
+        })
+    }
+
+    pub(crate) fn requested(&self, v: VersionNumber, key: DiceKey) {
+        self.record(v, |r| {
+            r.requested.insert(key);
+        })
+    }
+
+    pub(crate) fn computed(
+        &self,
+        v: VersionNumber,
+        key: DiceKey,
+        deps: impl Iterator<Item = DiceKey>,
+    ) {
+        self.record(v, |r| r.computed.push((key, deps.collect())))
+    }
+
+    fn start(&self) {
+        let mut transactions = self.transactions.lock();
+        transactions.clear();
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    fn stop(&self, key_index: &DiceKeyIndex) -> Option<DiceRecording> {
+        let transactions = {
+            let mut transactions = self.transactions.lock();
+            if !self.enabled.swap(false, Ordering::Relaxed) {
+                return None;
+            }
+            std::mem::take(&mut *transactions)
+        };
+
+        let name = |k: DiceKey| {
+            let key = key_index.get(k);
+            RecordedKey {
+                key_type: key.key_type_name().to_owned(),
+                key: key.to_string(),
+            }
+        };
+        let names = |keys: HashSet<DiceKey>| {
+            let mut names: Vec<RecordedKey> = keys.into_iter().map(name).collect();
+            names.sort();
+            names
+        };
+
+        Some(DiceRecording {
+            transactions: transactions
+                .into_values()
+                .map(|r| {
+                    let mut computed: Vec<RecordedComputation> = r
+                        .computed
+                        .into_iter()
+                        .map(|(key, deps)| {
+                            let mut seen = HashSet::default();
+                            RecordedComputation {
+                                key: name(key),
+                                deps: deps
+                                    .into_iter()
+                                    .filter(|dep| seen.insert(*dep))
+                                    .map(name)
+                                    .collect(),
+                            }
+                        })
+                        .collect();
+                    // Computations finish in any order, but the deps of each are kept in order.
+                    computed.sort_by(|a, b| a.key.cmp(&b.key));
+                    RecordedTransaction {
+                        invalidated: names(r.invalidated),
+                        changed_to: names(r.changed_to),
+                        requested: names(r.requested),
+                        computed,
+                    }
+                })
+                .collect(),
+        })
+    }
+}
+
+impl DiceModern {
+    pub(crate) fn start_recording(&self) {
+        self.recorder.start()
+    }
+
+    pub(crate) fn stop_recording(&self) -> Option<DiceRecording> {
+        self.recorder.stop(&self.key_index)
+    }
+}
@@ -8,10 +8,16 @@
  */
 
 mod activation_tracker;
+mod concurrency;
 mod demo;
 mod events;
+mod eviction;
 mod general;
+mod invalidations;
 mod keys;
+mod persistence;
+mod recording;
 mod spawner;
+mod test_keys;
 mod transients;
 mod user_data;
@@ -0,0 +1,182 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_futures::cancellation::CancellationContext;
+use derive_more::Display;
+use dupe::Dupe;
+use futures::FutureExt;
+
+use crate::api::computations::DiceComputations;
+use crate::api::concurrency::ConcurrencyClass;
+use crate::api::data::DiceData;
+use crate::api::key::Key;
+use crate::impls::dice::DiceModern;
+
+#[derive(Debug, Default)]
+struct Running {
+    now: AtomicUsize,
+    max: AtomicUsize,
+}
+
+#[derive(Allocative, Clone, Debug, Display)]
+#[display(fmt = "Limited({})", _0)]
+struct Limited(usize, #[allocative(skip)] Arc<Running>);
+
+impl PartialEq for Limited {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Limited {}
+
+impl Hash for Limited {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+#[async_trait]
+impl Key for Limited {
+    type Value = ();
+
+    async fn compute(
+        &self,
+        _ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let now = self.1.now.fetch_add(1, Ordering::SeqCst) + 1;
+        self.1.max.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        self.1.now.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn equality(_x: &Self::Value, _y: &Self::Value) -> bool {
+        true
+    }
+
+    fn concurrency_class() -> Option<ConcurrencyClass> {
+        Some(ConcurrencyClass::new("limited", 2, 0))
+    }
+}
+
+#[tokio::test]
+async fn concurrency_class_limits_running_computations() -> anyhow::Result<()> {
+    let dice = DiceModern::new(DiceData::new());
+    let running = Arc::new(Running::default());
+
+    let mut ctx = dice.updater().commit().await;
+    let keys = (0..8)
+        .map(|i| Limited(i, running.dupe()))
+        .collect::<Vec<_>>();
+    ctx.compute_join(keys.iter(), |ctx, k| ctx.compute(k).boxed())
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+
+    assert_eq!(2, running.max.load(Ordering::SeqCst));
+
+    let metrics = dice.metrics();
+    assert_eq!(1, metrics.concurrency_classes.len());
+    assert_eq!("limited", metrics.concurrency_classes[0].name);
+    assert_eq!(0, metrics.concurrency_classes[0].running);
+    assert_eq!(0, metrics.concurrency_classes[0].queued);
+
+    Ok(())
+}
+
+/// Depends on `Nested(n - 1)`, down to `Nested(0)`, which depends on `Limited` keys.
+#[derive(Allocative, Clone, Debug, Display)]
+#[display(fmt = "Nested({})", _0)]
+struct Nested(usize, #[allocative(skip)] Arc<Running>);
+
+impl PartialEq for Nested {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Nested {}
+
+impl Hash for Nested {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+#[async_trait]
+impl Key for Nested {
+    type Value = ();
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        if self.0 == 0 {
+            let keys = (0..4)
+                .map(|i| Limited(i, self.1.dupe()))
+                .collect::<Vec<_>>();
+            ctx.compute_join(keys.iter(), |ctx, k| ctx.compute(k).boxed())
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+        } else {
+            ctx.compute(&Nested(self.0 - 1, self.1.dupe()))
+                .await
+                .unwrap();
+        }
+    }
+
+    fn equality(_x: &Self::Value, _y: &Self::Value) -> bool {
+        true
+    }
+
+    fn concurrency_class() -> Option<ConcurrencyClass> {
+        Some(ConcurrencyClass::new("nested", 1, 1))
+    }
+}
+
+#[tokio::test]
+async fn computations_waiting_on_deps_give_back_their_slot() -> anyhow::Result<()> {
+    // A single slot across all classes: `Nested` keys depend on keys of their own class and on
+    // `Limited` keys, which could only run once the `Nested` keys waiting on them let go of it.
+    let dice = DiceModern::new_with_concurrency_limit(DiceData::new(), Some(1));
+    let running = Arc::new(Running::default());
+
+    let mut ctx = dice.updater().commit().await;
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        ctx.compute(&Nested(3, running.dupe())),
+    )
+    .await??;
+
+    assert_eq!(1, running.max.load(Ordering::SeqCst));
+
+    let metrics = dice.metrics();
+    assert_eq!("nested", metrics.concurrency_classes[0].name);
+    assert_eq!(1, metrics.concurrency_classes[0].limit);
+    assert_eq!(1, metrics.concurrency_classes[0].priority);
+    for class in &metrics.concurrency_classes {
+        assert_eq!(0, class.running);
+        assert_eq!(0, class.queued);
+    }
+
+    Ok(())
+}
@@ -149,6 +149,8 @@ async fn test_events_impl(builder: DiceDataBuilder) -> anyhow::Result<()> {
                 DiceEvent::CheckDepsFinished { key_type: "Stage0" },
                 DiceEvent::ComputeStarted { key_type: "Stage0" },
                 DiceEvent::ComputeFinished { key_type: "Stage0" },
+                // `Stage0` has the same value, so `Stage1` isn't recomputed.
+                DiceEvent::RecomputeUnchanged { key_type: "Stage0" },
                 DiceEvent::Finished { key_type: "Stage0" },
                 DiceEvent::CheckDepsFinished { key_type: "Stage1" },
                 DiceEvent::Finished { key_type: "Stage1" },
@@ -168,3 +170,24 @@ async fn test_events_legacy() -> anyhow::Result<()> {
 async fn test_events_modern() -> anyhow::Result<()> {
     test_events_impl(Dice::modern()).await
 }
+
+#[tokio::test]
+async fn test_early_cutoff_metrics() -> anyhow::Result<()> {
+    let dice = Dice::modern().build(DetectCycles::Enabled);
+
+    for value in [1, 2] {
+        let mut updater = dice.updater();
+        updater.changed_to(vec![(Injected, value)])?;
+        let mut transaction = updater.commit().await;
+        transaction.compute(&Stage1).await?;
+    }
+
+    // Only `Stage0` was recomputed, to a value equal to the previous one.
+    let early_cutoffs = dice.metrics().early_cutoffs;
+    assert_eq!(1, early_cutoffs.len());
+    assert_eq!("Stage0", early_cutoffs[0].key_type);
+    assert_eq!(1, early_cutoffs[0].cutoffs);
+    assert_eq!(0, early_cutoffs[0].changes);
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::atomic::Ordering;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_futures::cancellation::CancellationContext;
+use derive_more::Display;
+
+use crate::api::computations::DiceComputations;
+use crate::api::key::Key;
+use crate::impls::tests::test_keys::new_dice;
+use crate::impls::tests::test_keys::Base;
+use crate::impls::tests::test_keys::Double;
+use crate::impls::tests::test_keys::Quadruple;
+
+#[tokio::test]
+async fn evicted_values_are_recomputed() -> anyhow::Result<()> {
+    let (dice, computed) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 10)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(40, ctx.compute(&Quadruple(1)).await?);
+    drop(ctx);
+
+    // Within budget, nothing is evicted.
+    let stats = dice.evict_cold_values(u64::MAX).await;
+    assert_eq!(2, stats.candidate_count);
+    assert_eq!(0, stats.evicted_count);
+
+    // Injected values are never evicted.
+    let stats = dice.evict_cold_values(0).await;
+    assert_eq!(2, stats.evicted_count);
+    assert_eq!(stats.candidate_bytes, stats.evicted_bytes);
+    assert_eq!(0, dice.evict_cold_values(0).await.candidate_count);
+
+    let mut ctx = dice.updater().commit().await;
+    assert_eq!(40, ctx.compute(&Quadruple(1)).await?);
+    assert_eq!(2, computed.load(Ordering::SeqCst));
+    drop(ctx);
+
+    // Evicted nodes keep their edges, so changes still invalidate their dependents.
+    dice.evict_cold_values(0).await;
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 5)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(20, ctx.compute(&Quadruple(1)).await?);
+    assert_eq!(3, computed.load(Ordering::SeqCst));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn recomputed_evicted_values_keep_their_dependents() -> anyhow::Result<()> {
+    let (dice, computed) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 10)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(40, ctx.compute(&Quadruple(1)).await?);
+    drop(ctx);
+    dice.evict_cold_values(0).await;
+
+    // `Double(1)` can't reuse its evicted value, and replaces it with a new one.
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 5)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(10, ctx.compute(&Double(1)).await?);
+    assert_eq!(2, computed.load(Ordering::SeqCst));
+    assert_eq!(20, ctx.compute(&Quadruple(1)).await?);
+    drop(ctx);
+
+    // Changes still reach `Quadruple(1)` through the new `Double(1)`.
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 7)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(28, ctx.compute(&Quadruple(1)).await?);
+    assert_eq!(3, computed.load(Ordering::SeqCst));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn least_recently_used_values_are_evicted_first() -> anyhow::Result<()> {
+    let (dice, computed) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 10), (Base(2), 20)])?;
+    let mut ctx = updater.commit().await;
+    ctx.compute(&Double(1)).await?;
+    drop(ctx);
+    let mut ctx = dice.updater().commit().await;
+    ctx.compute(&Double(2)).await?;
+    drop(ctx);
+    assert_eq!(2, computed.load(Ordering::SeqCst));
+
+    // Both values have the same size, so only the older one has to go.
+    let candidate_bytes = dice.evict_cold_values(u64::MAX).await.candidate_bytes;
+    let stats = dice.evict_cold_values(candidate_bytes / 2).await;
+    assert_eq!(1, stats.evicted_count);
+
+    let mut ctx = dice.updater().commit().await;
+    assert_eq!(40, ctx.compute(&Double(2)).await?);
+    assert_eq!(2, computed.load(Ordering::SeqCst));
+    assert_eq!(20, ctx.compute(&Double(1)).await?);
+    assert_eq!(3, computed.load(Ordering::SeqCst));
+
+    Ok(())
+}
+
+/// Computes `Base * 3`, without opting into eviction.
+#[derive(Allocative, Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[display(fmt = "Triple({})", _0)]
+struct Triple(u32);
+
+#[async_trait]
+impl Key for Triple {
+    type Value = usize;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.compute(&Base(self.0)).await.unwrap() * 3
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[tokio::test]
+async fn only_evictable_values_are_evicted() -> anyhow::Result<()> {
+    let (dice, computed) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 10)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(30, ctx.compute(&Triple(1)).await?);
+    assert_eq!(20, ctx.compute(&Double(1)).await?);
+    drop(ctx);
+
+    let stats = dice.evict_cold_values(0).await;
+    assert_eq!(1, stats.candidate_count);
+    assert_eq!(1, stats.evicted_count);
+
+    let mut ctx = dice.updater().commit().await;
+    assert_eq!(30, ctx.compute(&Triple(1)).await?);
+    assert_eq!(20, ctx.compute(&Double(1)).await?);
+    assert_eq!(2, computed.load(Ordering::SeqCst));
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use crate::api::transaction::DiceEquality;
+use crate::impls::dice::DiceModern;
+use crate::impls::tests::test_keys::Base;
+use crate::impls::tests::test_keys::Double;
+use crate::impls::tests::test_keys::Quadruple;
+use crate::DiceData;
+
+#[tokio::test]
+async fn invalidation_paths_lead_from_changed_keys() -> anyhow::Result<()> {
+    let dice = DiceModern::new(DiceData::new());
+    dice.set_invalidation_tracing(true);
+
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 1), (Base(2), 2)])?;
+    let mut ctx = updater.commit().await;
+    ctx.compute(&Quadruple(1)).await?;
+    ctx.compute(&Double(2)).await?;
+    drop(ctx);
+
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 10)])?;
+    drop(updater.commit().await);
+    let v2 = dice.state_handle.current_version().await;
+
+    let paths = dice
+        .invalidation_paths(Box::new(|k| k.starts_with("Quadruple")))
+        .await;
+    assert_eq!(1, paths.len());
+    assert_eq!(DiceEquality(v2), paths[0].version);
+    assert_eq!(vec!["Base(1)", "Double(1)", "Quadruple(1)"], paths[0].keys);
+
+    // Keys that weren't dirtied have no path.
+    assert!(
+        dice.invalidation_paths(Box::new(|k| k == "Double(2)"))
+            .await
+            .is_empty()
+    );
+
+    let invalidations = dice
+        .invalidations_at(DiceEquality(v2), 10)
+        .await
+        .expect("update should be traced");
+    assert_eq!(3, invalidations.dirtied_count);
+    let paths: Vec<_> = invalidations.paths.iter().map(|p| p.keys.len()).collect();
+    assert_eq!(vec![1, 2, 3], paths);
+    assert_eq!(
+        1,
+        dice.invalidations_at(DiceEquality(v2), 1)
+            .await
+            .expect("update should be traced")
+            .paths
+            .len()
+    );
+
+    dice.set_invalidation_tracing(false);
+    assert!(dice.invalidations_at(DiceEquality(v2), 10).await.is_none());
+
+    Ok(())
+}
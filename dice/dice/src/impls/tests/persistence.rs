@@ -0,0 +1,160 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::atomic::Ordering;
+
+use crate::api::persistence::DicePersistence;
+use crate::api::persistence::DiceSnapshot;
+use crate::impls::tests::test_keys::new_dice;
+use crate::impls::tests::test_keys::Base;
+use crate::impls::tests::test_keys::Double;
+use crate::impls::tests::test_keys::IsEven;
+use crate::impls::tests::test_keys::Quadruple;
+
+#[tokio::test]
+async fn snapshot_restores_computed_values() -> anyhow::Result<()> {
+    let mut persistence = DicePersistence::new();
+    persistence.register::<Base>()?;
+    persistence.register::<Double>()?;
+
+    let (dice, computed) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 10)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(20, ctx.compute(&Double(1)).await?);
+    assert_eq!(1, computed.load(Ordering::SeqCst));
+    drop(ctx);
+
+    let mut bytes = Vec::new();
+    dice.snapshot(&persistence, "metadata".to_owned())
+        .await?
+        .write(&mut bytes)?;
+    let snapshot = DiceSnapshot::read(&bytes[..])?;
+    assert_eq!("metadata", snapshot.metadata());
+    assert_eq!(2, snapshot.len());
+
+    let (dice, computed) = new_dice();
+    assert_eq!(2, dice.restore(&snapshot, &persistence).await?);
+    assert!(dice.restore(&snapshot, &persistence).await.is_err());
+
+    let mut ctx = dice.updater().commit().await;
+    assert_eq!(20, ctx.compute(&Double(1)).await?);
+    assert_eq!(0, computed.load(Ordering::SeqCst));
+    drop(ctx);
+
+    // Changes made after the restore invalidate the restored nodes like computed ones.
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 5)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(10, ctx.compute(&Double(1)).await?);
+    assert_eq!(1, computed.load(Ordering::SeqCst));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn snapshot_skips_nodes_with_unregistered_deps() -> anyhow::Result<()> {
+    let mut persistence = DicePersistence::new();
+    persistence.register::<Double>()?;
+
+    let (dice, _computed) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 10)])?;
+    let mut ctx = updater.commit().await;
+    ctx.compute(&Double(1)).await?;
+    drop(ctx);
+
+    let snapshot = dice.snapshot(&persistence, String::new()).await?;
+    assert!(snapshot.is_empty());
+
+    assert!(persistence.register::<Double>().is_err());
+    assert!(DiceSnapshot::read(&b"not a snapshot"[..]).is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn snapshot_restores_keys_without_values() -> anyhow::Result<()> {
+    let mut persistence = DicePersistence::new();
+    persistence.register::<Base>()?;
+    persistence.register::<Double>()?;
+    persistence.register_without_value::<Quadruple>()?;
+    assert!(
+        DicePersistence::new()
+            .register_without_value::<Base>()
+            .is_err()
+    );
+
+    let (dice, _computed) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 10)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(40, ctx.compute(&Quadruple(1)).await?);
+    drop(ctx);
+
+    let snapshot = dice.snapshot(&persistence, String::new()).await?;
+    assert_eq!(3, snapshot.len());
+
+    let (dice, computed) = new_dice();
+    assert_eq!(3, dice.restore(&snapshot, &persistence).await?);
+
+    // `Quadruple(1)` is recomputed from the restored `Double(1)`.
+    let mut ctx = dice.updater().commit().await;
+    assert_eq!(40, ctx.compute(&Quadruple(1)).await?);
+    assert_eq!(0, computed.load(Ordering::SeqCst));
+    drop(ctx);
+
+    // The restored edges still propagate changes.
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 5)])?;
+    let mut ctx = updater.commit().await;
+    assert_eq!(20, ctx.compute(&Quadruple(1)).await?);
+    assert_eq!(1, computed.load(Ordering::SeqCst));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn snapshot_restores_projections() -> anyhow::Result<()> {
+    let mut persistence = DicePersistence::new();
+    persistence.register::<Base>()?;
+    persistence.register_without_value::<Double>()?;
+    persistence.register_projection::<IsEven>()?;
+
+    let (dice, _computed) = new_dice();
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 10)])?;
+    let mut ctx = updater.commit().await;
+    let double = ctx.compute_opaque(&Double(1)).await?;
+    assert!(ctx.projection(&double, &IsEven)?);
+    drop(double);
+    drop(ctx);
+
+    let snapshot = dice.snapshot(&persistence, String::new()).await?;
+    assert_eq!(3, snapshot.len());
+
+    let (dice, computed) = new_dice();
+    assert_eq!(3, dice.restore(&snapshot, &persistence).await?);
+
+    // The projection is restored with its value, while `Double(1)` is recomputed.
+    let mut ctx = dice.updater().commit().await;
+    let double = ctx.compute_opaque(&Double(1)).await?;
+    assert!(ctx.projection(&double, &IsEven)?);
+    drop(double);
+    drop(ctx);
+    assert_eq!(1, computed.load(Ordering::SeqCst));
+
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 5)])?;
+    let mut ctx = updater.commit().await;
+    let double = ctx.compute_opaque(&Double(1)).await?;
+    assert!(!ctx.projection(&double, &IsEven)?);
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use crate::api::recording::RecordedComputation;
+use crate::api::recording::RecordedKey;
+use crate::impls::tests::test_keys::new_dice;
+use crate::impls::tests::test_keys::Base;
+use crate::impls::tests::test_keys::Double;
+use crate::impls::tests::test_keys::Quadruple;
+
+fn key(key_type: &str, key: &str) -> RecordedKey {
+    RecordedKey {
+        key_type: key_type.to_owned(),
+        key: key.to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn recording_records_changes_requests_and_computations() -> anyhow::Result<()> {
+    let (dice, _computed) = new_dice();
+    dice.start_recording();
+
+    let mut updater = dice.updater();
+    updater.changed_to([(Base(1), 1)])?;
+    let mut ctx = updater.commit().await;
+    ctx.compute(&Quadruple(1)).await?;
+    drop(ctx);
+
+    // `Double(1)` recomputes to the same value, so `Quadruple(1)` isn't recomputed.
+    let mut updater = dice.updater();
+    updater.changed([Double(1)])?;
+    let mut ctx = updater.commit().await;
+    ctx.compute(&Quadruple(1)).await?;
+    drop(ctx);
+
+    let recording = dice.stop_recording().expect("should be recording");
+    assert_eq!(2, recording.transactions.len());
+
+    let first = &recording.transactions[0];
+    assert!(first.invalidated.is_empty());
+    assert_eq!(vec![key("Base", "Base(1)")], first.changed_to);
+    assert_eq!(vec![key("Quadruple", "Quadruple(1)")], first.requested);
+    assert_eq!(
+        vec![
+            RecordedComputation {
+                key: key("Double", "Double(1)"),
+                deps: vec![key("Base", "Base(1)")],
+            },
+            RecordedComputation {
+                key: key("Quadruple", "Quadruple(1)"),
+                deps: vec![key("Double", "Double(1)")],
+            },
+        ],
+        first.computed
+    );
+
+    let second = &recording.transactions[1];
+    assert_eq!(vec![key("Double", "Double(1)")], second.invalidated);
+    assert!(second.changed_to.is_empty());
+    assert_eq!(vec![key("Quadruple", "Quadruple(1)")], second.requested);
+    assert_eq!(
+        vec![&key("Double", "Double(1)")],
+        second.computed.iter().map(|c| &c.key).collect::<Vec<_>>()
+    );
+
+    assert!(dice.stop_recording().is_none());
+
+    Ok(())
+}
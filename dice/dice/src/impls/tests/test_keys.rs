@@ -0,0 +1,183 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A small graph of keys shared by the tests: `Quadruple(n)` depends on `Double(n)`, which
+//! depends on the injected `Base(n)`. `IsEven` is a projection of `Double(n)`.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_futures::cancellation::CancellationContext;
+use derive_more::Display;
+use dupe::Dupe;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::api::computations::DiceComputations;
+use crate::api::injected::InjectedKey;
+use crate::api::key::Key;
+use crate::api::persistence::PersistentKey;
+use crate::api::persistence::PersistentProjection;
+use crate::api::persistence::PersistentValue;
+use crate::api::projection::DiceProjectionComputations;
+use crate::api::projection::ProjectionKey;
+use crate::impls::dice::DiceModern;
+use crate::DiceData;
+
+#[derive(Allocative, Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "Base({})", _0)]
+pub(crate) struct Base(pub(crate) u32);
+
+impl InjectedKey for Base {
+    type Value = usize;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+/// Computes `Base * 2`, counting its computations in the `Arc<AtomicUsize>` of the global data,
+/// if there is one.
+#[derive(Allocative, Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "Double({})", _0)]
+pub(crate) struct Double(pub(crate) u32);
+
+#[async_trait]
+impl Key for Double {
+    type Value = usize;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        if let Ok(computed) = ctx.global_data().get::<Arc<AtomicUsize>>() {
+            computed.fetch_add(1, Ordering::SeqCst);
+        }
+        ctx.compute(&Base(self.0)).await.unwrap() * 2
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+
+    fn evictable() -> bool {
+        true
+    }
+}
+
+/// Computes `Double * 2`.
+#[derive(Allocative, Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "Quadruple({})", _0)]
+pub(crate) struct Quadruple(pub(crate) u32);
+
+#[async_trait]
+impl Key for Quadruple {
+    type Value = usize;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.compute(&Double(self.0)).await.unwrap() * 2
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+
+    fn evictable() -> bool {
+        true
+    }
+}
+
+impl PersistentKey for Base {
+    const PERSISTENT_ID: &'static str = "Base";
+}
+
+impl PersistentValue for Base {
+    type Persisted = usize;
+
+    fn to_persisted(value: &usize) -> Option<usize> {
+        Some(*value)
+    }
+
+    fn from_persisted(value: usize, _data: &DiceData) -> anyhow::Result<usize> {
+        Ok(value)
+    }
+}
+
+impl PersistentKey for Double {
+    const PERSISTENT_ID: &'static str = "Double";
+}
+
+impl PersistentValue for Double {
+    type Persisted = usize;
+
+    fn to_persisted(value: &usize) -> Option<usize> {
+        Some(*value)
+    }
+
+    fn from_persisted(value: usize, _data: &DiceData) -> anyhow::Result<usize> {
+        Ok(value)
+    }
+}
+
+/// Whether `Double(n) / 2` is even.
+#[derive(Allocative, Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "IsEven")]
+pub(crate) struct IsEven;
+
+impl ProjectionKey for IsEven {
+    type DeriveFromKey = Double;
+    type Value = bool;
+
+    fn compute(&self, derive_from: &usize, _ctx: &DiceProjectionComputations) -> bool {
+        derive_from % 4 == 0
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+impl PersistentProjection for IsEven {
+    const PERSISTENT_ID: &'static str = "IsEven";
+
+    type Persisted = bool;
+
+    fn to_persisted(value: &bool) -> Option<bool> {
+        Some(*value)
+    }
+
+    fn from_persisted(value: bool, _data: &DiceData) -> anyhow::Result<bool> {
+        Ok(value)
+    }
+}
+
+/// `Quadruple` is only saved without its values.
+impl PersistentKey for Quadruple {
+    const PERSISTENT_ID: &'static str = "Quadruple";
+}
+
+/// A DICE instance, along with the number of times it computed a `Double`.
+pub(crate) fn new_dice() -> (Arc<DiceModern>, Arc<AtomicUsize>) {
+    let computed = Arc::new(AtomicUsize::new(0));
+    let mut data = DiceData::new();
+    data.set(computed.dupe());
+    (DiceModern::new(data), computed)
+}
@@ -119,12 +119,23 @@ impl TransactionUpdater {
     }
 
     async fn commit_to_state(self) -> (SharedLiveTransactionCtx, ActiveTransactionGuard) {
+        let changed: Vec<(DiceKey, bool)> = self
+            .scheduled_changes
+            .changes
+            .iter()
+            .map(|(k, change)| (*k, !matches!(change, ChangeType::UpdateValue(..))))
+            .collect();
+
         let v = self
             .dice
             .state_handle
             .update_state(self.scheduled_changes.changes.into_iter().collect())
             .await;
 
+        for (k, invalidated) in changed {
+            self.dice.recorder.changed(v, k, invalidated);
+        }
+
         let guard = ActiveTransactionGuard::new(v, self.dice.state_handle.dupe());
 
         self.dice.state_handle.ctx_at_version(v, guard).await
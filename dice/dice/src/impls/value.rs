@@ -31,7 +31,6 @@ impl Debug for DiceValidValue {
 }
 
 impl DiceValidValue {
-    #[cfg(test)]
     pub(crate) fn downcast_ref<V: Any>(&self) -> Option<&V> {
         self.0.downcast_ref()
     }
@@ -40,6 +39,11 @@ impl DiceValidValue {
     pub(crate) fn equality(&self, other: &DiceValidValue) -> bool {
         self.0.equality(&*other.0)
     }
+
+    /// Dynamic version of `Key::evictable`.
+    pub(crate) fn evictable(&self) -> bool {
+        self.0.evictable()
+    }
 }
 
 /// Type erased value that may be transient, or whose dependencies are transient
@@ -160,6 +164,7 @@ pub(crate) trait DiceValueDyn: Allocative + Any + Send + Sync + 'static {
     /// Panics if called with incompatible values.
     fn equality(&self, other: &dyn DiceValueDyn) -> bool;
     fn validity(&self) -> bool;
+    fn evictable(&self) -> bool;
 }
 
 impl dyn DiceValueDyn {
@@ -197,6 +202,10 @@ where
     fn validity(&self) -> bool {
         K::validity(&self.value)
     }
+
+    fn evictable(&self) -> bool {
+        K::evictable()
+    }
 }
 
 #[derive(Allocative)]
@@ -228,6 +237,11 @@ where
     fn validity(&self) -> bool {
         K::validity(&self.value)
     }
+
+    fn evictable(&self) -> bool {
+        // `ProjectionKey` has no way to opt into eviction.
+        false
+    }
 }
 
 #[cfg(test)]
@@ -45,6 +45,7 @@ use crate::impls::task::PreviouslyCancelledTask;
 use crate::impls::user_cycle::KeyComputingUserCycleDetectorData;
 use crate::impls::user_cycle::UserCycleDetectorData;
 use crate::impls::value::DiceComputedValue;
+use crate::impls::worker::concurrency::ConcurrencySlot;
 use crate::impls::worker::state::ActivationInfo;
 use crate::impls::worker::state::DiceWorkerStateAwaitingPrevious;
 use crate::impls::worker::state::DiceWorkerStateEvaluating;
@@ -56,6 +57,7 @@ use crate::result::Cancelled;
 use crate::versions::VersionNumber;
 use crate::versions::VersionRange;
 
+pub(crate) mod concurrency;
 pub(crate) mod state;
 
 #[cfg(test)]
@@ -223,11 +225,29 @@ impl DiceTaskWorker {
             }
         };
 
+        // Only running the compute is limited, checking the deps above isn't.
+        let concurrency = match self.eval.concurrency_class(self.k) {
+            Some(class) => Some(
+                ConcurrencySlot::acquire(
+                    &self.eval.dice.concurrency,
+                    class,
+                    self.event_dispatcher.dupe(),
+                )
+                .await,
+            ),
+            None => None,
+        };
+
         let DiceWorkerStateFinishedEvaluating {
             state,
             activation_data,
             result,
-        } = self.compute(task_state, &cycles).await?;
+        } = self.compute(task_state, &cycles, concurrency).await?;
+
+        self.eval
+            .dice
+            .recorder
+            .computed(v, self.k, result.deps.iter_keys());
 
         // explicitly drop this here to make it explicit that its important that we hold onto it, it
         // otherwise appears unused, but we don't want to cancel anything that it has started requesting
@@ -249,8 +269,15 @@ impl DiceTaskWorker {
                             result.storage,
                             value,
                             Arc::new(result.deps),
+                            self.eval.dice.key_index.get(self.k).key_type_name(),
                         )
                         .await
+                        .map(|(res, outcome)| {
+                            if let Some(outcome) = outcome {
+                                self.event_dispatcher.recomputed(self.k, outcome);
+                            }
+                            res
+                        })
                 }
                 Err(value) => Ok(DiceComputedValue::new(
                     value,
@@ -266,6 +293,7 @@ impl DiceTaskWorker {
         &self,
         task_state: DiceWorkerStateEvaluating<'a, 'b>,
         cycles: &KeyComputingUserCycleDetectorData,
+        concurrency: Option<ConcurrencySlot>,
     ) -> CancellableResult<DiceWorkerStateFinishedEvaluating<'a, 'b>> {
         self.event_dispatcher.compute_started(self.k);
         scopeguard::defer! {
@@ -275,7 +303,9 @@ impl DiceTaskWorker {
         // TODO(bobyf) these also make good locations where we want to perform instrumentation
         debug!(msg = "running evaluator");
 
-        self.eval.evaluate(self.k, task_state, cycles.clone()).await
+        self.eval
+            .evaluate(self.k, task_state, cycles.clone(), concurrency)
+            .await
     }
 
     fn activation_info<'a>(
@@ -439,6 +469,9 @@ pub(crate) fn project_for_key(
         debug!(msg = "running projection");
 
         let eval_result = eval.evaluate(k);
+        eval.dice
+            .recorder
+            .computed(v, k, eval_result.deps.iter_keys());
 
         debug!(msg = "projection finished. updating caches");
 
@@ -452,9 +485,22 @@ pub(crate) fn project_for_key(
                         eval_result.storage,
                         value,
                         Arc::new(eval_result.deps),
+                        eval.dice.key_index.get(k).key_type_name(),
                     );
 
-                    Some(rx.map(|res| res.map_err(|_channel_drop| Cancelled)).boxed())
+                    let event_dispatcher = event_dispatcher.dupe();
+                    Some(
+                        rx.map(move |res| {
+                            res.map_err(|_channel_drop| Cancelled)
+                                .map(|(res, outcome)| {
+                                    if let Some(outcome) = outcome {
+                                        event_dispatcher.recomputed(k, outcome);
+                                    }
+                                    res
+                                })
+                        })
+                        .boxed(),
+                    )
                 }
                 Err(_transient_result) => {
                     // transients are never stored in the state, but the result should be shared
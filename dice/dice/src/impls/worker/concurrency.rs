@@ -0,0 +1,367 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Enforces the limits of concurrency classes, see `api::concurrency`.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+use std::task::Poll;
+
+use allocative::Allocative;
+use dupe::Dupe;
+use futures::pin_mut;
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+use crate::api::concurrency::ConcurrencyClass;
+use crate::impls::events::DiceEventDispatcher;
+use crate::metrics::ConcurrencyClassMetrics;
+
+#[derive(Allocative)]
+pub(crate) struct ConcurrencyLimiter {
+    #[allocative(skip)]
+    state: Arc<Mutex<LimiterState>>,
+}
+
+struct LimiterState {
+    /// The limit across all classes.
+    limit: usize,
+    running: usize,
+    /// Sorted by descending priority.
+    classes: Vec<ClassState>,
+}
+
+struct ClassState {
+    class: ConcurrencyClass,
+    running: usize,
+    queue: VecDeque<oneshot::Sender<ConcurrencyPermit>>,
+}
+
+/// Allows a computation of a class to run. The next queued computation starts when dropped.
+pub(crate) struct ConcurrencyPermit {
+    state: Arc<Mutex<LimiterState>>,
+    class: &'static str,
+}
+
+impl ConcurrencyLimiter {
+    pub(crate) fn new(limit: Option<usize>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(LimiterState {
+                limit: limit.unwrap_or(usize::MAX),
+                running: 0,
+                classes: Vec::new(),
+            })),
+        }
+    }
+
+    /// Waits until a computation of `class` can run. Computations `resuming` after waiting on
+    /// their deps are queued before the ones that haven't started yet, so that they finish first.
+    pub(crate) async fn acquire(
+        &self,
+        class: ConcurrencyClass,
+        resuming: bool,
+    ) -> ConcurrencyPermit {
+        let (tx, rx) = oneshot::channel();
+        let granted = {
+            let mut state = self.state.lock();
+            let queue = &mut state.class_mut(class).queue;
+            if resuming {
+                queue.push_front(tx);
+            } else {
+                queue.push_back(tx);
+            }
+            state.grant()
+        };
+        send_permits(&self.state, granted);
+
+        rx.await
+            .expect("queued senders are only dropped once their receiver is")
+    }
+
+    pub(crate) fn metrics(&self) -> Vec<ConcurrencyClassMetrics> {
+        self.state
+            .lock()
+            .classes
+            .iter()
+            .map(|class| ConcurrencyClassMetrics {
+                name: class.class.name,
+                limit: class.class.limit,
+                priority: class.class.priority,
+                running: class.running,
+                queued: class.queue.iter().filter(|tx| !tx.is_closed()).count(),
+            })
+            .collect()
+    }
+}
+
+/// The slot of a computation of a class. It is given back while the computation waits on deps
+/// that aren't computed yet, and taken again before the computation resumes, so that computations
+/// waiting on each other can't hold on to all the slots and deadlock.
+pub(crate) struct ConcurrencySlot {
+    limiter: ConcurrencyLimiter,
+    class: ConcurrencyClass,
+    events: DiceEventDispatcher,
+    state: Mutex<SlotState>,
+}
+
+struct SlotState {
+    permit: Option<RunningPermit>,
+    /// The deps being waited on, by the parallel computes of the computation.
+    waiting: usize,
+}
+
+/// A permit reported as running to the event listener while held.
+struct RunningPermit {
+    _permit: ConcurrencyPermit,
+    class: ConcurrencyClass,
+    events: DiceEventDispatcher,
+}
+
+impl ConcurrencySlot {
+    /// Waits until the computation can start.
+    pub(crate) async fn acquire(
+        limiter: &ConcurrencyLimiter,
+        class: ConcurrencyClass,
+        events: DiceEventDispatcher,
+    ) -> Self {
+        let slot = Self {
+            limiter: ConcurrencyLimiter {
+                state: limiter.state.dupe(),
+            },
+            class,
+            events,
+            state: Mutex::new(SlotState {
+                permit: None,
+                waiting: 0,
+            }),
+        };
+        let permit = slot.acquire_permit(false).await;
+        slot.state.lock().permit = Some(permit);
+        slot
+    }
+
+    async fn acquire_permit(&self, resuming: bool) -> RunningPermit {
+        self.events.concurrency_queued(self.class);
+        scopeguard::defer! {
+            self.events.concurrency_dequeued(self.class);
+        };
+        let permit = self.limiter.acquire(self.class, resuming).await;
+        self.events.concurrency_started(self.class);
+        RunningPermit {
+            _permit: permit,
+            class: self.class,
+            events: self.events.dupe(),
+        }
+    }
+
+    /// Waits on a dep, giving back the slot if it isn't ready yet.
+    pub(crate) async fn wait_on<F: Future>(&self, dep: F) -> F::Output {
+        pin_mut!(dep);
+        if let Poll::Ready(v) = futures::poll!(dep.as_mut()) {
+            return v;
+        }
+
+        let released = {
+            let mut state = self.state.lock();
+            state.waiting += 1;
+            state.permit.take()
+        };
+        drop(released);
+        let waiting = scopeguard::guard((), |()| self.state.lock().waiting -= 1);
+
+        let v = dep.await;
+
+        drop(waiting);
+        let resuming = {
+            let state = self.state.lock();
+            state.waiting == 0 && state.permit.is_none()
+        };
+        if resuming {
+            let permit = self.acquire_permit(true).await;
+            let mut state = self.state.lock();
+            // Another parallel compute may have taken the slot again, or be waiting on a dep.
+            if state.waiting == 0 && state.permit.is_none() {
+                state.permit = Some(permit);
+            }
+        }
+        v
+    }
+}
+
+impl Drop for RunningPermit {
+    fn drop(&mut self) {
+        self.events.concurrency_finished(self.class);
+    }
+}
+
+impl LimiterState {
+    fn class_mut(&mut self, class: ConcurrencyClass) -> &mut ClassState {
+        let idx = match self.classes.iter().position(|c| c.class.name == class.name) {
+            Some(idx) => idx,
+            None => {
+                let idx = self
+                    .classes
+                    .partition_point(|c| c.class.priority >= class.priority);
+                self.classes.insert(
+                    idx,
+                    ClassState {
+                        class,
+                        running: 0,
+                        queue: VecDeque::new(),
+                    },
+                );
+                idx
+            }
+        };
+        &mut self.classes[idx]
+    }
+
+    /// Takes the queued computations that can now run, in priority order. Sending them their
+    /// permits must happen after releasing the lock, as a permit that can't be sent is dropped,
+    /// which locks again.
+    fn grant(&mut self) -> Vec<(oneshot::Sender<ConcurrencyPermit>, &'static str)> {
+        let mut granted = Vec::new();
+        for class in &mut self.classes {
+            while self.running < self.limit && class.running < class.class.limit {
+                match class.queue.pop_front() {
+                    Some(tx) if tx.is_closed() => {}
+                    Some(tx) => {
+                        class.running += 1;
+                        self.running += 1;
+                        granted.push((tx, class.class.name));
+                    }
+                    None => break,
+                }
+            }
+        }
+        granted
+    }
+}
+
+fn send_permits(
+    state: &Arc<Mutex<LimiterState>>,
+    granted: Vec<(oneshot::Sender<ConcurrencyPermit>, &'static str)>,
+) {
+    for (tx, class) in granted {
+        // If the waiter is gone the permit is dropped, releasing it for the next one.
+        let _ignored = tx.send(ConcurrencyPermit {
+            state: state.dupe(),
+            class,
+        });
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        let granted = {
+            let mut state = self.state.lock();
+            state.running -= 1;
+            if let Some(class) = state
+                .classes
+                .iter_mut()
+                .find(|c| c.class.name == self.class)
+            {
+                class.running -= 1;
+            }
+            state.grant()
+        };
+        send_permits(&self.state, granted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::FutureExt;
+
+    use super::*;
+
+    const LOW: ConcurrencyClass = ConcurrencyClass::new("low", 2, 0);
+    const HIGH: ConcurrencyClass = ConcurrencyClass::new("high", 2, 1);
+
+    #[tokio::test]
+    async fn limits_each_class() {
+        let limiter = ConcurrencyLimiter::new(None);
+
+        let _a = limiter.acquire(LOW, false).await;
+        let b = limiter.acquire(LOW, false).await;
+        let _c = limiter.acquire(HIGH, false).await;
+
+        let queued = limiter.acquire(LOW, false);
+        futures::pin_mut!(queued);
+        assert!(queued.as_mut().now_or_never().is_none());
+        assert_eq!(1, limiter.metrics()[1].queued);
+
+        drop(b);
+        tokio::time::timeout(Duration::from_secs(1), queued)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn shared_limit_starts_higher_priority_first() {
+        let limiter = ConcurrencyLimiter::new(Some(1));
+
+        let running = limiter.acquire(LOW, false).await;
+
+        let low = limiter.acquire(LOW, false);
+        let high = limiter.acquire(HIGH, false);
+        futures::pin_mut!(low);
+        futures::pin_mut!(high);
+        assert!(low.as_mut().now_or_never().is_none());
+        assert!(high.as_mut().now_or_never().is_none());
+
+        let metrics = limiter.metrics();
+        assert_eq!("high", metrics[0].name);
+        assert_eq!(1, metrics[0].queued);
+        assert_eq!(1, metrics[1].running);
+
+        drop(running);
+        let high = high.now_or_never().unwrap();
+        assert!(low.as_mut().now_or_never().is_none());
+
+        drop(high);
+        assert!(low.now_or_never().is_some());
+    }
+
+    #[tokio::test]
+    async fn cancelled_waiters_release_their_slot() {
+        let limiter = ConcurrencyLimiter::new(Some(1));
+
+        let running = limiter.acquire(LOW, false).await;
+        let mut cancelled = limiter.acquire(LOW, false).boxed();
+        assert!((&mut cancelled).now_or_never().is_none());
+        // Grants the permit to the waiter, which is then dropped without ever taking it.
+        drop(running);
+        drop(cancelled);
+
+        assert!(limiter.acquire(HIGH, false).now_or_never().is_some());
+    }
+
+    #[tokio::test]
+    async fn resuming_computations_start_first() {
+        let limiter = ConcurrencyLimiter::new(None);
+
+        let _a = limiter.acquire(LOW, false).await;
+        let b = limiter.acquire(LOW, false).await;
+
+        let new = limiter.acquire(LOW, false);
+        let resuming = limiter.acquire(LOW, true);
+        futures::pin_mut!(new);
+        futures::pin_mut!(resuming);
+        assert!(new.as_mut().now_or_never().is_none());
+        assert!(resuming.as_mut().now_or_never().is_none());
+
+        drop(b);
+        let _resumed = resuming.now_or_never().unwrap();
+        assert!(new.now_or_never().is_none());
+    }
+}
@@ -25,6 +25,7 @@ use dupe::Dupe;
 use dupe::IterDupedExt;
 use futures::pin_mut;
 use futures::Future;
+use futures::FutureExt;
 use gazebo::prelude::SliceExt;
 use gazebo::variants::VariantName;
 use tokio::sync::Mutex;
@@ -907,13 +908,16 @@ fn update_computed_value(
     value: DiceValidValue,
     deps: Arc<SeriesParallelDeps>,
 ) -> impl Future<Output = CancellableResult<DiceComputedValue>> {
-    dice.state_handle.update_computed(
-        VersionedGraphKey::new(v, k),
-        ctx.testing_get_epoch(),
-        StorageType::Normal,
-        value,
-        deps,
-    )
+    dice.state_handle
+        .update_computed(
+            VersionedGraphKey::new(v, k),
+            ctx.testing_get_epoch(),
+            StorageType::Normal,
+            value,
+            deps,
+            "test",
+        )
+        .map(|res| res.map(|(res, _outcome)| res))
 }
 
 async fn get_ctx_at_version(
@@ -186,6 +186,7 @@ pub enum GraphNodeKind {
     Occupied,
     Transient,
     Vacant,
+    Evicted,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -206,13 +206,13 @@ pub use buck2_futures::spawn::WeakFutureError; // expose future errors as api
 pub(crate) type HashMap<K, V> = std::collections::HashMap<K, V, fxhash::FxBuildHasher>;
 pub(crate) type HashSet<K> = std::collections::HashSet<K, fxhash::FxBuildHasher>;
 use futures::future::Future;
-use metrics::Metrics;
 use serde::Serializer;
 
 pub use crate::api::activation_tracker::ActivationData;
 pub use crate::api::activation_tracker::ActivationTracker;
 pub use crate::api::computations::DiceComputations;
 pub use crate::api::computations::LinearRecomputeDiceComputations;
+pub use crate::api::concurrency::ConcurrencyClass;
 pub use crate::api::cycles::DetectCycles;
 pub use crate::api::data::DiceData;
 pub use crate::api::dice::Dice;
@@ -221,11 +221,23 @@ pub use crate::api::error::DiceError;
 pub use crate::api::error::DiceResult;
 pub use crate::api::events::DiceEvent;
 pub use crate::api::events::DiceEventListener;
+pub use crate::api::eviction::EvictionStats;
 pub use crate::api::injected::InjectedKey;
+pub use crate::api::invalidations::DiceInvalidationPath;
+pub use crate::api::invalidations::DiceInvalidations;
 pub use crate::api::key::Key;
 pub use crate::api::opaque::OpaqueValue;
+pub use crate::api::persistence::DicePersistence;
+pub use crate::api::persistence::DiceSnapshot;
+pub use crate::api::persistence::PersistentKey;
+pub use crate::api::persistence::PersistentProjection;
+pub use crate::api::persistence::PersistentValue;
 pub use crate::api::projection::DiceProjectionComputations;
 pub use crate::api::projection::ProjectionKey;
+pub use crate::api::recording::DiceRecording;
+pub use crate::api::recording::RecordedComputation;
+pub use crate::api::recording::RecordedKey;
+pub use crate::api::recording::RecordedTransaction;
 pub use crate::api::transaction::DiceEquality;
 pub use crate::api::transaction::DiceTransaction;
 pub use crate::api::transaction::DiceTransactionUpdater;
@@ -238,6 +250,9 @@ use crate::impls::dice::DiceModernDataBuilder;
 use crate::introspection::graph::GraphIntrospectable;
 use crate::introspection::serialize_dense_graph;
 use crate::introspection::serialize_graph;
+pub use crate::metrics::ConcurrencyClassMetrics;
+pub use crate::metrics::EarlyCutoffMetrics;
+pub use crate::metrics::Metrics;
 pub use crate::stats::GlobalStats;
 use crate::transaction_update::DiceTransactionUpdaterImpl;
 
@@ -315,6 +330,69 @@ impl DiceImplementation {
             DiceImplementation::Modern(dice) => dice.is_idle().await,
         }
     }
+
+    pub async fn snapshot(
+        &self,
+        persistence: &DicePersistence,
+        metadata: String,
+    ) -> anyhow::Result<DiceSnapshot> {
+        match self {
+            DiceImplementation::Modern(dice) => dice.snapshot(persistence, metadata).await,
+        }
+    }
+
+    pub async fn restore(
+        &self,
+        snapshot: &DiceSnapshot,
+        persistence: &DicePersistence,
+    ) -> anyhow::Result<usize> {
+        match self {
+            DiceImplementation::Modern(dice) => dice.restore(snapshot, persistence).await,
+        }
+    }
+
+    pub async fn evict_cold_values(&self, memory_budget: u64) -> EvictionStats {
+        match self {
+            DiceImplementation::Modern(dice) => dice.evict_cold_values(memory_budget).await,
+        }
+    }
+
+    pub fn set_invalidation_tracing(&self, enabled: bool) {
+        match self {
+            DiceImplementation::Modern(dice) => dice.set_invalidation_tracing(enabled),
+        }
+    }
+
+    pub async fn invalidation_paths(
+        &self,
+        matches: Box<dyn Fn(&str) -> bool + Send>,
+    ) -> Vec<DiceInvalidationPath> {
+        match self {
+            DiceImplementation::Modern(dice) => dice.invalidation_paths(matches).await,
+        }
+    }
+
+    pub async fn invalidations_at(
+        &self,
+        version: DiceEquality,
+        limit: usize,
+    ) -> Option<DiceInvalidations> {
+        match self {
+            DiceImplementation::Modern(dice) => dice.invalidations_at(version, limit).await,
+        }
+    }
+
+    pub fn start_recording(&self) {
+        match self {
+            DiceImplementation::Modern(dice) => dice.start_recording(),
+        }
+    }
+
+    pub fn stop_recording(&self) -> Option<DiceRecording> {
+        match self {
+            DiceImplementation::Modern(dice) => dice.stop_recording(),
+        }
+    }
 }
 
 pub(crate) enum DiceDataBuilderImpl {
@@ -332,6 +410,12 @@ impl DiceDataBuilderImpl {
         }
     }
 
+    pub fn set_concurrency_limit(&mut self, limit: usize) {
+        match self {
+            DiceDataBuilderImpl::Modern(d) => d.set_concurrency_limit(limit),
+        }
+    }
+
     pub fn build(self, detect_cycles: DetectCycles) -> Arc<Dice> {
         Dice::new(match self {
             DiceDataBuilderImpl::Modern(d) => DiceImplementation::Modern(d.build(detect_cycles)),
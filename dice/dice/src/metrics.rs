@@ -14,4 +14,32 @@ pub struct Metrics {
     /// The number of keys currently active in the per transaction cache
     pub currently_active_key_count: usize,
     pub active_transaction_count: u32,
+    /// The concurrency classes computations were requested for, highest priority first
+    pub concurrency_classes: Vec<ConcurrencyClassMetrics>,
+    /// The recomputations of each key type since DICE was created, sorted by key type
+    pub early_cutoffs: Vec<EarlyCutoffMetrics>,
+}
+
+/// Computations of a concurrency class, see `api::concurrency`.
+#[derive(Debug)]
+pub struct ConcurrencyClassMetrics {
+    pub name: &'static str,
+    /// How many computations of this class can run at once
+    pub limit: usize,
+    pub priority: u32,
+    /// The number of computations of this class currently running
+    pub running: usize,
+    /// The number of computations of this class waiting to start
+    pub queued: usize,
+}
+
+/// Recomputations of a key type. A recomputation whose value is equal to the previous one is cut
+/// off: the keys depending on it don't need to be recomputed.
+#[derive(Debug, Clone)]
+pub struct EarlyCutoffMetrics {
+    pub key_type: &'static str,
+    /// The number of recomputations whose value was equal to the previous one
+    pub cutoffs: u64,
+    /// The number of recomputations whose value changed
+    pub changes: u64,
 }
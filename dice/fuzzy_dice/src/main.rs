@@ -18,6 +18,7 @@ use anyhow::Context;
 use clap::Args;
 use clap::FromArgMatches;
 use clap::Parser;
+use dice::DiceRecording;
 use quickcheck::Arbitrary;
 use quickcheck::Gen;
 use quickcheck::QuickCheck;
@@ -30,6 +31,7 @@ use tracing_subscriber::EnvFilter;
 
 mod computation;
 mod execution;
+mod recording;
 
 use crate::execution::DiceExecutionOrder;
 use crate::execution::DiceExecutionOrderOptions;
@@ -38,6 +40,8 @@ use crate::execution::DiceExecutionOrderOptions;
 pub enum DiceFuzzError {
     #[error("couldn't parse provided replay file")]
     UnparsableReplay,
+    #[error("found {0} problems replaying the recording")]
+    ReplayProblems(usize),
 }
 
 fn magical_cleanup(stderr: &str) -> anyhow::Result<&str> {
@@ -77,6 +81,11 @@ enum Commands {
     Replay(SubCommandCommon<Replay>),
     #[clap(about = "Searches for new failures.")]
     Fuzz(SubCommandCommon<Fuzz>),
+    #[clap(
+        about = "Replays a recording of DICE transactions with mocked computations, checking for \
+            stale values and unexpected recomputations."
+    )]
+    ReplayRecording(ReplayRecording),
 }
 
 #[derive(Parser)]
@@ -100,6 +109,15 @@ struct Replay {
     path: PathBuf,
 }
 
+#[derive(Parser)]
+struct ReplayRecording {
+    #[clap(
+        value_parser,
+        help = "the path to the JSON `DiceRecording`, as returned by `Dice::stop_recording`"
+    )]
+    path: PathBuf,
+}
+
 #[derive(Parser)]
 struct Fuzz {
     #[clap(
@@ -225,6 +243,21 @@ fn main() -> anyhow::Result<()> {
                 .max_tests(1)
                 .quickcheck(Replayer { execution, options });
         }
+        Commands::ReplayRecording(replay_cmd) => {
+            let recording: DiceRecording =
+                serde_json::from_reader(File::open(&replay_cmd.path)?)
+                    .with_context(|| format!("While parsing {}", replay_cmd.path.display()))?;
+            let problems =
+                tokio::runtime::Runtime::new()?.block_on(recording::replay(&recording))?;
+            for problem in &problems {
+                eprintln!("{}", problem);
+            }
+            if !problems.is_empty() {
+                return Err(DiceFuzzError::ReplayProblems(problems.len()).into());
+            }
+            println!("Replayed {} transactions.", recording.transactions.len());
+            return Ok(());
+        }
     }
 
     println!("Fuzzing complete.");
@@ -0,0 +1,598 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Replays a `DiceRecording` against a fresh DICE with mocked computations.
+//!
+//! Each recorded key is mocked by a key whose deps at a transaction are those of its latest
+//! computation recorded up to that transaction, and whose value hashes its name, the last
+//! transaction that changed it and the values of its deps. A key set with `changed_to` gets a
+//! value hashed from its name and that transaction.
+//! Values then change whenever anything they depend on changes, so the value of every key at every
+//! transaction is known upfront, and a recomputation that doesn't produce a new value shouldn't
+//! have happened.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_futures::cancellation::CancellationContext;
+use derivative::Derivative;
+use dice::DetectCycles;
+use dice::Dice;
+use dice::DiceComputations;
+use dice::DiceRecording;
+use dice::Key;
+use dice::RecordedKey;
+use dice::UserComputationData;
+use dupe::Dupe;
+use parking_lot::Mutex;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Change {
+    Invalidated,
+    ChangedTo,
+}
+
+struct ReplayTransaction {
+    invalidated: Vec<usize>,
+    changed_to: Vec<usize>,
+    requested: Vec<usize>,
+}
+
+/// The recording, with keys referred to by their index in `names`.
+pub(crate) struct ReplayGraph {
+    names: Vec<RecordedKey>,
+    /// For each key, the deps of each of its recorded computations, along with the transaction
+    /// it was computed at, in order. Never cyclic, see `break_cycles`.
+    deps: Vec<Vec<(usize, Vec<usize>)>>,
+    /// For each key, the transactions that changed it, in order.
+    changes: Vec<Vec<(usize, Change)>>,
+    transactions: Vec<ReplayTransaction>,
+}
+
+impl ReplayGraph {
+    pub(crate) fn new(recording: &DiceRecording) -> Self {
+        let mut names = Vec::new();
+        let mut index = HashMap::new();
+        // Keys of different types may have the same `Display`, so they are told apart by type too.
+        let mut intern = |name: &RecordedKey| -> usize {
+            *index.entry(name.clone()).or_insert_with(|| {
+                names.push(name.clone());
+                names.len() - 1
+            })
+        };
+
+        let mut deps: HashMap<usize, Vec<(usize, Vec<usize>)>> = HashMap::new();
+        let mut changes: HashMap<usize, Vec<(usize, Change)>> = HashMap::new();
+        let mut transactions = Vec::new();
+        for (t, transaction) in recording.transactions.iter().enumerate() {
+            for computation in &transaction.computed {
+                let key = intern(&computation.key);
+                let computed = computation.deps.iter().map(|d| intern(d)).collect();
+                deps.entry(key).or_default().push((t, computed));
+            }
+            let mut changed = |keys: &[RecordedKey], change: Change| -> Vec<usize> {
+                keys.iter()
+                    .map(|k| {
+                        let key = intern(k);
+                        changes.entry(key).or_default().push((t, change));
+                        key
+                    })
+                    .collect()
+            };
+            let invalidated = changed(&transaction.invalidated, Change::Invalidated);
+            let changed_to = changed(&transaction.changed_to, Change::ChangedTo);
+            transactions.push(ReplayTransaction {
+                invalidated,
+                changed_to,
+                requested: transaction.requested.iter().map(|k| intern(k)).collect(),
+            });
+        }
+
+        let len = names.len();
+        let mut graph = ReplayGraph {
+            names,
+            deps: (0..len)
+                .map(|k| deps.remove(&k).unwrap_or_default())
+                .collect(),
+            changes: (0..len)
+                .map(|k| changes.remove(&k).unwrap_or_default())
+                .collect(),
+            transactions,
+        };
+        graph.break_cycles();
+        graph
+    }
+
+    /// Deps recorded at different transactions can form cycles, which mocked keys can't have. The
+    /// dep closing a cycle in the deps of all transactions together is dropped from every
+    /// computation of the key, so that the deps at any one transaction aren't cyclic either.
+    fn break_cycles(&mut self) {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Visit {
+            New,
+            InProgress,
+            Done,
+        }
+
+        let mut all_deps: Vec<Vec<usize>> = self
+            .deps
+            .iter()
+            .map(|computations| {
+                let mut deps: Vec<usize> = computations
+                    .iter()
+                    .flat_map(|(_, deps)| deps.iter().copied())
+                    .collect();
+                deps.sort_unstable();
+                deps.dedup();
+                deps
+            })
+            .collect();
+
+        let mut visits = vec![Visit::New; self.names.len()];
+        for start in 0..self.names.len() {
+            if visits[start] != Visit::New {
+                continue;
+            }
+            let mut stack = vec![(start, 0)];
+            visits[start] = Visit::InProgress;
+            while let Some((key, dep_pos)) = stack.last_mut() {
+                let key = *key;
+                match all_deps[key].get(*dep_pos).copied() {
+                    Some(dep) => match visits[dep] {
+                        Visit::New => {
+                            *dep_pos += 1;
+                            visits[dep] = Visit::InProgress;
+                            stack.push((dep, 0));
+                        }
+                        Visit::InProgress => {
+                            all_deps[key].remove(*dep_pos);
+                            for (_, deps) in &mut self.deps[key] {
+                                deps.retain(|d| *d != dep);
+                            }
+                        }
+                        Visit::Done => *dep_pos += 1,
+                    },
+                    None => {
+                        visits[key] = Visit::Done;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    /// The deps of `key` at transaction `t`: those of its latest computation up to `t`, or of its
+    /// first one if it was only computed later.
+    fn deps_at(&self, key: usize, t: usize) -> &[usize] {
+        let computations = &self.deps[key];
+        computations
+            .iter()
+            .take_while(|(computed_at, _)| *computed_at <= t)
+            .last()
+            .or_else(|| computations.first())
+            .map_or(&[][..], |(_, deps)| deps.as_slice())
+    }
+
+    fn last_change(&self, key: usize, t: usize) -> Option<(usize, Change)> {
+        self.changes[key]
+            .iter()
+            .take_while(|(changed_at, _)| *changed_at <= t)
+            .last()
+            .copied()
+    }
+
+    fn computed_value(&self, key: usize, t: usize, dep_values: &[u64]) -> u64 {
+        hash((
+            &self.names[key],
+            self.last_change(key, t).map(|(changed_at, _)| changed_at),
+            dep_values,
+        ))
+    }
+
+    fn injected_value(&self, key: usize, changed_at: usize) -> u64 {
+        hash((&self.names[key], "injected", changed_at))
+    }
+
+    /// The value `key` should have at transaction `t`.
+    fn expected_value(&self, key: usize, t: usize, memo: &mut HashMap<usize, u64>) -> u64 {
+        // Iterative, as recorded graphs are too deep to recurse.
+        let mut stack = vec![key];
+        while let Some(&key) = stack.last() {
+            if memo.contains_key(&key) {
+                stack.pop();
+                continue;
+            }
+            if let Some((changed_at, Change::ChangedTo)) = self.last_change(key, t) {
+                memo.insert(key, self.injected_value(key, changed_at));
+                stack.pop();
+                continue;
+            }
+            let missing: Vec<usize> = self
+                .deps_at(key, t)
+                .iter()
+                .copied()
+                .filter(|dep| !memo.contains_key(dep))
+                .collect();
+            if missing.is_empty() {
+                let dep_values: Vec<u64> =
+                    self.deps_at(key, t).iter().map(|dep| memo[dep]).collect();
+                memo.insert(key, self.computed_value(key, t, &dep_values));
+                stack.pop();
+            } else {
+                stack.extend(missing);
+            }
+        }
+        memo[&key]
+    }
+}
+
+fn hash(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Allocative)]
+struct ReplayState {
+    #[allocative(skip)]
+    graph: ReplayGraph,
+    /// The keys computed since last taken, with the transaction they were computed at and their
+    /// value.
+    #[allocative(skip)]
+    computations: Mutex<Vec<(usize, usize, u64)>>,
+}
+
+impl ReplayState {
+    fn key(self: &Arc<Self>, key: usize) -> ReplayKey {
+        ReplayKey {
+            key,
+            state: self.dupe(),
+        }
+    }
+}
+
+/// Stored in the per transaction data, so that mocked keys know which transaction they're
+/// computed at.
+struct TransactionIndex(usize);
+
+#[derive(Derivative, Clone, Allocative)]
+#[derivative(Hash, Debug)]
+struct ReplayKey {
+    key: usize,
+    #[derivative(Debug = "ignore", Hash = "ignore")]
+    state: Arc<ReplayState>,
+}
+
+impl PartialEq for ReplayKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key && Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl Eq for ReplayKey {}
+
+impl fmt::Display for ReplayKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.state.graph.names[self.key])
+    }
+}
+
+#[async_trait]
+impl Key for ReplayKey {
+    type Value = Result<u64, Arc<anyhow::Error>>;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let t = ctx
+            .per_transaction_data()
+            .data
+            .get::<TransactionIndex>()
+            .map_err(|e| Arc::new(anyhow::Error::from(e)))?
+            .0;
+
+        let mut dep_values = Vec::new();
+        for dep in self.state.graph.deps_at(self.key, t) {
+            let value = ctx
+                .compute(&self.state.key(*dep))
+                .await
+                .map_err(|e| Arc::new(anyhow::Error::from(e)))??;
+            dep_values.push(value);
+        }
+
+        let value = self.state.graph.computed_value(self.key, t, &dep_values);
+        self.state.computations.lock().push((t, self.key, value));
+        Ok(value)
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ReplayProblem {
+    Stale {
+        transaction: usize,
+        key: String,
+        expected: u64,
+        actual: u64,
+    },
+    UnexpectedRecomputation {
+        transaction: usize,
+        key: String,
+    },
+}
+
+impl fmt::Display for ReplayProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayProblem::Stale {
+                transaction,
+                key,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "transaction {}: `{}` was {:x}, expected {:x}",
+                transaction, key, actual, expected
+            ),
+            ReplayProblem::UnexpectedRecomputation { transaction, key } => write!(
+                f,
+                "transaction {}: `{}` was recomputed though nothing it depends on changed",
+                transaction, key
+            ),
+        }
+    }
+}
+
+/// Replays the transactions of `recording` in order, returning the requested keys which didn't
+/// have their expected value, and the keys which were recomputed without needing to.
+pub(crate) async fn replay(recording: &DiceRecording) -> anyhow::Result<Vec<ReplayProblem>> {
+    let state = Arc::new(ReplayState {
+        graph: ReplayGraph::new(recording),
+        computations: Mutex::new(Vec::new()),
+    });
+    let dice = Dice::builder().build(DetectCycles::Disabled);
+
+    let mut problems = Vec::new();
+    // The value each key had when it was last computed.
+    let mut computed_values: HashMap<usize, u64> = HashMap::new();
+    for (t, transaction) in state.graph.transactions.iter().enumerate() {
+        let mut data = UserComputationData::new();
+        data.data.set(TransactionIndex(t));
+        let mut updater = dice.updater_with_data(data);
+        updater.changed(
+            transaction
+                .invalidated
+                .iter()
+                .map(|k| state.key(*k))
+                .collect::<Vec<_>>(),
+        )?;
+        updater.changed_to(
+            transaction
+                .changed_to
+                .iter()
+                .map(|k| (state.key(*k), Ok(state.graph.injected_value(*k, t))))
+                .collect::<Vec<_>>(),
+        )?;
+        let mut ctx = updater.commit().await;
+
+        let mut expected_values = HashMap::new();
+        for key in &transaction.requested {
+            let actual = ctx
+                .compute(&state.key(*key))
+                .await?
+                .map_err(|e| anyhow::anyhow!("{:#}", e))?;
+            let expected = state.graph.expected_value(*key, t, &mut expected_values);
+            if actual != expected {
+                problems.push(ReplayProblem::Stale {
+                    transaction: t,
+                    key: state.graph.names[*key].to_string(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+        drop(ctx);
+        dice.wait_for_idle().await;
+
+        for (computed_at, key, value) in std::mem::take(&mut *state.computations.lock()) {
+            if computed_values.insert(key, value) == Some(value) {
+                problems.push(ReplayProblem::UnexpectedRecomputation {
+                    transaction: computed_at,
+                    key: state.graph.names[key].to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use dice::RecordedComputation;
+    use dice::RecordedTransaction;
+
+    use super::*;
+
+    fn key(key: &str) -> RecordedKey {
+        RecordedKey {
+            key_type: "Test".to_owned(),
+            key: key.to_owned(),
+        }
+    }
+
+    fn computation(name: &str, deps: &[&str]) -> RecordedComputation {
+        RecordedComputation {
+            key: key(name),
+            deps: keys(deps),
+        }
+    }
+
+    fn keys(names: &[&str]) -> Vec<RecordedKey> {
+        names.iter().map(|k| key(k)).collect()
+    }
+
+    /// `top -> mid -> {a, b}`, with `a` and `b` injected, then `a` changed, then `mid`
+    /// invalidated, then nothing changed.
+    fn recording() -> DiceRecording {
+        DiceRecording {
+            transactions: vec![
+                RecordedTransaction {
+                    invalidated: vec![],
+                    changed_to: keys(&["a", "b"]),
+                    requested: keys(&["top"]),
+                    computed: vec![
+                        computation("mid", &["a", "b"]),
+                        computation("top", &["mid"]),
+                    ],
+                },
+                RecordedTransaction {
+                    invalidated: vec![],
+                    changed_to: keys(&["a"]),
+                    requested: keys(&["top"]),
+                    computed: vec![],
+                },
+                RecordedTransaction {
+                    invalidated: keys(&["mid"]),
+                    changed_to: vec![],
+                    requested: keys(&["top", "mid"]),
+                    computed: vec![],
+                },
+                RecordedTransaction {
+                    invalidated: vec![],
+                    changed_to: vec![],
+                    requested: keys(&["top"]),
+                    computed: vec![],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn expected_values_change_with_deps() {
+        let graph = ReplayGraph::new(&recording());
+        let top = graph.names.iter().position(|n| n.key == "top").unwrap();
+        let value = |t| graph.expected_value(top, t, &mut HashMap::new());
+
+        assert_ne!(value(0), value(1));
+        assert_ne!(value(1), value(2));
+        assert_eq!(value(2), value(3));
+    }
+
+    #[test]
+    fn cycles_are_broken() {
+        let graph = ReplayGraph::new(&DiceRecording {
+            transactions: vec![RecordedTransaction {
+                computed: vec![computation("a", &["b"]), computation("b", &["a"])],
+                ..Default::default()
+            }],
+        });
+        assert_eq!(vec![vec![(0, vec![1])], vec![(0, vec![])]], graph.deps);
+    }
+
+    #[test]
+    fn keys_are_told_apart_by_type() {
+        let other = RecordedKey {
+            key_type: "Other".to_owned(),
+            key: "a".to_owned(),
+        };
+        let graph = ReplayGraph::new(&DiceRecording {
+            transactions: vec![RecordedTransaction {
+                computed: vec![RecordedComputation {
+                    key: key("a"),
+                    deps: vec![other.clone()],
+                }],
+                ..Default::default()
+            }],
+        });
+        assert_eq!(vec![key("a"), other], graph.names);
+        assert_eq!(vec![vec![(0, vec![1])], vec![]], graph.deps);
+    }
+
+    #[test]
+    fn deps_are_kept_per_transaction() {
+        let graph = ReplayGraph::new(&DiceRecording {
+            transactions: vec![
+                RecordedTransaction {
+                    computed: vec![computation("top", &["a"])],
+                    ..Default::default()
+                },
+                RecordedTransaction::default(),
+                RecordedTransaction {
+                    computed: vec![computation("top", &["b"])],
+                    ..Default::default()
+                },
+            ],
+        });
+        let name = |k: &usize| graph.names[*k].key.as_str();
+        let top = graph.names.iter().position(|n| n.key == "top").unwrap();
+
+        assert_eq!(
+            vec!["a"],
+            graph.deps_at(top, 0).iter().map(name).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["a"],
+            graph.deps_at(top, 1).iter().map(name).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["b"],
+            graph.deps_at(top, 2).iter().map(name).collect::<Vec<_>>()
+        );
+    }
+
+    /// `top` switches from depending on `a` to depending on `b`, then `a` changes, which `top`
+    /// no longer depends on.
+    #[tokio::test]
+    async fn replays_deps_changing_between_transactions() -> anyhow::Result<()> {
+        let recording = DiceRecording {
+            transactions: vec![
+                RecordedTransaction {
+                    changed_to: keys(&["a", "b", "switch"]),
+                    requested: keys(&["top"]),
+                    computed: vec![computation("top", &["switch", "a"])],
+                    ..Default::default()
+                },
+                RecordedTransaction {
+                    changed_to: keys(&["switch"]),
+                    requested: keys(&["top"]),
+                    computed: vec![computation("top", &["switch", "b"])],
+                    ..Default::default()
+                },
+                RecordedTransaction {
+                    changed_to: keys(&["a"]),
+                    requested: keys(&["top"]),
+                    ..Default::default()
+                },
+            ],
+        };
+        assert_eq!(Vec::<ReplayProblem>::new(), replay(&recording).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replays_without_problems() -> anyhow::Result<()> {
+        assert_eq!(Vec::<ReplayProblem>::new(), replay(&recording()).await?);
+        Ok(())
+    }
+}
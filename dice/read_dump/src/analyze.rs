@@ -0,0 +1,259 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Questions that can be asked of a DICE dump.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::graph::DumpGraph;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct KeyCount {
+    pub(crate) key: String,
+    pub(crate) count: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct TypeCount {
+    pub(crate) type_name: String,
+    pub(crate) count: usize,
+}
+
+/// The `n` keys with the most rdeps.
+pub(crate) fn top_fan_in(graph: &DumpGraph, n: usize) -> Vec<KeyCount> {
+    top_by(graph, n, |idx| graph.nodes[idx].rdeps.len())
+}
+
+/// The `n` keys with the most deps.
+pub(crate) fn top_fan_out(graph: &DumpGraph, n: usize) -> Vec<KeyCount> {
+    top_by(graph, n, |idx| graph.nodes[idx].deps.len())
+}
+
+fn top_by(graph: &DumpGraph, n: usize, count: impl Fn(usize) -> usize) -> Vec<KeyCount> {
+    let mut counts: Vec<(usize, usize)> = (0..graph.nodes.len())
+        .map(|idx| (idx, count(idx)))
+        .collect();
+    // Break ties by key so the output is stable across dumps of the same graph.
+    counts.sort_by(|(a_idx, a), (b_idx, b)| {
+        b.cmp(a)
+            .then_with(|| graph.nodes[*a_idx].key.cmp(&graph.nodes[*b_idx].key))
+    });
+    counts
+        .into_iter()
+        .take(n)
+        .map(|(idx, count)| KeyCount {
+            key: graph.nodes[idx].key.clone(),
+            count,
+        })
+        .collect()
+}
+
+/// The number of keys of each type, most common first.
+pub(crate) fn type_histogram(graph: &DumpGraph) -> Vec<TypeCount> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for node in &graph.nodes {
+        *counts.entry(&node.type_name).or_default() += 1;
+    }
+    let mut counts: Vec<TypeCount> = counts
+        .into_iter()
+        .map(|(type_name, count)| TypeCount {
+            type_name: type_name.to_owned(),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.type_name.cmp(&b.type_name)));
+    counts
+}
+
+/// The `n` longest dependency chains, each starting at a key nothing depends on, as a chain
+/// starting anywhere else is part of a longer one.
+pub(crate) fn longest_chains(graph: &DumpGraph, n: usize) -> Vec<Vec<String>> {
+    let (depths, next) = chain_depths(graph);
+
+    let mut roots: Vec<usize> = (0..graph.nodes.len())
+        .filter(|idx| graph.nodes[*idx].rdeps.is_empty())
+        .collect();
+    roots.sort_by(|a, b| {
+        depths[*b]
+            .cmp(&depths[*a])
+            .then_with(|| graph.nodes[*a].key.cmp(&graph.nodes[*b].key))
+    });
+
+    roots
+        .into_iter()
+        .take(n)
+        .map(|root| {
+            let mut chain = vec![root];
+            while let Some(dep) = next[*chain.last().unwrap()] {
+                chain.push(dep);
+            }
+            graph.keys(&chain)
+        })
+        .collect()
+}
+
+/// For each node, the number of keys in the longest chain of deps starting from it, and the dep
+/// that chain continues with.
+///
+/// DICE graphs have no cycles, but a dump taken while a cycle was being computed could. A dep that
+/// closes a cycle is ignored.
+fn chain_depths(graph: &DumpGraph) -> (Vec<usize>, Vec<Option<usize>>) {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        New,
+        InProgress,
+        Done,
+    }
+
+    let len = graph.nodes.len();
+    let mut visits = vec![Visit::New; len];
+    let mut depths = vec![1; len];
+    let mut next = vec![None; len];
+
+    for start in 0..len {
+        if visits[start] != Visit::New {
+            continue;
+        }
+        // Iterative post-order traversal, the graphs are too deep to recurse.
+        let mut stack = vec![(start, 0)];
+        visits[start] = Visit::InProgress;
+        while let Some((idx, dep_pos)) = stack.last_mut() {
+            let idx = *idx;
+            match graph.nodes[idx].deps.get(*dep_pos) {
+                Some(&dep) => {
+                    *dep_pos += 1;
+                    if visits[dep] == Visit::New {
+                        visits[dep] = Visit::InProgress;
+                        stack.push((dep, 0));
+                    }
+                }
+                None => {
+                    for &dep in &graph.nodes[idx].deps {
+                        if visits[dep] == Visit::Done && depths[dep] + 1 > depths[idx] {
+                            depths[idx] = depths[dep] + 1;
+                            next[idx] = Some(dep);
+                        }
+                    }
+                    visits[idx] = Visit::Done;
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    (depths, next)
+}
+
+/// The shortest dependency path from `from` to `to`, if `from` depends on `to` at all.
+pub(crate) fn path(graph: &DumpGraph, from: usize, to: usize) -> Option<Vec<String>> {
+    let mut parents: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(idx) = queue.pop_front() {
+        if idx == to {
+            let mut path = vec![to];
+            while let Some(parent) = parents.get(path.last().unwrap()) {
+                path.push(*parent);
+            }
+            path.reverse();
+            return Some(graph.keys(&path));
+        }
+        for &dep in &graph.nodes[idx].deps {
+            if dep != from && !parents.contains_key(&dep) {
+                parents.insert(dep, idx);
+                queue.push_back(dep);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::*;
+    use crate::graph::DumpNode;
+
+    /// `a -> b -> c -> d`, `a -> c`, `e -> c`.
+    fn graph() -> DumpGraph {
+        let edges: &[(&str, &str, &[usize])] = &[
+            ("a", "Root", &[1, 2]),
+            ("b", "Mid", &[2]),
+            ("c", "Mid", &[3]),
+            ("d", "Leaf", &[]),
+            ("e", "Root", &[2]),
+        ];
+        let nodes = edges
+            .iter()
+            .map(|(key, type_name, deps)| DumpNode {
+                key: (*key).to_owned(),
+                type_name: (*type_name).to_owned(),
+                deps: deps.to_vec(),
+                rdeps: Vec::new(),
+            })
+            .collect();
+        DumpGraph::from_nodes(nodes)
+    }
+
+    #[test]
+    fn test_fan_in_and_out() {
+        let graph = graph();
+        assert_eq!(
+            vec![KeyCount {
+                key: "c".to_owned(),
+                count: 3
+            }],
+            top_fan_in(&graph, 1)
+        );
+        assert_eq!(
+            vec![
+                KeyCount {
+                    key: "a".to_owned(),
+                    count: 2
+                },
+                KeyCount {
+                    key: "b".to_owned(),
+                    count: 1
+                }
+            ],
+            top_fan_out(&graph, 2)
+        );
+    }
+
+    #[test]
+    fn test_type_histogram() {
+        let histogram = type_histogram(&graph());
+        assert_eq!(
+            vec![("Mid", 2), ("Root", 2), ("Leaf", 1)],
+            histogram
+                .iter()
+                .map(|t| (t.type_name.as_str(), t.count))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_longest_chains() {
+        assert_eq!(
+            vec![vec!["a", "b", "c", "d"], vec!["e", "c", "d"]],
+            longest_chains(&graph(), 5)
+        );
+    }
+
+    #[test]
+    fn test_path() {
+        let graph = graph();
+        assert_eq!(
+            Some(vec!["a".to_owned(), "c".to_owned()]),
+            path(&graph, 0, 2)
+        );
+        assert_eq!(None, path(&graph, 4, 1));
+    }
+}
@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The graph of a DICE dump, read from either of the dump formats of `buck2 debug dice-dump`:
+//! the bincode dump of `--serde`, or the tsv dump, a directory with gzipped `nodes.gz` and
+//! `edges.gz` listings. The tsv dump doesn't record the values or versions of the nodes, only
+//! which keys depend on which.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use dice::introspection::graph::SerializedGraphNodesForKey;
+
+/// A DICE dump, indexed for analysis. Nodes are referred to by their index in `nodes`.
+pub(crate) struct DumpGraph {
+    pub(crate) nodes: Vec<DumpNode>,
+}
+
+pub(crate) struct DumpNode {
+    pub(crate) key: String,
+    pub(crate) type_name: String,
+    pub(crate) deps: Vec<usize>,
+    pub(crate) rdeps: Vec<usize>,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum DumpGraphError {
+    #[error("No key matches `{0}`")]
+    NoMatch(String),
+    #[error("`{0}` matches {1} keys, including `{2}` and `{3}`, use a more specific key")]
+    AmbiguousMatch(String, usize, String, String),
+    #[error("Invalid line in the tsv dump: `{0}`")]
+    InvalidTsvLine(String),
+}
+
+impl DumpGraph {
+    pub(crate) fn new(dump: Vec<SerializedGraphNodesForKey>) -> Self {
+        let index: HashMap<usize, usize> = dump
+            .iter()
+            .enumerate()
+            .map(|(idx, node)| (node.id.0, idx))
+            .collect();
+
+        let nodes = dump
+            .into_iter()
+            .map(|node| {
+                // Deps of a node whose locks were poisoned aren't in the dump, nor are deps that
+                // weren't in the graph when it was dumped.
+                let mut deps: Vec<usize> = node
+                    .nodes
+                    .and_then(|n| n.deps)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|dep| index.get(&dep.0).copied())
+                    .collect();
+                deps.sort_unstable();
+                DumpNode {
+                    key: node.key,
+                    type_name: node.type_name,
+                    deps,
+                    rdeps: Vec::new(),
+                }
+            })
+            .collect();
+
+        Self::from_nodes(nodes)
+    }
+
+    /// Reads a tsv dump, from the contents of its `nodes.gz` and `edges.gz`. A node is a line of
+    /// `index\ttype\tkey`, an edge a line of `index\tdep_index`.
+    pub(crate) fn from_tsv(nodes: impl BufRead, edges: impl BufRead) -> anyhow::Result<Self> {
+        let mut index = HashMap::new();
+        let mut dump_nodes = Vec::new();
+        for line in nodes.lines() {
+            let line = line?;
+            let (id, type_name, key) = match line.splitn(3, '\t').collect::<Vec<_>>()[..] {
+                [id, type_name, key] => (id, type_name, key),
+                _ => return Err(DumpGraphError::InvalidTsvLine(line.clone()).into()),
+            };
+            let id: u64 = id
+                .parse()
+                .map_err(|_| DumpGraphError::InvalidTsvLine(line.clone()))?;
+            index.insert(id, dump_nodes.len());
+            dump_nodes.push(DumpNode {
+                key: key.to_owned(),
+                type_name: type_name.to_owned(),
+                deps: Vec::new(),
+                rdeps: Vec::new(),
+            });
+        }
+
+        for line in edges.lines() {
+            let line = line?;
+            let edge = line
+                .split_once('\t')
+                .and_then(|(k, dep)| Some((k.parse::<u64>().ok()?, dep.parse::<u64>().ok()?)))
+                .and_then(|(k, dep)| Some((*index.get(&k)?, *index.get(&dep)?)));
+            match edge {
+                Some((k, dep)) => dump_nodes[k].deps.push(dep),
+                None => return Err(DumpGraphError::InvalidTsvLine(line).into()),
+            }
+        }
+        for node in &mut dump_nodes {
+            node.deps.sort_unstable();
+            node.deps.dedup();
+        }
+
+        Ok(Self::from_nodes(dump_nodes))
+    }
+
+    /// Creates a graph from nodes whose `rdeps` are yet to be filled in.
+    pub(crate) fn from_nodes(mut nodes: Vec<DumpNode>) -> Self {
+        for idx in 0..nodes.len() {
+            for dep in nodes[idx].deps.clone() {
+                nodes[dep].rdeps.push(idx);
+            }
+        }
+        Self { nodes }
+    }
+
+    /// Finds the node whose key is `key`, or else the only node whose key contains it.
+    pub(crate) fn find(&self, key: &str) -> anyhow::Result<usize> {
+        if let Some(idx) = self.nodes.iter().position(|n| n.key == key) {
+            return Ok(idx);
+        }
+        let matches: Vec<usize> = (0..self.nodes.len())
+            .filter(|idx| self.nodes[*idx].key.contains(key))
+            .collect();
+        match matches.as_slice() {
+            [] => Err(DumpGraphError::NoMatch(key.to_owned()).into()),
+            [idx] => Ok(*idx),
+            [first, second, ..] => Err(DumpGraphError::AmbiguousMatch(
+                key.to_owned(),
+                matches.len(),
+                self.nodes[*first].key.clone(),
+                self.nodes[*second].key.clone(),
+            )
+            .into()),
+        }
+    }
+
+    pub(crate) fn keys(&self, path: &[usize]) -> Vec<String> {
+        path.iter()
+            .map(|idx| self.nodes[*idx].key.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::DumpGraph;
+
+    #[test]
+    fn test_from_tsv() -> anyhow::Result<()> {
+        let nodes = "0\tRoot\ta\n1\tLeaf\tb\tc\n2\tLeaf\td\n";
+        let edges = "0\t1\n0\t2\n0\t1\n2\t1\n";
+        let graph = DumpGraph::from_tsv(nodes.as_bytes(), edges.as_bytes())?;
+
+        assert_eq!(3, graph.nodes.len());
+        assert_eq!("b\tc", graph.nodes[1].key);
+        assert_eq!("Leaf", graph.nodes[1].type_name);
+        assert_eq!(vec![1, 2], graph.nodes[0].deps);
+        assert_eq!(vec![0, 2], graph.nodes[1].rdeps);
+
+        assert!(DumpGraph::from_tsv("0\tRoot\ta\n".as_bytes(), "0\t1\n".as_bytes()).is_err());
+        Ok(())
+    }
+}
@@ -8,19 +8,63 @@
  */
 
 use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
 
+use anyhow::Context as _;
 use clap::CommandFactory;
 use clap::FromArgMatches;
 use dice::introspection::graph::SerializedGraphNodesForKey;
+use flate2::read::GzDecoder;
+use serde::Serialize;
+
+use crate::graph::DumpGraph;
+
+mod analyze;
+mod graph;
 
 #[derive(Debug, clap::Parser)]
 #[clap(name = "read_dump", about = "dice dump reader")]
 pub(crate) struct Opt {
-    #[clap(name = "DICE_DUMP", help = "The dice dump")]
+    #[clap(
+        name = "DICE_DUMP",
+        help = "The dice dump: a bincode file, gzipped or not, or the directory of a tsv dump"
+    )]
     file: PathBuf,
     #[clap(long = "out", help = "Copy the output to this path")]
     out: Option<PathBuf>,
+    #[clap(long = "json", help = "Print the analysis results as JSON")]
+    json: bool,
+    /// Without a command, the whole dump is converted to pretty JSON, which needs a bincode dump.
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// The keys with the most rdeps.
+    FanIn {
+        #[clap(short = 'n', default_value = "20", help = "How many keys to show")]
+        n: usize,
+    },
+    /// The keys with the most deps.
+    FanOut {
+        #[clap(short = 'n', default_value = "20", help = "How many keys to show")]
+        n: usize,
+    },
+    /// The longest chains of deps.
+    Chains {
+        #[clap(short = 'n', default_value = "5", help = "How many chains to show")]
+        n: usize,
+    },
+    /// The number of keys of each type.
+    Types,
+    /// The shortest chain of deps from a key to another. Keys are matched exactly, or else by the
+    /// only key containing them.
+    Path { from: String, to: String },
 }
 
 fn main() -> anyhow::Result<()> {
@@ -28,18 +72,109 @@ fn main() -> anyhow::Result<()> {
     let matches = clap.get_matches_from(std::env::args().collect::<Vec<String>>());
     let opt = Opt::from_arg_matches(&matches)?;
 
-    let file = File::open(opt.file)?;
-
-    let out: Vec<SerializedGraphNodesForKey> = bincode::deserialize_from(&file)?;
+    let mut writer: Box<dyn Write> = match opt.out {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(std::io::stdout()),
+    };
 
-    match opt.out {
-        Some(path) => {
-            serde_json::to_writer_pretty(File::create(path)?, &out)?;
-        }
-        None => {
-            serde_json::to_writer_pretty(std::io::stdout(), &out)?;
+    let (graph, command) = if opt.file.is_dir() {
+        let command = opt.command.ok_or_else(|| {
+            anyhow::anyhow!("Tsv dumps can't be converted to JSON, only analyzed by a command")
+        })?;
+        let graph = DumpGraph::from_tsv(
+            open_gz(&opt.file.join("nodes.gz"))?,
+            open_gz(&opt.file.join("edges.gz"))?,
+        )?;
+        (graph, command)
+    } else {
+        let mut file = BufReader::new(File::open(&opt.file)?);
+        let out: Vec<SerializedGraphNodesForKey> = if file.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            bincode::deserialize_from(GzDecoder::new(file))?
+        } else {
+            bincode::deserialize_from(file)?
+        };
+        match opt.command {
+            Some(command) => (DumpGraph::new(out), command),
+            None => {
+                serde_json::to_writer_pretty(writer, &out)?;
+                return Ok(());
+            }
         }
     };
 
-    Ok(())
+    let output = Output {
+        writer: &mut writer,
+        json: opt.json,
+    };
+    match command {
+        Command::FanIn { n } => output.write(&analyze::top_fan_in(&graph, n), |w, counts| {
+            for c in counts {
+                writeln!(w, "{:>8}  {}", c.count, c.key)?;
+            }
+            Ok(())
+        }),
+        Command::FanOut { n } => output.write(&analyze::top_fan_out(&graph, n), |w, counts| {
+            for c in counts {
+                writeln!(w, "{:>8}  {}", c.count, c.key)?;
+            }
+            Ok(())
+        }),
+        Command::Chains { n } => output.write(&analyze::longest_chains(&graph, n), |w, chains| {
+            for chain in chains {
+                writeln!(w, "{} keys:", chain.len())?;
+                for key in chain {
+                    writeln!(w, "  {}", key)?;
+                }
+            }
+            Ok(())
+        }),
+        Command::Types => output.write(&analyze::type_histogram(&graph), |w, counts| {
+            for c in counts {
+                writeln!(w, "{:>8}  {}", c.count, c.type_name)?;
+            }
+            Ok(())
+        }),
+        Command::Path { from, to } => {
+            let path = analyze::path(&graph, graph.find(&from)?, graph.find(&to)?);
+            output.write(&path, |w, path| {
+                match path {
+                    Some(path) => {
+                        for key in path {
+                            writeln!(w, "{}", key)?;
+                        }
+                    }
+                    None => writeln!(w, "`{}` doesn't depend on `{}`", from, to)?,
+                }
+                Ok(())
+            })
+        }
+    }
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+fn open_gz(path: &Path) -> anyhow::Result<impl BufRead> {
+    let file = File::open(path).with_context(|| format!("Failed to open `{}`", path.display()))?;
+    Ok(BufReader::new(GzDecoder::new(file)))
+}
+
+struct Output<'a> {
+    writer: &'a mut dyn Write,
+    json: bool,
+}
+
+impl Output<'_> {
+    fn write<T: Serialize>(
+        self,
+        value: &T,
+        text: impl FnOnce(&mut dyn Write, &T) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        if self.json {
+            serde_json::to_writer_pretty(&mut *self.writer, value)?;
+            writeln!(self.writer)?;
+            Ok(())
+        } else {
+            text(self.writer, value)
+        }
+    }
 }